@@ -0,0 +1,266 @@
+//! `--split-every`: the selected elements written a fixed number to a file,
+//! like `split(1)`, to files named from a template. Elements are whole
+//! whatever the mode, so a chunk never ends inside a line, a CSV record, or
+//! a grapheme cluster.
+
+use crate::{
+    ext::{Annotation, FieldWriter, Rewrite},
+    ElementSink,
+};
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, BufWriter, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Error produced while parsing a [`ChunkTemplate`].
+#[derive(Clone, Eq, PartialEq, Debug, thiserror::Error)]
+pub enum ParseChunkTemplateError {
+    #[error("a chunk file template needs a '%d' for the chunk number (e.g. 'part-%04d.txt')")]
+    MissingNumber,
+    #[error("unknown conversion '%{0}' in chunk file template (expected %d, %0Nd, %s, or %%)")]
+    UnknownConversion(String),
+}
+
+/// How the chunk files of [`slice_every`](crate::slice_every) are named: a
+/// path with `printf`-style conversions. `%d` is the chunk number, counted
+/// from 0; `%04d` pads it with zeros to 4 digits. `%s` is the name of the
+/// input file (`stdin` for standard input), and makes each input's chunks
+/// numbered on their own rather than running on from the last input's.
+/// `%%` is a literal `%`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ChunkTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Part {
+    Literal(String),
+    Number { width: usize },
+    Input,
+}
+
+impl FromStr for ChunkTemplate {
+    type Err = ParseChunkTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = s;
+        while let Some(at) = rest.find('%') {
+            literal.push_str(&rest[..at]);
+            let spec = &rest[at + 1..];
+            let digits = spec.len() - spec.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let part = match (&spec[..digits], spec[digits..].chars().next()) {
+                ("", Some('%')) => {
+                    literal.push('%');
+                    None
+                }
+                ("", Some('s')) => Some(Part::Input),
+                (width, Some('d')) if width.is_empty() || width.starts_with('0') => {
+                    Some(Part::Number {
+                        width: width.parse().unwrap_or(0),
+                    })
+                }
+                (width, next) => {
+                    let next = next.map(String::from).unwrap_or_default();
+                    return Err(ParseChunkTemplateError::UnknownConversion(format!(
+                        "{width}{next}"
+                    )));
+                }
+            };
+            if let Some(part) = part {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+                parts.push(part);
+            }
+            // Past the digits and the one-byte conversion.
+            rest = &spec[digits + 1..];
+        }
+        literal.push_str(rest);
+        parts.push(Part::Literal(literal));
+        if !parts.iter().any(|part| matches!(part, Part::Number { .. })) {
+            return Err(ParseChunkTemplateError::MissingNumber);
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl ChunkTemplate {
+    /// The path of chunk `number` of `input` (`None` for standard input).
+    pub fn path(&self, input: Option<&Path>, number: u64) -> PathBuf {
+        let mut path = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => path.push_str(literal),
+                Part::Number { width } => {
+                    write!(path, "{number:0width$}").expect("writing to a String");
+                }
+                Part::Input => path.push_str(
+                    &input
+                        .and_then(Path::file_name)
+                        .map_or("stdin".into(), |name| name.to_string_lossy()),
+                ),
+            }
+        }
+        PathBuf::from(path)
+    }
+
+    /// Whether each input is chunked on its own (`%s`).
+    pub fn per_input(&self) -> bool {
+        self.parts.contains(&Part::Input)
+    }
+}
+
+/// Where [`slice_every`](crate::slice_every) writes: one element at a time,
+/// into the current chunk file, starting the next one every `every`
+/// elements. A chunk file is only created once it has an element for it, so
+/// input that selects nothing leaves no empty file behind.
+pub(crate) struct Chunker<'a> {
+    template: &'a ChunkTemplate,
+    every: NonZeroUsize,
+    rewrite: Option<&'a Rewrite>,
+    terminator: &'a [u8],
+    annotation: Annotation,
+    io_buffer_size: Option<NonZeroUsize>,
+    input: Option<PathBuf>,
+    /// The input's header (`--keep-header`), repeated at the top of every
+    /// chunk file.
+    header: Vec<u8>,
+    /// The number of the next chunk file.
+    next: u64,
+    /// The chunk file being written and the elements in it so far.
+    file: Option<(PathBuf, FieldWriter<'a, BufWriter<fs::File>>, usize)>,
+}
+
+impl<'a> Chunker<'a> {
+    pub(crate) fn new(
+        template: &'a ChunkTemplate,
+        every: NonZeroUsize,
+        rewrite: Option<&'a Rewrite>,
+        terminator: &'a [u8],
+        annotation: Annotation,
+        io_buffer_size: Option<NonZeroUsize>,
+    ) -> Self {
+        Self {
+            template,
+            every,
+            rewrite,
+            terminator,
+            annotation,
+            io_buffer_size,
+            input: None,
+            header: Vec::new(),
+            next: 0,
+            file: None,
+        }
+    }
+
+    /// Move on to `input`. Under a `%s` template its chunks are numbered
+    /// from 0 in files of their own; otherwise the current chunk runs on
+    /// into it.
+    pub(crate) fn start(&mut self, input: Option<&Path>) -> io::Result<()> {
+        if self.template.per_input() {
+            self.finish()?;
+            self.next = 0;
+        }
+        self.input = input.map(Path::to_path_buf);
+        self.header.clear();
+        Ok(())
+    }
+
+    /// Flush and close the current chunk file, if any.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some((path, mut file, _)) => file.flush().map_err(|err| at(&path, err)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ElementSink for Chunker<'_> {
+    /// The header of the current input, for each chunk file opened from now.
+    fn header(&mut self, header: &[u8]) -> io::Result<()> {
+        self.header.clear();
+        self.header.extend_from_slice(header);
+        Ok(())
+    }
+
+    fn element(&mut self, element: &[u8]) -> io::Result<()> {
+        let (path, file, count) = match &mut self.file {
+            Some(file) => file,
+            None => {
+                let path = self.template.path(self.input.as_deref(), self.next);
+                let file = fs::File::create(&path).map_err(|err| at(&path, err))?;
+                let file = match self.io_buffer_size {
+                    Some(capacity) => BufWriter::with_capacity(capacity.get(), file),
+                    None => BufWriter::new(file),
+                };
+                let mut file =
+                    FieldWriter::new(file, self.rewrite, self.terminator, self.annotation);
+                file.write_all(&self.header).map_err(|err| at(&path, err))?;
+                self.next += 1;
+                self.file.insert((path, file, 0))
+            }
+        };
+        file.write_all(element).map_err(|err| at(path, err))?;
+        *count += 1;
+        if *count == self.every.get() {
+            self.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// `err`, naming the chunk file it happened to.
+fn at(path: &Path, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(s: &str) -> ChunkTemplate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn names_chunks_from_the_template() {
+        let part = template("part-%04d.txt");
+        assert_eq!(part.path(None, 7), Path::new("part-0007.txt"));
+        assert_eq!(part.path(None, 12345), Path::new("part-12345.txt"));
+        assert!(!part.per_input());
+
+        let named = template("out/%s.%d");
+        let input = Path::new("logs/app.log");
+        assert_eq!(named.path(Some(input), 3), Path::new("out/app.log.3"));
+        assert_eq!(named.path(None, 0), Path::new("out/stdin.0"));
+        assert!(named.per_input());
+
+        assert_eq!(template("100%%-%d").path(None, 1), Path::new("100%-1"));
+    }
+
+    #[test]
+    fn rejects_templates_without_a_number() {
+        let cases = [
+            ("part.txt", ParseChunkTemplateError::MissingNumber),
+            ("%s.txt", ParseChunkTemplateError::MissingNumber),
+            ("%%d", ParseChunkTemplateError::MissingNumber),
+            (
+                "%x-%d",
+                ParseChunkTemplateError::UnknownConversion("x".into()),
+            ),
+            (
+                "%4d",
+                ParseChunkTemplateError::UnknownConversion("4d".into()),
+            ),
+            ("%d%", ParseChunkTemplateError::UnknownConversion("".into())),
+        ];
+        for (input, err) in cases {
+            assert_eq!(input.parse::<ChunkTemplate>(), Err(err), "{input}");
+        }
+    }
+}
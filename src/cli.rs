@@ -1,7 +1,13 @@
-use crate::range::{SliceRange, TranslateDialect};
 use bytesize::ByteSize;
-use clap::{ArgGroup, Parser, ValueEnum};
-use std::{num::NonZeroUsize, path::PathBuf, str::FromStr};
+use clap::{
+    builder::{StringValueParser, TypedValueParser},
+    parser::ValueSource,
+    ArgGroup, CommandFactory, Parser, ValueEnum,
+};
+use slice_command::{
+    DelimiterRegex, Follow, OutputFormat, ParseSliceRangeError, SliceRanges, TranslateDialect,
+};
+use std::{ffi::OsString, num::NonZeroUsize, path::PathBuf, str::FromStr};
 
 // `CompletePowershell` (not `CompletePowerShell`) so the kebab-cased value is
 // `complete-powershell` rather than `complete-power-shell`.
@@ -54,6 +60,64 @@ impl FromStr for MaxRecordSize {
     }
 }
 
+/// The `<RANGE>` argument. Which numbers it may hold depends on the mode,
+/// which is only known once every flag is parsed, so both readings are kept:
+/// the plain one (absent when the range uses sizes or hex) and the byte one.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct RangeArg {
+    plain: Option<SliceRanges>,
+    bytes: SliceRanges,
+}
+
+impl RangeArg {
+    /// The ranges as the mode reads them, or `None` when a range outside
+    /// byte mode uses a size suffix or a hex offset.
+    #[inline]
+    pub(crate) fn get(&self, bytes: bool) -> Option<&SliceRanges> {
+        if bytes {
+            Some(&self.bytes)
+        } else {
+            self.plain.as_ref()
+        }
+    }
+}
+
+impl FromStr for RangeArg {
+    type Err = ParseSliceRangeError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match SliceRanges::from_str(s) {
+            Ok(plain) => Ok(Self {
+                plain: Some(plain.clone()),
+                bytes: plain,
+            }),
+            // The two readings differ only on sizes and hex, so the byte
+            // reading's error is the plain one whenever neither is involved.
+            Err(_) => SliceRanges::parse_bytes(s).map(|bytes| Self { plain: None, bytes }),
+        }
+    }
+}
+
+/// The first positional argument: a `<RANGE>`, or, where it does not read
+/// as one, the first of FILES, which `--fields` lets stand in its place
+/// (see [`Args::range`]).
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum RangeOrFile {
+    Range(Box<RangeArg>),
+    File(OsString),
+}
+
+impl From<OsString> for RangeOrFile {
+    #[inline]
+    fn from(value: OsString) -> Self {
+        match value.to_str().map(RangeArg::from_str) {
+            Some(Ok(range)) => Self::Range(Box::new(range)),
+            _ => Self::File(value),
+        }
+    }
+}
+
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[command(
     name = env!("CARGO_BIN_NAME"),
@@ -61,7 +125,7 @@ impl FromStr for MaxRecordSize {
     about,
     author,
     arg_required_else_help = true,
-    group(ArgGroup::new("mode").args(["lines", "bytes", "chars", "graphemes", "delimiter", "null"])),
+    group(ArgGroup::new("mode").args(["lines", "bytes", "chars", "graphemes", "words", "sentences", "delimiter", "delimiter_regex", "null", "csv", "paragraphs", "record_size", "columns"])),
     // --explain and --translate are both read-and-exit actions handled in
     // precedence order by entry(); group them so clap rejects both at once
     // rather than silently running one. (--generate is `exclusive`, so it
@@ -75,19 +139,31 @@ pub(crate) struct Args {
     // The field is an Option with an explicit `required = true`: clap skips
     // required-argument validation when an `exclusive` arg (`--generate`) is
     // present, and the explicit requiredness keeps `<RANGE>` (not `[RANGE]`)
-    // in the usage line.
+    // in the usage line. `--build-index` waives it the same way, by
+    // conflicting with it, and `--fields` by slicing every row without it
+    // (`Args::parse_args`). A value that is no range is only rejected by
+    // `Args::range`, once `--fields` could have made it a file.
     #[arg(
         allow_hyphen_values = true,
         required = true,
+        value_parser = clap::builder::OsStringValueParser::new().map(RangeOrFile::from),
         help = "The slice syntax is similar to Python's slice syntax, with the format `start:end:step`.
 Each value is optional and, if omitted, defaults to the start of the file, the end of the file, and a step of 1, respectively.
 Negative start/end values count back from the end of the input, like Python.
-A negative step selects in reverse, like Python ('::-1' reverses the input); it buffers the whole input in memory.
+A negative step selects in reverse, like Python ('::-1' reverses the input); input other than a regular file is buffered (see --max-memory).
 e.g., '50:100', '50:100:1', '-5:', '::-1'
 and the extended syntax 'start:+line' is supported. (experimental)
-e.g., '50:+50'"
+e.g., '50:+50'
+Comma-separated ranges select their union in one pass, each element printed once, in input order.
+e.g., '0:5,100:110,-3:'
+A /regex/ start or end anchors at the first matching element (the end is searched after the start, and excluded).
+e.g., '/^BEGIN/:/^END/', '/ERROR/:+5'
+With -b, numbers may be hex offsets or sizes with a suffix (K = 1000, KiB = 1024).
+e.g., '1MiB:+4KiB', '0x1f00:'
+A percentage is a share of the element count, rounded down; the input is counted first (input other than a regular file is buffered).
+e.g., '50%:', '10%:20%', '::1%'"
     )]
-    pub(crate) range: Option<SliceRange>,
+    pub(crate) range: Option<RangeOrFile>,
     #[arg(short, help = "Slice the lines (default)")]
     pub(crate) lines: bool,
     // `-c` is a hidden short alias kept for backward compatibility.
@@ -99,10 +175,88 @@ e.g., '50:+50'"
     pub(crate) chars: bool,
     #[arg(long, help = "Slice the user-perceived characters (grapheme clusters)")]
     pub(crate) graphemes: bool,
+    // Words and sentences split like grapheme clusters, so they take no
+    // fields, kept header, follow, or index either, and no other tool's
+    // syntax segments text this way.
+    #[arg(
+        long,
+        conflicts_with_all = ["fields", "keep_header", "translate", "follow", "index", "build_index"],
+        help = "Slice the words (UAX #29): the spaces and punctuation between words are elements of their own"
+    )]
+    pub(crate) words: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["fields", "keep_header", "translate", "follow", "index", "build_index"],
+        help = "Slice the sentences (UAX #29), each with its trailing spaces and line break"
+    )]
+    pub(crate) sentences: bool,
     #[arg(long, help = "Slice by delimiter")]
     pub(crate) delimiter: Option<String>,
+    // Records delimited by a regex cannot have their fields rejoined, keep a
+    // header, be followed, or be indexed (see Options::check), and no other
+    // tool's syntax translates the pattern.
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        conflicts_with_all = ["fields", "keep_header", "translate", "follow", "index", "build_index"],
+        help = "Slice by a delimiter regex: each match ends a record and stays with it (e.g., '\\n\\s*\\n' for blank lines that may hold whitespace); ^ and $ match at line boundaries"
+    )]
+    pub(crate) delimiter_regex: Option<DelimiterRegex>,
     #[arg(short = 'z', long = "null", help = "Use NUL (\\0) as the delimiter")]
     pub(crate) null: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["fields", "translate"],
+        help = "Slice the CSV records (RFC 4180): a quoted field may contain newlines"
+    )]
+    pub(crate) csv: bool,
+    // A paragraph is only known to have ended once the next one's first line
+    // is read, so fields, a kept header, and following are out (see
+    // Options::check), and no other tool's syntax normalizes blank lines.
+    #[arg(
+        long,
+        conflicts_with_all = ["fields", "keep_header", "translate", "follow"],
+        help = "Slice the paragraphs: blocks of lines separated by blank lines (empty, or only spaces and tabs), each printed with one empty line after it"
+    )]
+    pub(crate) paragraphs: bool,
+    // Fixed-size records have no delimiter to rejoin fields with or to end a
+    // header at, and their offsets are already arithmetic, so an index has
+    // nothing to add.
+    #[arg(
+        long,
+        value_name = "SIZE",
+        conflicts_with_all = ["fields", "keep_header", "translate", "index", "build_index"],
+        help = "Slice fixed-size records of SIZE bytes each, with no delimiter (e.g., '128' or '4KiB'); the last record may be shorter"
+    )]
+    pub(crate) record_size: Option<NonZeroByteSize>,
+    // The range selects columns inside every line rather than lines, so
+    // there are no records for fields, a header, a complement, or an index
+    // to work on.
+    #[arg(
+        long,
+        conflicts_with_all = ["fields", "keep_header", "complement", "translate", "index", "build_index"],
+        help = "Slice the display columns of every line, as a terminal renders them: wide characters take two, combining marks none, and a grapheme cluster is never split (e.g., ':80' to fit 80 columns)"
+    )]
+    pub(crate) columns: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "columns",
+        help = "Put the --columns tab stops every N columns. Defaults to 8"
+    )]
+    pub(crate) tab_width: Option<NonZeroUsize>,
+    #[arg(
+        long,
+        conflicts_with_all = ["paragraphs", "record_size", "fields", "columns", "keep_header", "follow", "index", "build_index", "translate"],
+        help = "Treat ANSI escape sequences (colors, cursor moves, hyperlinks) as zero-width: they are never split or counted as elements, and each slice starts in the color state it had in the input and ends with it reset"
+    )]
+    pub(crate) ansi: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["bytes", "chars", "graphemes", "translate"],
+        help = "Always print the first line or record (the header); the range counts from the one after it"
+    )]
+    pub(crate) keep_header: bool,
     #[arg(
         short = 'e',
         long = "escape",
@@ -110,6 +264,125 @@ e.g., '50:+50'"
         help = "Interpret backslash escapes in --delimiter (\\t \\n \\r \\0 \\\\ \\xHH)"
     )]
     pub(crate) escape: bool,
+    #[arg(
+        long,
+        value_name = "RANGE",
+        allow_hyphen_values = true,
+        conflicts_with_all = ["bytes", "chars", "graphemes", "translate"],
+        help = "Also slice the fields of each selected line or record, with the same range syntax (e.g., '2:5', '-2:', '::-1'). Without a <RANGE>, every line or record is selected"
+    )]
+    pub(crate) fields: Option<SliceRanges>,
+    #[arg(
+        long,
+        value_name = "DELIM",
+        requires = "fields",
+        help = "Split fields on DELIM and rejoin the selected ones with it; backslash escapes are always interpreted (e.g., '\\t'). Defaults to runs of whitespace, rejoined with one space"
+    )]
+    pub(crate) field_delimiter: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "translate",
+        help = "Print every element the range does not select, in input order (reverse ranges keep their reverse order)"
+    )]
+    pub(crate) complement: bool,
+    #[arg(
+        short = 'n',
+        long,
+        conflicts_with = "translate",
+        help = "Prefix each element printed with its index, counted as the range counts (from 0), and a colon"
+    )]
+    pub(crate) number: bool,
+    #[arg(
+        long,
+        requires = "number",
+        help = "Count the --number index from 1 instead of 0"
+    )]
+    pub(crate) one_based: bool,
+    #[arg(
+        long,
+        conflicts_with = "translate",
+        help = "Prefix each element printed with the byte offset where it starts in the input, and a colon (after any --number)"
+    )]
+    pub(crate) offsets: bool,
+    // `require_equals` for the same reason as --translate: a bare `--follow`
+    // must not swallow the <RANGE> after it.
+    #[arg(
+        long,
+        value_name = "HOW",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "descriptor",
+        requires = "files",
+        conflicts_with_all = ["chars", "graphemes"],
+        help = "After the current contents, keep reading data appended to FILES and print the elements still in the range. Tail-relative bounds are resolved once, against the contents at startup. `name` reopens a file that is renamed or replaced (log rotation)"
+    )]
+    pub(crate) follow: Option<Follow>,
+    #[arg(
+        long,
+        requires = "files",
+        conflicts_with_all = ["bytes", "graphemes", "follow"],
+        help = "Seek into FILES through a sidecar index of element offsets (FILE.slice-index), built on first use and rebuilt when FILE changes. For lines, delimited or CSV records, paragraphs, and characters"
+    )]
+    pub(crate) index: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 1..,
+        conflicts_with_all = ["range", "bytes", "graphemes", "follow", "index", "explain", "translate", "files"],
+        help = "Build the sidecar index --index uses for each FILE, for the selected mode, and exit without slicing"
+    )]
+    pub(crate) build_index: Vec<PathBuf>,
+    // A compressed file is read through from its start, so there is nothing
+    // appended to follow, and no other tool's syntax decompresses.
+    #[arg(
+        long,
+        conflicts_with_all = ["follow", "build_index", "translate"],
+        help = "Decompress gzip, zstd, xz, and bzip2 input, recognized by its magic number, before slicing it; byte ranges then count decompressed bytes. Other input is sliced as it is"
+    )]
+    pub(crate) decompress: bool,
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["build_index", "explain", "translate"],
+        help = "Write the output to FILE instead of stdout, replacing it. With --split-every, a template naming the chunk files: %d is the chunk number from 0 (%04d pads it to 4 digits), %s the input file's name, which numbers each input's chunks on their own, and %% a literal %"
+    )]
+    pub(crate) output: Option<PathBuf>,
+    // Chunks are cut between the elements a Selected iterator hands out, so
+    // nothing is left to carry a color state across files or to follow.
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "output",
+        conflicts_with_all = ["ansi", "follow"],
+        help = "Write the output N elements to a file, to files named from the --output template, the way split(1) does; a chunk never ends inside an element. With --keep-header, every chunk starts with the header"
+    )]
+    pub(crate) split_every: Option<NonZeroUsize>,
+    // `require_equals` for the same reason as --translate: a bare `-i` must
+    // not swallow the <RANGE> after it.
+    #[arg(
+        short = 'i',
+        long,
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        requires = "files",
+        conflicts_with_all = ["output", "split_every", "follow", "decompress", "build_index", "explain", "translate"],
+        help = "Rewrite each of FILES to what the range selects from it, atomically: through a temporary file in the same directory, synced and renamed over it, keeping its permissions. With SUFFIX, keep the original as FILE plus SUFFIX (e.g., '--in-place=.bak')"
+    )]
+    pub(crate) in_place: Option<OsString>,
+    // Each object carries its own index and offset, and is written whole, so
+    // there is no prefix to add, color state to carry, or appended data to
+    // wait on.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        conflicts_with_all = ["number", "offsets", "ansi", "follow", "split_every", "in_place", "build_index", "translate"],
+        help = "Print each selected element as a JSON object with its file, index, byte offset, length, and content (UTF-8, or content_base64 where it is not): all in one array (json) or one per line (jsonl)"
+    )]
+    pub(crate) format: OutputFormat,
     #[arg(
         long,
         help = "Explain what the range selects and exit without reading input. Any FILES are ignored"
@@ -172,9 +445,15 @@ e.g., '50:+50'"
     #[arg(
         long,
         value_name = "SIZE|unlimited",
-        help = "Maximum bytes retained for one line, custom-delimited record, or grapheme cluster in tail-relative and reverse ranges. Defaults to unlimited"
+        help = "Maximum bytes retained for one line, custom-delimited record, or grapheme cluster in tail-relative, reverse, and /regex/ ranges. Defaults to unlimited"
     )]
     pub(crate) max_record_size: Option<MaxRecordSize>,
+    #[arg(
+        long,
+        value_name = "SIZE",
+        help = "Maximum bytes a reverse range buffers in memory, input and element boundaries together; past it, they spill to temporary files. Regular files are read in place instead. Defaults to unlimited"
+    )]
+    pub(crate) max_memory: Option<NonZeroByteSize>,
     #[arg(help = "Target files. if not provided use stdin")]
     pub(crate) files: Vec<PathBuf>,
 }
@@ -193,6 +472,72 @@ impl Args {
         }
     }
 
+    #[inline]
+    pub(crate) fn record_size(&self) -> Option<NonZeroUsize> {
+        self.record_size.map(|it| it.0)
+    }
+
+    #[inline]
+    pub(crate) fn max_memory(&self) -> Option<usize> {
+        self.max_memory.map(|it| it.0.get())
+    }
+
+    /// [`Parser::parse`], except that `--fields` with no `<RANGE>` slices
+    /// every row: the range defaults to `:`. Without one there is no
+    /// positional argument, so it goes first.
+    pub(crate) fn parse_args() -> Self {
+        let mut args: Vec<OsString> = std::env::args_os().collect();
+        let fields_only = Self::command()
+            .ignore_errors(true)
+            .try_get_matches_from(&args)
+            .is_ok_and(|matches| {
+                matches.value_source("fields") == Some(ValueSource::CommandLine)
+                    && matches.value_source("range").is_none()
+                    && matches.value_source("build_index").is_none()
+            });
+        if fields_only {
+            args.insert(args.len().min(1), ":".into());
+        }
+        Self::parse_from(args)
+    }
+
+    /// The `<RANGE>`, taken out of the arguments. With `--fields`, the rows
+    /// default to all of them (`:`), so a first positional argument that is
+    /// no range is the first of FILES instead, unless it starts with `-` as a
+    /// mistyped flag would; the rest get the error clap gives any invalid
+    /// value.
+    pub(crate) fn range(&mut self) -> Result<Option<RangeArg>, clap::Error> {
+        match self.range.take() {
+            Some(RangeOrFile::Range(range)) => Ok(Some(*range)),
+            Some(RangeOrFile::File(file))
+                if self.fields.is_some() && !file.as_encoded_bytes().starts_with(b"-") =>
+            {
+                self.files.insert(0, PathBuf::from(file));
+                Ok(Some(RangeArg::from_str(":").expect("a valid range")))
+            }
+            Some(RangeOrFile::File(value)) => {
+                let mut command = Self::command();
+                command.build();
+                let arg = command.get_arguments().find(|arg| arg.get_id() == "range");
+                // The parser clap derives for a `FromStr` type, for its error.
+                match StringValueParser::new()
+                    .try_map(|value| RangeArg::from_str(&value))
+                    .parse_ref(&command, arg, &value)
+                {
+                    Err(err) => Err(err),
+                    Ok(_) => unreachable!("the value reads as no range"),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The number `-n` gives the first element, if it numbers them at all.
+    #[inline]
+    pub(crate) fn first_number(&self) -> Option<u64> {
+        self.number.then_some(u64::from(self.one_based))
+    }
+
     /// Resolve the effective delimiter bytes. `--null` yields a single NUL
     /// byte; otherwise `--delimiter` is taken literally unless `--escape` is
     /// set, in which case backslash escapes are expanded.
@@ -206,6 +551,18 @@ impl Args {
             None => Ok(None),
         }
     }
+
+    /// Resolve `--field-delimiter`. Unlike `--delimiter`, escapes are always
+    /// expanded: a literal backslash is rarely a column separator, and the
+    /// usual one (`\t`) is awkward to type raw.
+    pub(crate) fn field_delimiter(&self) -> Result<Option<Vec<u8>>, String> {
+        match self.field_delimiter.as_deref().map(unescape).transpose()? {
+            Some(delimiter) if delimiter.is_empty() => {
+                Err("the field delimiter must not be empty".to_owned())
+            }
+            delimiter => Ok(delimiter),
+        }
+    }
 }
 
 /// Expand C-style backslash escapes (`\t \n \r \0 \\ \xHH`) into raw bytes.
@@ -255,20 +612,28 @@ fn unescape(s: &str) -> Result<Vec<u8>, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::range::{SliceIndex, Step};
+    use slice_command::{SliceIndex, SliceRange, Step};
     use std::num::NonZeroUsize;
 
+    /// The ranges as every mode but bytes reads them.
+    fn range(args: &Args) -> Option<SliceRanges> {
+        args.clone().range().ok()??.get(false).cloned()
+    }
+
     #[test]
     fn line_mode_args() {
         let args = Args::parse_from(["slice", "-l", "0::1", "text.txt"]);
         assert!(args.lines);
         assert_eq!(
-            args.range,
-            Some(SliceRange {
-                start: SliceIndex::FromStart(0),
-                end: None,
-                step: Step::forward(1),
-            })
+            range(&args),
+            Some(
+                SliceRange {
+                    start: SliceIndex::FromStart(0),
+                    end: None,
+                    step: Step::Forward(NonZeroUsize::MIN),
+                }
+                .into()
+            )
         );
         assert_eq!(args.files, vec![PathBuf::from("text.txt")]);
     }
@@ -278,12 +643,15 @@ mod tests {
         let args = Args::parse_from(["slice", "-b", "0::1", "text.txt"]);
         assert!(args.bytes);
         assert_eq!(
-            args.range,
-            Some(SliceRange {
-                start: SliceIndex::FromStart(0),
-                end: None,
-                step: Step::forward(1),
-            })
+            range(&args),
+            Some(
+                SliceRange {
+                    start: SliceIndex::FromStart(0),
+                    end: None,
+                    step: Step::Forward(NonZeroUsize::MIN),
+                }
+                .into()
+            )
         );
         assert_eq!(args.files, vec![PathBuf::from("text.txt")]);
     }
@@ -338,24 +706,24 @@ mod tests {
         let tail = SliceRange {
             start: SliceIndex::FromEnd(NonZeroUsize::new(5).unwrap()),
             end: None,
-            step: Step::forward(1),
+            step: Step::Forward(NonZeroUsize::MIN),
         };
         let args = Args::parse_from(["slice", "-5:"]);
-        assert_eq!(args.range, Some(tail.clone()));
+        assert_eq!(range(&args), Some(tail.clone().into()));
 
         let args = Args::parse_from(["slice", "-l", "-5:", "text.txt"]);
         assert!(args.lines);
-        assert_eq!(args.range, Some(tail.clone()));
+        assert_eq!(range(&args), Some(tail.clone().into()));
         assert_eq!(args.files, vec![PathBuf::from("text.txt")]);
 
         let args = Args::parse_from(["slice", "-5:", "-l", "text.txt"]);
         assert!(args.lines);
-        assert_eq!(args.range, Some(tail.clone()));
+        assert_eq!(range(&args), Some(tail.clone().into()));
         assert_eq!(args.files, vec![PathBuf::from("text.txt")]);
 
         let args = Args::parse_from(["slice", "--explain", "-5:"]);
         assert!(args.explain);
-        assert_eq!(args.range, Some(tail.clone()));
+        assert_eq!(range(&args), Some(tail.clone().into()));
     }
 
     #[test]
@@ -363,12 +731,15 @@ mod tests {
         let args = Args::parse_from(["slice", "--explain", "10:20"]);
         assert!(args.explain);
         assert_eq!(
-            args.range,
-            Some(SliceRange {
-                start: SliceIndex::FromStart(10),
-                end: Some(SliceIndex::FromStart(20)),
-                step: Step::forward(1),
-            })
+            range(&args),
+            Some(
+                SliceRange {
+                    start: SliceIndex::FromStart(10),
+                    end: Some(SliceIndex::FromStart(20)),
+                    step: Step::Forward(NonZeroUsize::MIN),
+                }
+                .into()
+            )
         );
     }
 
@@ -415,6 +786,87 @@ mod tests {
         assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
     }
 
+    #[test]
+    fn record_size_is_a_mode() {
+        let args = Args::parse_from(["slice", "--record-size", "4KiB", "1000:2000"]);
+        assert_eq!(args.record_size(), NonZeroUsize::new(4096));
+        for flag in [
+            "--record-size=0",
+            "--fields=1:",
+            "--keep-header",
+            "--index",
+            "-z",
+        ] {
+            let args = ["slice", "--record-size=128", flag, "0:1", "a.bin"];
+            assert!(Args::try_parse_from(args).is_err(), "{flag}");
+        }
+        assert!(Args::try_parse_from(["slice", "--record-size=8", "-n", "::-1"]).is_ok());
+    }
+
+    #[test]
+    fn sized_ranges_only_read_as_bytes() {
+        let mut args = Args::parse_from(["slice", "-b", "1MiB:+0x10"]);
+        let sized = args.range().unwrap().expect("a range");
+        assert_eq!(sized.get(false), None);
+        assert_eq!(
+            sized.get(true),
+            Some(&SliceRanges::from_str("1048576:1048592").unwrap())
+        );
+
+        let mut args = Args::parse_from(["slice", "10:20"]);
+        let plain = args.range().unwrap().expect("a range");
+        assert_eq!(plain.get(false), plain.get(true));
+
+        let mut args = Args::parse_from(["slice", "-b", "4Q:"]);
+        assert!(args.range().is_err());
+    }
+
+    #[test]
+    fn max_memory_parses_size() {
+        let args = Args::parse_from(["slice", "--max-memory", "64MiB", "::-1"]);
+        assert_eq!(args.max_memory(), Some(64 * 1024 * 1024));
+        let args = Args::parse_from(["slice", "::-1"]);
+        assert_eq!(args.max_memory(), None);
+    }
+
+    #[test]
+    fn fields_default_the_rows_to_all() {
+        let mut args = Args::parse_from(["slice", "--fields", "2:5", "data.tsv", "b.tsv"]);
+        assert_eq!(
+            args.range().unwrap(),
+            Some(RangeArg::from_str(":").unwrap())
+        );
+        assert_eq!(
+            args.files,
+            [PathBuf::from("data.tsv"), PathBuf::from("b.tsv")]
+        );
+        let mut args = Args::parse_from(["slice", "--fields", "2:5", "1:", "data.tsv"]);
+        assert_eq!(
+            args.range().unwrap(),
+            Some(RangeArg::from_str("1:").unwrap())
+        );
+        assert_eq!(args.files, [PathBuf::from("data.tsv")]);
+        // Without --fields, a range that does not parse is still an error.
+        let mut args = Args::parse_from(["slice", "data.tsv"]);
+        assert_eq!(
+            args.range().unwrap_err().kind(),
+            clap::error::ErrorKind::ValueValidation
+        );
+    }
+
+    #[test]
+    fn numbering_follows_the_flags() {
+        let args = Args::parse_from(["slice", "0:"]);
+        assert_eq!(args.first_number(), None);
+        assert!(!args.offsets);
+        let args = Args::parse_from(["slice", "-n", "--offsets", "0:"]);
+        assert_eq!(args.first_number(), Some(0));
+        assert!(args.offsets);
+        let args = Args::parse_from(["slice", "-n", "--one-based", "0:"]);
+        assert_eq!(args.first_number(), Some(1));
+        assert!(Args::try_parse_from(["slice", "--one-based", "0:"]).is_err());
+    }
+
     #[test]
     fn delimiter_null() {
         let args = Args::parse_from(["slice", "-z", "0:"]);
@@ -478,6 +930,153 @@ mod tests {
         assert_eq!(args.delimiter().unwrap(), None);
     }
 
+    #[test]
+    fn field_delimiter_always_escaped() {
+        let args = Args::parse_from(["slice", "--fields", "1:", "--field-delimiter", "\\t", "0:"]);
+        assert_eq!(args.field_delimiter().unwrap(), Some(b"\t".to_vec()));
+        let args = Args::parse_from(["slice", "--fields", "1:", "--field-delimiter", ",", "0:"]);
+        assert_eq!(args.field_delimiter().unwrap(), Some(b",".to_vec()));
+    }
+
+    #[test]
+    fn field_delimiter_rejects_empty() {
+        let args = Args::parse_from(["slice", "--fields", "1:", "--field-delimiter", "", "0:"]);
+        assert!(args.field_delimiter().is_err());
+    }
+
+    #[test]
+    fn field_delimiter_requires_fields() {
+        assert!(Args::try_parse_from(["slice", "--field-delimiter", ",", "0:"]).is_err());
+    }
+
+    #[test]
+    fn fields_accept_tail_relative_ranges() {
+        let args = Args::parse_from(["slice", "10:20", "--fields", "-2:"]);
+        assert_eq!(args.fields, Some(SliceRanges::from_str("-2:").unwrap()));
+        assert_eq!(range(&args), Some(SliceRanges::from_str("10:20").unwrap()));
+    }
+
+    #[test]
+    fn fields_need_a_record_mode() {
+        for mode in ["-b", "--chars", "--graphemes"] {
+            assert!(Args::try_parse_from(["slice", mode, "--fields", "1:", "0:"]).is_err());
+        }
+        assert!(Args::try_parse_from(["slice", "-z", "--fields", "1:", "0:"]).is_ok());
+    }
+
+    #[test]
+    fn csv_is_a_mode() {
+        assert!(Args::try_parse_from(["slice", "--csv", "-b", "0:"]).is_err());
+        assert!(Args::try_parse_from(["slice", "--csv", "--delimiter", ",", "0:"]).is_err());
+        assert!(Args::try_parse_from(["slice", "--csv", "--keep-header", "0:"]).is_ok());
+    }
+
+    #[test]
+    fn delimiter_regex_is_a_mode() {
+        let regex = |flags: &[&str]| {
+            Args::try_parse_from(
+                ["slice", "--delimiter-regex", r"\n\s*\n"]
+                    .iter()
+                    .chain(flags),
+            )
+        };
+        assert!(regex(&["0:"]).is_ok());
+        assert!(regex(&["--delimiter", ",", "0:"]).is_err());
+        for flag in ["--fields=1:", "--keep-header", "--index", "--follow"] {
+            assert!(regex(&[flag, "0:", "a.txt"]).is_err(), "{flag}");
+        }
+        for pattern in ["(", "", "x*", r"\b"] {
+            assert!(
+                Args::try_parse_from(["slice", "--delimiter-regex", pattern, "0:"]).is_err(),
+                "{pattern}"
+            );
+        }
+    }
+
+    #[test]
+    fn paragraphs_is_a_mode() {
+        let paragraphs =
+            |flags: &[&str]| Args::try_parse_from(["slice", "--paragraphs"].iter().chain(flags));
+        assert!(paragraphs(&["-1:"]).is_ok());
+        assert!(paragraphs(&["--index", "0:", "a.txt"]).is_ok());
+        assert!(paragraphs(&["--csv", "0:"]).is_err());
+        for flag in [
+            "--fields=1:",
+            "--keep-header",
+            "--follow",
+            "--translate=sed",
+        ] {
+            assert!(paragraphs(&[flag, "0:", "a.txt"]).is_err(), "{flag}");
+        }
+    }
+
+    #[test]
+    fn words_and_sentences_are_modes() {
+        for mode in ["--words", "--sentences"] {
+            let parse = |flags: &[&str]| Args::try_parse_from(["slice", mode].iter().chain(flags));
+            assert!(parse(&["::-1"]).is_ok(), "{mode}");
+            assert!(parse(&["--graphemes", "0:"]).is_err(), "{mode}");
+            for flag in [
+                "--fields=1:",
+                "--keep-header",
+                "--follow",
+                "--index",
+                "--translate=sed",
+            ] {
+                assert!(parse(&[flag, "0:", "a.txt"]).is_err(), "{mode} {flag}");
+            }
+        }
+        assert!(Args::try_parse_from(["slice", "--words", "--sentences", "0:"]).is_err());
+    }
+
+    #[test]
+    fn columns_is_a_mode() {
+        let columns =
+            |flags: &[&str]| Args::try_parse_from(["slice", "--columns"].iter().chain(flags));
+        assert!(columns(&[":80"]).is_ok());
+        assert!(columns(&["--tab-width", "4", "-n", ":80", "a.txt"]).is_ok());
+        assert!(columns(&["--chars", ":80"]).is_err());
+        assert!(columns(&["--tab-width", "0", ":80"]).is_err());
+        for flag in [
+            "--fields=1:",
+            "--keep-header",
+            "--complement",
+            "--index",
+            "--translate=sed",
+        ] {
+            assert!(columns(&[flag, ":80", "a.txt"]).is_err(), "{flag}");
+        }
+        assert!(Args::try_parse_from(["slice", "--tab-width", "4", ":80"]).is_err());
+    }
+
+    #[test]
+    fn ansi_composes_with_element_modes() {
+        let ansi = |flags: &[&str]| Args::try_parse_from(["slice", "--ansi"].iter().chain(flags));
+        for mode in ["--chars", "--graphemes", "-b", "-z", "--csv", "--words"] {
+            assert!(ansi(&[mode, ":10"]).is_ok(), "{mode}");
+        }
+        assert!(ansi(&["-n", "-10:"]).is_ok());
+        for flag in [
+            "--paragraphs",
+            "--fields=1:",
+            "--columns",
+            "--keep-header",
+            "--follow",
+            "--index",
+            "--translate=sed",
+        ] {
+            assert!(ansi(&[flag, ":10", "a.txt"]).is_err(), "{flag}");
+        }
+    }
+
+    #[test]
+    fn keep_header_needs_a_record_mode() {
+        for mode in ["-b", "--chars", "--graphemes"] {
+            assert!(Args::try_parse_from(["slice", mode, "--keep-header", "0:"]).is_err());
+        }
+        assert!(Args::try_parse_from(["slice", "-z", "--keep-header", "0:"]).is_ok());
+    }
+
     #[test]
     fn mode_flags_are_mutually_exclusive() {
         assert!(Args::try_parse_from(["slice", "-z", "-b", "0:"]).is_err());
@@ -494,7 +1093,7 @@ mod tests {
     fn generate_parses_without_range() {
         let args = Args::parse_from(["slice", "--generate", "man"]);
         assert_eq!(args.generate, Some(Generate::Man));
-        assert_eq!(args.range, None);
+        assert_eq!(range(&args), None);
     }
 
     #[test]
@@ -567,22 +1166,28 @@ mod tests {
         let args = Args::parse_from(["slice", "--translate", "5:10"]);
         assert!(args.translate.is_some());
         assert_eq!(
-            args.range,
-            Some(SliceRange {
-                start: SliceIndex::FromStart(5),
-                end: Some(SliceIndex::FromStart(10)),
-                step: Step::forward(1),
-            })
+            range(&args),
+            Some(
+                SliceRange {
+                    start: SliceIndex::FromStart(5),
+                    end: Some(SliceIndex::FromStart(10)),
+                    step: Step::Forward(NonZeroUsize::MIN),
+                }
+                .into()
+            )
         );
         let args = Args::parse_from(["slice", "--translate", "-5:"]);
         assert!(args.translate.is_some());
         assert_eq!(
-            args.range,
-            Some(SliceRange {
-                start: SliceIndex::FromEnd(NonZeroUsize::new(5).unwrap()),
-                end: None,
-                step: Step::forward(1),
-            })
+            range(&args),
+            Some(
+                SliceRange {
+                    start: SliceIndex::FromEnd(NonZeroUsize::new(5).unwrap()),
+                    end: None,
+                    step: Step::Forward(NonZeroUsize::MIN),
+                }
+                .into()
+            )
         );
     }
 
@@ -0,0 +1,115 @@
+//! `--decompress`: input whose first bytes are the magic number of a gzip,
+//! zstd, xz, or bzip2 stream is sliced as the stream it decompresses to.
+//! Each codec is a cargo feature of its own; a stream of a codec left out of
+//! the build is an error rather than sliced as the compressed bytes.
+
+use std::io::{self, BufRead, Read};
+
+/// A compression format recognised by its magic number.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Codec {
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Codec {
+    /// The codec `input` starts with, looked up in what its buffer already
+    /// holds, so nothing is consumed: plain input reads on from its first
+    /// byte, and a regular file keeps its seekable reader.
+    pub(crate) fn sniff<R: BufRead>(input: &mut R) -> io::Result<Option<Self>> {
+        Ok(match input.fill_buf()? {
+            [0x1f, 0x8b, ..] => Some(Self::Gzip),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Self::Zstd),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(Self::Xz),
+            [b'B', b'Z', b'h', b'1'..=b'9', ..] => Some(Self::Bzip2),
+            _ => None,
+        })
+    }
+
+    /// The cargo feature that builds the codec in.
+    fn feature(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Xz => "xz",
+            Self::Bzip2 => "bzip2",
+        }
+    }
+
+    /// What `input` decompresses to. Concatenated streams, as `cat a.gz
+    /// b.gz` makes, decompress to their concatenated contents, as their own
+    /// tools give them.
+    pub(crate) fn decoder<'a, R: BufRead + 'a>(self, input: R) -> io::Result<Box<dyn Read + 'a>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Ok(Box::new(flate2::bufread::MultiGzDecoder::new(input))),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(Box::new(zstd::Decoder::with_buffer(input)?)),
+            #[cfg(feature = "xz")]
+            Self::Xz => Ok(Box::new(liblzma::bufread::XzDecoder::new_multi_decoder(
+                input,
+            ))),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Ok(Box::new(bzip2::bufread::MultiBzDecoder::new(input))),
+            #[allow(unreachable_patterns)]
+            codec => {
+                drop(input);
+                Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "{0} input needs slice built with the `{0}` feature",
+                        codec.feature()
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_magic_numbers() {
+        let cases: [(&[u8], Option<Codec>); 7] = [
+            (b"\x1f\x8b\x08\x00", Some(Codec::Gzip)),
+            (b"\x28\xb5\x2f\xfd\x00", Some(Codec::Zstd)),
+            (b"\xfd7zXZ\x00\x00", Some(Codec::Xz)),
+            (b"BZh91AY&SY", Some(Codec::Bzip2)),
+            // A bzip2 block size is a digit from 1 to 9.
+            (b"BZh0", None),
+            (b"\x1f", None),
+            (b"", None),
+        ];
+        for (input, codec) in cases {
+            let mut reader = io::BufReader::new(input);
+            assert_eq!(Codec::sniff(&mut reader).unwrap(), codec, "{input:?}");
+            // Sniffing consumes nothing.
+            assert_eq!(reader.fill_buf().unwrap(), input);
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn concatenated_gzip_members_decompress_whole() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut input = Vec::new();
+        for part in [&b"a\nb\n"[..], b"c\n"] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(part).unwrap();
+            input.extend(encoder.finish().unwrap());
+        }
+        let mut out = Vec::new();
+        Codec::Gzip
+            .decoder(&input[..])
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"a\nb\nc\n");
+    }
+}
@@ -1,12 +1,43 @@
+mod annotate;
+mod ansi;
+#[cfg(feature = "tokio")]
+mod async_buf_read;
+mod backward;
 mod buf_read;
+mod column;
+mod field;
 mod grapheme;
 mod iterator;
+mod paragraph;
+mod segment;
+mod spool;
 mod utf8;
 
+pub(crate) use annotate::{Annotated, Annotation};
+pub(crate) use ansi::{Ansi, AnsiWriter};
+#[cfg(feature = "tokio")]
+pub(crate) use async_buf_read::{
+    byte_mode_async, slice_lag_async, slice_stepped_async, slice_tail_async, slice_window_async,
+    AsyncChars, AsyncSplit,
+};
+pub(crate) use backward::{char_starts, delimited_starts, scans_backward};
 pub(crate) use buf_read::{
-    read_all_with_record_limit, slice_lag, slice_lag_with_record_limit, slice_stepped, slice_tail,
-    slice_tail_with_record_limit, slice_window, Byte, Bytes,
+    chunk_spans, read_all_with_record_limit, slice_anchored, slice_gap, slice_lag,
+    slice_lag_with_record_limit, slice_stepped, slice_tail, slice_tail_with_record_limit,
+    slice_union, slice_window,
 };
-pub(crate) use grapheme::{GraphemeElements, Graphemes};
+pub use buf_read::{Byte, Bytes, Csv, DelimiterRegex, EachByte, FixedSize, RegexDelimited, Split};
+pub(crate) use column::Columns;
+pub use field::FieldSeparator;
+pub(crate) use field::{FieldWriter, Fields, Rewrite};
+pub(crate) use grapheme::GraphemeElements;
+pub use grapheme::Graphemes;
 pub(crate) use iterator::IteratorExt;
+pub use iterator::{Chunked, Chunks, Selected, SliceChunks};
+pub use paragraph::Paragraphs;
+pub(crate) use paragraph::{paragraph_spans, ParagraphSpans};
+pub(crate) use segment::{SegmentElements, Unit};
+pub use segment::{Sentences, Words};
+pub(crate) use spool::{ChunkSpans, ChunkSpansWriter, Offsets, OffsetsWriter, Spool, SpoolWriter};
+pub use utf8::Chars;
 pub(crate) use utf8::{char_lag, char_stepped, char_tail, char_window, Utf8Elements};
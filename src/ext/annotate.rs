@@ -0,0 +1,261 @@
+//! `-n`/`--offsets`: a prefix naming where each printed element came from.
+//! Forward drivers get it from [`Annotated`], a split that prefixes every
+//! chunk it reads, so a chunk held back — in the tail ring, a lag or union
+//! queue — carries its prefix along; the reverse walk writes it from the
+//! element spans it already has.
+
+use crate::ext::Split;
+use std::io::{self, BufRead, Write};
+
+/// What precedes each element: its index (`-n`), then the byte offset where
+/// it starts in the input (`--offsets`), each followed by a colon, as in
+/// `grep -nb`.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub(crate) struct Annotation {
+    /// The number the first element gets: 0, or 1 with `--one-based`.
+    pub(crate) number: Option<u64>,
+    pub(crate) offsets: bool,
+    /// The index of the element the input stands at, and the offset where it
+    /// starts: past a kept header, or where an index seek landed.
+    pub(crate) index: u64,
+    pub(crate) offset: u64,
+}
+
+impl Annotation {
+    /// The same prefix, counted from element `index` at `offset`.
+    #[inline]
+    pub(crate) fn at(self, index: u64, offset: u64) -> Self {
+        Self {
+            index,
+            offset,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.number.is_none() && !self.offsets
+    }
+
+    #[inline]
+    fn fields(&self) -> usize {
+        usize::from(self.number.is_some()) + usize::from(self.offsets)
+    }
+
+    /// The prefix of the element `index` elements and `offset` bytes past
+    /// where the input stands.
+    pub(crate) fn write<W: Write + ?Sized>(
+        &self,
+        output: &mut W,
+        index: u64,
+        offset: u64,
+    ) -> io::Result<()> {
+        if let Some(first) = self.number {
+            write!(output, "{}:", first + self.index + index)?;
+        }
+        if self.offsets {
+            write!(output, "{}:", self.offset + offset)?;
+        }
+        Ok(())
+    }
+
+    /// The length of the prefix `chunk` begins with; `None` while the bytes
+    /// at hand end inside it.
+    pub(crate) fn prefix_len(&self, chunk: &[u8]) -> Option<usize> {
+        let mut len = 0;
+        for _ in 0..self.fields() {
+            len += memchr::memchr(b':', &chunk[len..])? + 1;
+        }
+        Some(len)
+    }
+
+    /// Split a prefixed element into its prefix and the element itself.
+    #[inline]
+    pub(crate) fn split_prefix<'c>(&self, chunk: &'c [u8]) -> (&'c [u8], &'c [u8]) {
+        chunk.split_at(self.prefix_len(chunk).unwrap_or(0))
+    }
+}
+
+/// A split whose chunks come out prefixed with their annotation, counted
+/// from where the annotation says the input stands.
+pub(crate) struct Annotated<S> {
+    split: S,
+    annotation: Annotation,
+    /// Chunks and bytes read or skipped so far.
+    index: u64,
+    offset: u64,
+}
+
+impl<S> Annotated<S> {
+    #[inline]
+    pub(crate) fn new(split: S, annotation: Annotation) -> Self {
+        Self {
+            split,
+            annotation,
+            index: 0,
+            offset: 0,
+        }
+    }
+
+    #[inline]
+    fn advance(&mut self, len: usize) -> usize {
+        if len > 0 {
+            self.index += 1;
+            self.offset += len as u64;
+        }
+        len
+    }
+}
+
+/// Writes the prefix before the first byte of the chunk, so the end of the
+/// stream (a read of nothing) writes no prefix either.
+struct Prefixed<'a, W: ?Sized> {
+    inner: &'a mut W,
+    prefix: Option<(Annotation, u64, u64)>,
+}
+
+impl<W: Write + ?Sized> Write for Prefixed<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some((annotation, index, offset)) = self.prefix.take() {
+            annotation.write(self.inner, index, offset)?;
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: Split> Split for Annotated<S> {
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        let mut w = Prefixed {
+            inner: w,
+            prefix: Some((self.annotation, self.index, self.offset)),
+        };
+        let len = self.split.read_to(r, &mut w)?;
+        Ok(self.advance(len))
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        let len = self.split.skip(r)?;
+        Ok(self.advance(len))
+    }
+
+    /// The bulk skip reports chunks, not bytes, so it serves only when no
+    /// offset needs counting.
+    fn skip_n<R: BufRead + ?Sized>(&mut self, r: &mut R, n: usize) -> io::Result<usize> {
+        if !self.annotation.offsets {
+            let skipped = self.split.skip_n(r, n)?;
+            self.index += skipped as u64;
+            return Ok(skipped);
+        }
+        for skipped in 0..n {
+            if self.skip(r)? == 0 {
+                return Ok(skipped);
+            }
+        }
+        Ok(n)
+    }
+
+    /// Every chunk needs its own prefix, so the rest is no longer one span;
+    /// whatever the split still holds past its last chunk goes out after.
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        let mut copied = 0;
+        loop {
+            match self.read_to(r, w)? {
+                0 => return Ok(copied + self.split.copy_rest(r, w)?),
+                len => copied += len as u64,
+            }
+        }
+    }
+
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        self.split.content(self.annotation.split_prefix(chunk).1)
+    }
+
+    #[inline]
+    fn terminated(&self, chunk: &[u8]) -> bool {
+        self.split.terminated(self.annotation.split_prefix(chunk).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::{slice_tail, slice_window, Byte, Chars};
+    use std::num::NonZeroUsize;
+
+    const BOTH: Annotation = Annotation {
+        number: Some(0),
+        offsets: true,
+        index: 0,
+        offset: 0,
+    };
+
+    #[test]
+    fn prefixes_index_then_offset() {
+        let mut out = Vec::new();
+        let split = Annotated::new(Byte(b'\n'), BOTH);
+        slice_window(split, &b"a\nbb\nc\nd"[..], &mut out, 1, None).unwrap();
+        assert_eq!(out, b"1:2:bb\n2:5:c\n3:7:d");
+    }
+
+    #[test]
+    fn one_based_numbers_without_offsets() {
+        let annotation = Annotation {
+            number: Some(1),
+            ..Annotation::default()
+        };
+        let mut out = Vec::new();
+        let split = Annotated::new(Byte(b'\n'), annotation);
+        slice_window(split, &b"a\nb\nc\n"[..], &mut out, 1, Some(2)).unwrap();
+        assert_eq!(out, b"2:b\n");
+    }
+
+    #[test]
+    fn a_held_chunk_keeps_its_prefix() {
+        let mut out = Vec::new();
+        let split = Annotated::new(Chars::new(), BOTH);
+        let back = NonZeroUsize::new(2).unwrap();
+        slice_tail(
+            split,
+            "aéb".as_bytes(),
+            &mut out,
+            back,
+            None,
+            NonZeroUsize::MIN,
+        )
+        .unwrap();
+        assert_eq!(out, "1:1:é2:3:b".as_bytes());
+    }
+
+    #[test]
+    fn counting_starts_where_the_split_does() {
+        let mut out = Vec::new();
+        let split = Annotated::new(Byte(b'\n'), BOTH.at(10, 100));
+        slice_window(split, &b"x\ny\n"[..], &mut out, 1, None).unwrap();
+        assert_eq!(out, b"11:102:y\n");
+    }
+
+    #[test]
+    fn content_excludes_the_prefix() {
+        let split = Annotated::new(Byte(b'\n'), BOTH);
+        assert_eq!(split.content(b"3:12:a:b\n"), b"a:b");
+        assert!(split.terminated(b"3:12:a:b\n"));
+        assert!(!split.terminated(b"3:12:a:b"));
+    }
+}
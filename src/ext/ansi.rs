@@ -0,0 +1,872 @@
+//! `--ansi`: terminal escape sequences take no element. CSI sequences
+//! (`ESC [ … final`), OSC strings (`ESC ] … BEL` or `ESC ] … ESC \`) and the
+//! short `ESC …` forms are cut out of what the split sees and put back, whole
+//! and verbatim, in front of the visible byte they preceded; escapes after the
+//! last element go out only with a slice that runs to the end. Colors follow
+//! the elements: [`Ansi`] marks each element with the SGR state in effect
+//! where it starts, however the elements around it were sliced away, and
+//! [`AnsiWriter`] turns the marks into what it takes to get there from the
+//! output's state — nothing, inside a run of adjacent elements — and resets
+//! the state once the output ends. The input's own sequences always go out
+//! as they came in.
+
+use crate::ext::Split;
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Read, Write},
+};
+
+const ESC: u8 = 0x1b;
+const RESET: &[u8] = b"\x1b[0m";
+/// How [`Ansi`] marks the state an element starts in for [`AnsiWriter`]: a
+/// CSI with a private `=` where SGR parameters would be, which no SGR the
+/// input holds can be taken for.
+const MARK: &[u8] = b"\x1b[=";
+
+/// Where an escape sequence's parse stands after its last byte.
+#[derive(Clone, Copy, Debug)]
+enum Parse {
+    /// The `ESC` alone.
+    Esc,
+    /// `ESC [`: parameters and intermediates up to a final byte.
+    Csi,
+    /// `ESC ]`: a string up to `BEL` or `ESC \`.
+    Osc,
+    /// An `ESC` inside an OSC string.
+    OscEsc,
+    /// `ESC` and intermediates up to a final byte.
+    Intermediate,
+}
+
+/// Where a byte leaves the sequence.
+enum Step {
+    /// Inside it, more to come.
+    More,
+    /// Its last byte.
+    Done,
+    /// Past its end: the sequence ended with the byte before.
+    Past,
+}
+
+impl Parse {
+    fn step(&mut self, byte: u8) -> Step {
+        match (*self, byte) {
+            (Parse::Esc, b'[') => *self = Parse::Csi,
+            (Parse::Esc, b']') => *self = Parse::Osc,
+            (Parse::Esc | Parse::Intermediate, 0x20..=0x2f) => *self = Parse::Intermediate,
+            (Parse::Esc | Parse::Intermediate, 0x30..=0x7e) => return Step::Done,
+            (Parse::Csi, 0x20..=0x3f) => {}
+            (Parse::Csi, 0x40..=0x7e) => return Step::Done,
+            (Parse::Osc, 0x07) => return Step::Done,
+            (Parse::Osc, ESC) => *self = Parse::OscEsc,
+            (Parse::Osc, _) => {}
+            (Parse::OscEsc, b'\\') => return Step::Done,
+            // A malformed sequence ends before the byte that cannot continue
+            // it, which is then read as text.
+            _ => return Step::Past,
+        }
+        Step::More
+    }
+}
+
+/// The graphic state the SGR (`ESC [ … m`) sequences set: for each
+/// attribute, the parameter that last set it (`1`, `38;5;196`, `4:3`), or
+/// nothing while it is off; codes it does not know are kept as last given,
+/// one each. However many sequences led to it, the state restates in one
+/// sequence of bounded length.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+struct Sgr {
+    attributes: [Vec<u8>; ATTRIBUTES],
+    other: Vec<(u16, Vec<u8>)>,
+}
+
+const ATTRIBUTES: usize = 17;
+const FOREGROUND: usize = 12;
+const BACKGROUND: usize = 13;
+const UNDERLINE_COLOR: usize = 14;
+
+/// What one SGR parameter does to the state.
+enum Effect {
+    Reset,
+    /// Sets the attribute, replacing what set it before.
+    Set(usize),
+    /// Turns the attributes off.
+    Unset(&'static [usize]),
+    /// A code it does not know, kept as given.
+    Other(u16),
+    /// A code too large to be one.
+    Ignore,
+}
+
+impl Effect {
+    fn of(code: u16) -> Self {
+        match code {
+            0 => Effect::Reset,
+            1 => Effect::Set(0),
+            2 => Effect::Set(1),
+            // Fraktur is the italic of old.
+            3 | 20 => Effect::Set(2),
+            4 | 21 => Effect::Set(3),
+            5 | 6 => Effect::Set(4),
+            7 => Effect::Set(5),
+            8 => Effect::Set(6),
+            9 => Effect::Set(7),
+            10 => Effect::Unset(&[8]),
+            11..=19 => Effect::Set(8),
+            22 => Effect::Unset(&[0, 1]),
+            23 => Effect::Unset(&[2]),
+            24 => Effect::Unset(&[3]),
+            25 => Effect::Unset(&[4]),
+            26 => Effect::Set(9),
+            27 => Effect::Unset(&[5]),
+            28 => Effect::Unset(&[6]),
+            29 => Effect::Unset(&[7]),
+            30..=38 | 90..=97 => Effect::Set(FOREGROUND),
+            39 => Effect::Unset(&[FOREGROUND]),
+            40..=48 | 100..=107 => Effect::Set(BACKGROUND),
+            49 => Effect::Unset(&[BACKGROUND]),
+            50 => Effect::Unset(&[9]),
+            51 | 52 => Effect::Set(10),
+            53 => Effect::Set(11),
+            54 => Effect::Unset(&[10]),
+            55 => Effect::Unset(&[11]),
+            58 => Effect::Set(UNDERLINE_COLOR),
+            59 => Effect::Unset(&[UNDERLINE_COLOR]),
+            60..=64 => Effect::Set(15),
+            65 => Effect::Unset(&[15]),
+            73 | 74 => Effect::Set(16),
+            75 => Effect::Unset(&[16]),
+            code => Effect::Other(code),
+        }
+    }
+}
+
+impl Sgr {
+    /// Apply `escape`; returns whether it was an SGR sequence.
+    fn apply(&mut self, escape: &[u8]) -> bool {
+        let Some(params) = escape
+            .strip_prefix(b"\x1b[")
+            .and_then(|rest| rest.strip_suffix(b"m"))
+            .filter(|params| params.iter().all(|&b| matches!(b, b'0'..=b';')))
+        else {
+            return false;
+        };
+        let mut params = params.split(|&b| b == b';');
+        while let Some(param) = params.next() {
+            // Subparameters (`4:3`, `38:2::1:2:3`) ride along with the code.
+            let digits = param.split(|&b| b == b':').next().unwrap_or_default();
+            // Only digits are left, so a code that does not parse overflowed.
+            let code = match digits {
+                b"" => Some(0),
+                digits => std::str::from_utf8(digits)
+                    .ok()
+                    .and_then(|it| it.parse().ok()),
+            };
+            let mut param = param.to_vec();
+            // The `;` color forms take the parameters after them too:
+            // `38;5;n` one, `38;2;r;g;b` three.
+            if matches!(code, Some(38 | 48 | 58)) && !param.contains(&b':') {
+                let more = match params.next() {
+                    Some(kind) => {
+                        param.push(b';');
+                        param.extend_from_slice(kind);
+                        if kind == b"2" {
+                            3
+                        } else {
+                            1
+                        }
+                    }
+                    None => 0,
+                };
+                for part in params.by_ref().take(more) {
+                    param.push(b';');
+                    param.extend_from_slice(part);
+                }
+            }
+            match code.map_or(Effect::Ignore, Effect::of) {
+                Effect::Reset => *self = Sgr::default(),
+                Effect::Set(attribute) => self.attributes[attribute] = param,
+                Effect::Unset(attributes) => {
+                    for &attribute in attributes {
+                        self.attributes[attribute].clear();
+                    }
+                }
+                Effect::Other(code) => match self.other.iter_mut().find(|(it, _)| *it == code) {
+                    Some((_, given)) => *given = param,
+                    None => self.other.push((code, param)),
+                },
+                Effect::Ignore => {}
+            }
+        }
+        true
+    }
+
+    /// The state an [`Ansi`] mark gives, if `escape` is one.
+    fn marked(escape: &[u8]) -> Option<Sgr> {
+        let params = escape.strip_prefix(MARK)?;
+        let mut sgr = Sgr::default();
+        sgr.apply(&[b"\x1b[", params].concat()).then_some(sgr)
+    }
+
+    #[inline]
+    fn is_reset(&self) -> bool {
+        self.attributes.iter().all(Vec::is_empty) && self.other.is_empty()
+    }
+
+    /// The parameters set going from `from` to `self`, when nothing turns
+    /// off.
+    fn since(&self, from: &Sgr) -> Option<Sgr> {
+        let mut change = Sgr::default();
+        let attributes = self.attributes.iter().zip(&from.attributes);
+        for (attribute, (now, before)) in attributes.enumerate() {
+            if now != before {
+                if now.is_empty() {
+                    return None;
+                }
+                change.attributes[attribute].clone_from(now);
+            }
+        }
+        let kept = self.other.get(..from.other.len())?;
+        if kept != from.other {
+            return None;
+        }
+        change.other = self.other[kept.len()..].to_vec();
+        Some(change)
+    }
+
+    /// The state's parameters, `;`-separated.
+    fn write_params<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        let attributes = self.attributes.iter().filter(|param| !param.is_empty());
+        let params = attributes.chain(self.other.iter().map(|(_, param)| param));
+        for (i, param) in params.enumerate() {
+            if i > 0 {
+                w.write_all(b";")?;
+            }
+            w.write_all(param)?;
+        }
+        Ok(())
+    }
+
+    /// The one sequence that sets the state from a reset, if any.
+    fn write_to<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        if self.is_reset() {
+            return Ok(());
+        }
+        w.write_all(b"\x1b[")?;
+        self.write_params(w)?;
+        w.write_all(b"m")
+    }
+}
+
+/// The escape sequences cut out of the input so far.
+#[derive(Default)]
+struct Scan {
+    /// The sequence being read, while the buffer ended inside it.
+    parse: Option<Parse>,
+    partial: Vec<u8>,
+    /// Complete sequences not yet put back, each with the count of visible
+    /// bytes before it.
+    queue: VecDeque<(u64, Vec<u8>)>,
+    /// Visible bytes consumed from the reader.
+    visible: u64,
+}
+
+impl Scan {
+    /// Read the sequence in progress on from `buf`, returning how many bytes
+    /// it took.
+    fn feed(&mut self, buf: &[u8]) -> usize {
+        let Some(parse) = &mut self.parse else {
+            return 0;
+        };
+        for (i, &byte) in buf.iter().enumerate() {
+            match parse.step(byte) {
+                Step::More => self.partial.push(byte),
+                Step::Done => {
+                    self.partial.push(byte);
+                    self.end();
+                    return i + 1;
+                }
+                Step::Past => {
+                    self.end();
+                    return i;
+                }
+            }
+        }
+        buf.len()
+    }
+
+    fn end(&mut self) {
+        if self.parse.take().is_some() {
+            let escape = std::mem::take(&mut self.partial);
+            self.queue.push_back((self.visible, escape));
+        }
+    }
+}
+
+/// The reader the wrapped split sees: the input minus its escape sequences,
+/// which go to the [`Scan`] queue.
+struct Visible<'a, R: ?Sized> {
+    inner: &'a mut R,
+    scan: &'a mut Scan,
+}
+
+impl<R: BufRead + ?Sized> Read for Visible<'_, R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let buf = self.fill_buf()?;
+        let len = buf.len().min(out.len());
+        out[..len].copy_from_slice(&buf[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead + ?Sized> BufRead for Visible<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let visible = loop {
+            let buf = self.inner.fill_buf()?;
+            if buf.is_empty() {
+                self.scan.end();
+                break 0;
+            }
+            if self.scan.parse.is_some() {
+                let used = self.scan.feed(buf);
+                self.inner.consume(used);
+                continue;
+            }
+            match memchr::memchr(ESC, buf) {
+                Some(0) => {
+                    self.scan.parse = Some(Parse::Esc);
+                    self.scan.partial.push(ESC);
+                    self.inner.consume(1);
+                }
+                Some(at) => break at,
+                None => break buf.len(),
+            }
+        };
+        // The same buffered bytes again, now that no branch holds them.
+        Ok(&self.inner.fill_buf()?[..visible])
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.scan.visible += amt as u64;
+    }
+}
+
+/// A split over the visible bytes of the input, whose chunks come out with
+/// their escape sequences put back and, unless [`Ansi::verbatim`], marked
+/// with the SGR state they start in. The wrapped split must emit its
+/// elements verbatim. The returned lengths count the sequences too, so they
+/// are the input's own.
+pub(crate) struct Ansi<S> {
+    split: S,
+    scan: Scan,
+    /// Visible bytes in the elements read or skipped so far.
+    pos: u64,
+    /// The graphic state where the next element starts.
+    state: Sgr,
+    mark: bool,
+    /// The visible bytes of the element being read.
+    chunk: Vec<u8>,
+}
+
+impl<S> Ansi<S> {
+    #[inline]
+    pub(crate) fn new(split: S) -> Self {
+        Self {
+            split,
+            scan: Scan::default(),
+            pos: 0,
+            state: Sgr::default(),
+            mark: true,
+            chunk: Vec::new(),
+        }
+    }
+
+    /// Without the state marks, so a chunk is exactly the input it covers:
+    /// for re-reading elements that already carry theirs.
+    #[inline]
+    pub(crate) fn verbatim(split: S) -> Self {
+        Self {
+            mark: false,
+            ..Self::new(split)
+        }
+    }
+
+    /// Take the sequences before visible byte `end`, writing each where it
+    /// stood among the element's visible bytes when `w` is given. Returns the
+    /// bytes they took.
+    fn put_back<W: Write + ?Sized>(
+        &mut self,
+        end: u64,
+        mut w: Option<&mut W>,
+    ) -> io::Result<usize> {
+        let start = self.pos;
+        let (mut taken, mut written) = (0, 0);
+        while let Some((at, _)) = self.scan.queue.front() {
+            if *at >= end {
+                break;
+            }
+            let (at, escape) = self.scan.queue.pop_front().expect("just seen");
+            if let Some(w) = w.as_deref_mut() {
+                let at = (at - start) as usize;
+                w.write_all(&self.chunk[written..at])?;
+                w.write_all(&escape)?;
+                written = at;
+            }
+            self.state.apply(&escape);
+            taken += escape.len();
+        }
+        if let Some(w) = w {
+            w.write_all(&self.chunk[written..])?;
+        }
+        self.pos = end;
+        Ok(taken)
+    }
+}
+
+impl<S: Split> Split for Ansi<S> {
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        self.chunk.clear();
+        let mut visible = Visible {
+            inner: r,
+            scan: &mut self.scan,
+        };
+        let len = self.split.read_to(&mut visible, &mut self.chunk)?;
+        if len == 0 {
+            return Ok(0);
+        }
+        debug_assert_eq!(len, self.chunk.len(), "the wrapped split is verbatim");
+        // Every element, as whether any sequence comes before it depends on
+        // how far the split read ahead; the output drops what is redundant.
+        if self.mark {
+            w.write_all(MARK)?;
+            self.state.write_params(w)?;
+            w.write_all(b"m")?;
+        }
+        let end = self.pos + len as u64;
+        Ok(len + self.put_back(end, Some(w))?)
+    }
+
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        let mut visible = Visible {
+            inner: r,
+            scan: &mut self.scan,
+        };
+        let len = self.split.skip(&mut visible)?;
+        if len == 0 {
+            return Ok(0);
+        }
+        let end = self.pos + len as u64;
+        Ok(len + self.put_back::<io::Sink>(end, None)?)
+    }
+
+    /// Each element starts in a state of its own, so the rest goes out
+    /// element by element, then the sequences after the last one.
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        let mut copied = 0;
+        while let len @ 1.. = self.read_to(r, w)? {
+            copied += len as u64;
+        }
+        for (_, escape) in self.scan.queue.drain(..) {
+            w.write_all(&escape)?;
+            self.state.apply(&escape);
+            copied += escape.len() as u64;
+        }
+        Ok(copied)
+    }
+
+    /// A `/pattern/` sees past the sequences a chunk starts with, state
+    /// mark included; a verbatim chunk's content starts where the chunk
+    /// does, as element spans measure it.
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        if self.mark {
+            self.split.content(&chunk[leading_escapes(chunk)..])
+        } else {
+            self.split.content(chunk)
+        }
+    }
+
+    #[inline]
+    fn terminated(&self, chunk: &[u8]) -> bool {
+        self.split.terminated(&chunk[leading_escapes(chunk)..])
+    }
+}
+
+/// The length of the complete escape sequences `chunk` starts with.
+fn leading_escapes(chunk: &[u8]) -> usize {
+    let mut pos = 0;
+    'sequences: while chunk.get(pos) == Some(&ESC) {
+        let mut parse = Parse::Esc;
+        for (i, &byte) in chunk[pos + 1..].iter().enumerate() {
+            match parse.step(byte) {
+                Step::More => {}
+                Step::Done => {
+                    pos += i + 2;
+                    continue 'sequences;
+                }
+                Step::Past => {
+                    pos += i + 1;
+                    continue 'sequences;
+                }
+            }
+        }
+        break;
+    }
+    pos
+}
+
+/// An output adapter that tracks the SGR state written so far. The
+/// sequences of the input pass through as they are; an [`Ansi`] state mark
+/// becomes the change from the state in effect to the one marked — nothing
+/// when they agree or the SGR sequences right after it get there on their
+/// own, the set parameters when nothing turns off, a reset and the whole
+/// state otherwise. `flush` ends the stream, as for `FieldWriter`: a state
+/// still set is reset. When disabled, writes pass straight through.
+pub(crate) struct AnsiWriter<W: Write> {
+    inner: W,
+    enabled: bool,
+    /// The state the output is in.
+    state: Sgr,
+    /// The state last marked and the SGR sequences since, until something
+    /// else follows them.
+    marked: Option<Marked>,
+    /// The sequence being written, while a write ended inside it.
+    parse: Option<Parse>,
+    escape: Vec<u8>,
+}
+
+impl<W: Write> AnsiWriter<W> {
+    #[inline]
+    pub(crate) fn new(inner: W, enabled: bool) -> Self {
+        Self {
+            inner,
+            enabled,
+            state: Sgr::default(),
+            marked: None,
+            parse: None,
+            escape: Vec::new(),
+        }
+    }
+
+    fn end_escape(&mut self) -> io::Result<()> {
+        if self.parse.take().is_some() {
+            if let Some(state) = Sgr::marked(&self.escape) {
+                self.settle()?;
+                self.marked = Some(Marked {
+                    target: state.clone(),
+                    direct: self.state.clone(),
+                    state,
+                    held: Vec::new(),
+                });
+            } else if let Some(marked) = &mut self.marked {
+                if marked.target.apply(&self.escape) {
+                    marked.direct.apply(&self.escape);
+                    marked.held.extend_from_slice(&self.escape);
+                } else {
+                    self.settle()?;
+                    self.inner.write_all(&self.escape)?;
+                }
+            } else {
+                self.inner.write_all(&self.escape)?;
+                self.state.apply(&self.escape);
+            }
+            self.escape.clear();
+        }
+        Ok(())
+    }
+
+    /// Bring the output to the state last marked, by setting what changed in
+    /// the one in effect or, when something turned off, by resetting it;
+    /// then write the SGR sequences held after the mark.
+    fn settle(&mut self) -> io::Result<()> {
+        let Some(marked) = self.marked.take() else {
+            return Ok(());
+        };
+        if marked.direct != marked.target && marked.state != self.state {
+            match marked.state.since(&self.state) {
+                Some(change) => change.write_to(&mut self.inner)?,
+                None => {
+                    self.inner.write_all(RESET)?;
+                    marked.state.write_to(&mut self.inner)?;
+                }
+            }
+        }
+        self.inner.write_all(&marked.held)?;
+        self.state = marked.target;
+        Ok(())
+    }
+}
+
+/// A state mark the output has not acted on yet.
+struct Marked {
+    state: Sgr,
+    /// The state after the SGR sequences held, from the one marked and from
+    /// the one in effect.
+    target: Sgr,
+    direct: Sgr,
+    held: Vec<u8>,
+}
+
+impl<W: Write> Write for AnsiWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.enabled {
+            return self.inner.write(buf);
+        }
+        let mut pos = 0;
+        while pos < buf.len() {
+            if let Some(parse) = &mut self.parse {
+                match parse.step(buf[pos]) {
+                    Step::More => {
+                        self.escape.push(buf[pos]);
+                        pos += 1;
+                    }
+                    Step::Done => {
+                        self.escape.push(buf[pos]);
+                        pos += 1;
+                        self.end_escape()?;
+                    }
+                    Step::Past => self.end_escape()?,
+                }
+                continue;
+            }
+            let text = memchr::memchr(ESC, &buf[pos..]).map_or(buf.len(), |at| pos + at);
+            if text > pos {
+                self.settle()?;
+                self.inner.write_all(&buf[pos..text])?;
+                pos = text;
+            } else {
+                self.parse = Some(Parse::Esc);
+                self.escape.push(ESC);
+                pos += 1;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.enabled {
+            self.end_escape()?;
+            self.settle()?;
+            if !self.state.is_reset() {
+                self.inner.write_all(RESET)?;
+            }
+            self.state = Sgr::default();
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::{slice_stepped, slice_tail, slice_window, Byte, Chars, Graphemes};
+    use std::num::NonZeroUsize;
+
+    const RED: &str = "\x1b[31m";
+    const OFF: &str = "\x1b[0m";
+
+    /// Run `drive` over `input` at several read capacities, through the
+    /// output adapter, and check they agree.
+    fn sliced(input: &str, drive: impl Fn(&mut dyn BufRead, &mut Vec<u8>)) -> String {
+        let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+            let mut reader = io::BufReader::with_capacity(capacity, input.as_bytes());
+            let mut out = Vec::new();
+            drive(&mut reader, &mut out);
+            let mut writer = AnsiWriter::new(Vec::new(), true);
+            writer.write_all(&out).unwrap();
+            writer.flush().unwrap();
+            String::from_utf8(writer.inner).unwrap()
+        });
+        let first = outputs.next().unwrap();
+        assert!(
+            outputs.all(|out| out == first),
+            "every capacity must produce the same slice"
+        );
+        first
+    }
+
+    fn chars(input: &str, start: usize, end: Option<usize>) -> String {
+        sliced(input, |r, w| {
+            slice_window(Ansi::new(Chars::new()), r, w, start, end).unwrap()
+        })
+    }
+
+    #[test]
+    fn sequences_take_no_element() {
+        let input = format!("ab{RED}cd{OFF}ef");
+        assert_eq!(chars(&input, 0, Some(4)), format!("ab{RED}cd{OFF}"));
+        assert_eq!(chars(&input, 0, Some(2)), "ab");
+    }
+
+    #[test]
+    fn the_state_is_restored_at_the_start_and_reset_at_the_end() {
+        let input = format!("ab{RED}cdef{OFF}gh");
+        assert_eq!(chars(&input, 3, Some(5)), format!("{RED}de{OFF}"));
+        assert_eq!(chars(&input, 6, None), format!("{OFF}gh"));
+    }
+
+    #[test]
+    fn skipped_state_changes_still_apply() {
+        let input = format!("a{RED}b\x1b[1mc{OFF}d");
+        let out = sliced(&input, |r, w| {
+            slice_stepped(
+                Ansi::new(Chars::new()),
+                r,
+                w,
+                0,
+                None,
+                NonZeroUsize::new(2).unwrap(),
+            )
+            .unwrap()
+        });
+        assert_eq!(out, format!("a{RED}\x1b[1mc{OFF}"));
+    }
+
+    #[test]
+    fn lines_keep_their_color_across_a_tail() {
+        let input = format!("{RED}one\ntwo\nthree{OFF}\nfour\n");
+        let out = sliced(&input, |r, w| {
+            let back = NonZeroUsize::new(3).unwrap();
+            slice_tail(Ansi::new(Byte(b'\n')), r, w, back, None, NonZeroUsize::MIN).unwrap()
+        });
+        assert_eq!(out, format!("{RED}two\nthree{OFF}\nfour\n"));
+    }
+
+    #[test]
+    fn graphemes_join_across_a_sequence() {
+        let input = format!("e{RED}\u{301}x");
+        let out = sliced(&input, |r, w| {
+            slice_window(Ansi::new(Graphemes::new()), r, w, 0, Some(1)).unwrap()
+        });
+        assert_eq!(out, format!("e{RED}\u{301}{OFF}"));
+    }
+
+    #[test]
+    fn osc_and_malformed_sequences_stay_whole() {
+        let link = "\x1b]8;;http://x\x1b\\";
+        let input = format!("{link}ab\x1b]0;t\x07c\x1b[1Xd\x1b[\u{1}e");
+        assert_eq!(chars(&input, 0, Some(1)), format!("{link}a"));
+        assert_eq!(chars(&input, 2, Some(3)), "\x1b]0;t\x07c");
+        assert_eq!(chars(&input, 3, Some(4)), "\x1b[1Xd");
+        // `ESC [` cut off by a control byte: the byte is text again.
+        assert_eq!(chars(&input, 4, None), "\x1b[\u{1}e");
+    }
+
+    #[test]
+    fn lengths_count_the_input() {
+        let input = format!("a{RED}b");
+        let mut ansi = Ansi::new(Chars::new());
+        let mut reader = input.as_bytes();
+        assert_eq!(ansi.skip(&mut reader).unwrap(), 1);
+        assert_eq!(ansi.read_to(&mut reader, &mut io::sink()).unwrap(), 6);
+        assert_eq!(ansi.read_to(&mut reader, &mut io::sink()).unwrap(), 0);
+    }
+
+    fn restated(sgr: &Sgr) -> String {
+        let mut out = Vec::new();
+        sgr.write_to(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn sgr_state_tracks_resets() {
+        let mut sgr = Sgr::default();
+        assert!(sgr.apply(b"\x1b[1m"));
+        assert!(sgr.apply(b"\x1b[31m"));
+        assert_eq!(restated(&sgr), "\x1b[1;31m");
+        assert!(sgr.apply(b"\x1b[0;32m"));
+        assert_eq!(restated(&sgr), "\x1b[32m");
+        assert!(sgr.apply(b"\x1b[m"));
+        assert!(sgr.is_reset());
+        assert!(!sgr.apply(b"\x1b[2K"));
+        assert!(!sgr.apply(b"\x1b[?25m"));
+    }
+
+    #[test]
+    fn sgr_state_keeps_one_setting_per_attribute() {
+        let mut sgr = Sgr::default();
+        assert!(sgr.apply(b"\x1b[1;2;4:3m"));
+        assert!(sgr.apply(b"\x1b[38;2;1;2;3;48;5;7;58:5:9;99m"));
+        assert!(sgr.apply(b"\x1b[66;99:1m"));
+        assert_eq!(
+            restated(&sgr),
+            "\x1b[1;2;4:3;38;2;1;2;3;48;5;7;58:5:9;99:1;66m"
+        );
+        assert!(sgr.apply(b"\x1b[22;39;24m"));
+        assert_eq!(restated(&sgr), "\x1b[48;5;7;58:5:9;99:1;66m");
+        assert!(sgr.apply(b"\x1b[49;59;0m"));
+        assert!(sgr.is_reset());
+    }
+
+    #[test]
+    fn the_state_mark_stays_bounded() {
+        let colors = ["31", "32", "1", "22", "4", "24", "38;5;9", "39"];
+        let input: String = (0..10_000)
+            .map(|i| format!("\x1b[{}m{}", colors[i % colors.len()], i % 10))
+            .collect();
+        let mut ansi = Ansi::new(Chars::new());
+        let mut reader = input.as_bytes();
+        let mut longest = 0;
+        loop {
+            let mut chunk = Vec::new();
+            if ansi.read_to(&mut reader, &mut chunk).unwrap() == 0 {
+                break;
+            }
+            longest = longest.max(leading_escapes(&chunk));
+        }
+        assert!(longest <= 32, "a mark of {longest} bytes");
+    }
+
+    #[test]
+    fn the_writer_passes_through_when_disabled() {
+        let mut writer = AnsiWriter::new(Vec::new(), false);
+        writer.write_all(RED.as_bytes()).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.inner, RED.as_bytes());
+    }
+
+    #[test]
+    fn the_writer_passes_sequences_through() {
+        let mut writer = AnsiWriter::new(Vec::new(), true);
+        for part in ["a\x1b[", "2Kb", "\x1b[31", "m\x1b[66m", "\x1b[39mc\x1b[m"] {
+            writer.write_all(part.as_bytes()).unwrap();
+        }
+        writer.write_all(b"\x1b]0;t\x07\x1b[1m").unwrap();
+        writer.flush().unwrap();
+        let input = "a\x1b[2Kb\x1b[31m\x1b[66m\x1b[39mc\x1b[m\x1b]0;t\x07\x1b[1m";
+        assert_eq!(writer.inner, format!("{input}{OFF}").as_bytes());
+    }
+
+    #[test]
+    fn the_writer_sets_only_the_marked_changes() {
+        let mut writer = AnsiWriter::new(Vec::new(), true);
+        let marks = [
+            // From a reset, then once more, then with more on top.
+            ("\x1b[=31ma", "\x1b[31ma"),
+            ("\x1b[=31mb", "b"),
+            ("\x1b[=31;1mc", "\x1b[1mc"),
+            // Red turns off: a reset and the rest again.
+            ("\x1b[=1md", "\x1b[0m\x1b[1md"),
+            // The element's own sequences get there by themselves.
+            ("\x1b[=4m\x1b[0;4me", "\x1b[0;4me"),
+        ];
+        for (written, out) in marks {
+            let before = writer.inner.len();
+            writer.write_all(written.as_bytes()).unwrap();
+            assert_eq!(&writer.inner[before..], out.as_bytes(), "{written:?}");
+        }
+        writer.flush().unwrap();
+        assert!(writer.inner.ends_with(b"e\x1b[0m"));
+    }
+}
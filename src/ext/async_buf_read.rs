@@ -0,0 +1,675 @@
+//! The streaming drivers over tokio's `AsyncBufRead`, for callers that must
+//! not block a thread on input. Chunks end where the blocking splits end
+//! them — the delimiter kinds share their [`Boundary`] scans, characters
+//! their [`element_len`] rule — so both backends cut identical elements.
+
+use super::{
+    buf_read::{Boundary, ByteEnd, CsvEnd, SizeEnd},
+    utf8::{element_len, Decision},
+    Byte, Bytes, Csv, EachByte, FixedSize, IteratorExt,
+};
+use crate::range::SliceIndex;
+use std::{collections::VecDeque, io, num::NonZeroUsize};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// [`Split`](super::Split) for an async reader: the same contract, awaiting
+/// input instead of blocking on it.
+pub(crate) trait AsyncSplit {
+    async fn read_to<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized;
+
+    async fn skip<R>(&mut self, r: &mut R) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized;
+
+    async fn skip_n<R>(&mut self, r: &mut R, n: usize) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+    {
+        for skipped in 0..n {
+            if self.skip(r).await? == 0 {
+                return Ok(skipped);
+            }
+        }
+        Ok(n)
+    }
+
+    /// Emit the rest of the stream verbatim, read-ahead first.
+    async fn copy_rest<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<u64>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized;
+}
+
+/// Scan one chunk, to where `boundary` ends it, writing it to `w` if given.
+/// Returns its length; `Ok(0)` only at EOF.
+async fn scan<R, W>(
+    r: &mut R,
+    mut boundary: impl Boundary,
+    mut w: Option<&mut W>,
+) -> io::Result<usize>
+where
+    R: AsyncBufRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let mut read = 0;
+    loop {
+        let block = r.fill_buf().await?;
+        if block.is_empty() {
+            return Ok(read);
+        }
+        let (done, used) = match boundary.find(block) {
+            Some(used) => (true, used),
+            None => (false, block.len()),
+        };
+        if let Some(w) = w.as_deref_mut() {
+            w.write_all(&block[..used]).await?;
+        }
+        r.consume(used);
+        read += used;
+        if done {
+            return Ok(read);
+        }
+    }
+}
+
+/// The stateless boundary kinds: nothing is read ahead, so the rest is the
+/// reader's remainder.
+macro_rules! delimited {
+    ($kind:ty, $split:ident => $boundary:expr) => {
+        impl AsyncSplit for $kind {
+            #[inline]
+            async fn read_to<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<usize>
+            where
+                R: AsyncBufRead + Unpin + ?Sized,
+                W: AsyncWrite + Unpin + ?Sized,
+            {
+                let $split = &*self;
+                scan(r, $boundary, Some(w)).await
+            }
+
+            #[inline]
+            async fn skip<R>(&mut self, r: &mut R) -> io::Result<usize>
+            where
+                R: AsyncBufRead + Unpin + ?Sized,
+            {
+                let $split = &*self;
+                scan::<R, tokio::io::Sink>(r, $boundary, None).await
+            }
+
+            #[inline]
+            async fn copy_rest<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<u64>
+            where
+                R: AsyncBufRead + Unpin + ?Sized,
+                W: AsyncWrite + Unpin + ?Sized,
+            {
+                tokio::io::copy_buf(r, w).await
+            }
+        }
+    };
+}
+
+delimited!(Byte, split => ByteEnd(split.0));
+delimited!(Bytes<'_>, split => split.boundary());
+delimited!(Csv, _split => CsvEnd::default());
+delimited!(FixedSize, split => SizeEnd(split.0.get()));
+
+impl AsyncSplit for EachByte {
+    async fn read_to<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let block = r.fill_buf().await?;
+        if block.is_empty() {
+            return Ok(0);
+        }
+        w.write_all(&block[..1]).await?;
+        r.consume(1);
+        Ok(1)
+    }
+
+    async fn skip<R>(&mut self, r: &mut R) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+    {
+        self.skip_n(r, 1).await
+    }
+
+    /// Consumes whole blocks instead of one byte per call.
+    async fn skip_n<R>(&mut self, r: &mut R, n: usize) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+    {
+        let mut skipped = 0;
+        while skipped < n {
+            let block = r.fill_buf().await?;
+            if block.is_empty() {
+                break;
+            }
+            let used = block.len().min(n - skipped);
+            r.consume(used);
+            skipped += used;
+        }
+        Ok(skipped)
+    }
+
+    #[inline]
+    async fn copy_rest<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<u64>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        tokio::io::copy_buf(r, w).await
+    }
+}
+
+/// Characters for an async reader, by the rule of [`element_len`]. A sequence
+/// can straddle a block edge, and a block cannot be un-consumed, so the bytes
+/// of an undecided sequence wait in `pending` — at most 3, since 4 bytes of
+/// lookahead always decide.
+#[derive(Default)]
+pub(crate) struct AsyncChars {
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl AsyncChars {
+    /// Consume one element, writing it to `w` if given; `Ok(0)` only at EOF.
+    async fn next<R, W>(&mut self, r: &mut R, w: Option<&mut W>) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        loop {
+            let block = r.fill_buf().await?;
+            let held = self.pending_len;
+            if held == 0 {
+                if block.is_empty() {
+                    return Ok(0);
+                }
+                if let Decision::Complete(len) = element_len(block) {
+                    if let Some(w) = w {
+                        w.write_all(&block[..len]).await?;
+                    }
+                    r.consume(len);
+                    return Ok(len);
+                }
+            }
+            // The head of the element is pending: decide on it and the
+            // block's first bytes together.
+            let mut window = self.pending;
+            let taken = block.len().min(4 - held);
+            window[held..held + taken].copy_from_slice(&block[..taken]);
+            let window = &window[..held + taken];
+            let len = match element_len(window) {
+                Decision::Complete(len) => len,
+                // The input ends inside a sequence: one element per byte.
+                Decision::NeedMore if block.is_empty() => 1,
+                Decision::NeedMore => {
+                    // `window` is shorter than 4 bytes, so it took the whole
+                    // block: hold it and wait for more.
+                    self.pending[held..held + taken].copy_from_slice(&block[..taken]);
+                    self.pending_len += taken;
+                    r.consume(taken);
+                    continue;
+                }
+            };
+            if let Some(w) = w {
+                w.write_all(&window[..len]).await?;
+            }
+            if len < held {
+                self.pending.copy_within(len..held, 0);
+                self.pending_len = held - len;
+            } else {
+                self.pending_len = 0;
+                r.consume(len - held);
+            }
+            return Ok(len);
+        }
+    }
+}
+
+impl AsyncSplit for AsyncChars {
+    #[inline]
+    async fn read_to<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        self.next(r, Some(w)).await
+    }
+
+    #[inline]
+    async fn skip<R>(&mut self, r: &mut R) -> io::Result<usize>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+    {
+        self.next::<R, tokio::io::Sink>(r, None).await
+    }
+
+    /// The pending bytes come first, then the reader's remainder.
+    async fn copy_rest<R, W>(&mut self, r: &mut R, w: &mut W) -> io::Result<u64>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let held = std::mem::take(&mut self.pending_len);
+        w.write_all(&self.pending[..held]).await?;
+        Ok(held as u64 + tokio::io::copy_buf(r, w).await?)
+    }
+}
+
+/// Async `slice_window`: skip `start` chunks, then copy the rest or the next
+/// `end - start` chunks.
+pub(crate) async fn slice_window_async<S, R, W>(
+    mut split: S,
+    mut input: R,
+    mut output: W,
+    start: usize,
+    end: Option<usize>,
+) -> io::Result<()>
+where
+    S: AsyncSplit,
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    if split.skip_n(&mut input, start).await? < start {
+        return output.flush().await;
+    }
+    match end {
+        None => {
+            split.copy_rest(&mut input, &mut output).await?;
+        }
+        Some(end) => {
+            for _ in 0..end.saturating_sub(start) {
+                if split.read_to(&mut input, &mut output).await? == 0 {
+                    break;
+                }
+            }
+        }
+    }
+    output.flush().await
+}
+
+/// Async `slice_stepped`: the survivors of `take(end).skip(start).step_by(step)`
+/// over the index stream, the gaps between them skipped.
+pub(crate) async fn slice_stepped_async<S, R, W>(
+    mut split: S,
+    mut input: R,
+    mut output: W,
+    start: usize,
+    end: Option<usize>,
+    step: NonZeroUsize,
+) -> io::Result<()>
+where
+    S: AsyncSplit,
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut index = 0usize;
+    for target in (0usize..).slice(start, end, Some(step)) {
+        let gap = target - index;
+        let skipped = split.skip_n(&mut input, gap).await?;
+        index += skipped;
+        if skipped < gap {
+            break;
+        }
+        if split.read_to(&mut input, &mut output).await? == 0 {
+            break;
+        }
+        index += 1;
+    }
+    output.flush().await
+}
+
+/// Async `slice_tail`: the last `back` chunks ride a ring until EOF fixes the
+/// length.
+pub(crate) async fn slice_tail_async<S, R, W>(
+    mut split: S,
+    mut input: R,
+    mut output: W,
+    back: NonZeroUsize,
+    end: Option<SliceIndex>,
+    step: NonZeroUsize,
+) -> io::Result<()>
+where
+    S: AsyncSplit,
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let cap = back.get();
+    let bound = match end {
+        Some(SliceIndex::FromStart(end)) => Some(end),
+        _ => None,
+    };
+    let mut ring: Vec<Vec<u8>> = Vec::new();
+    let mut scratch = Vec::new();
+    let mut total = 0usize;
+    loop {
+        if bound.is_some_and(|end| total >= end) {
+            total += split.skip_n(&mut input, usize::MAX).await?;
+            break;
+        }
+        scratch.clear();
+        if split.read_to(&mut input, &mut scratch).await? == 0 {
+            break;
+        }
+        let slot = total % cap;
+        if ring.len() <= slot {
+            ring.push(std::mem::take(&mut scratch));
+        } else {
+            std::mem::swap(&mut ring[slot], &mut scratch);
+        }
+        total += 1;
+    }
+    let len = total as u64;
+    let start = SliceIndex::FromEnd(back).resolve(len) as usize;
+    let end = end.map_or(total, |end| end.resolve(len) as usize);
+    for i in (0..total).slice(start, Some(end), Some(step)) {
+        output.write_all(&ring[i % cap]).await?;
+    }
+    output.flush().await
+}
+
+/// Async `slice_lag`: a chunk is written once `back` successors prove it lies
+/// before the dropped tail.
+pub(crate) async fn slice_lag_async<S, R, W>(
+    mut split: S,
+    mut input: R,
+    mut output: W,
+    start: usize,
+    back: NonZeroUsize,
+    step: NonZeroUsize,
+) -> io::Result<()>
+where
+    S: AsyncSplit,
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    if split.skip_n(&mut input, start).await? < start {
+        return output.flush().await;
+    }
+    let m = back.get();
+    let step = step.get();
+    let mut pending: VecDeque<(usize, Vec<u8>)> = VecDeque::new();
+    let mut free: Vec<Vec<u8>> = Vec::new();
+    let mut next = start;
+    loop {
+        let len = if (next - start) % step == 0 {
+            let mut buf = free.pop().unwrap_or_default();
+            buf.clear();
+            let len = split.read_to(&mut input, &mut buf).await?;
+            if len > 0 {
+                pending.push_back((next, buf));
+            } else {
+                free.push(buf);
+            }
+            len
+        } else {
+            split.skip(&mut input).await?
+        };
+        if len == 0 {
+            break;
+        }
+        next += 1;
+        while pending
+            .front()
+            .is_some_and(|&(i, _)| i.saturating_add(m) < next)
+        {
+            let (_, buf) = pending.pop_front().expect("front was just matched");
+            output.write_all(&buf).await?;
+            free.push(buf);
+        }
+    }
+    output.flush().await
+}
+
+/// Async `byte_mode`: bytes `start..end` by `step`, contiguous runs copied
+/// straight through and strides picked out block by block.
+pub(crate) async fn byte_mode_async<R, W>(
+    mut input: R,
+    mut output: W,
+    start: usize,
+    end: Option<usize>,
+    step: NonZeroUsize,
+) -> io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut remaining = end.map(|end| end.saturating_sub(start));
+    if remaining == Some(0) {
+        return output.flush().await;
+    }
+    EachByte.skip_n(&mut input, start).await?;
+    if step.get() == 1 {
+        match remaining {
+            Some(len) => {
+                tokio::io::copy_buf(&mut (&mut input).take(len as u64), &mut output).await?
+            }
+            None => tokio::io::copy_buf(&mut input, &mut output).await?,
+        };
+        return output.flush().await;
+    }
+    let step = step.get();
+    let mut buf = Vec::with_capacity(crate::WRITE_BUF_SIZE);
+    // Offset of the next selected byte within the unread stream.
+    let mut phase = 0;
+    loop {
+        let block = input.fill_buf().await?;
+        if block.is_empty() {
+            break;
+        }
+        let limit = remaining.map_or(block.len(), |remaining| block.len().min(remaining));
+        while phase < limit {
+            buf.push(block[phase]);
+            phase = phase.saturating_add(step);
+        }
+        phase -= limit;
+        input.consume(limit);
+        if buf.len() >= crate::WRITE_BUF_SIZE {
+            output.write_all(&buf).await?;
+            buf.clear();
+        }
+        if let Some(remaining) = &mut remaining {
+            *remaining -= limit;
+            if *remaining == 0 {
+                break;
+            }
+        }
+    }
+    output.write_all(&buf).await?;
+    output.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::{Chars, Split};
+    use std::future::Future;
+    use tokio::io::{duplex, BufReader, DuplexStream};
+
+    type Piped = BufReader<DuplexStream>;
+
+    /// Run `drive` over `input` fed through an in-memory duplex pipe in
+    /// `chunk`-byte writes, so elements straddle the reads.
+    async fn piped<T, F, Fut>(input: &[u8], chunk: usize, drive: F) -> T
+    where
+        F: FnOnce(Piped) -> Fut,
+        Fut: Future<Output = io::Result<T>>,
+    {
+        let (mut tx, rx) = duplex(chunk);
+        let feed = async move {
+            for piece in input.chunks(chunk) {
+                // A bounded range stops reading, closing the pipe early.
+                if tx.write_all(piece).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let (output, ()) = tokio::join!(drive(BufReader::with_capacity(chunk, rx)), feed);
+        output.unwrap()
+    }
+
+    fn nz(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+
+    /// The chunks the blocking split cuts, as the reference.
+    fn elements<S: Split>(mut split: S, mut input: &[u8]) -> Vec<Vec<u8>> {
+        let mut elements = Vec::new();
+        loop {
+            let mut element = Vec::new();
+            if split.read_to(&mut input, &mut element).unwrap() == 0 {
+                return elements;
+            }
+            elements.push(element);
+        }
+    }
+
+    async fn async_elements<S: AsyncSplit>(
+        mut split: S,
+        input: &[u8],
+        chunk: usize,
+    ) -> Vec<Vec<u8>> {
+        piped(input, chunk, |mut r| async move {
+            let mut elements = Vec::new();
+            loop {
+                let mut element = Vec::new();
+                if split.read_to(&mut r, &mut element).await? == 0 {
+                    return Ok(elements);
+                }
+                elements.push(element);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn splits_cut_what_the_blocking_ones_do() {
+        let mut text = "a\u{e9}--b\u{1f600}--\u{301}--".as_bytes().to_vec();
+        text.extend_from_slice(b"\xff-\xf0\x9f\x98");
+        let csv = b"a,\"b\nc\"\nd\n";
+        for chunk in [1, 2, 3, 64] {
+            assert_eq!(
+                async_elements(Byte(b'-'), &text, chunk).await,
+                elements(Byte(b'-'), &text)
+            );
+            assert_eq!(
+                async_elements(Bytes::new(b"--"), &text, chunk).await,
+                elements(Bytes::new(b"--"), &text)
+            );
+            assert_eq!(
+                async_elements(AsyncChars::default(), &text, chunk).await,
+                elements(Chars::new(), &text)
+            );
+            assert_eq!(
+                async_elements(EachByte, &text, chunk).await,
+                elements(EachByte, &text)
+            );
+            assert_eq!(async_elements(Csv, csv, chunk).await, elements(Csv, csv));
+        }
+    }
+
+    #[tokio::test]
+    async fn drivers_select_like_the_blocking_ones() {
+        let lines: Vec<u8> = (0..20)
+            .flat_map(|i| format!("{i}\n").into_bytes())
+            .collect();
+        let all = elements(Byte(b'\n'), &lines);
+        let pick = |indices: &mut dyn Iterator<Item = usize>| -> Vec<u8> {
+            indices.flat_map(|i| all[i].clone()).collect()
+        };
+        for chunk in [1, 5, 64] {
+            let out = piped(&lines, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_window_async(Byte(b'\n'), r, &mut out, 3, Some(6)).await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, pick(&mut (3..6)));
+            let out = piped(&lines, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_window_async(Byte(b'\n'), r, &mut out, 17, None).await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, pick(&mut (17..20)));
+            let out = piped(&lines, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_stepped_async(Byte(b'\n'), r, &mut out, 1, Some(12), nz(4)).await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, pick(&mut [1, 5, 9].into_iter()));
+            let out = piped(&lines, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_tail_async(Byte(b'\n'), r, &mut out, nz(5), None, nz(2)).await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, pick(&mut [15, 17, 19].into_iter()));
+            let out = piped(&lines, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_tail_async(
+                    Byte(b'\n'),
+                    r,
+                    &mut out,
+                    nz(5),
+                    Some(SliceIndex::FromEnd(nz(3))),
+                    nz(1),
+                )
+                .await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, pick(&mut (15..17)));
+            let out = piped(&lines, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_lag_async(Byte(b'\n'), r, &mut out, 2, nz(14), nz(2)).await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, pick(&mut [2, 4].into_iter()));
+        }
+    }
+
+    #[tokio::test]
+    async fn byte_and_char_modes() {
+        let data = "h\u{e9}llo w\u{f6}rld".as_bytes();
+        for chunk in [1, 3, 64] {
+            for (start, end, step) in [(0, None, 1), (2, Some(9), 1), (1, None, 3), (4, Some(5), 2)]
+            {
+                let out = piped(data, chunk, |r| async move {
+                    let mut out = Vec::new();
+                    byte_mode_async(r, &mut out, start, end, nz(step)).await?;
+                    Ok(out)
+                })
+                .await;
+                let expected: Vec<u8> = data
+                    .iter()
+                    .copied()
+                    .slice(start, end, Some(nz(step)))
+                    .collect();
+                assert_eq!(out, expected, "{start}:{end:?}:{step}");
+            }
+            let out = piped(data, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_window_async(AsyncChars::default(), r, &mut out, 1, Some(3)).await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, "\u{e9}l".as_bytes());
+            let out = piped(data, chunk, |r| async move {
+                let mut out = Vec::new();
+                slice_window_async(AsyncChars::default(), r, &mut out, 7, None).await?;
+                Ok(out)
+            })
+            .await;
+            assert_eq!(out, "\u{f6}rld".as_bytes());
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! Counting elements back from the end of a seekable file, as `tail -n` does:
+//! a tail-relative bound then costs reading the tail, not the whole input.
+//! Every function here takes the offset where the counted elements begin
+//! (`floor`, past a kept header) and the length fixed by the caller, and
+//! answers, for each `n` in `backs`, the offset where the `n`-th element
+//! from the end starts — `floor` when there are fewer than `n`.
+
+use crate::ext::utf8::{element_len, Decision};
+use memchr::{memmem, memrchr_iter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Whether the delimiter's matches can be found from either end alike. The
+/// forward split takes matches leftmost first, so one that can overlap itself
+/// (`aa` in `aaa`) would split differently when searched from the end.
+pub(crate) fn scans_backward(delimiter: &[u8]) -> bool {
+    (1..delimiter.len()).all(|n| delimiter[..n] != delimiter[delimiter.len() - n..])
+}
+
+fn read_at<F: Read + Seek>(file: &mut F, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buf)
+}
+
+/// Element starts for a split on `delimiter`, which must pass
+/// [`scans_backward`]. Blocks are read from the end until the `n`-th
+/// delimiter before the last element turns up, overlapping by the delimiter
+/// length less one so a match straddling two blocks is still seen.
+pub(crate) fn delimited_starts<F: Read + Seek>(
+    file: &mut F,
+    floor: u64,
+    len: u64,
+    delimiter: &[u8],
+    backs: &[usize],
+) -> io::Result<Vec<u64>> {
+    debug_assert!(scans_backward(delimiter), "checked by the caller");
+    debug_assert!(backs.is_sorted(), "each answer resumes the last scan");
+    let d = delimiter.len() as u64;
+    let mut buf = vec![0; BLOCK_SIZE + delimiter.len() - 1];
+    // The last element's own delimiter ends it rather than preceding it.
+    let mut end = len;
+    if len >= floor + d {
+        let tail = &mut buf[..delimiter.len()];
+        read_at(file, len - d, tail)?;
+        if tail == delimiter {
+            end -= d;
+        }
+    }
+    // Every match starting at or past `pos` has been counted.
+    let mut pos = end;
+    let mut found = 0;
+    let mut starts = Vec::with_capacity(backs.len());
+    for &n in backs {
+        'scan: while found < n {
+            if pos <= floor {
+                break;
+            }
+            let from = pos.saturating_sub(BLOCK_SIZE as u64).max(floor);
+            let to = (pos + d - 1).min(end);
+            let block = &mut buf[..(to - from) as usize];
+            read_at(file, from, block)?;
+            let hits: Box<dyn Iterator<Item = usize>> = match delimiter {
+                &[b] => Box::new(memrchr_iter(b, block)),
+                _ => Box::new(memmem::rfind_iter(block, delimiter)),
+            };
+            for hit in hits {
+                let hit = from + hit as u64;
+                if hit >= pos {
+                    continue;
+                }
+                pos = hit;
+                found += 1;
+                if found == n {
+                    break 'scan;
+                }
+            }
+            pos = from;
+        }
+        starts.push(if found == n { pos + d } else { floor });
+    }
+    Ok(starts)
+}
+
+/// Element starts for the chars split. Any byte but a UTF-8 continuation
+/// byte begins an element, and so does a continuation byte with no lead byte
+/// in the three before it; the scan starts from such a byte far enough back
+/// to hold every wanted element — each is at most 4 bytes — and walks
+/// forward twice: once to count, once to find the answers.
+pub(crate) fn char_starts<F: Read + Seek>(
+    file: &mut F,
+    floor: u64,
+    len: u64,
+    backs: &[usize],
+) -> io::Result<Vec<u64>> {
+    let reach = backs
+        .iter()
+        .max()
+        .map_or(0, |&n| n as u64)
+        .saturating_mul(4);
+    let mut from = len.saturating_sub(reach).max(floor);
+    if from > floor {
+        let before = from.saturating_sub(3).max(floor);
+        let mut head = [0; 4];
+        let head = &mut head[..(from - before) as usize + 1];
+        read_at(file, before, head)?;
+        let continuation = |b: u8| b & 0xc0 == 0x80;
+        if continuation(head[head.len() - 1]) {
+            if let Some(lead) = head.iter().rposition(|&b| !continuation(b)) {
+                from = before + lead as u64;
+            }
+        }
+    }
+    let mut count: usize = 0;
+    walk_chars(file, from, len, |_| count += 1)?;
+    let targets: Vec<usize> = backs.iter().map(|&n| count.saturating_sub(n)).collect();
+    let mut starts = vec![from; backs.len()];
+    let mut index = 0;
+    walk_chars(file, from, len, |offset| {
+        for (start, &target) in starts.iter_mut().zip(&targets) {
+            if target == index {
+                *start = offset;
+            }
+        }
+        index += 1;
+    })?;
+    Ok(starts)
+}
+
+/// Call `f` with the offset of every element in `[from, to)`; `from` must
+/// begin one.
+fn walk_chars<F: Read + Seek>(
+    file: &mut F,
+    from: u64,
+    to: u64,
+    mut f: impl FnMut(u64),
+) -> io::Result<()> {
+    file.seek(SeekFrom::Start(from))?;
+    let mut file = file.take(to - from);
+    let mut buf = vec![0; BLOCK_SIZE];
+    let (mut filled, mut offset) = (0, from);
+    loop {
+        let read = file.read(&mut buf[filled..])?;
+        filled += read;
+        let eof = read == 0;
+        let mut i = 0;
+        // Short of 4 bytes, a sequence may continue in the next block.
+        while i < filled && (eof || filled - i >= 4) {
+            f(offset + i as u64);
+            i += match element_len(&buf[i..filled]) {
+                Decision::Complete(n) => n,
+                Decision::NeedMore => 1,
+            };
+        }
+        if eof {
+            return Ok(());
+        }
+        buf.copy_within(i..filled, 0);
+        filled -= i;
+        offset += i as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::Utf8Elements;
+    use std::io::Cursor;
+
+    /// The element starts by forward splitting, the oracle for both scans.
+    fn oracle(floor: usize, starts: &[usize], backs: &[usize]) -> Vec<u64> {
+        let starts: Vec<usize> = starts.iter().copied().filter(|&s| s >= floor).collect();
+        backs
+            .iter()
+            .map(|&n| match starts.len().checked_sub(n) {
+                Some(i) => starts[i] as u64,
+                None => floor as u64,
+            })
+            .collect()
+    }
+
+    fn delimited_oracle(data: &[u8], delimiter: &[u8], floor: usize, backs: &[usize]) -> Vec<u64> {
+        let mut starts = Vec::new();
+        let mut pos = floor;
+        while pos < data.len() {
+            starts.push(pos);
+            pos = match memmem::find(&data[pos..], delimiter) {
+                Some(hit) => pos + hit + delimiter.len(),
+                None => data.len(),
+            };
+        }
+        oracle(floor, &starts, backs)
+    }
+
+    fn delimited(data: &[u8], delimiter: &[u8], floor: usize, backs: &[usize]) -> Vec<u64> {
+        let mut file = Cursor::new(data);
+        delimited_starts(&mut file, floor as u64, data.len() as u64, delimiter, backs).unwrap()
+    }
+
+    #[test]
+    fn counts_lines_from_the_end() {
+        assert_eq!(
+            delimited(b"a\nb\nc\n", b"\n", 0, &[1, 2, 3, 4]),
+            [4, 2, 0, 0]
+        );
+        assert_eq!(delimited(b"a\nb\nc", b"\n", 0, &[1, 2, 3]), [4, 2, 0]);
+        assert_eq!(delimited(b"a\n\n\n", b"\n", 0, &[1, 2, 3]), [3, 2, 0]);
+        assert_eq!(delimited(b"\n", b"\n", 0, &[1, 2]), [0, 0]);
+    }
+
+    #[test]
+    fn floor_excludes_a_header() {
+        assert_eq!(delimited(b"h\na\nb\n", b"\n", 2, &[1, 2, 3]), [4, 2, 2]);
+        assert_eq!(delimited(b"h\n", b"\n", 2, &[1]), [2]);
+    }
+
+    #[test]
+    fn agrees_with_forward_splitting() {
+        let inputs: &[&[u8]] = &[
+            b"one<>two<>three<>",
+            b"<><>x<><>",
+            b"no delimiter at all",
+            b"<>",
+            b"a<b<>c<",
+        ];
+        for data in inputs {
+            for floor in [0, 1] {
+                let backs = [1, 2, 3, 5, 10];
+                assert_eq!(
+                    delimited(data, b"<>", floor, &backs),
+                    delimited_oracle(data, b"<>", floor, &backs),
+                    "{data:?} floor {floor}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_straddle_block_edges() {
+        let mut data = vec![b'x'; BLOCK_SIZE * 2 + 7];
+        for at in [5, BLOCK_SIZE - 1, BLOCK_SIZE * 2 - 2] {
+            data[at..at + 3].copy_from_slice(b"<=>");
+        }
+        let backs = [1, 2, 3, 4];
+        assert_eq!(
+            delimited(&data, b"<=>", 0, &backs),
+            delimited_oracle(&data, b"<=>", 0, &backs)
+        );
+    }
+
+    #[test]
+    fn self_overlapping_delimiters_stay_forward() {
+        assert!(scans_backward(b"\n"));
+        assert!(scans_backward(b"\r\n"));
+        assert!(scans_backward(b"<=>"));
+        assert!(!scans_backward(b"aa"));
+        assert!(!scans_backward(b"abab"));
+        assert!(!scans_backward(b"a-a"));
+    }
+
+    fn chars(data: &[u8], backs: &[usize]) -> Vec<u64> {
+        let mut file = Cursor::new(data);
+        char_starts(&mut file, 0, data.len() as u64, backs).unwrap()
+    }
+
+    fn chars_oracle(data: &[u8], backs: &[usize]) -> Vec<u64> {
+        let mut starts = Vec::new();
+        let mut pos = 0;
+        for element in Utf8Elements::new(data) {
+            starts.push(pos);
+            pos += element.len();
+        }
+        oracle(0, &starts, backs)
+    }
+
+    #[test]
+    fn counts_chars_from_the_end() {
+        let inputs: &[&[u8]] = &[
+            "héllo wörld 👋".as_bytes(),
+            b"\x80\x80\x80\x80\x80abc",
+            b"a\xf0\x9f\x91\x8b\x80\x80\x80b",
+            b"ab\xe2\x82",
+            b"\xf0\x9f\x91",
+        ];
+        for data in inputs {
+            let backs = [1, 2, 3, 4, 6, 100];
+            assert_eq!(chars(data, &backs), chars_oracle(data, &backs), "{data:?}");
+        }
+    }
+
+    #[test]
+    fn chars_straddle_block_edges() {
+        let data = "é".repeat(BLOCK_SIZE).into_bytes();
+        let backs = [1, BLOCK_SIZE / 2 + 1, BLOCK_SIZE];
+        assert_eq!(chars(&data[1..], &backs), chars_oracle(&data[1..], &backs));
+        assert_eq!(chars(&data, &backs), chars_oracle(&data, &backs));
+    }
+}
@@ -1,10 +1,23 @@
-use crate::{ext::IteratorExt, range::SliceIndex};
+use crate::{
+    ext::{ChunkSpans, ChunkSpansWriter, IteratorExt},
+    range::{Anchor, AnchoredEnd, AnchoredPlan, SliceIndex, UnionPlan},
+};
 use memchr::memmem;
+use regex::bytes::{Regex, RegexBuilder};
+use regex_automata::{
+    hybrid::dfa::{Cache, DFA},
+    nfa::thompson,
+    util::syntax,
+    Input, MatchErrorKind,
+};
 use std::{
+    cmp::Ordering,
     collections::VecDeque,
     fmt,
+    hash::{Hash, Hasher},
     io::{self, BufRead, Write},
     num::NonZeroUsize,
+    str::FromStr,
 };
 
 #[derive(Debug)]
@@ -24,14 +37,14 @@ impl fmt::Display for RecordSizeLimitExceeded {
 
 impl std::error::Error for RecordSizeLimitExceeded {}
 
-struct LimitedVec<'a> {
+pub(super) struct LimitedVec<'a> {
     buf: &'a mut Vec<u8>,
     limit: Option<usize>,
 }
 
 impl<'a> LimitedVec<'a> {
     #[inline]
-    fn new(buf: &'a mut Vec<u8>, limit: Option<usize>) -> Self {
+    pub(super) fn new(buf: &'a mut Vec<u8>, limit: Option<usize>) -> Self {
         Self { buf, limit }
     }
 }
@@ -57,16 +70,112 @@ impl Write for LimitedVec<'_> {
     }
 }
 
-/// Scan to the first `delim` byte. `sink` receives each consumed slice; it is the
-/// only thing that differs between emitting (write through) and skipping
-/// (discard), and a sink error aborts the scan. Returns the total bytes
-/// consumed: `Ok(0)` means the stream was already at EOF (no chunk),
-/// `Ok(n > 0)` means a chunk was consumed — including a final chunk that lacks
-/// a trailing `delim`.
+/// Measures a chunk instead of holding it: counts the bytes written, failing
+/// past the record limit like [`LimitedVec`], and keeps only the last `keep`
+/// of them, where a delimiter would be.
+pub(super) struct Measured {
+    written: usize,
+    tail: Vec<u8>,
+    keep: usize,
+    limit: Option<usize>,
+}
+
+impl Measured {
+    #[inline]
+    pub(super) fn new(keep: usize, limit: Option<usize>) -> Self {
+        Self {
+            written: 0,
+            tail: Vec::with_capacity(keep),
+            keep,
+            limit,
+        }
+    }
+
+    /// Start the next chunk.
+    #[inline]
+    pub(super) fn clear(&mut self) {
+        self.written = 0;
+        self.tail.clear();
+    }
+
+    #[inline]
+    pub(super) fn written(&self) -> usize {
+        self.written
+    }
+
+    /// The chunk's last `keep` bytes, or all of a shorter one.
+    #[inline]
+    pub(super) fn tail(&self) -> &[u8] {
+        &self.tail
+    }
+}
+
+impl Write for Measured {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.written.saturating_add(buf.len());
+        if let Some(limit) = self.limit {
+            if written > limit {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    RecordSizeLimitExceeded { limit },
+                ));
+            }
+        }
+        extend_carry(&mut self.tail, buf, self.keep);
+        self.written = written;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Where the chunk being scanned ends. Fed each `fill_buf` block in turn, it
+/// returns how much of the block the chunk takes when it ends there, `None`
+/// when the whole block belongs to it. Any state carried across blocks lives
+/// for one chunk, so every scan starts a fresh one. The blocking scanners
+/// here and the async ones share these, so both cut identical chunks.
+pub(super) trait Boundary {
+    fn find(&mut self, block: &[u8]) -> Option<usize>;
+}
+
+/// The first `delim` byte.
+pub(super) struct ByteEnd(pub(super) u8);
+
+impl Boundary for ByteEnd {
+    #[inline]
+    fn find(&mut self, block: &[u8]) -> Option<usize> {
+        memchr::memchr(self.0, block).map(|i| i + 1)
+    }
+}
+
+/// The byte that completes a fixed-size chunk, `.0` bytes on.
+pub(super) struct SizeEnd(pub(super) usize);
+
+impl Boundary for SizeEnd {
+    #[inline]
+    fn find(&mut self, block: &[u8]) -> Option<usize> {
+        if block.len() >= self.0 {
+            return Some(self.0);
+        }
+        self.0 -= block.len();
+        None
+    }
+}
+
+/// Scan one chunk, to where `boundary` ends it. `sink` receives each consumed
+/// slice; it is the only thing that differs between emitting (write through)
+/// and skipping (discard), and a sink error aborts the scan. Returns the
+/// total bytes consumed: `Ok(0)` means the stream was already at EOF (no
+/// chunk), `Ok(n > 0)` means a chunk was consumed — including a final chunk
+/// that lacks a trailing delimiter.
 #[inline]
-fn scan_until<R: BufRead + ?Sized>(
+fn scan<R: BufRead + ?Sized>(
     r: &mut R,
-    delim: u8,
+    mut boundary: impl Boundary,
     mut sink: impl FnMut(&[u8]) -> io::Result<()>,
 ) -> io::Result<usize> {
     let mut read = 0;
@@ -78,10 +187,10 @@ fn scan_until<R: BufRead + ?Sized>(
                 Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
                 Err(e) => return Err(e),
             };
-            match memchr::memchr(delim, available) {
-                Some(i) => {
-                    sink(&available[..=i])?;
-                    (true, i + 1)
+            match boundary.find(available) {
+                Some(used) => {
+                    sink(&available[..used])?;
+                    (true, used)
                 }
                 None => {
                     sink(available)?;
@@ -97,6 +206,16 @@ fn scan_until<R: BufRead + ?Sized>(
     }
 }
 
+/// Scan to the first `delim` byte.
+#[inline]
+fn scan_until<R: BufRead + ?Sized>(
+    r: &mut R,
+    delim: u8,
+    sink: impl FnMut(&[u8]) -> io::Result<()>,
+) -> io::Result<usize> {
+    scan(r, ByteEnd(delim), sink)
+}
+
 #[inline]
 fn skip_until<R: BufRead + ?Sized>(r: &mut R, delim: u8) -> io::Result<usize> {
     scan_until(r, delim, |_| Ok(()))
@@ -108,7 +227,7 @@ fn skip_until<R: BufRead + ?Sized>(r: &mut R, delim: u8) -> io::Result<usize> {
 /// (`Ok(0)` only at end of stream); `read_to` additionally writes the chunk to
 /// `w`, straight from the reader's buffer. Kinds are separate types so the
 /// single-byte path never pays for the multi-byte straddle machinery.
-pub(crate) trait Split {
+pub trait Split {
     /// Returns `Ok(0)` only at true end of stream: a stateful kind must first
     /// drain any bytes it consumed ahead of the current element.
     fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
@@ -142,25 +261,47 @@ pub(crate) trait Split {
         r: &mut R,
         w: &mut W,
     ) -> io::Result<u64>;
+
+    /// The part of a chunk a `/pattern/` endpoint matches against: the chunk
+    /// minus its trailing delimiter, so `$` anchors at the element's end.
+    /// Delimiterless kinds match the whole chunk.
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        chunk
+    }
+
+    /// Whether `chunk` is a whole element rather than the start of one cut
+    /// off by the end of the data read so far — which `--follow` holds back
+    /// until the rest is appended. Delimited kinds end an element at their
+    /// delimiter; delimiterless ones must say when an element is complete.
+    #[inline]
+    fn terminated(&self, chunk: &[u8]) -> bool {
+        self.content(chunk).len() < chunk.len()
+    }
 }
 
 /// Single-byte delimiter. Lines are `Byte(b'\n')`, `-z` is `Byte(0)`.
-pub(crate) struct Byte(pub u8);
+pub struct Byte(pub u8);
 
-/// Multi-byte delimiter (len >= 2; constructed only by the delimiter-shape
-/// dispatch). The `memmem` finder is precomputed here so its searcher setup is
+/// Multi-byte delimiter. A single byte works too, but [`Byte`] finds it
+/// faster; the delimiter-shape dispatch only builds this one for two or more.
+/// The `memmem` finder is precomputed here so its searcher setup is
 /// paid once per run, not once per chunk.
-pub(crate) struct Bytes<'d> {
+pub struct Bytes<'d> {
     delimiter: &'d [u8],
     finder: memmem::Finder<'d>,
 }
 
 impl<'d> Bytes<'d> {
+    /// # Panics
+    ///
+    /// If `delimiter` is empty: it would end an element before every byte,
+    /// which is what [`EachByte`] does.
     #[inline]
-    pub(crate) fn new(delimiter: &'d [u8]) -> Self {
-        debug_assert!(
-            delimiter.len() >= 2,
-            "single-byte and empty delimiters dispatch to other kinds"
+    pub fn new(delimiter: &'d [u8]) -> Self {
+        assert!(
+            !delimiter.is_empty(),
+            "an empty delimiter splits every byte; use EachByte"
         );
         Self {
             delimiter,
@@ -170,6 +311,11 @@ impl<'d> Bytes<'d> {
 }
 
 impl Split for Byte {
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        chunk.strip_suffix(&[self.0]).unwrap_or(chunk)
+    }
+
     #[inline]
     fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
         &mut self,
@@ -240,21 +386,26 @@ impl Split for Byte {
 }
 
 impl Split for Bytes<'_> {
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        chunk.strip_suffix(self.delimiter).unwrap_or(chunk)
+    }
+
     #[inline]
     fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
         &mut self,
         r: &mut R,
         w: &mut W,
     ) -> io::Result<usize> {
-        scan_until_delim(r, self.delimiter, &self.finder, |chunk| w.write_all(chunk))
+        scan(r, self.boundary(), |chunk| w.write_all(chunk))
     }
 
     #[inline]
     fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
-        scan_until_delim(r, self.delimiter, &self.finder, |_| Ok(()))
+        scan(r, self.boundary(), |_| Ok(()))
     }
 
-    /// Stateless: the carry in `scan_until_delim` lives within one chunk, so
+    /// Stateless: the carry in `DelimiterEnd` lives within one chunk, so
     /// nothing is read ahead and the reader is the whole remainder.
     #[inline]
     fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
@@ -266,9 +417,201 @@ impl Split for Bytes<'_> {
     }
 }
 
-/// Multi-byte counterpart of `scan_until`: scan to the first full `delimiter`
-/// match, same contract (`sink` receives each consumed slice and may abort with
-/// an error, `Ok(0)` means EOF, the final chunk may lack the delimiter).
+/// Byte mode as a split: every byte is one chunk. Only the generic drivers
+/// need it — byte mode's own paths seek and copy spans directly.
+pub struct EachByte;
+
+impl EachByte {
+    /// Consume up to one byte, feeding it to `sink`; `Ok(0)` only at EOF.
+    #[inline]
+    fn next<R: BufRead + ?Sized>(
+        r: &mut R,
+        sink: impl FnOnce(&[u8]) -> io::Result<()>,
+    ) -> io::Result<usize> {
+        let block = loop {
+            match r.fill_buf() {
+                Ok([]) => return Ok(0),
+                Ok(block) => break block,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        sink(&block[..1])?;
+        r.consume(1);
+        Ok(1)
+    }
+}
+
+impl Split for EachByte {
+    /// A byte is always whole.
+    #[inline]
+    fn terminated(&self, _chunk: &[u8]) -> bool {
+        true
+    }
+
+    #[inline]
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        Self::next(r, |byte| w.write_all(byte))
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        Self::next(r, |_| Ok(()))
+    }
+
+    /// Consumes whole `fill_buf` blocks instead of one byte per call.
+    fn skip_n<R: BufRead + ?Sized>(&mut self, r: &mut R, n: usize) -> io::Result<usize> {
+        let mut skipped = 0;
+        while skipped < n {
+            let used = match r.fill_buf() {
+                Ok([]) => break,
+                Ok(block) => block.len().min(n - skipped),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            r.consume(used);
+            skipped += used;
+        }
+        Ok(skipped)
+    }
+
+    /// Stateless: nothing is read ahead, the reader is the whole remainder.
+    #[inline]
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        io::copy(r, w)
+    }
+}
+
+/// Fixed-size records: every chunk is the next `N` bytes, and the last one
+/// whatever is left. Like [`EachByte`], only the generic drivers need it —
+/// record mode's own paths seek and copy byte spans directly.
+pub struct FixedSize(pub NonZeroUsize);
+
+impl Split for FixedSize {
+    /// A record is whole once it has all its bytes.
+    #[inline]
+    fn terminated(&self, chunk: &[u8]) -> bool {
+        chunk.len() == self.0.get()
+    }
+
+    #[inline]
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        scan(r, SizeEnd(self.0.get()), |block| w.write_all(block))
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        scan(r, SizeEnd(self.0.get()), |_| Ok(()))
+    }
+
+    /// Consumes `n` records' worth of bytes in whole `fill_buf` blocks.
+    fn skip_n<R: BufRead + ?Sized>(&mut self, r: &mut R, n: usize) -> io::Result<usize> {
+        let size = self.0.get() as u64;
+        let total = size.saturating_mul(n as u64);
+        let mut skipped = 0;
+        while skipped < total {
+            let used = match r.fill_buf() {
+                Ok([]) => break,
+                Ok(block) => block
+                    .len()
+                    .min((total - skipped).min(usize::MAX as u64) as usize),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            r.consume(used);
+            skipped += used as u64;
+        }
+        Ok(skipped.div_ceil(size) as usize)
+    }
+
+    /// Stateless: nothing is read ahead, the reader is the whole remainder.
+    #[inline]
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        io::copy(r, w)
+    }
+}
+
+/// RFC 4180 records: a chunk ends at the first `\n` outside a quoted field,
+/// so a quoted field may span lines. An escaped quote (`""`) toggles the
+/// quoting twice, so quote parity alone decides whether a newline is inside
+/// a field; nothing else is validated, and malformed input (a stray quote)
+/// still splits losslessly, at worst into one long record. A CRLF ending
+/// keeps its `\r` in the chunk, as in line mode.
+pub struct Csv;
+
+/// A record's end: the first `\n` outside a quoted field, the quote state
+/// carried across blocks for the length of one record.
+#[derive(Default)]
+pub(super) struct CsvEnd {
+    quoted: bool,
+}
+
+impl Boundary for CsvEnd {
+    #[inline]
+    fn find(&mut self, block: &[u8]) -> Option<usize> {
+        memchr::memchr2_iter(b'"', b'\n', block)
+            .find(|&i| {
+                if block[i] == b'"' {
+                    self.quoted = !self.quoted;
+                    false
+                } else {
+                    !self.quoted
+                }
+            })
+            .map(|i| i + 1)
+    }
+}
+
+impl Split for Csv {
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        chunk.strip_suffix(b"\n").unwrap_or(chunk)
+    }
+
+    #[inline]
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        scan(r, CsvEnd::default(), |chunk| w.write_all(chunk))
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        scan(r, CsvEnd::default(), |_| Ok(()))
+    }
+
+    /// Stateless between records: the quote state lives within one chunk,
+    /// so nothing is read ahead and the reader is the whole remainder.
+    #[inline]
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        io::copy(r, w)
+    }
+}
+
+/// The first full `delimiter` match: the multi-byte counterpart of
+/// [`ByteEnd`].
 ///
 /// A match can straddle a `fill_buf` boundary, so the last `delimiter.len() - 1`
 /// consumed bytes are carried across blocks. Each block is searched in two
@@ -278,50 +621,46 @@ impl Split for Bytes<'_> {
 /// hit — the leftmost match overall wins. The carry lives for one chunk only,
 /// so a confirmed match never seeds the next chunk's window (`aaaaaa` with
 /// `aaa` -> `aaa`, `aaa`).
-fn scan_until_delim<R: BufRead + ?Sized>(
-    r: &mut R,
-    delimiter: &[u8],
-    finder: &memmem::Finder<'_>,
-    mut sink: impl FnMut(&[u8]) -> io::Result<()>,
-) -> io::Result<usize> {
-    let keep = delimiter.len() - 1;
+pub(super) struct DelimiterEnd<'f, 'd> {
+    delimiter: &'d [u8],
+    finder: &'f memmem::Finder<'d>,
     // Allocated lazily: the carry is only written when a chunk spans a
     // fill_buf boundary, so non-straddling chunks stay allocation-free.
-    let mut carry: Vec<u8> = Vec::new();
-    let mut read = 0;
-    loop {
-        let (done, used) = {
-            let block = match r.fill_buf() {
-                Ok([]) => return Ok(read),
-                Ok(n) => n,
-                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
-                Err(e) => return Err(e),
-            };
-            let straddle = if carry.is_empty() {
-                None
-            } else {
-                let carried = carry.len();
-                carry.extend_from_slice(&block[..block.len().min(keep)]);
-                let hit = finder.find(&carry).map(|p| p + delimiter.len() - carried);
-                carry.truncate(carried);
-                hit
-            };
-            match straddle.or_else(|| finder.find(block).map(|i| i + delimiter.len())) {
-                Some(used) => {
-                    sink(&block[..used])?;
-                    (true, used)
-                }
-                None => {
-                    sink(block)?;
-                    extend_carry(&mut carry, block, keep);
-                    (false, block.len())
-                }
-            }
+    carry: Vec<u8>,
+}
+
+impl Boundary for DelimiterEnd<'_, '_> {
+    fn find(&mut self, block: &[u8]) -> Option<usize> {
+        let keep = self.delimiter.len() - 1;
+        let carry = &mut self.carry;
+        let straddle = if carry.is_empty() {
+            None
+        } else {
+            let carried = carry.len();
+            carry.extend_from_slice(&block[..block.len().min(keep)]);
+            let hit = self
+                .finder
+                .find(carry)
+                .map(|p| p + self.delimiter.len() - carried);
+            carry.truncate(carried);
+            hit
         };
-        r.consume(used);
-        read += used;
-        if done {
-            return Ok(read);
+        let end = straddle.or_else(|| self.finder.find(block).map(|i| i + self.delimiter.len()));
+        if end.is_none() {
+            extend_carry(carry, block, keep);
+        }
+        end
+    }
+}
+
+impl<'d> Bytes<'d> {
+    /// A fresh scan for the next chunk's end.
+    #[inline]
+    pub(super) fn boundary(&self) -> DelimiterEnd<'_, 'd> {
+        DelimiterEnd {
+            delimiter: self.delimiter,
+            finder: &self.finder,
+            carry: Vec::new(),
         }
     }
 }
@@ -340,6 +679,268 @@ fn extend_carry(carry: &mut Vec<u8>, block: &[u8], keep: usize) {
     }
 }
 
+/// A `--delimiter-regex` pattern, for separators no fixed byte string
+/// spells: a blank line that may hold stray whitespace (`\n\s*\n`), a rule
+/// line (`^-{3,}\n`, with `^` and `$` anchoring at line boundaries). A
+/// pattern that can match the empty string is rejected: such a match would
+/// end a record without taking a byte of it. Compared, ordered and hashed by
+/// its source, like a `/pattern/` endpoint, so a mode holding one stays a
+/// plain value.
+#[derive(Clone, Debug)]
+pub struct DelimiterRegex {
+    /// Finds the match ending a whole record, for `content`.
+    regex: Regex,
+    /// Finds where a streamed record ends; see [`RegexDelimited`].
+    dfa: DFA,
+}
+
+impl DelimiterRegex {
+    /// The pattern as written.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+}
+
+impl FromStr for DelimiterRegex {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let regex = RegexBuilder::new(pattern)
+            .multi_line(true)
+            .build()
+            .map_err(|err| err.to_string())?;
+        // The settings `regex::bytes` runs with: any byte may match, and an
+        // empty match may split a UTF-8 sequence.
+        let nfa = thompson::Compiler::new()
+            .syntax(syntax::Config::new().multi_line(true).utf8(false))
+            .configure(thompson::Config::new().utf8(false))
+            .build(pattern)
+            .map_err(|err| err.to_string())?;
+        if nfa.has_empty() {
+            return Err(format!("'{pattern}' can match the empty string"));
+        }
+        // A lazy DFA handles a Unicode `\b` only on ASCII input; see
+        // `word_boundary_quit`.
+        let dfa = DFA::builder()
+            .configure(DFA::config().unicode_word_boundary(true))
+            .build_from_nfa(nfa)
+            .map_err(|err| err.to_string())?;
+        Ok(Self { regex, dfa })
+    }
+}
+
+impl fmt::Display for DelimiterRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for DelimiterRegex {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for DelimiterRegex {}
+
+impl PartialOrd for DelimiterRegex {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DelimiterRegex {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for DelimiterRegex {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+/// Records ended by a [`DelimiterRegex`] match, which the record keeps as
+/// its trailing delimiter. The record ends where the leftmost-first match
+/// in the rest of the input does, wherever the `fill_buf` blocks fall.
+///
+/// Unlike a fixed delimiter, a match is only known to be over once no longer
+/// one can follow: `\n\s*\n` still grows at `\n \n\t`. So the pattern runs
+/// as a lazy DFA, stepped over the input until it can match nothing more,
+/// which may take bytes past the record's end — from several blocks. Those
+/// are moved out of the reader into a buffer of the split's own, read ahead
+/// like [`Chars`](super::Chars) and drained by `copy_rest`. The buffer keeps
+/// the byte before the next record, so `^` and `\b` see what precedes it. A
+/// record is thus always retained while it is cut, and the record limit
+/// bounds it on every path.
+pub struct RegexDelimited<'r> {
+    delimiter: &'r DelimiterRegex,
+    cache: Cache,
+    max_record_size: Option<usize>,
+    buf: Vec<u8>,
+    /// Where the next record starts in `buf`.
+    pos: usize,
+}
+
+impl<'r> RegexDelimited<'r> {
+    #[inline]
+    pub fn new(delimiter: &'r DelimiterRegex, max_record_size: Option<usize>) -> Self {
+        Self {
+            delimiter,
+            cache: Cache::new(&delimiter.dfa),
+            max_record_size,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Move the reader's next block into the buffer, first dropping all but
+    /// the byte before the next record. `Ok(false)` at end of stream.
+    fn read_block<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<bool> {
+        if self.pos > 1 {
+            self.buf.drain(..self.pos - 1);
+            self.pos = 1;
+        }
+        loop {
+            let used = match r.fill_buf() {
+                Ok([]) => return Ok(false),
+                Ok(block) => {
+                    self.buf.extend_from_slice(block);
+                    block.len()
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            r.consume(used);
+            return Ok(true);
+        }
+    }
+
+    /// Buffer the next record, reading as far as it takes, and return its
+    /// length; `Ok(0)` at end of stream. Unless `hold`, the record is only
+    /// measured: what is surely part of it leaves the buffer as the search
+    /// moves on, and the record is passed once found.
+    fn next_record<R: BufRead + ?Sized>(&mut self, r: &mut R, hold: bool) -> io::Result<usize> {
+        let dfa = &self.delimiter.dfa;
+        let input = Input::new(&self.buf).range(self.pos..);
+        let mut sid = dfa
+            .start_state_forward(&mut self.cache, &input)
+            .map_err(|err| match err.kind() {
+                MatchErrorKind::Quit { .. } => word_boundary_quit(),
+                _ => io::Error::other(err),
+            })?;
+        // Bytes past `pos` stepped through, and where the latest match ended.
+        let mut stepped = 0;
+        let mut end = None;
+        // Bytes of the record already passed, when it is not held.
+        let mut passed = 0;
+        let len = 'record: loop {
+            while let Some(&byte) = self.buf.get(self.pos + stepped) {
+                sid = (dfa.next_state(&mut self.cache, sid, byte)).map_err(io::Error::other)?;
+                // Matches are reported one byte late: this one ended before
+                // `byte`.
+                if sid.is_match() {
+                    end = Some(stepped);
+                } else if sid.is_dead() {
+                    // The unanchored search only gives up past a match.
+                    break 'record end.unwrap_or(stepped);
+                } else if sid.is_quit() {
+                    return Err(word_boundary_quit());
+                }
+                stepped += 1;
+                // The record reaches at least this far.
+                self.limited(passed + end.unwrap_or(stepped))?;
+            }
+            if !hold {
+                let sure = end.unwrap_or(stepped);
+                self.pos += sure;
+                passed += sure;
+                stepped -= sure;
+                end = end.map(|end| end - sure);
+            }
+            if !self.read_block(r)? {
+                sid = (dfa.next_eoi_state(&mut self.cache, sid)).map_err(io::Error::other)?;
+                if sid.is_match() {
+                    end = Some(stepped);
+                }
+                break end.unwrap_or(stepped);
+            }
+        };
+        if !hold {
+            self.pos += len;
+        }
+        self.limited(passed + len)
+    }
+
+    #[inline]
+    fn limited(&self, len: usize) -> io::Result<usize> {
+        match self.max_record_size {
+            Some(limit) if len > limit => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                RecordSizeLimitExceeded { limit },
+            )),
+            _ => Ok(len),
+        }
+    }
+}
+
+/// A lazy DFA quits where a Unicode `\b` meets a non-ASCII byte.
+fn word_boundary_quit() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        r"the delimiter regex's \b only works on ASCII input; use (?-u:\b) instead",
+    )
+}
+
+impl Split for RegexDelimited<'_> {
+    /// The record minus the match ending it.
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        match self.delimiter.regex.find(chunk) {
+            Some(m) if m.end() == chunk.len() => &chunk[..m.start()],
+            _ => chunk,
+        }
+    }
+
+    #[inline]
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        let len = self.next_record(r, true)?;
+        w.write_all(&self.buf[self.pos..self.pos + len])?;
+        self.pos += len;
+        Ok(len)
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        self.next_record(r, false)
+    }
+
+    /// The buffered bytes come first, then the reader's remainder.
+    #[inline]
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        let held = &self.buf[self.pos..];
+        w.write_all(held)?;
+        let carried = held.len() as u64;
+        self.buf.clear();
+        self.pos = 0;
+        Ok(carried + io::copy(r, w)?)
+    }
+}
+
 /// Unit-step line/delimiter fast path. A unit-step range selects contiguous
 /// chunks, i.e. one contiguous byte span: skip `start` chunks, then emit the
 /// window. Unbounded `start:` copies the tail verbatim; bounded `start:end`
@@ -470,24 +1071,67 @@ pub(crate) fn slice_tail_with_record_limit<S: Split, R: BufRead, W: Write>(
     output.flush()
 }
 
-/// Read the whole stream into one buffer, splitting only to bound each
-/// record: the reverse plan retains every record, so an oversized one must
-/// fail during the read — after at most `max_record_size` buffered bytes of
-/// it — not after the input was swallowed whole.
-pub(crate) fn read_all_with_record_limit<S: Split, R: BufRead>(
+/// Copy the whole stream into the reverse path's spool, splitting only to
+/// bound each record: the reverse plan retains every record, so an oversized
+/// one must fail during the read — after at most `max_record_size` buffered
+/// bytes of it — not after the input was swallowed whole.
+pub(crate) fn read_all_with_record_limit<S: Split, R: BufRead, W: Write>(
     mut split: S,
     mut input: R,
+    mut output: W,
     max_record_size: Option<usize>,
-) -> io::Result<Vec<u8>> {
-    let mut data = Vec::new();
+) -> io::Result<()> {
     let mut scratch = Vec::new();
     loop {
         scratch.clear();
         let mut limited = LimitedVec::new(&mut scratch, max_record_size);
         if split.read_to(&mut input, &mut limited)? == 0 {
-            return Ok(data);
+            return Ok(());
         }
-        data.extend_from_slice(&scratch);
+        output.write_all(&scratch)?;
+    }
+}
+
+/// The span of every chunk, for a reverse walk over input it cannot hold in
+/// memory, tabled within `max_memory`. A chunk is measured as it passes, not
+/// held: only its last `delimiter` bytes are kept, enough to tell whether
+/// its content leaves a delimiter out. The record limit still applies.
+pub(crate) fn chunk_spans<S: Split, R: BufRead>(
+    mut split: S,
+    mut input: R,
+    max_record_size: Option<usize>,
+    delimiter: usize,
+    max_memory: usize,
+) -> io::Result<ChunkSpans> {
+    let mut spans = ChunkSpansWriter::new(max_memory);
+    let mut measured = Measured::new(delimiter, max_record_size);
+    loop {
+        // With no delimiter to look for, a skip measures the chunk.
+        if delimiter == 0 {
+            match split.skip(&mut input)? {
+                0 => return spans.finish(),
+                len => match max_record_size {
+                    Some(limit) if len > limit => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            RecordSizeLimitExceeded { limit },
+                        ))
+                    }
+                    _ => {
+                        spans.push(len, len)?;
+                        continue;
+                    }
+                },
+            }
+        }
+        measured.clear();
+        let len = split.read_to(&mut input, &mut measured)?;
+        if len == 0 {
+            return spans.finish();
+        }
+        let tail = measured.tail();
+        let stripped = tail.len() - split.content(tail).len();
+        spans.push(len, len - stripped)?;
     }
 }
 
@@ -545,21 +1189,268 @@ pub(crate) fn slice_lag_with_record_limit<S: Split, R: BufRead, W: Write>(
             // EOF: whatever is still pending lies within the dropped tail.
             break;
         }
-        next += 1;
-        // Chunk i survives iff i + m < next; checked after the `next` increment
-        // so the read proving the m-th successor exists has already landed.
-        while pending
-            .front()
-            .is_some_and(|&(i, _)| i.saturating_add(m) < next)
-        {
-            let (_, buf) = pending.pop_front().expect("front was just matched");
+        next += 1;
+        // Chunk i survives iff i + m < next; checked after the `next` increment
+        // so the read proving the m-th successor exists has already landed.
+        while pending
+            .front()
+            .is_some_and(|&(i, _)| i.saturating_add(m) < next)
+        {
+            let (_, buf) = pending.pop_front().expect("front was just matched");
+            output.write_all(&buf)?;
+            free.push(buf);
+        }
+    }
+    output.flush()
+}
+
+/// Complement of a bounded unit-step window: the first `start` chunks, then
+/// everything past chunk `end` verbatim, with the window between them skipped
+/// without copying.
+pub(crate) fn slice_gap<S: Split, R: BufRead, W: Write>(
+    mut split: S,
+    mut input: R,
+    mut output: W,
+    start: usize,
+    end: usize,
+) -> io::Result<()> {
+    for _ in 0..start {
+        if split.read_to(&mut input, &mut output)? == 0 {
+            return output.flush();
+        }
+    }
+    let gap = end.saturating_sub(start);
+    if split.skip_n(&mut input, gap)? == gap {
+        split.copy_rest(&mut input, &mut output)?;
+    }
+    output.flush()
+}
+
+/// Union of several forward ranges (`0:5,100:110,-3:`) in one pass. With
+/// head-relative bounds only, each chunk's fate is known on arrival: the gaps
+/// are bulk-skipped and the survivors written straight from the reader, and
+/// reading stops after the last selectable chunk (a complement has no last
+/// one, so it decides chunk by chunk to the end). A tail-relative bound
+/// delays every decision by the plan's delay, so the last `delay` chunks wait
+/// in a queue (recycled through a free pool) until enough successors arrive
+/// or EOF fixes the length; the record limit bounds each waiting chunk.
+pub(crate) fn slice_union<S: Split, R: BufRead, W: Write>(
+    mut split: S,
+    mut input: R,
+    mut output: W,
+    plan: &UnionPlan,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    let delay = plan.delay();
+    if delay == 0 && plan.is_complement() {
+        let mut index = 0;
+        loop {
+            let len = if plan.selects(index, None) {
+                split.read_to(&mut input, &mut output)?
+            } else {
+                split.skip(&mut input)?
+            };
+            if len == 0 {
+                break;
+            }
+            index += 1;
+        }
+        return output.flush();
+    }
+    if delay == 0 {
+        let mut index = 0;
+        while let Some(target) = plan.next_selected(index) {
+            let gap = target - index;
+            if split.skip_n(&mut input, gap)? < gap {
+                break;
+            }
+            if split.read_to(&mut input, &mut output)? == 0 {
+                break;
+            }
+            index = target + 1;
+        }
+        return output.flush();
+    }
+    let mut pending: VecDeque<Vec<u8>> = VecDeque::new();
+    let mut free: Vec<Vec<u8>> = Vec::new();
+    // Chunks read so far; the queue holds the last `pending.len()` of them.
+    let mut total = 0usize;
+    loop {
+        let mut buf = free.pop().unwrap_or_default();
+        buf.clear();
+        let mut limited = LimitedVec::new(&mut buf, max_record_size);
+        if split.read_to(&mut input, &mut limited)? == 0 {
+            break;
+        }
+        pending.push_back(buf);
+        total += 1;
+        if pending.len() > delay {
+            let index = total - pending.len();
+            let buf = pending.pop_front().expect("the queue outgrew the delay");
+            if plan.selects(index, None) {
+                output.write_all(&buf)?;
+            }
+            free.push(buf);
+        }
+    }
+    let first = total - pending.len();
+    for (offset, buf) in pending.iter().enumerate() {
+        if plan.selects(first + offset, Some(total)) {
+            output.write_all(buf)?;
+        }
+    }
+    output.flush()
+}
+
+/// A range with `/regex/` endpoints, in one pass. A pattern start is found by
+/// testing each chunk's content as it streams past — chunks before it are
+/// emitted only for a complement — and the element that matches opens the
+/// range. Inside it the stride picks the survivors, until the end: a counted
+/// or absolute end needs no content, so chunks pass straight from the reader
+/// and a non-complement stops reading there; a pattern end tests every chunk
+/// after the start, and the first match closes the range (exclusive). The
+/// record limit bounds each chunk held for a test.
+pub(crate) fn slice_anchored<S: Split, R: BufRead, W: Write>(
+    mut split: S,
+    mut input: R,
+    mut output: W,
+    plan: &AnchoredPlan,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    let range = plan.range();
+    let complement = plan.is_complement();
+    let step = range.step.get();
+    let mut buf = Vec::new();
+    // Chunks consumed so far; once the start is found, also its index.
+    let mut index = 0usize;
+    // Whether `buf` holds the start chunk, read while searching for it.
+    let mut held = false;
+    match &range.start {
+        Anchor::Index(start) => {
+            let skipped = if complement {
+                pass(&mut split, &mut input, &mut output, *start, true)?
+            } else {
+                split.skip_n(&mut input, *start)?
+            };
+            if skipped < *start {
+                return output.flush();
+            }
+            index = *start;
+        }
+        Anchor::Match(pattern) => loop {
+            buf.clear();
+            if split.read_to(&mut input, &mut LimitedVec::new(&mut buf, max_record_size))? == 0 {
+                return output.flush();
+            }
+            if pattern.is_match(split.content(&buf)) {
+                held = true;
+                break;
+            }
+            if complement {
+                output.write_all(&buf)?;
+            }
+            index += 1;
+        },
+    }
+    let count = match &range.end {
+        None => None,
+        Some(AnchoredEnd::Index(end)) => Some(end.saturating_sub(index)),
+        Some(AnchoredEnd::Ahead(count)) => Some(*count),
+        Some(AnchoredEnd::Match(pattern)) => {
+            // Offset of the current chunk from the start.
+            let mut offset = 0usize;
+            loop {
+                if !held {
+                    buf.clear();
+                    let mut limited = LimitedVec::new(&mut buf, max_record_size);
+                    if split.read_to(&mut input, &mut limited)? == 0 {
+                        return output.flush();
+                    }
+                    if offset > 0 && pattern.is_match(split.content(&buf)) {
+                        break;
+                    }
+                }
+                held = false;
+                if (offset % step == 0) != complement {
+                    output.write_all(&buf)?;
+                }
+                offset += 1;
+            }
+            // `buf` holds the closing chunk, the first one past the range.
+            if complement {
+                output.write_all(&buf)?;
+                split.copy_rest(&mut input, &mut output)?;
+            }
+            return output.flush();
+        }
+    };
+    if count == Some(0) {
+        if held && complement {
+            output.write_all(&buf)?;
+        }
+        if complement {
+            split.copy_rest(&mut input, &mut output)?;
+        }
+        return output.flush();
+    }
+    // The start chunk, if already read, is offset 0 and always on the stride.
+    let mut offset = 0usize;
+    if held {
+        if !complement {
             output.write_all(&buf)?;
-            free.push(buf);
+        }
+        offset = 1;
+    }
+    match count {
+        None if step == 1 => {
+            if !complement {
+                split.copy_rest(&mut input, &mut output)?;
+            }
+        }
+        None => loop {
+            let emit = (offset % step == 0) != complement;
+            if pass(&mut split, &mut input, &mut output, 1, emit)? == 0 {
+                break;
+            }
+            offset += 1;
+        },
+        Some(count) => {
+            while offset < count {
+                let emit = (offset % step == 0) != complement;
+                if pass(&mut split, &mut input, &mut output, 1, emit)? == 0 {
+                    return output.flush();
+                }
+                offset += 1;
+            }
+            if complement {
+                split.copy_rest(&mut input, &mut output)?;
+            }
         }
     }
     output.flush()
 }
 
+/// Advance up to `n` chunks, writing them to `output` when `emit` is set;
+/// returns how many there were, fewer than `n` only at end of stream.
+#[inline]
+fn pass<S: Split, R: BufRead, W: Write>(
+    split: &mut S,
+    input: &mut R,
+    output: &mut W,
+    n: usize,
+    emit: bool,
+) -> io::Result<usize> {
+    if !emit {
+        return split.skip_n(input, n);
+    }
+    for passed in 0..n {
+        if split.read_to(input, output)? == 0 {
+            return Ok(passed);
+        }
+    }
+    Ok(n)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1032,7 +1923,7 @@ mod tests {
     #[test]
     fn stepped_straddle() {
         // capacity 1 forces every delimiter to straddle a fill_buf boundary;
-        // stepping drives scan_until_delim's carry from both the skip and the
+        // stepping drives DelimiterEnd's carry from both the skip and the
         // read call sites on alternating chunks.
         for (input, delim) in [
             (&b"a||b||c||d||e"[..], &b"||"[..]),
@@ -1593,23 +2484,32 @@ mod tests {
         use super::*;
         use std::cell::Cell;
 
+        fn read_all<S: Split, R: BufRead>(
+            split: S,
+            input: R,
+            max_record_size: Option<usize>,
+        ) -> io::Result<Vec<u8>> {
+            let mut data = Vec::new();
+            read_all_with_record_limit(split, input, &mut data, max_record_size)?;
+            Ok(data)
+        }
+
         #[test]
         fn concatenates_all_records_verbatim() {
             let input = b"sl\xaace\nbin\xff\nslice\xaa";
-            let data = read_all_with_record_limit(Byte(b'\n'), &input[..], None).unwrap();
+            let data = read_all(Byte(b'\n'), &input[..], None).unwrap();
             assert_eq!(data, input);
         }
 
         #[test]
         fn record_limit_allows_exact_size() {
-            let data =
-                read_all_with_record_limit(Byte(b'\n'), &b"abc\ndef\n"[..], Some(4)).unwrap();
+            let data = read_all(Byte(b'\n'), &b"abc\ndef\n"[..], Some(4)).unwrap();
             assert_eq!(data, b"abc\ndef\n");
         }
 
         #[test]
         fn record_limit_rejects_oversized_record_mid_stream() {
-            let err = read_all_with_record_limit(Byte(b'\n'), &b"ok\ntoolong\nx\n"[..], Some(4))
+            let err = read_all(Byte(b'\n'), &b"ok\ntoolong\nx\n"[..], Some(4))
                 .expect_err("an oversized record must fail the read");
             assert_eq!(err.kind(), io::ErrorKind::InvalidData);
             assert!(err.to_string().contains("--max-record-size=4 bytes"));
@@ -1617,7 +2517,7 @@ mod tests {
 
         #[test]
         fn record_limit_rejects_oversized_unterminated_tail() {
-            let err = read_all_with_record_limit(Byte(b'\n'), &b"ok\ntoolong"[..], Some(4))
+            let err = read_all(Byte(b'\n'), &b"ok\ntoolong"[..], Some(4))
                 .expect_err("the delimiter-less tail is still one record");
             assert_eq!(err.kind(), io::ErrorKind::InvalidData);
         }
@@ -1628,7 +2528,7 @@ mod tests {
             // Capacities below the delimiter length force the straddle path.
             for capacity in [1, 2, 3, 8 * 1024] {
                 let reader = BufReader::with_capacity(capacity, &input[..]);
-                let data = read_all_with_record_limit(Bytes::new(b"||"), reader, Some(6)).unwrap();
+                let data = read_all(Bytes::new(b"||"), reader, Some(6)).unwrap();
                 assert_eq!(data, input);
             }
         }
@@ -1648,7 +2548,7 @@ mod tests {
             let big = vec![b'x'; 1024 * 1024];
             let served = Cell::new(0);
             let reader = BufReader::with_capacity(4 * 1024, Counted(&big[..], &served));
-            let err = read_all_with_record_limit(Byte(b'\n'), reader, Some(1024))
+            let err = read_all(Byte(b'\n'), reader, Some(1024))
                 .expect_err("an endless record must be rejected");
             assert_eq!(err.kind(), io::ErrorKind::InvalidData);
             assert!(
@@ -1658,4 +2558,555 @@ mod tests {
             );
         }
     }
+
+    mod spans {
+        use super::*;
+
+        // An element larger than the table's budget, or any buffer, is
+        // measured as it passes: only its last delimiter's worth is kept.
+        #[test]
+        fn elements_larger_than_the_budget_are_measured() {
+            let input = [vec![b'x'; 1024 * 1024], b"xy\nxy".to_vec()].concat();
+            let reader = BufReader::with_capacity(4 * 1024, &input[..]);
+            let mut spans = chunk_spans(Bytes::new(b"xy"), reader, None, 2, 0).unwrap();
+            assert_eq!(spans.len(), 2);
+            assert_eq!(spans.content(0, 2).unwrap(), (0, 1024 * 1024));
+            assert_eq!(
+                spans.content(1, 2).unwrap(),
+                (1024 * 1024 + 2, 1024 * 1024 + 3)
+            );
+            let mut measured = Measured::new(2, None);
+            measured.write_all(&input).unwrap();
+            assert_eq!(
+                (measured.written(), measured.tail()),
+                (input.len(), &b"xy"[..])
+            );
+            assert!(measured.tail.capacity() < 64);
+        }
+
+        #[test]
+        fn the_record_limit_still_applies() {
+            for delimiter in [0, 1] {
+                let err = chunk_spans(Byte(b'\n'), &b"ok\ntoolong\n"[..], Some(4), delimiter, 0)
+                    .err()
+                    .expect("an oversized record must fail");
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            }
+        }
+    }
+
+    mod union {
+        use super::*;
+        use crate::range::{Plan, SliceRanges};
+        use std::str::FromStr;
+
+        fn union_plan(ranges: &str) -> UnionPlan {
+            match SliceRanges::from_str(ranges).unwrap().plan() {
+                Plan::Union(union) => union,
+                other => panic!("{ranges} must merge into a union, planned {other:?}"),
+            }
+        }
+
+        fn unioned<S: Split>(
+            split: S,
+            input: &[u8],
+            ranges: &str,
+            capacity: usize,
+            max_record_size: Option<usize>,
+        ) -> io::Result<Vec<u8>> {
+            let mut out = Vec::new();
+            slice_union(
+                split,
+                BufReader::with_capacity(capacity, input),
+                &mut out,
+                &union_plan(ranges),
+                max_record_size,
+            )?;
+            Ok(out)
+        }
+
+        // Oracle: the plan's own EOF-time decision over the materialized
+        // chunks, so the streaming paths (early decisions, bulk skips, the
+        // queue) are checked against the selection rule they implement.
+        fn reference(input: &[u8], ranges: &str) -> Vec<u8> {
+            let plan = union_plan(ranges);
+            let all = chunks(Byte(b'\n'), input);
+            let len = all.len();
+            all.into_iter()
+                .enumerate()
+                .filter(|&(i, _)| plan.selects(i, Some(len)))
+                .flat_map(|(_, chunk)| chunk)
+                .collect()
+        }
+
+        #[test]
+        fn streaming_matches_the_eof_decision() {
+            let inputs: Vec<Vec<u8>> = (0..=12)
+                .map(|n| (0..n).map(|i| format!("l{i}\n")).collect::<String>().into())
+                .chain([b"a\nb\nc".to_vec(), b"\n\n\n".to_vec()])
+                .collect();
+            for ranges in [
+                "0:2,4:6", "::3,1:3", "0:2,-3:", "1:-4,-2:", "-5:-3,7:", "0:1,-1:",
+            ] {
+                for input in &inputs {
+                    for capacity in [1, 2, 8 * 1024] {
+                        assert_eq!(
+                            unioned(Byte(b'\n'), input, ranges, capacity, None).unwrap(),
+                            reference(input, ranges),
+                            "{ranges} on {input:?} capacity {capacity}"
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn overlapping_ranges_emit_each_chunk_once() {
+            let input = b"0\n1\n2\n3\n4\n";
+            let out = unioned(Byte(b'\n'), input, "0:3,1:4,2:3", 8 * 1024, None).unwrap();
+            assert_eq!(out, b"0\n1\n2\n3\n");
+        }
+
+        #[test]
+        fn each_byte_is_one_chunk() {
+            assert_eq!(chunks_at(EachByte, b"ab\n", 2), [b"a", b"b", b"\n"]);
+            let out = unioned(EachByte, b"abcdefgh", "0:2,-2:", 3, None).unwrap();
+            assert_eq!(out, b"abgh");
+        }
+
+        // A head-relative union stops reading after its last selectable
+        // chunk.
+        #[test]
+        fn bounded_union_stops_reading() {
+            let input = b"0\n1\n2\n3\n4\n";
+            let mut reader = BufReader::with_capacity(1, &input[..]);
+            let mut out = Vec::new();
+            slice_union(
+                Byte(b'\n'),
+                &mut reader,
+                &mut out,
+                &union_plan("0:1,2:3"),
+                None,
+            )
+            .unwrap();
+            assert_eq!(out, b"0\n2\n");
+            let mut rest = Vec::new();
+            io::Read::read_to_end(&mut reader, &mut rest).unwrap();
+            assert_eq!(rest, b"3\n4\n");
+        }
+
+        #[test]
+        fn complement_emits_the_unselected_chunks() {
+            let input = b"0\n1\n2\n3\n4\n5\n6\n7";
+            for (ranges, expected) in [
+                ("0:2,5:7", &b"2\n3\n4\n7"[..]),
+                ("::3,1:2", b"2\n4\n5\n7"),
+                ("0:1,-2:", b"1\n2\n3\n4\n5\n"),
+            ] {
+                let plan = match SliceRanges::from_str(ranges).unwrap().plan().complement() {
+                    Plan::Union(union) => union,
+                    other => panic!("{ranges} must invert into a union, planned {other:?}"),
+                };
+                for capacity in [1, 3, 8192] {
+                    let mut out = Vec::new();
+                    slice_union(
+                        Byte(b'\n'),
+                        BufReader::with_capacity(capacity, &input[..]),
+                        &mut out,
+                        &plan,
+                        None,
+                    )
+                    .unwrap();
+                    assert_eq!(out, expected, "{ranges} at capacity {capacity}");
+                }
+            }
+        }
+
+        #[test]
+        fn record_limit_applies_to_waiting_chunks() {
+            let err = unioned(Byte(b'\n'), b"ok\ntoo long\n", "0:1,-1:", 8 * 1024, Some(4))
+                .expect_err("a retained record over the limit must be rejected");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            // Nothing is retained without a tail-relative bound.
+            let out = unioned(Byte(b'\n'), b"too long\nok\n", "0:1,1:2", 8 * 1024, Some(4));
+            assert_eq!(out.unwrap(), b"too long\nok\n");
+        }
+    }
+
+    mod gap {
+        use super::*;
+
+        fn gapped(input: &[u8], start: usize, end: usize) -> Vec<u8> {
+            let mut out = Vec::new();
+            slice_gap(
+                Byte(b'\n'),
+                BufReader::with_capacity(2, input),
+                &mut out,
+                start,
+                end,
+            )
+            .expect("");
+            out
+        }
+
+        #[test]
+        fn emits_head_and_tail() {
+            assert_eq!(gapped(b"a\nb\nc\nd\ne", 1, 3), b"a\nd\ne");
+        }
+
+        #[test]
+        fn short_input_keeps_what_precedes_the_window() {
+            assert_eq!(gapped(b"a\nb\n", 1, 5), b"a\n");
+            assert_eq!(gapped(b"a\nb", 4, 5), b"a\nb");
+        }
+
+        #[test]
+        fn multi_byte_delimiter() {
+            let mut out = Vec::new();
+            slice_gap(Bytes::new(b"||"), &b"a||b||c"[..], &mut out, 1, 2).expect("");
+            assert_eq!(out, b"a||c");
+        }
+    }
+
+    mod csv {
+        use super::*;
+
+        const INPUT: &[u8] = b"id,note\n1,\"two\nlines\"\n2,\"say \"\"hi\"\"\"\n3,\"a,b\"";
+
+        #[test]
+        fn quoted_newlines_stay_in_the_record() {
+            assert_eq!(
+                chunks(Csv, INPUT),
+                [
+                    &b"id,note\n"[..],
+                    b"1,\"two\nlines\"\n",
+                    b"2,\"say \"\"hi\"\"\"\n",
+                    b"3,\"a,b\"",
+                ]
+            );
+        }
+
+        #[test]
+        fn quote_state_survives_block_boundaries() {
+            for capacity in 1..=8 {
+                assert_eq!(chunks_at(Csv, INPUT, capacity), chunks(Csv, INPUT));
+            }
+        }
+
+        #[test]
+        fn crlf_and_empty_records() {
+            assert_eq!(
+                chunks(Csv, b"a\r\n\r\n\"b\r\nc\"\r\n"),
+                [&b"a\r\n"[..], b"\r\n", b"\"b\r\nc\"\r\n"]
+            );
+        }
+
+        #[test]
+        fn unterminated_quote_runs_to_the_end() {
+            assert_eq!(chunks(Csv, b"a\n\"b\nc\nd"), [&b"a\n"[..], b"\"b\nc\nd"]);
+        }
+
+        #[test]
+        fn drivers_slice_records() {
+            let mut out = Vec::new();
+            slice_tail(
+                Csv,
+                INPUT,
+                &mut out,
+                NonZeroUsize::new(2).unwrap(),
+                None,
+                NonZeroUsize::MIN,
+            )
+            .expect("");
+            assert_eq!(out, b"2,\"say \"\"hi\"\"\"\n3,\"a,b\"");
+            let mut out = Vec::new();
+            slice_stepped(Csv, INPUT, &mut out, 0, None, NonZeroUsize::new(2).unwrap()).expect("");
+            assert_eq!(out, b"id,note\n2,\"say \"\"hi\"\"\"\n");
+        }
+
+        #[test]
+        fn content_drops_the_newline_only() {
+            assert_eq!(Csv.content(b"a,b\r\n"), b"a,b\r");
+            assert_eq!(Csv.content(b"a,b"), b"a,b");
+        }
+    }
+
+    mod regex {
+        use super::*;
+
+        fn pattern(source: &str) -> DelimiterRegex {
+            source.parse().expect("valid pattern")
+        }
+
+        // Independent oracle: the leftmost-first match from each record's
+        // start, searched in the whole input at once.
+        fn whole(input: &[u8], delimiter: &DelimiterRegex) -> Vec<Vec<u8>> {
+            let mut chunks = Vec::new();
+            let mut pos = 0;
+            while pos < input.len() {
+                let end = (delimiter.regex.find_at(input, pos)).map_or(input.len(), |m| m.end());
+                chunks.push(input[pos..end].to_vec());
+                pos = end;
+            }
+            chunks
+        }
+
+        const CASES: &[(&str, &[u8])] = &[
+            (r"\n\s*\n", b"a\n\nb\n \n\t\nc\n\n\n\nd"),
+            (r"ab+c|b", b"xabbbcxbx"),
+            (r"\n\s*\n", b"\n\n\n"),
+            (r",+", b"a,,b,c,,,"),
+            (r"^-{3,}\n", b"a\n---\nb---\n-----\nc"),
+            (r"^x", b"x\nxx\nyx"),
+            (r"\bend\b", b"bend end ends end"),
+            (r"\d+", b"abc"),
+            (r"\n\s*\n", b""),
+        ];
+
+        #[test]
+        fn chunks_match_the_whole_input_search_across_block_boundaries() {
+            for &(source, input) in CASES {
+                let delimiter = pattern(source);
+                for capacity in [1, 2, 3, 8 * 1024] {
+                    assert_eq!(
+                        chunks_at(RegexDelimited::new(&delimiter, None), input, capacity),
+                        whole(input, &delimiter),
+                        "/{source}/ over {input:?} at capacity {capacity}"
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn a_match_still_growing_past_a_block_is_not_cut_short() {
+            let delimiter = pattern(r"\n\s*\n");
+            assert_eq!(
+                chunks_at(RegexDelimited::new(&delimiter, None), b"a\n \n\t\n\nb", 2),
+                [&b"a\n \n\t\n\n"[..], b"b"]
+            );
+        }
+
+        #[test]
+        fn empty_matching_patterns_are_rejected() {
+            for source in ["", "x*", "^", "$", r"\b", "a|"] {
+                assert!(source.parse::<DelimiterRegex>().is_err(), "{source}");
+            }
+            assert!("(".parse::<DelimiterRegex>().is_err());
+        }
+
+        #[test]
+        fn skips_measure_the_records_without_holding_them() {
+            for &(source, input) in CASES {
+                let delimiter = pattern(source);
+                let lens: Vec<_> = whole(input, &delimiter).iter().map(Vec::len).collect();
+                for capacity in [1, 2, 3, 8 * 1024] {
+                    let mut split = RegexDelimited::new(&delimiter, None);
+                    let mut reader = BufReader::with_capacity(capacity, input);
+                    let skipped: Vec<_> = std::iter::from_fn(|| {
+                        Some(split.skip(&mut reader).unwrap()).filter(|&len| len > 0)
+                    })
+                    .collect();
+                    assert_eq!(skipped, lens, "/{source}/ at capacity {capacity}");
+                }
+            }
+            let delimiter = pattern(r"\n\s*\n");
+            let input = [vec![b'x'; 1024 * 1024], b"\n\ny".to_vec()].concat();
+            let mut split = RegexDelimited::new(&delimiter, None);
+            let mut reader = BufReader::with_capacity(4 * 1024, &input[..]);
+            assert_eq!(split.skip(&mut reader).unwrap(), 1024 * 1024 + 2);
+            assert!(
+                split.buf.capacity() <= 16 * 1024,
+                "{}",
+                split.buf.capacity()
+            );
+            assert_eq!(split.skip(&mut reader).unwrap(), 1);
+        }
+
+        #[test]
+        fn content_drops_the_match_that_ends_the_chunk() {
+            let delimiter = pattern(r"\n\s*\n");
+            let split = RegexDelimited::new(&delimiter, None);
+            assert_eq!(split.content(b"a\n \n"), b"a");
+            assert_eq!(split.content(b"a\nb"), b"a\nb");
+        }
+
+        #[test]
+        fn copy_rest_drains_the_read_ahead_first() {
+            let delimiter = pattern(",");
+            let mut split = RegexDelimited::new(&delimiter, None);
+            let mut input = BufReader::with_capacity(16, &b"a,b,c,d"[..]);
+            assert_eq!(split.skip(&mut input).expect(""), 2);
+            let mut out = Vec::new();
+            assert_eq!(split.copy_rest(&mut input, &mut out).expect(""), 5);
+            assert_eq!(out, b"b,c,d");
+        }
+
+        #[test]
+        fn record_limit_bounds_each_record() {
+            let delimiter = pattern("-+");
+            let limited = |input: &[u8]| {
+                let mut split = RegexDelimited::new(&delimiter, Some(4));
+                let mut input = BufReader::with_capacity(2, input);
+                let mut out = Vec::new();
+                while split.read_to(&mut input, &mut out)? != 0 {}
+                Ok::<_, io::Error>(out)
+            };
+            assert_eq!(limited(b"ab--cd-").expect(""), b"ab--cd-");
+            let err = limited(b"ab--cdef-").unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(limited(b"abcdefgh").is_err());
+        }
+
+        #[test]
+        fn drivers_slice_records() {
+            let delimiter = pattern(r"\n\s*\n");
+            let input: &[u8] = b"a\n\nb\n \nc\n\n\nd";
+            let mut out = Vec::new();
+            let split = RegexDelimited::new(&delimiter, None);
+            slice_window(split, input, &mut out, 1, Some(3)).expect("");
+            assert_eq!(out, b"b\n \nc\n\n\n");
+            let mut out = Vec::new();
+            let split = RegexDelimited::new(&delimiter, None);
+            let back = NonZeroUsize::new(2).unwrap();
+            slice_tail(split, input, &mut out, back, None, NonZeroUsize::MIN).expect("");
+            assert_eq!(out, b"c\n\n\nd");
+        }
+    }
+
+    mod anchored {
+        use super::*;
+        use crate::range::{Plan, SliceRanges};
+        use std::str::FromStr;
+
+        const INPUT: &[u8] = b"a\nBEGIN\nb\nc\nEND\nd\nBEGIN\ne";
+
+        fn anchored_plan(range: &str, complement: bool) -> AnchoredPlan {
+            let plan = SliceRanges::from_str(range).unwrap().plan();
+            match if complement { plan.complement() } else { plan } {
+                Plan::Anchored(plan) => plan,
+                other => panic!("{range} must anchor, planned {other:?}"),
+            }
+        }
+
+        fn anchored_with<S: Split>(
+            split: impl Fn() -> S,
+            input: &[u8],
+            range: &str,
+            complement: bool,
+        ) -> Vec<u8> {
+            let plan = anchored_plan(range, complement);
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let mut out = Vec::new();
+                slice_anchored(
+                    split(),
+                    BufReader::with_capacity(capacity, input),
+                    &mut out,
+                    &plan,
+                    None,
+                )
+                .unwrap();
+                out
+            });
+            let first = outputs.next().unwrap();
+            assert!(outputs.all(|out| out == first), "capacities diverged");
+            first
+        }
+
+        fn lines(range: &str) -> Vec<u8> {
+            anchored_with(|| Byte(b'\n'), INPUT, range, false)
+        }
+
+        #[test]
+        fn pattern_to_pattern_excludes_the_end() {
+            assert_eq!(lines("/^BEGIN/:/^END/"), b"BEGIN\nb\nc\n");
+        }
+
+        #[test]
+        fn only_the_first_match_anchors() {
+            assert_eq!(lines("/^BEGIN/:+2"), b"BEGIN\nb\n");
+            assert_eq!(lines("/BEGIN/:/BEGIN/"), b"BEGIN\nb\nc\nEND\nd\n");
+        }
+
+        #[test]
+        fn unmatched_start_selects_nothing() {
+            assert_eq!(lines("/nope/:"), b"");
+        }
+
+        #[test]
+        fn unmatched_end_runs_to_the_end() {
+            assert_eq!(lines("/^d$/:/nope/"), b"d\nBEGIN\ne");
+        }
+
+        #[test]
+        fn index_start_never_ends_at_itself() {
+            assert_eq!(lines("4:/END/"), b"END\nd\nBEGIN\ne");
+        }
+
+        #[test]
+        fn absolute_end_and_step() {
+            assert_eq!(lines("/BEGIN/:6"), b"BEGIN\nb\nc\nEND\nd\n");
+            assert_eq!(lines("/BEGIN/::2"), b"BEGIN\nc\nd\ne");
+            assert_eq!(lines("/BEGIN/:/^d/:3"), b"BEGIN\nEND\n");
+        }
+
+        #[test]
+        fn dollar_anchors_before_the_delimiter() {
+            assert_eq!(lines("/^c$/:+1"), b"c\n");
+            let custom = anchored_with(|| Bytes::new(b"||"), b"x||END||y", "/^END$/:", false);
+            assert_eq!(custom, b"END||y");
+        }
+
+        #[test]
+        fn complement_is_everything_else() {
+            for range in [
+                "/^BEGIN/:/^END/",
+                "/^BEGIN/:+2",
+                "/BEGIN/:",
+                "/BEGIN/::2",
+                "/nope/:",
+                "2:/END/",
+                "/BEGIN/:/^d/:3",
+                "/e/:+0",
+            ] {
+                let selected = lines(range);
+                let complement = anchored_with(|| Byte(b'\n'), INPUT, range, true);
+                // Every line is terminated but the last, so the selected
+                // and complemented lines partition the input's lines.
+                let mut both: Vec<&[u8]> = selected
+                    .split_inclusive(|&b| b == b'\n')
+                    .chain(complement.split_inclusive(|&b| b == b'\n'))
+                    .collect();
+                both.sort();
+                let mut all: Vec<&[u8]> = INPUT.split_inclusive(|&b| b == b'\n').collect();
+                all.sort();
+                assert_eq!(both, all, "{range}");
+            }
+            assert_eq!(
+                anchored_with(|| Byte(b'\n'), INPUT, "/^BEGIN/:/^END/", true),
+                b"a\nEND\nd\nBEGIN\ne"
+            );
+        }
+
+        #[test]
+        fn each_byte_matches_single_bytes() {
+            assert_eq!(
+                anchored_with(|| EachByte, b"hello world", "/o/:/o/", false),
+                b"o w"
+            );
+        }
+
+        #[test]
+        fn record_limit_applies_while_searching() {
+            let plan = anchored_plan("/END/:", false);
+            let err = slice_anchored(
+                Byte(b'\n'),
+                &b"far too long\nEND\n"[..],
+                &mut Vec::new(),
+                &plan,
+                Some(4),
+            )
+            .expect_err("a held record over the limit must be rejected");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
 }
@@ -0,0 +1,188 @@
+//! Display columns: the cells a line takes up on a terminal. A grapheme
+//! cluster is as wide as `unicode-width` renders it — two for East Asian wide
+//! characters and most emoji, nothing for a combining mark, which its base
+//! carries anyway — a tab runs to the next tab stop, a control character
+//! takes no cell, and a byte that is not valid UTF-8 takes one, as the
+//! replacement character a terminal shows for it. A cluster is never split:
+//! it is printed, whole and verbatim, only when every column it covers is
+//! selected.
+
+use crate::{ext::GraphemeElements, range::Plan};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+/// The range `--columns` applies inside every line.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Columns {
+    pub(crate) plan: Plan,
+    pub(crate) tab_width: usize,
+}
+
+/// One cluster's bytes, with any zero-width clusters after it.
+struct Cell<'a> {
+    bytes: &'a [u8],
+    width: usize,
+}
+
+impl Columns {
+    /// The cells of one line, in order. Clusters that take no column ride
+    /// along with the one before them (with the first visible one at the
+    /// start of a line), so they are printed when it is; a line with no
+    /// visible cluster has no cells at all.
+    fn cells<'a>(&self, content: &'a [u8]) -> Vec<Cell<'a>> {
+        let mut cells: Vec<Cell<'a>> = Vec::new();
+        let (mut pos, mut column, mut leading) = (0, 0, 0);
+        for cluster in GraphemeElements::new(content) {
+            let width = self.width(cluster, column);
+            pos += cluster.len();
+            match cells.last_mut() {
+                Some(cell) if width == 0 => {
+                    let start = pos - cluster.len() - cell.bytes.len();
+                    cell.bytes = &content[start..pos];
+                }
+                None if width == 0 => leading = pos,
+                _ => {
+                    cells.push(Cell {
+                        bytes: &content[pos - cluster.len() - leading..pos],
+                        width,
+                    });
+                    leading = 0;
+                }
+            }
+            column += width;
+        }
+        cells
+    }
+
+    fn width(&self, cluster: &[u8], column: usize) -> usize {
+        match std::str::from_utf8(cluster) {
+            Ok("\t") => self.tab_width - column % self.tab_width,
+            Ok(text) if text.chars().all(char::is_control) => 0,
+            Ok(text) => text.width(),
+            Err(_) => 1,
+        }
+    }
+
+    /// Write the selected cells of one line's `content`, then its
+    /// `terminator`, keeping a CRLF ending whole as `--fields` does.
+    pub(crate) fn write_record<W: Write>(
+        &self,
+        output: &mut W,
+        content: &[u8],
+        terminator: Option<&[u8]>,
+    ) -> io::Result<()> {
+        let (content, cr) = match (terminator, content.strip_suffix(b"\r")) {
+            (Some(b"\n"), Some(content)) => (content, true),
+            _ => (content, false),
+        };
+        let cells = self.cells(content);
+        // Every column names the cell covering it, both for the plan's
+        // `/pattern/`s and to count how much of each cell is selected.
+        let mut owner = Vec::new();
+        let mut columns = Vec::new();
+        for (i, cell) in cells.iter().enumerate() {
+            owner.extend(std::iter::repeat_n(i, cell.width));
+            columns.extend(std::iter::repeat_n(cell.bytes, cell.width));
+        }
+        let selected = self.plan.select(&columns);
+        let mut covered = vec![0; cells.len()];
+        for &column in &selected {
+            covered[owner[column]] += 1;
+        }
+        for column in selected {
+            let i = owner[column];
+            // Printed on the walk's first visit to a wholly selected cell.
+            if covered[i] == cells[i].width {
+                output.write_all(cells[i].bytes)?;
+                covered[i] = 0;
+            }
+        }
+        if cr {
+            output.write_all(b"\r")?;
+        }
+        if let Some(terminator) = terminator {
+            output.write_all(terminator)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::SliceRanges;
+    use std::str::FromStr;
+
+    fn columns(range: &str, line: &str) -> String {
+        let columns = Columns {
+            plan: SliceRanges::from_str(range).unwrap().plan(),
+            tab_width: 4,
+        };
+        let mut out = Vec::new();
+        columns
+            .write_record(&mut out, line.as_bytes(), Some(b"\n"))
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn ascii_columns_are_characters() {
+        assert_eq!(columns(":3", "abcdef"), "abc\n");
+        assert_eq!(columns("-2:", "abcdef"), "ef\n");
+        assert_eq!(columns("::-2", "abcdef"), "fdb\n");
+    }
+
+    #[test]
+    fn wide_characters_take_two_columns_and_never_split() {
+        // 漢 | 字 | a — columns 0-1, 2-3, 4.
+        assert_eq!(columns(":4", "漢字a"), "漢字\n");
+        assert_eq!(columns(":3", "漢字a"), "漢\n");
+        assert_eq!(columns("1:", "漢字a"), "字a\n");
+        assert_eq!(columns("::-1", "漢字a"), "a字漢\n");
+    }
+
+    #[test]
+    fn combining_marks_and_emoji_sequences_stay_whole() {
+        assert_eq!(columns(":2", "e\u{301}xy"), "e\u{301}x\n");
+        assert_eq!(columns(":2", "👨‍👩‍👧b"), "👨‍👩‍👧\n");
+        assert_eq!(columns(":1", "👨‍👩‍👧b"), "\n");
+    }
+
+    #[test]
+    fn tabs_run_to_the_next_stop() {
+        // a | tab to column 4 | b.
+        assert_eq!(columns(":5", "a\tb"), "a\tb\n");
+        assert_eq!(columns(":4", "a\tb"), "a\t\n");
+        assert_eq!(columns(":3", "a\tb"), "a\n");
+        assert_eq!(columns("4:", "a\tb"), "b\n");
+    }
+
+    #[test]
+    fn zero_width_clusters_ride_along() {
+        assert_eq!(columns("1:", "a\u{200b}bc"), "bc\n");
+        assert_eq!(columns(":1", "a\u{200b}bc"), "a\u{200b}\n");
+        assert_eq!(columns(":1", "\x1bab"), "\x1ba\n");
+        assert_eq!(columns(":", "\u{200b}"), "\n");
+    }
+
+    #[test]
+    fn patterns_match_the_cell_under_each_column() {
+        assert_eq!(columns("/字/:", "漢字a"), "字a\n");
+    }
+
+    #[test]
+    fn crlf_and_invalid_bytes() {
+        let columns = Columns {
+            plan: SliceRanges::from_str("1:").unwrap().plan(),
+            tab_width: 8,
+        };
+        let mut out = Vec::new();
+        columns
+            .write_record(&mut out, b"\xffab\r", Some(b"\n"))
+            .unwrap();
+        assert_eq!(out, b"ab\r\n");
+        out.clear();
+        columns.write_record(&mut out, b"a\xffb", None).unwrap();
+        assert_eq!(out, b"\xffb");
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    ext::{Annotation, Columns},
+    range::Plan,
+};
+use memchr::memmem;
+use std::io::{self, Write};
+
+/// How `--fields` splits a record into fields and rejoins the selection.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub enum FieldSeparator {
+    /// Runs of ASCII whitespace, leading and trailing ones ignored (awk's
+    /// default); the selected fields are rejoined with one space.
+    #[default]
+    Whitespace,
+    /// An exact, non-empty delimiter: adjacent delimiters enclose an empty
+    /// field (cut's rule), and the selected fields are rejoined with it.
+    Delimiter(Vec<u8>),
+}
+
+impl FieldSeparator {
+    fn split<'a>(&self, content: &'a [u8]) -> Vec<&'a [u8]> {
+        match self {
+            FieldSeparator::Whitespace => content
+                .split(u8::is_ascii_whitespace)
+                .filter(|field| !field.is_empty())
+                .collect(),
+            FieldSeparator::Delimiter(delimiter) => {
+                debug_assert!(!delimiter.is_empty(), "rejected by the CLI");
+                let mut fields = Vec::new();
+                let mut pos = 0;
+                for hit in memmem::find_iter(content, delimiter) {
+                    fields.push(&content[pos..hit]);
+                    pos = hit + delimiter.len();
+                }
+                fields.push(&content[pos..]);
+                fields
+            }
+        }
+    }
+
+    #[inline]
+    fn join(&self) -> &[u8] {
+        match self {
+            FieldSeparator::Whitespace => b" ",
+            FieldSeparator::Delimiter(delimiter) => delimiter,
+        }
+    }
+}
+
+/// The column half of a 2-D selection: a field range applied inside every
+/// record the row range selects.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Fields {
+    pub(crate) plan: Plan,
+    pub(crate) separator: FieldSeparator,
+}
+
+impl Fields {
+    /// Write the selected fields of one record's `content`, then its
+    /// `terminator` (absent for an unterminated final record). A line's
+    /// CRLF ending stays whole, so the `\r` never sticks to the last field.
+    fn write_record<W: Write>(
+        &self,
+        output: &mut W,
+        content: &[u8],
+        terminator: Option<&[u8]>,
+    ) -> io::Result<()> {
+        let (content, cr) = match (terminator, content.strip_suffix(b"\r")) {
+            (Some(b"\n"), Some(content)) => (content, true),
+            _ => (content, false),
+        };
+        let fields = self.separator.split(content);
+        for (n, i) in self.plan.select(&fields).into_iter().enumerate() {
+            if n > 0 {
+                output.write_all(self.separator.join())?;
+            }
+            output.write_all(fields[i])?;
+        }
+        if cr {
+            output.write_all(b"\r")?;
+        }
+        if let Some(terminator) = terminator {
+            output.write_all(terminator)?;
+        }
+        Ok(())
+    }
+}
+
+/// How [`FieldWriter`] rewrites each record: to its selected fields, or to
+/// its selected display columns.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Rewrite {
+    Fields(Fields),
+    Columns(Columns),
+}
+
+impl Rewrite {
+    #[inline]
+    fn write_record<W: Write>(
+        &self,
+        output: &mut W,
+        content: &[u8],
+        terminator: Option<&[u8]>,
+    ) -> io::Result<()> {
+        match self {
+            Rewrite::Fields(fields) => fields.write_record(output, content, terminator),
+            Rewrite::Columns(columns) => columns.write_record(output, content, terminator),
+        }
+    }
+}
+
+/// An output adapter rewriting each record that passes through to its
+/// selected fields or columns. Every row driver emits whole records, each followed by
+/// the terminator except possibly the last, so re-splitting the output
+/// stream recovers the records without touching the drivers. `flush` ends
+/// the stream: the drivers flush only once they are done, which is when an
+/// unterminated final record is complete. Without a rewrite it passes
+/// writes straight through. A record's `-n`/`--offsets` prefix passes
+/// through ahead of its fields.
+pub(crate) struct FieldWriter<'a, W: Write> {
+    inner: W,
+    rewrite: Option<&'a Rewrite>,
+    terminator: &'a [u8],
+    annotation: Annotation,
+    /// Output received but not yet followed by a terminator.
+    pending: Vec<u8>,
+    /// Where the next terminator search in `pending` starts; everything
+    /// before it is known not to begin one.
+    searched: usize,
+    /// The length of the pending record's prefix, once all of it is in.
+    prefix: Option<usize>,
+}
+
+impl<'a, W: Write> FieldWriter<'a, W> {
+    #[inline]
+    pub(crate) fn new(
+        inner: W,
+        rewrite: Option<&'a Rewrite>,
+        terminator: &'a [u8],
+        annotation: Annotation,
+    ) -> Self {
+        debug_assert!(!terminator.is_empty(), "fields need a record mode");
+        Self {
+            inner,
+            rewrite,
+            terminator,
+            annotation,
+            pending: Vec::new(),
+            searched: 0,
+            prefix: None,
+        }
+    }
+}
+
+impl<W: Write> Write for FieldWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(rewrite) = self.rewrite else {
+            return self.inner.write(buf);
+        };
+        self.pending.extend_from_slice(buf);
+        let finder = memmem::Finder::new(self.terminator);
+        let mut from = 0;
+        loop {
+            // The terminator is searched for only past the prefix, whose
+            // digits and colons it may share.
+            let prefix = match self.prefix {
+                Some(prefix) => prefix,
+                None => match self.annotation.prefix_len(&self.pending[from..]) {
+                    Some(prefix) => {
+                        self.searched = from + prefix;
+                        *self.prefix.insert(prefix)
+                    }
+                    None => break,
+                },
+            };
+            let Some(hit) = finder.find(&self.pending[self.searched..]) else {
+                break;
+            };
+            let end = self.searched + hit;
+            let (head, content) = self.pending[from..end].split_at(prefix);
+            self.inner.write_all(head)?;
+            rewrite.write_record(&mut self.inner, content, Some(self.terminator))?;
+            from = end + self.terminator.len();
+            self.prefix = None;
+        }
+        self.pending.drain(..from);
+        self.searched = match self.prefix {
+            Some(prefix) => {
+                prefix.max(self.pending.len().saturating_sub(self.terminator.len() - 1))
+            }
+            None => 0,
+        };
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(rewrite) = self.rewrite {
+            if !self.pending.is_empty() {
+                let (head, content) = self.annotation.split_prefix(&self.pending);
+                self.inner.write_all(head)?;
+                rewrite.write_record(&mut self.inner, content, None)?;
+                self.pending.clear();
+                self.searched = 0;
+                self.prefix = None;
+            }
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::SliceRanges;
+    use std::str::FromStr;
+
+    fn fields(range: &str, separator: FieldSeparator) -> Rewrite {
+        Rewrite::Fields(Fields {
+            plan: SliceRanges::from_str(range).unwrap().plan(),
+            separator,
+        })
+    }
+
+    fn run(fields: &Rewrite, terminator: &[u8], writes: &[&[u8]]) -> Vec<u8> {
+        run_annotated(fields, terminator, Annotation::default(), writes)
+    }
+
+    fn run_annotated(
+        fields: &Rewrite,
+        terminator: &[u8],
+        annotation: Annotation,
+        writes: &[&[u8]],
+    ) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut writer = FieldWriter::new(&mut out, Some(fields), terminator, annotation);
+        for buf in writes {
+            writer.write_all(buf).unwrap();
+        }
+        writer.flush().unwrap();
+        out
+    }
+
+    fn tab(range: &str) -> Rewrite {
+        fields(range, FieldSeparator::Delimiter(b"\t".to_vec()))
+    }
+
+    #[test]
+    fn selects_fields_of_every_record() {
+        let out = run(&tab("1:3"), b"\n", &[b"a\tb\tc\td\n1\t2\t3\t4\n"]);
+        assert_eq!(out, b"b\tc\n2\t3\n");
+    }
+
+    #[test]
+    fn python_rules_apply_per_record() {
+        assert_eq!(
+            run(&tab("-2:"), b"\n", &[b"a\tb\tc\nx\ty\n"]),
+            b"b\tc\nx\ty\n"
+        );
+        assert_eq!(run(&tab("::-1"), b"\n", &[b"a\tb\tc\n"]), b"c\tb\ta\n");
+        assert_eq!(run(&tab("::2"), b"\n", &[b"a\tb\tc\td\te\n"]), b"a\tc\te\n");
+        assert_eq!(run(&tab("0:1,-1:"), b"\n", &[b"a\tb\tc\n"]), b"a\tc\n");
+        assert_eq!(run(&tab("/b/:"), b"\n", &[b"a\tb\tc\n"]), b"b\tc\n");
+    }
+
+    #[test]
+    fn records_split_across_writes() {
+        let out = run(&tab("1:"), b"\n", &[b"a\tb", b"\nc", b"\td\ne\tf"]);
+        assert_eq!(out, b"b\nd\nf");
+    }
+
+    #[test]
+    fn multibyte_terminator_split_across_writes() {
+        let out = run(&tab("0:1"), b"<>", &[b"a\tb<", b">c\td<", b">"]);
+        assert_eq!(out, b"a<>c<>");
+    }
+
+    #[test]
+    fn unterminated_final_record_stays_unterminated() {
+        assert_eq!(run(&tab("1:"), b"\n", &[b"a\tb\nc\td"]), b"b\nd");
+    }
+
+    #[test]
+    fn whitespace_runs_rejoin_with_one_space() {
+        let fields = fields("1:", FieldSeparator::Whitespace);
+        let out = run(&fields, b"\n", &[b"  a \t b   c\n\n"]);
+        assert_eq!(out, b"b c\n\n");
+    }
+
+    #[test]
+    fn adjacent_delimiters_enclose_empty_fields() {
+        let fields = fields("1:3", FieldSeparator::Delimiter(b",".to_vec()));
+        assert_eq!(run(&fields, b"\n", &[b"a,,c,d\n"]), b",c\n");
+    }
+
+    #[test]
+    fn crlf_stays_whole() {
+        assert_eq!(
+            run(&tab("-1:"), b"\n", &[b"a\tb\r\nc\td\r\n"]),
+            b"b\r\nd\r\n"
+        );
+    }
+
+    #[test]
+    fn passthrough_without_fields() {
+        let mut out = Vec::new();
+        let mut writer = FieldWriter::new(&mut out, None, b"\n", Annotation::default());
+        writer.write_all(b"a\tb\nc").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(out, b"a\tb\nc");
+    }
+
+    #[test]
+    fn prefixes_pass_through_ahead_of_the_fields() {
+        let annotation = Annotation {
+            number: Some(0),
+            offsets: true,
+            ..Annotation::default()
+        };
+        let out = run_annotated(
+            &tab("1:"),
+            b"\n",
+            annotation,
+            &[b"3:1", b"0:a\tb\n4:", b"14:c\td"],
+        );
+        assert_eq!(out, b"3:10:b\n4:14:d");
+        // A terminator made of the prefix's own bytes is not looked for there.
+        let fields = fields("1:", FieldSeparator::Delimiter(b",".to_vec()));
+        let out = run_annotated(&fields, b":", annotation, &[b"0:0:a,b:1:4:c,d:"]);
+        assert_eq!(out, b"0:0:b:1:4:d:");
+    }
+}
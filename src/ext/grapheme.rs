@@ -100,12 +100,12 @@ enum Carried {
     Invalid(Elem),
 }
 
-/// The streaming counterpart of [`GraphemeElements`]: each chunk is one
-/// element. Layered on the chars [`Scanner`] (which owns the fill_buf
+/// The streaming counterpart of `GraphemeElements`: each chunk is one
+/// element. Layered on the chars `Scanner` (which owns the fill_buf
 /// straddle handling); this layer groups scalars into clusters, holding the
 /// one element read past each boundary in `carry` and the current cluster's
 /// bytes as segmentation context.
-pub(crate) struct Graphemes {
+pub struct Graphemes {
     scanner: Scanner,
     carry: Option<Carried>,
     cluster: Vec<u8>,
@@ -113,7 +113,7 @@ pub(crate) struct Graphemes {
 
 impl Graphemes {
     #[inline]
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             scanner: Scanner::new(),
             carry: None,
@@ -183,6 +183,13 @@ impl Graphemes {
     }
 }
 
+impl Default for Graphemes {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Split for Graphemes {
     #[inline]
     fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
@@ -406,8 +413,13 @@ mod tests {
                 "the limit error must name grapheme mode: {err}"
             );
 
-            let err = read_all_with_record_limit(Graphemes::new(), data.as_slice(), Some(1024))
-                .expect_err("the reverse read is bounded the same way");
+            let err = read_all_with_record_limit(
+                Graphemes::new(),
+                data.as_slice(),
+                io::sink(),
+                Some(1024),
+            )
+            .expect_err("the reverse read is bounded the same way");
             assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
 
             let mut out = Vec::new();
@@ -1,6 +1,13 @@
+use crate::{
+    ext::Split,
+    range::{AnchoredCursor, DeferredPlan, Plan, ReverseWalk, SlicePlan, UnionPlan},
+};
 use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
     iter::{Skip, StepBy, Take},
     num::NonZeroUsize,
+    vec,
 };
 
 #[derive(Debug)]
@@ -36,3 +43,460 @@ pub(crate) trait IteratorExt {
 }
 
 impl<I: Iterator> IteratorExt for I {}
+
+/// A source of elements for [`Selected`]: one chunk at a time, with a bulk
+/// skip for the ones no plan selects, and the content a `/pattern/` endpoint
+/// matches against.
+pub trait Chunked {
+    /// One element, delimiter included.
+    type Chunk: AsRef<[u8]>;
+
+    /// The next element; `None` at end of input.
+    fn read_chunk(&mut self) -> io::Result<Option<Self::Chunk>>;
+
+    /// Skip up to `n` elements, returning how many there were; fewer than
+    /// `n` only at end of input.
+    fn skip_chunks(&mut self, n: usize) -> io::Result<usize>;
+
+    /// The part of `chunk` a `/pattern/` endpoint matches: [`Split::content`].
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8];
+}
+
+/// The elements of a reader as owned chunks, one allocation each. Skipped
+/// elements are never copied out.
+pub struct Chunks<S, R> {
+    split: S,
+    input: R,
+}
+
+impl<S: Split, R: BufRead> Chunks<S, R> {
+    #[inline]
+    pub fn new(split: S, input: R) -> Self {
+        Self { split, input }
+    }
+
+    /// The reader, positioned after the last element returned. A stateful
+    /// split may hold bytes read ahead of it.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
+
+impl<S: Split, R: BufRead> Chunked for Chunks<S, R> {
+    type Chunk = Vec<u8>;
+
+    #[inline]
+    fn read_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut chunk = Vec::new();
+        Ok((self.split.read_to(&mut self.input, &mut chunk)? > 0).then_some(chunk))
+    }
+
+    #[inline]
+    fn skip_chunks(&mut self, n: usize) -> io::Result<usize> {
+        self.split.skip_n(&mut self.input, n)
+    }
+
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        self.split.content(chunk)
+    }
+}
+
+impl<S: Split, R: BufRead> Iterator for Chunks<S, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_chunk().transpose()
+    }
+}
+
+/// The elements of in-memory data as subslices of it, for any [`Split`] —
+/// what `Utf8Elements` and `GraphemeElements` are for characters and
+/// clusters. Nothing is copied: the split only measures each element.
+pub struct SliceChunks<'a, S> {
+    split: S,
+    data: &'a [u8],
+    /// What the split has not read yet; a stateful one reads ahead of `pos`.
+    rest: &'a [u8],
+    /// Where the next element starts in `data`.
+    pos: usize,
+}
+
+impl<'a, S: Split> SliceChunks<'a, S> {
+    #[inline]
+    pub fn new(split: S, data: &'a [u8]) -> Self {
+        Self {
+            split,
+            data,
+            rest: data,
+            pos: 0,
+        }
+    }
+}
+
+impl<'a, S: Split> Chunked for SliceChunks<'a, S> {
+    type Chunk = &'a [u8];
+
+    #[inline]
+    fn read_chunk(&mut self) -> io::Result<Option<&'a [u8]>> {
+        let len = self.split.skip(&mut self.rest)?;
+        let chunk = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok((len > 0).then_some(chunk))
+    }
+
+    #[inline]
+    fn skip_chunks(&mut self, n: usize) -> io::Result<usize> {
+        for skipped in 0..n {
+            let len = self.split.skip(&mut self.rest)?;
+            if len == 0 {
+                return Ok(skipped);
+            }
+            self.pos += len;
+        }
+        Ok(n)
+    }
+
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        self.split.content(chunk)
+    }
+}
+
+impl<'a, S: Split> Iterator for SliceChunks<'a, S> {
+    type Item = io::Result<&'a [u8]>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_chunk().transpose()
+    }
+}
+
+/// The elements a [`Plan`] selects, in output order. Buffering is the
+/// command's: a head-relative range streams and stops reading past its end;
+/// `-k:` holds the last `k` elements until EOF; `:-k` streams `k` elements
+/// behind; a union with tail-relative bounds lags by the farthest of them;
+/// an anchored range streams; a reverse range, and one with a percentage,
+/// holds the whole input. After an error the iterator ends.
+pub struct Selected<C: Chunked> {
+    chunks: C,
+    /// Index of the next element to read, or of the front of `queue`.
+    index: usize,
+    state: State<C::Chunk>,
+}
+
+enum State<T> {
+    /// Decided on arrival; the unselected are skipped in bulk.
+    Span(SlicePlan),
+    /// A head-relative union, jumping from one selected element to the next.
+    Union(UnionPlan),
+    /// Decided on arrival, from each element's content.
+    Anchored(AnchoredCursor),
+    /// Decided once `delay()` successors are read, or at EOF, the length
+    /// then in hand.
+    Delayed {
+        rule: Delayed,
+        queue: VecDeque<T>,
+        len: Option<usize>,
+    },
+    /// Not yet read: the whole input is needed before the first element.
+    Whole(Plan),
+    /// The whole input, walked in output order.
+    Walk {
+        chunks: Vec<Option<T>>,
+        order: vec::IntoIter<usize>,
+    },
+    Done,
+}
+
+enum Delayed {
+    Deferred(DeferredPlan),
+    Union(UnionPlan),
+}
+
+impl Delayed {
+    fn delay(&self) -> usize {
+        match self {
+            Delayed::Deferred(DeferredPlan::Tail { back, .. } | DeferredPlan::Lag { back, .. }) => {
+                back.get()
+            }
+            Delayed::Deferred(DeferredPlan::Proportional(_)) => {
+                unreachable!("a proportional plan is walked whole")
+            }
+            Delayed::Union(plan) => plan.delay(),
+        }
+    }
+
+    /// Whether element `index` is selected; `len` as for
+    /// [`UnionPlan::selects`].
+    fn selects(&self, index: usize, len: Option<usize>) -> bool {
+        match (self, len) {
+            (Delayed::Union(plan), len) => plan.selects(index, len),
+            (Delayed::Deferred(plan), Some(len)) => plan
+                .resolve(len as u64)
+                .expect("an element count fits usize")
+                .contains(index),
+            // More than `back` elements follow: before a tail-relative start,
+            // and before a tail-relative end.
+            (Delayed::Deferred(DeferredPlan::Tail { .. }), None) => false,
+            (Delayed::Deferred(DeferredPlan::Lag { start, step, .. }), None) => {
+                index >= *start && (index - start) % step.get() == 0
+            }
+            (Delayed::Deferred(DeferredPlan::Proportional(_)), None) => {
+                unreachable!("a proportional plan is walked whole")
+            }
+        }
+    }
+}
+
+impl<C: Chunked> Selected<C> {
+    pub fn new(chunks: C, plan: &Plan) -> Self {
+        let state = match plan {
+            Plan::Resolved(plan) => State::Span(*plan),
+            Plan::Union(plan) if plan.delay() == 0 && !plan.is_complement() => {
+                State::Union(plan.clone())
+            }
+            Plan::Union(plan) => State::Delayed {
+                rule: Delayed::Union(plan.clone()),
+                queue: VecDeque::new(),
+                len: None,
+            },
+            Plan::Reverse(_)
+            | Plan::ReverseUnion(_)
+            | Plan::Deferred(DeferredPlan::Proportional(_)) => State::Whole(plan.clone()),
+            Plan::Deferred(plan) => State::Delayed {
+                rule: Delayed::Deferred(*plan),
+                queue: VecDeque::new(),
+                len: None,
+            },
+            Plan::Anchored(plan) => State::Anchored(plan.cursor()),
+        };
+        Self {
+            chunks,
+            index: 0,
+            state,
+        }
+    }
+
+    /// The next selected element, jumping to index `target` first.
+    fn read_at(&mut self, target: usize) -> io::Result<Option<C::Chunk>> {
+        let gap = target - self.index;
+        if self.chunks.skip_chunks(gap)? < gap {
+            return Ok(None);
+        }
+        self.index = target + 1;
+        self.chunks.read_chunk()
+    }
+
+    fn advance(&mut self) -> io::Result<Option<C::Chunk>> {
+        loop {
+            match &mut self.state {
+                State::Span(plan) => {
+                    let Some(target) = plan.next_selected(self.index) else {
+                        return Ok(None);
+                    };
+                    return self.read_at(target);
+                }
+                State::Union(plan) => {
+                    let Some(target) = plan.next_selected(self.index) else {
+                        return Ok(None);
+                    };
+                    return self.read_at(target);
+                }
+                State::Anchored(cursor) => {
+                    if cursor.exhausted() {
+                        return Ok(None);
+                    }
+                    let Some(chunk) = self.chunks.read_chunk()? else {
+                        return Ok(None);
+                    };
+                    if cursor.selects(self.chunks.content(chunk.as_ref())) {
+                        return Ok(Some(chunk));
+                    }
+                }
+                State::Delayed { rule, queue, len } => {
+                    if len.is_none() {
+                        match self.chunks.read_chunk()? {
+                            Some(chunk) => queue.push_back(chunk),
+                            None => *len = Some(self.index + queue.len()),
+                        }
+                        if len.is_none() && queue.len() <= rule.delay() {
+                            continue;
+                        }
+                    }
+                    let Some(chunk) = queue.pop_front() else {
+                        return Ok(None);
+                    };
+                    let index = self.index;
+                    self.index += 1;
+                    if rule.selects(index, *len) {
+                        return Ok(Some(chunk));
+                    }
+                }
+                State::Whole(plan) => {
+                    let mut chunks = Vec::new();
+                    while let Some(chunk) = self.chunks.read_chunk()? {
+                        chunks.push(Some(chunk));
+                    }
+                    let order: Vec<usize> = match plan {
+                        Plan::Reverse(plan) => plan.indices(chunks.len()).collect(),
+                        Plan::ReverseUnion(plan) => plan.indices(chunks.len()).collect(),
+                        Plan::Deferred(DeferredPlan::Proportional(plan)) => {
+                            plan.indices(chunks.len())
+                        }
+                        _ => unreachable!("only reverse and proportional plans are walked"),
+                    };
+                    self.state = State::Walk {
+                        chunks,
+                        order: order.into_iter(),
+                    };
+                }
+                State::Walk { chunks, order } => {
+                    return Ok(order.next().map(|index| {
+                        chunks[index]
+                            .take()
+                            .expect("a walk visits each element once")
+                    }));
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<C: Chunked> Iterator for Selected<C> {
+    type Item = io::Result<C::Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.advance().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.state = State::Done;
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ext::{Byte, Bytes, Chars, Graphemes},
+        range::SliceRanges,
+    };
+
+    fn selected<'a>(data: &'a [u8], range: &str) -> Vec<&'a [u8]> {
+        let plan = range.parse::<SliceRanges>().unwrap().plan();
+        Selected::new(SliceChunks::new(Byte(b'\n'), data), &plan)
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn slice_chunks_borrow_each_element() {
+        let chunks = |split| -> Vec<&[u8]> {
+            SliceChunks::new(split, "a--é--".as_bytes())
+                .collect::<io::Result<_>>()
+                .unwrap()
+        };
+        assert_eq!(chunks(Bytes::new(b"--")), [&b"a--"[..], "é--".as_bytes()]);
+        let chars: Vec<&[u8]> = SliceChunks::new(Chars::new(), "aé\u{301}".as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(chars, [&b"a"[..], "é".as_bytes(), "\u{301}".as_bytes()]);
+        let clusters: Vec<&[u8]> = SliceChunks::new(Graphemes::new(), "aé\u{301}".as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(clusters, [&b"a"[..], "é\u{301}".as_bytes()]);
+    }
+
+    #[test]
+    fn owned_chunks_match_borrowed_ones() {
+        let data = b"a\nb\nc";
+        let owned: Vec<Vec<u8>> = Chunks::new(Byte(b'\n'), &data[..])
+            .collect::<io::Result<_>>()
+            .unwrap();
+        let borrowed: Vec<&[u8]> = SliceChunks::new(Byte(b'\n'), data)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(owned, borrowed);
+    }
+
+    #[test]
+    fn every_plan_selects_what_the_in_memory_rules_do() {
+        let data: Vec<u8> = (0..9).flat_map(|i| format!("{i}\n").into_bytes()).collect();
+        let elements: Vec<&[u8]> = data.split_inclusive(|&b| b == b'\n').collect();
+        for range in [
+            ":",
+            "2:5",
+            "1::3",
+            "7:2",
+            "-3:",
+            "-5:-1:2",
+            ":-2",
+            "2:-3:2",
+            "::-1",
+            "-2::-3",
+            "0:2,-2:",
+            "1:3,5::2",
+            "3:1:-1,8::-2",
+            "/3/:",
+            "/2/:/6/:2",
+            "1:+3",
+        ] {
+            let plan = range.parse::<SliceRanges>().unwrap().plan();
+            for plan in [plan.clone(), plan.complement()] {
+                let expected: Vec<&[u8]> = plan
+                    .select(&elements)
+                    .into_iter()
+                    .map(|i| elements[i])
+                    .collect();
+                let got: Vec<&[u8]> = Selected::new(SliceChunks::new(Byte(b'\n'), &data), &plan)
+                    .collect::<io::Result<_>>()
+                    .unwrap();
+                assert_eq!(got, expected, "{range} as {plan:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn head_relative_ranges_stop_reading_at_their_end() {
+        // An endless reader: only a range that stops reading terminates.
+        let endless = io::BufReader::new(io::repeat(b'\n'));
+        let plan = "2:5".parse::<SliceRanges>().unwrap().plan();
+        let taken: Vec<Vec<u8>> = Selected::new(Chunks::new(Byte(b'\n'), endless), &plan)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(taken.len(), 3);
+        let plan = "/x/:+1".parse::<SliceRanges>().unwrap().plan();
+        let input = io::BufReader::new(io::Read::chain(&b"a\nx\n"[..], io::repeat(b'\n')));
+        let taken: Vec<Vec<u8>> = Selected::new(Chunks::new(Byte(b'\n'), input), &plan)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(taken, [b"x\n"]);
+    }
+
+    #[test]
+    fn tail_relative_ranges_hold_only_their_distance() {
+        assert_eq!(selected(b"a\nb\nc\nd", "-2:"), [&b"c\n"[..], b"d"]);
+        assert_eq!(selected(b"a\nb\nc\nd", ":-3"), [b"a\n"]);
+        assert_eq!(selected(b"a\n", "-5:"), [b"a\n"]);
+        assert!(selected(b"", "::-1").is_empty());
+    }
+
+    #[test]
+    fn an_error_ends_the_iteration() {
+        struct Failing;
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("boom"))
+            }
+        }
+        let plan = ":".parse::<SliceRanges>().unwrap().plan();
+        let mut selected =
+            Selected::new(Chunks::new(Byte(b'\n'), io::BufReader::new(Failing)), &plan);
+        assert!(selected.next().unwrap().is_err());
+        assert!(selected.next().is_none());
+    }
+}
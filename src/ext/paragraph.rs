@@ -0,0 +1,445 @@
+//! Paragraphs: blocks of lines separated by blank lines, as Perl's paragraph
+//! mode (`$/ = ""`) reads them. A blank line holds nothing but spaces and
+//! tabs before its `\n` (a `\r` before it too, so CRLF blank lines count).
+//! Runs of blank lines are normalized: a paragraph comes out as its lines,
+//! verbatim, then one empty line in the paragraph's own line ending (`\n`,
+//! or `\r\n` when its last line ends in CRLF); the rest of the run, and any
+//! blank lines before the first paragraph, are dropped. A final paragraph no
+//! blank line follows comes out as it is.
+
+use crate::ext::{
+    buf_read::{Measured, Split},
+    Offsets, OffsetsWriter,
+};
+use std::io::{self, BufRead, Write};
+
+/// Bytes a blank line may hold before its `\n`.
+#[inline]
+fn is_blank(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r')
+}
+
+/// Where a paragraph scan stands.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Phase {
+    /// Blank lines before the paragraph's first line.
+    Lead,
+    /// At the start of a line inside the paragraph.
+    LineStart,
+    /// Inside a line known not to be blank.
+    Line,
+    /// Blank lines after the paragraph, up to the next one's first line.
+    Trail,
+}
+
+/// The streaming paragraph split. Deciding that a line is not blank takes
+/// its first byte that is not a space or tab, so the blank lines after a
+/// paragraph are read to the start of the next one; that line's bytes so
+/// far wait in `ahead`. Each chunk is the normalized paragraph, but the
+/// length reported is the input it covers — the blank lines it dropped
+/// included — so offsets count input bytes.
+pub struct Paragraphs {
+    /// The start of the line being classified: blank so far, or, between
+    /// chunks, the next paragraph's first bytes.
+    ahead: Vec<u8>,
+    /// Input bytes the last chunk dropped before its first line.
+    lead: usize,
+}
+
+impl Paragraphs {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            ahead: Vec::new(),
+            lead: 0,
+        }
+    }
+
+    /// Read one paragraph, feeding its normalized bytes to `sink`. Returns
+    /// the input bytes it covers; `Ok(0)` once only blank lines remain.
+    fn next_paragraph<R: BufRead + ?Sized>(
+        &mut self,
+        r: &mut R,
+        mut sink: impl FnMut(&[u8]) -> io::Result<()>,
+    ) -> io::Result<usize> {
+        let mut phase = Phase::Lead;
+        let mut read = 0;
+        // Whether the last line written ended in CRLF, and the last byte
+        // written was a `\r` (a CRLF may straddle two blocks).
+        let mut crlf = false;
+        let mut after_cr = false;
+        self.lead = 0;
+        if !self.ahead.is_empty() {
+            sink(&self.ahead)?;
+            read = self.ahead.len();
+            self.ahead.clear();
+            phase = Phase::Line;
+        }
+        loop {
+            let block = match r.fill_buf() {
+                Ok([]) => break,
+                Ok(block) => block,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let mut used = 0;
+            let mut next = false;
+            while used < block.len() {
+                if phase == Phase::Line {
+                    let rest = &block[used..];
+                    match memchr::memchr(b'\n', rest) {
+                        Some(i) => {
+                            sink(&rest[..=i])?;
+                            crlf = if i > 0 {
+                                rest[i - 1] == b'\r'
+                            } else {
+                                after_cr
+                            };
+                            used += i + 1;
+                            phase = Phase::LineStart;
+                        }
+                        None => {
+                            sink(rest)?;
+                            after_cr = rest.last() == Some(&b'\r');
+                            used = block.len();
+                        }
+                    }
+                    continue;
+                }
+                let b = block[used];
+                used += 1;
+                if b == b'\n' {
+                    match phase {
+                        Phase::Lead => self.lead = read + used,
+                        Phase::LineStart => {
+                            sink(if crlf { b"\r\n" } else { b"\n" })?;
+                            phase = Phase::Trail;
+                        }
+                        Phase::Line | Phase::Trail => {}
+                    }
+                    self.ahead.clear();
+                    continue;
+                }
+                self.ahead.push(b);
+                if is_blank(b) {
+                    continue;
+                }
+                if phase == Phase::Trail {
+                    next = true;
+                    break;
+                }
+                sink(&self.ahead)?;
+                self.ahead.clear();
+                phase = Phase::Line;
+                after_cr = false;
+            }
+            r.consume(used);
+            read += used;
+            if next {
+                return Ok(read - self.ahead.len());
+            }
+        }
+        // A last line of blanks without its `\n` is a blank line too.
+        self.ahead.clear();
+        Ok(if phase == Phase::Lead { 0 } else { read })
+    }
+}
+
+impl Default for Paragraphs {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Strip one `\n` or `\r\n`.
+#[inline]
+fn strip_ending(chunk: &[u8]) -> &[u8] {
+    match chunk.strip_suffix(b"\n") {
+        Some(line) => line.strip_suffix(b"\r").unwrap_or(line),
+        None => chunk,
+    }
+}
+
+impl Split for Paragraphs {
+    #[inline]
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        self.next_paragraph(r, |bytes| w.write_all(bytes))
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        self.next_paragraph(r, |_| Ok(()))
+    }
+
+    /// The rest is normalized too, so it goes paragraph by paragraph.
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        let mut copied = 0;
+        loop {
+            match self.read_to(r, w)? {
+                0 => return Ok(copied),
+                len => copied += len as u64,
+            }
+        }
+    }
+
+    /// The paragraph without its empty line or its last line's ending, so
+    /// `$` anchors after its last character.
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        let text = strip_ending(chunk);
+        if text.len() < chunk.len() && text.ends_with(b"\n") {
+            strip_ending(text)
+        } else {
+            text
+        }
+    }
+
+    /// A paragraph is whole once the blank line after it is read.
+    #[inline]
+    fn terminated(&self, chunk: &[u8]) -> bool {
+        chunk.ends_with(b"\n\n") || chunk.ends_with(b"\n\r\n")
+    }
+}
+
+/// Where a paragraph lies in the input, for a reverse walk that writes it
+/// from there.
+pub(crate) struct ParagraphSpan {
+    /// Where the input it covers starts, blank lines before it included.
+    pub(crate) start: u64,
+    /// Its lines, without the last one's ending.
+    pub(crate) content: (u64, u64),
+    /// Its lines, the last one's ending included.
+    pub(crate) end: u64,
+    pub(crate) crlf: bool,
+    /// Whether a blank line follows it.
+    pub(crate) terminated: bool,
+}
+
+impl ParagraphSpan {
+    /// The empty line that ends it, in its own line ending.
+    #[inline]
+    pub(crate) fn separator(&self) -> &'static [u8] {
+        if self.crlf {
+            b"\r\n\r\n"
+        } else {
+            b"\n\n"
+        }
+    }
+}
+
+/// Every paragraph's span, a row of offsets each: the flags ride in the top
+/// bits of its end.
+pub(crate) struct ParagraphSpans(Offsets<4>);
+
+const CRLF: u64 = 1 << 63;
+const TERMINATED: u64 = 1 << 62;
+
+impl ParagraphSpans {
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn get(&mut self, i: usize) -> io::Result<ParagraphSpan> {
+        let [start, text, content, end] = self.0.get(i)?;
+        Ok(ParagraphSpan {
+            start,
+            content: (text, content),
+            end: end & !(CRLF | TERMINATED),
+            crlf: end & CRLF != 0,
+            terminated: end & TERMINATED != 0,
+        })
+    }
+}
+
+/// The span of every paragraph, tabled within `max_memory`, each measured as
+/// it passes like `chunk_spans` does.
+pub(crate) fn paragraph_spans<R: BufRead>(
+    mut input: R,
+    max_record_size: Option<usize>,
+    max_memory: usize,
+) -> io::Result<ParagraphSpans> {
+    let mut split = Paragraphs::new();
+    let mut spans = OffsetsWriter::new(max_memory);
+    // Two line endings at most are cut off a paragraph's end.
+    let mut measured = Measured::new(4, max_record_size);
+    let mut pos = 0;
+    loop {
+        measured.clear();
+        let len = split.read_to(&mut input, &mut measured)?;
+        if len == 0 {
+            return Ok(ParagraphSpans(spans.finish()?));
+        }
+        let tail = measured.tail();
+        let terminated = split.terminated(tail);
+        let lines = if terminated { strip_ending(tail) } else { tail };
+        let lines_len = (measured.written() - (tail.len() - lines.len())) as u64;
+        let content_len = lines_len - (lines.len() - strip_ending(lines).len()) as u64;
+        let text = pos + split.lead as u64;
+        let mut end = text + lines_len;
+        if lines.ends_with(b"\r\n") {
+            end |= CRLF;
+        }
+        if terminated {
+            end |= TERMINATED;
+        }
+        spans.push([pos, text, text + content_len, end])?;
+        pos += len as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::{slice_tail, slice_window};
+    use std::num::NonZeroUsize;
+
+    /// Every chunk and the input length it reports, read through a buffer
+    /// of `capacity` bytes.
+    fn chunks(input: &[u8], capacity: usize) -> Vec<(Vec<u8>, usize)> {
+        let mut reader = io::BufReader::with_capacity(capacity, input);
+        let mut split = Paragraphs::new();
+        let mut chunks = Vec::new();
+        loop {
+            let mut chunk = Vec::new();
+            match split.read_to(&mut reader, &mut chunk).unwrap() {
+                0 => return chunks,
+                len => chunks.push((chunk, len)),
+            }
+        }
+    }
+
+    fn paragraphs(input: &[u8]) -> Vec<Vec<u8>> {
+        let expected = chunks(input, 8192);
+        for capacity in [1, 2, 3] {
+            assert_eq!(chunks(input, capacity), expected, "capacity {capacity}");
+        }
+        let covered: usize = expected.iter().map(|(_, len)| len).sum();
+        assert!(covered <= input.len());
+        expected.into_iter().map(|(chunk, _)| chunk).collect()
+    }
+
+    #[test]
+    fn blank_line_runs_collapse_to_one_empty_line() {
+        assert_eq!(
+            paragraphs(b"a\nb\n\n\n\nc\n\nd"),
+            [&b"a\nb\n\n"[..], b"c\n\n", b"d"]
+        );
+    }
+
+    #[test]
+    fn whitespace_only_lines_are_blank() {
+        assert_eq!(
+            paragraphs(b"a\n  \n\t\n \tb\nc\n \n"),
+            [&b"a\n\n"[..], b" \tb\nc\n\n"]
+        );
+    }
+
+    #[test]
+    fn blank_lines_before_the_first_paragraph_are_dropped() {
+        assert_eq!(paragraphs(b"\n \n\na\n"), [b"a\n"]);
+        assert_eq!(chunks(b"\n \n\na\n\n\nb", 8192)[0].1, 8);
+    }
+
+    #[test]
+    fn crlf_paragraphs_keep_their_line_ending() {
+        assert_eq!(
+            paragraphs(b"a\r\nb\r\n \r\n\r\nc\n\r\nd\r\n"),
+            [&b"a\r\nb\r\n\r\n"[..], b"c\n\n", b"d\r\n"]
+        );
+    }
+
+    #[test]
+    fn blank_input_has_no_paragraphs() {
+        assert!(paragraphs(b"").is_empty());
+        assert!(paragraphs(b"\n\n \t\n  ").is_empty());
+    }
+
+    #[test]
+    fn a_trailing_line_of_blanks_is_dropped() {
+        assert_eq!(paragraphs(b"a\n  "), [b"a\n"]);
+        assert_eq!(paragraphs(b"a\n\n  "), [b"a\n\n"]);
+    }
+
+    #[test]
+    fn lengths_cover_the_input() {
+        let input = b"a\n\n \n\nb\r\n\r\nc";
+        let lens: Vec<_> = chunks(input, 8192).into_iter().map(|(_, n)| n).collect();
+        assert_eq!(lens, [6, 5, 1]);
+        assert_eq!(lens.iter().sum::<usize>(), input.len());
+    }
+
+    #[test]
+    fn content_and_termination() {
+        let split = Paragraphs::new();
+        assert_eq!(split.content(b"a\nb\n\n"), b"a\nb");
+        assert_eq!(split.content(b"a\r\n\r\n"), b"a");
+        assert_eq!(split.content(b"a\n"), b"a");
+        assert_eq!(split.content(b"a"), b"a");
+        assert!(split.terminated(b"a\n\n"));
+        assert!(split.terminated(b"a\r\n\r\n"));
+        assert!(!split.terminated(b"a\n"));
+        assert!(!split.terminated(b"a"));
+    }
+
+    #[test]
+    fn copy_rest_normalizes_too() {
+        let mut reader = &b"a\n\n\nb\n \nc"[..];
+        let mut split = Paragraphs::new();
+        split.skip(&mut reader).unwrap();
+        let mut out = Vec::new();
+        split.copy_rest(&mut reader, &mut out).unwrap();
+        assert_eq!(out, b"b\n\nc");
+    }
+
+    #[test]
+    fn spans_locate_each_paragraph() {
+        let input = b"\n\na\nb\n\n \nc\r\n\r\nd\n";
+        let mut spans = paragraph_spans(&input[..], None, 0).unwrap();
+        let located: Vec<_> = (0..spans.len())
+            .map(|i| {
+                let span = spans.get(i).unwrap();
+                let (start, end) = span.content;
+                (
+                    span.start,
+                    &input[start as usize..end as usize],
+                    span.end,
+                    span.separator(),
+                    span.terminated,
+                )
+            })
+            .collect();
+        assert_eq!(
+            located,
+            [
+                (0, &b"a\nb"[..], 6, &b"\n\n"[..], true),
+                (9, b"c", 12, b"\r\n\r\n", true),
+                (14, b"d", 16, b"\n\n", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn drivers_slice_paragraphs() {
+        let input = b"a\n\nb\n\n\nc\nd\n \ne\n";
+        let mut out = Vec::new();
+        slice_window(Paragraphs::new(), &input[..], &mut out, 1, Some(3)).unwrap();
+        assert_eq!(out, b"b\n\nc\nd\n\n");
+        out.clear();
+        slice_window(Paragraphs::new(), &input[..], &mut out, 1, None).unwrap();
+        assert_eq!(out, b"b\n\nc\nd\n\ne\n");
+        out.clear();
+        let one = NonZeroUsize::new(1).unwrap();
+        slice_tail(Paragraphs::new(), &input[..], &mut out, one, None, one).unwrap();
+        assert_eq!(out, b"e\n");
+    }
+}
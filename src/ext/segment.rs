@@ -0,0 +1,440 @@
+//! Word and sentence elements (UAX #29 word and sentence boundaries). As in
+//! grapheme mode, each maximal valid UTF-8 run is segmented on its own and a
+//! byte that starts no valid sequence is one element, so any byte stream
+//! splits losslessly. Every segment is an element — between two word
+//! boundaries lie words, but also runs of spaces and single punctuation
+//! marks — so selected elements are emitted verbatim.
+
+use crate::ext::buf_read::Split;
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Write},
+    str,
+};
+use unicode_segmentation::{USentenceBounds, UWordBounds, UnicodeSegmentation};
+
+/// Which boundaries segment the text.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub(crate) enum Unit {
+    Word,
+    Sentence,
+}
+
+/// The segments of one valid run.
+enum Run<'a> {
+    Words(UWordBounds<'a>),
+    Sentences(USentenceBounds<'a>),
+}
+
+impl Unit {
+    #[inline]
+    fn segments(self, text: &str) -> Run<'_> {
+        match self {
+            Unit::Word => Run::Words(text.split_word_bounds()),
+            Unit::Sentence => Run::Sentences(text.split_sentence_bounds()),
+        }
+    }
+}
+
+impl<'a> Iterator for Run<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a str> {
+        match self {
+            Run::Words(words) => words.next(),
+            Run::Sentences(sentences) => sentences.next(),
+        }
+    }
+}
+
+/// The valid prefix of `data`, and whether what follows it starts no valid
+/// sequence even with more bytes (`Some(true)`), or is a sequence cut short
+/// by the end of the data (`Some(false)`).
+#[inline]
+fn valid_prefix(data: &[u8]) -> (&str, Option<bool>) {
+    match str::from_utf8(data) {
+        Ok(text) => (text, None),
+        Err(err) => {
+            // SAFETY: from_utf8 validated exactly this prefix.
+            let text = unsafe { str::from_utf8_unchecked(&data[..err.valid_up_to()]) };
+            (text, Some(err.error_len().is_some()))
+        }
+    }
+}
+
+/// The element slices of in-memory data; the reverse path walks these, and
+/// the streaming kinds are tested against them.
+pub(crate) struct SegmentElements<'a> {
+    unit: Unit,
+    data: &'a [u8],
+    /// Segments of the current valid run; `data` is the unsegmented
+    /// remainder after it.
+    run: Run<'a>,
+}
+
+impl<'a> SegmentElements<'a> {
+    #[inline]
+    pub(crate) fn new(unit: Unit, data: &'a [u8]) -> Self {
+        Self {
+            unit,
+            data,
+            run: unit.segments(""),
+        }
+    }
+}
+
+impl<'a> Iterator for SegmentElements<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if let Some(segment) = self.run.next() {
+            return Some(segment.as_bytes());
+        }
+        if self.data.is_empty() {
+            return None;
+        }
+        let (valid, _) = valid_prefix(self.data);
+        if valid.is_empty() {
+            // An invalid sequence or a truncated one at the end of the data:
+            // the element is the single byte at the head, as in chars mode.
+            let (element, rest) = self.data.split_at(1);
+            self.data = rest;
+            return Some(element);
+        }
+        self.data = &self.data[valid.len()..];
+        self.run = self.unit.segments(valid);
+        self.run.next().map(str::as_bytes)
+    }
+}
+
+/// How many of a run's last segments wait for more input. A boundary the
+/// input so far puts in place moves only while what decides it is unread:
+/// a word boundary looks one character past it (`can` `'` before the `t`
+/// arrives), a sentence boundary up to the next letter or terminator, which
+/// the segment after it always holds once another follows. So with two
+/// segments after it, a boundary is final; the held segments start at one,
+/// where they re-segment exactly as they would inside the whole text.
+const HELD: usize = 2;
+
+/// The streaming counterpart of `SegmentElements`. The segmenter has no
+/// incremental interface, so the text read ahead is buffered and segmented
+/// again as blocks arrive: segments found final queue up in `ready`, the
+/// last `HELD` of a run that may continue stay in `text` to be segmented
+/// with what follows. Re-segmenting waits until the new bytes outweigh the
+/// held ones, so a long segment costs linear time, not quadratic.
+struct Segmenter {
+    unit: Unit,
+    /// Bytes read and not yet emitted, from `head`: first the `ready`
+    /// segments, then those held, then any not segmented yet.
+    text: Vec<u8>,
+    head: usize,
+    ready: VecDeque<usize>,
+    /// The length of `text` past the `ready` segments when it was last
+    /// segmented.
+    held: usize,
+}
+
+impl Segmenter {
+    #[inline]
+    fn new(unit: Unit) -> Self {
+        Self {
+            unit,
+            text: Vec::new(),
+            head: 0,
+            ready: VecDeque::new(),
+            held: 0,
+        }
+    }
+
+    /// Resolve and consume one element, feeding it to `sink`. Returns its
+    /// byte length; `Ok(0)` only at true end of stream.
+    fn next_segment<R: BufRead + ?Sized>(
+        &mut self,
+        r: &mut R,
+        sink: impl FnOnce(&[u8]) -> io::Result<()>,
+    ) -> io::Result<usize> {
+        loop {
+            if let Some(len) = self.ready.pop_front() {
+                let element = &self.text[self.head..self.head + len];
+                self.head += len;
+                sink(element)?;
+                return Ok(len);
+            }
+            self.text.drain(..self.head);
+            self.head = 0;
+            let block = match r.fill_buf() {
+                Ok(block) => block,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if block.is_empty() {
+                if self.text.is_empty() {
+                    return Ok(0);
+                }
+                self.segment(true);
+                continue;
+            }
+            let len = block.len();
+            self.text.extend_from_slice(block);
+            r.consume(len);
+            if self.text.len() > 2 * self.held {
+                self.segment(false);
+            }
+        }
+    }
+
+    /// Queue the final segments of `text`; at EOF (`flush`) all of it is.
+    fn segment(&mut self, flush: bool) {
+        debug_assert!(self.head == 0 && self.ready.is_empty());
+        let mut pos = 0;
+        while pos < self.text.len() {
+            let (valid, invalid) = valid_prefix(&self.text[pos..]);
+            let segments = self.unit.segments(valid).map(str::len);
+            // An invalid byte ends the run, and so does EOF, which also
+            // leaves a truncated sequence invalid.
+            if invalid != Some(true) && !flush {
+                let lens: Vec<usize> = segments.collect();
+                let done = &lens[..lens.len().saturating_sub(HELD)];
+                self.ready.extend(done);
+                pos += done.iter().sum::<usize>();
+                break;
+            }
+            self.ready.extend(segments);
+            pos += valid.len();
+            if invalid.is_some() {
+                self.ready.push_back(1);
+                pos += 1;
+            }
+        }
+        self.held = self.text.len() - pos;
+    }
+
+    /// Everything read ahead — the ready segments, the held ones, and the
+    /// unsegmented rest — precedes the reader's bytes.
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        let ahead = &self.text[self.head..];
+        w.write_all(ahead)?;
+        let flushed = ahead.len() as u64;
+        self.text.clear();
+        self.head = 0;
+        self.ready.clear();
+        self.held = 0;
+        Ok(flushed + io::copy(r, w)?)
+    }
+}
+
+macro_rules! segment_split {
+    ($(#[$doc:meta])* $name:ident, $unit:expr) => {
+        $(#[$doc])*
+        pub struct $name(Segmenter);
+
+        impl $name {
+            #[inline]
+            pub fn new() -> Self {
+                Self(Segmenter::new($unit))
+            }
+        }
+
+        impl Default for $name {
+            #[inline]
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl Split for $name {
+            #[inline]
+            fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+                &mut self,
+                r: &mut R,
+                w: &mut W,
+            ) -> io::Result<usize> {
+                self.0.next_segment(r, |bytes| w.write_all(bytes))
+            }
+
+            #[inline]
+            fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+                self.0.next_segment(r, |_| Ok(()))
+            }
+
+            #[inline]
+            fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+                &mut self,
+                r: &mut R,
+                w: &mut W,
+            ) -> io::Result<u64> {
+                self.0.copy_rest(r, w)
+            }
+        }
+    };
+}
+
+segment_split!(
+    /// Segments between UAX #29 word boundaries: words, and the spaces and
+    /// punctuation between them, each an element.
+    Words,
+    Unit::Word
+);
+
+segment_split!(
+    /// Segments between UAX #29 sentence boundaries, each sentence with the
+    /// spaces after it.
+    Sentences,
+    Unit::Sentence
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn elements(unit: Unit, data: &[u8]) -> Vec<&[u8]> {
+        SegmentElements::new(unit, data).collect()
+    }
+
+    #[test]
+    fn words_and_what_lies_between_them() {
+        assert_eq!(
+            elements(Unit::Word, b"Hello, world!"),
+            [&b"Hello"[..], b",", b" ", b"world", b"!"]
+        );
+        assert_eq!(
+            elements(Unit::Word, b"can't  3.14"),
+            [&b"can't"[..], b"  ", b"3.14"]
+        );
+    }
+
+    #[test]
+    fn sentences_keep_their_trailing_spaces() {
+        assert_eq!(
+            elements(Unit::Sentence, b"Hi there. Etc. and more! Ok?"),
+            [&b"Hi there. "[..], b"Etc. and more! ", b"Ok?"]
+        );
+    }
+
+    #[test]
+    fn valid_text_matches_the_crate_segmentation() {
+        let text = "Mr. Smith's café — naïve 👨‍👩‍👧 ok.\r\nNext line? 日本語です。";
+        let words: Vec<&[u8]> = text.split_word_bounds().map(str::as_bytes).collect();
+        assert_eq!(elements(Unit::Word, text.as_bytes()), words);
+        let sentences: Vec<&[u8]> = text.split_sentence_bounds().map(str::as_bytes).collect();
+        assert_eq!(elements(Unit::Sentence, text.as_bytes()), sentences);
+    }
+
+    #[test]
+    fn invalid_bytes_stand_alone_and_break_the_segment() {
+        assert_eq!(
+            elements(Unit::Word, b"ab\xffcd \xe2\x82"),
+            [&b"ab"[..], b"\xff", b"cd", b" ", b"\xe2", b"\x82"]
+        );
+        assert_eq!(
+            elements(Unit::Sentence, b"One. \x80Two."),
+            [&b"One. "[..], b"\x80", b"Two."]
+        );
+    }
+
+    mod streaming {
+        use super::*;
+
+        const CAPACITIES: [usize; 6] = [1, 2, 3, 4, 5, 8192];
+
+        // Apostrophes and decimal points a boundary waits on, abbreviations
+        // a later lowercase letter joins, multi-byte text and emoji,
+        // invalid bytes between runs, and a truncated sequence at EOF.
+        fn mixed_data() -> Vec<u8> {
+            let mut data = "It's 3.5 km. Mr. Smith said etc. (( and left! 日本語です。👍🏽 ok"
+                .as_bytes()
+                .to_vec();
+            data.extend_from_slice(&[0xFF, 0x80]);
+            data.extend_from_slice("can't. Next?\r\n\r\nyes".as_bytes());
+            data.extend_from_slice(&[0xF0, 0x90]);
+            data
+        }
+
+        fn streamed<S: Split>(mut split: S, data: &[u8], capacity: usize) -> Vec<Vec<u8>> {
+            let mut reader = BufReader::with_capacity(capacity, data);
+            let mut out = Vec::new();
+            loop {
+                let mut element = Vec::new();
+                let n = split.read_to(&mut reader, &mut element).unwrap();
+                if n == 0 {
+                    assert!(element.is_empty(), "Ok(0) must not produce bytes");
+                    break;
+                }
+                assert_eq!(n, element.len(), "length must match emitted bytes");
+                out.push(element);
+            }
+            out
+        }
+
+        fn expected(unit: Unit, data: &[u8]) -> Vec<Vec<u8>> {
+            SegmentElements::new(unit, data)
+                .map(<[u8]>::to_vec)
+                .collect()
+        }
+
+        #[test]
+        fn read_to_matches_in_memory_at_every_capacity() {
+            let data = mixed_data();
+            for capacity in CAPACITIES {
+                assert_eq!(
+                    streamed(Words::new(), &data, capacity),
+                    expected(Unit::Word, &data),
+                    "words, capacity {capacity}"
+                );
+                assert_eq!(
+                    streamed(Sentences::new(), &data, capacity),
+                    expected(Unit::Sentence, &data),
+                    "sentences, capacity {capacity}"
+                );
+            }
+        }
+
+        #[test]
+        fn a_long_segment_streams_whole() {
+            let data = "word ".repeat(2000) + &"x".repeat(20_000) + ". Done.";
+            for capacity in [1, 7, 8192] {
+                assert_eq!(
+                    streamed(Words::new(), data.as_bytes(), capacity),
+                    expected(Unit::Word, data.as_bytes()),
+                    "capacity {capacity}"
+                );
+                assert_eq!(
+                    streamed(Sentences::new(), data.as_bytes(), capacity),
+                    expected(Unit::Sentence, data.as_bytes()),
+                    "capacity {capacity}"
+                );
+            }
+        }
+
+        #[test]
+        fn skip_n_then_copy_rest_round_trips() {
+            let data = mixed_data();
+            let total = SegmentElements::new(Unit::Word, &data).count();
+            for n in 0..=total + 2 {
+                for capacity in [1, 3, 8192] {
+                    let mut reader = BufReader::with_capacity(capacity, data.as_slice());
+                    let mut split = Words::new();
+                    assert_eq!(
+                        split.skip_n(&mut reader, n).unwrap(),
+                        n.min(total),
+                        "n={n} capacity={capacity}"
+                    );
+                    let mut rest = Vec::new();
+                    split.copy_rest(&mut reader, &mut rest).unwrap();
+                    let expected: Vec<u8> = SegmentElements::new(Unit::Word, &data)
+                        .skip(n)
+                        .flatten()
+                        .copied()
+                        .collect();
+                    assert_eq!(rest, expected, "n={n} capacity={capacity}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,475 @@
+//! Random access to an input the reverse path must walk backwards. Input
+//! that cannot be re-read (stdin, pipes) is buffered: in memory up to the
+//! `--max-memory` budget, in an anonymous temporary file past it. A regular
+//! file is read in place and never buffered at all. The element boundaries
+//! a walk needs are tabled the same way, whatever the input.
+
+use std::{
+    fs,
+    io::{self, BufRead, Read, Seek, SeekFrom, Write},
+};
+
+/// How much of a file-backed spool one read brings in. Reverse walks move
+/// backwards, so each refill ends where the next wanted span does.
+const WINDOW_SIZE: usize = 1024 * 1024;
+
+/// What a spool's offset tables may hold in memory when no `--max-memory`
+/// says: a regular file read in place must not cost memory in proportion to
+/// its element count either.
+const TABLE_MEMORY: usize = 16 * 1024 * 1024;
+
+pub(crate) enum Spool {
+    Memory(Vec<u8>),
+    File {
+        file: fs::File,
+        /// Where the spooled bytes begin in `file` — past a kept header, for
+        /// a file read in place.
+        base: u64,
+        len: u64,
+        /// A cached stretch of the file starting at `window_start`.
+        window: Vec<u8>,
+        window_start: u64,
+    },
+}
+
+impl Spool {
+    /// The bytes of `file` from its current position to `len`, read in place.
+    pub(crate) fn in_place(mut file: fs::File, len: u64) -> io::Result<Self> {
+        let base = file.stream_position()?;
+        Ok(Self::file(file, base, len.saturating_sub(base)))
+    }
+
+    fn file(file: fs::File, base: u64, len: u64) -> Self {
+        Self::File {
+            file,
+            base,
+            len,
+            window: Vec::new(),
+            window_start: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> u64 {
+        match self {
+            Spool::Memory(data) => data.len() as u64,
+            Spool::File { len, .. } => *len,
+        }
+    }
+
+    /// The whole spool in memory, when it is there.
+    #[inline]
+    pub(crate) fn as_slice(&self) -> Option<&[u8]> {
+        match self {
+            Spool::Memory(data) => Some(data),
+            Spool::File { .. } => None,
+        }
+    }
+
+    /// What of `max_memory` the spool leaves its offset tables.
+    pub(crate) fn table_memory(&self, max_memory: Option<usize>) -> usize {
+        match max_memory {
+            Some(max) => max.saturating_sub(self.as_slice().map_or(0, <[u8]>::len)),
+            None => TABLE_MEMORY,
+        }
+    }
+
+    /// A reader over the spool from its start, for the pass that finds the
+    /// element boundaries.
+    pub(crate) fn reader(&self) -> io::Result<Box<dyn BufRead + '_>> {
+        Ok(match self {
+            Spool::Memory(data) => Box::new(&data[..]),
+            Spool::File {
+                file, base, len, ..
+            } => {
+                let mut file = file;
+                file.seek(SeekFrom::Start(*base))?;
+                Box::new(io::BufReader::with_capacity(WINDOW_SIZE, file.take(*len)))
+            }
+        })
+    }
+
+    /// Write the spooled bytes `[start, end)`.
+    pub(crate) fn write_span<W: Write + ?Sized>(
+        &mut self,
+        start: u64,
+        end: u64,
+        output: &mut W,
+    ) -> io::Result<()> {
+        match self {
+            Spool::Memory(data) => output.write_all(&data[start as usize..end as usize]),
+            Spool::File {
+                file,
+                base,
+                window,
+                window_start,
+                ..
+            } => {
+                let cached = *window_start..*window_start + window.len() as u64;
+                if !(cached.contains(&start) && end <= cached.end) {
+                    if end - start > WINDOW_SIZE as u64 {
+                        // Longer than a window: stream it through instead.
+                        file.seek(SeekFrom::Start(*base + start))?;
+                        io::copy(&mut (&*file).take(end - start), output)?;
+                        return Ok(());
+                    }
+                    *window_start = end.saturating_sub(WINDOW_SIZE as u64);
+                    window.resize((end - *window_start) as usize, 0);
+                    file.seek(SeekFrom::Start(*base + *window_start))?;
+                    file.read_exact(window)?;
+                }
+                let from = (start - *window_start) as usize;
+                output.write_all(&window[from..from + (end - start) as usize])
+            }
+        }
+    }
+}
+
+/// Collects a spool: bytes stay in memory until `max_memory` would be
+/// exceeded, then everything moves to a temporary file, deleted once closed.
+pub(crate) struct SpoolWriter {
+    memory: Vec<u8>,
+    max_memory: Option<usize>,
+    spill: Option<io::BufWriter<fs::File>>,
+    len: u64,
+}
+
+impl SpoolWriter {
+    pub(crate) fn new(max_memory: Option<usize>) -> Self {
+        Self {
+            memory: Vec::new(),
+            max_memory,
+            spill: None,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn finish(self) -> io::Result<Spool> {
+        Ok(match self.spill {
+            None => Spool::Memory(self.memory),
+            Some(spill) => Spool::file(
+                spill.into_inner().map_err(|err| err.into_error())?,
+                0,
+                self.len,
+            ),
+        })
+    }
+}
+
+impl Write for SpoolWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.spill.is_none()
+            && self
+                .max_memory
+                .is_some_and(|max| self.memory.len() + buf.len() > max)
+        {
+            let mut spill = io::BufWriter::new(tempfile::tempfile()?);
+            spill.write_all(&self.memory)?;
+            self.memory = Vec::new();
+            self.spill = Some(spill);
+        }
+        match &mut self.spill {
+            Some(spill) => spill.write_all(buf)?,
+            None => self.memory.extend_from_slice(buf),
+        }
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.spill {
+            Some(spill) => spill.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A table of rows of `N` offsets, written in order and read back in any:
+/// in memory up to its budget, in a temporary file past it.
+pub(crate) enum Offsets<const N: usize> {
+    Memory(Vec<[u64; N]>),
+    File {
+        file: fs::File,
+        len: usize,
+        /// The encoded rows from `window_start` on.
+        window: Vec<u8>,
+        window_start: usize,
+    },
+}
+
+impl<const N: usize> Offsets<N> {
+    const ROW: usize = N * 8;
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Offsets::Memory(rows) => rows.len(),
+            Offsets::File { len, .. } => *len,
+        }
+    }
+
+    /// Row `i`. From a file, a miss reads the window of rows that ends one
+    /// past it — walks descend, and a span's end is often the next row.
+    pub(crate) fn get(&mut self, i: usize) -> io::Result<[u64; N]> {
+        let (file, len, window, window_start) = match self {
+            Offsets::Memory(rows) => return Ok(rows[i]),
+            Offsets::File {
+                file,
+                len,
+                window,
+                window_start,
+            } => (file, *len, window, window_start),
+        };
+        let cached = *window_start..*window_start + window.len() / Self::ROW;
+        if !cached.contains(&i) {
+            let end = (i + 2).min(len);
+            *window_start = end.saturating_sub(WINDOW_SIZE / Self::ROW);
+            window.resize((end - *window_start) * Self::ROW, 0);
+            file.seek(SeekFrom::Start((*window_start * Self::ROW) as u64))?;
+            file.read_exact(window)?;
+        }
+        let from = (i - *window_start) * Self::ROW;
+        let mut row = [0; N];
+        for (offset, bytes) in row.iter_mut().zip(window[from..].chunks_exact(8)) {
+            *offset = u64::from_le_bytes(bytes.try_into().expect("eight bytes"));
+        }
+        Ok(row)
+    }
+}
+
+/// Collects an [`Offsets`] table like [`SpoolWriter`] collects a spool.
+pub(crate) struct OffsetsWriter<const N: usize> {
+    memory: Vec<[u64; N]>,
+    /// The most rows the budget holds in memory.
+    max_rows: usize,
+    spill: Option<io::BufWriter<fs::File>>,
+    len: usize,
+}
+
+impl<const N: usize> OffsetsWriter<N> {
+    pub(crate) fn new(max_memory: usize) -> Self {
+        Self {
+            memory: Vec::new(),
+            max_rows: max_memory / Offsets::<N>::ROW,
+            spill: None,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, row: [u64; N]) -> io::Result<()> {
+        if self.spill.is_none() && self.memory.len() == self.max_rows {
+            let mut spill = io::BufWriter::new(tempfile::tempfile()?);
+            for row in std::mem::take(&mut self.memory) {
+                write_row(&mut spill, row)?;
+            }
+            self.spill = Some(spill);
+        }
+        match &mut self.spill {
+            Some(spill) => write_row(spill, row)?,
+            None => {
+                // Grow by doubling, but never past the budget.
+                if self.memory.len() == self.memory.capacity() {
+                    let room = self.max_rows - self.memory.len();
+                    self.memory.reserve_exact(self.memory.len().clamp(1, room));
+                }
+                self.memory.push(row);
+            }
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> io::Result<Offsets<N>> {
+        Ok(match self.spill {
+            None => Offsets::Memory(self.memory),
+            Some(spill) => Offsets::File {
+                file: spill.into_inner().map_err(|err| err.into_error())?,
+                len: self.len,
+                window: Vec::new(),
+                window_start: 0,
+            },
+        })
+    }
+}
+
+fn write_row<const N: usize>(output: &mut impl Write, row: [u64; N]) -> io::Result<()> {
+    row.iter()
+        .try_for_each(|offset| output.write_all(&offset.to_le_bytes()))
+}
+
+/// Where each element of a spool starts, 8 bytes apiece: an element ends
+/// where the next one starts, the last one where the spool does. The top
+/// bit of a start marks a chunk whose content leaves its delimiter out.
+pub(crate) struct ChunkSpans {
+    starts: Offsets<1>,
+    len: u64,
+}
+
+const STRIPPED: u64 = 1 << 63;
+
+impl ChunkSpans {
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Element `i`, delimiter included.
+    pub(crate) fn span(&mut self, i: usize) -> io::Result<(u64, u64)> {
+        let end = if i + 1 < self.len() {
+            self.starts.get(i + 1)?[0] & !STRIPPED
+        } else {
+            self.len
+        };
+        Ok((self.starts.get(i)?[0] & !STRIPPED, end))
+    }
+
+    /// Element `i` without the `delimiter` bytes its split's content leaves
+    /// out.
+    pub(crate) fn content(&mut self, i: usize, delimiter: usize) -> io::Result<(u64, u64)> {
+        let (start, end) = self.span(i)?;
+        if self.stripped(i)? {
+            Ok((start, end - delimiter as u64))
+        } else {
+            Ok((start, end))
+        }
+    }
+
+    /// Whether element `i`'s content leaves out a delimiter, which only the
+    /// last chunk can lack.
+    pub(crate) fn stripped(&mut self, i: usize) -> io::Result<bool> {
+        Ok(self.starts.get(i)?[0] & STRIPPED != 0)
+    }
+}
+
+/// Collects [`ChunkSpans`] element by element, in order.
+pub(crate) struct ChunkSpansWriter {
+    starts: OffsetsWriter<1>,
+    pos: u64,
+}
+
+impl ChunkSpansWriter {
+    pub(crate) fn new(max_memory: usize) -> Self {
+        Self {
+            starts: OffsetsWriter::new(max_memory),
+            pos: 0,
+        }
+    }
+
+    /// An element of `len` bytes, of which its split's content takes
+    /// `content`.
+    pub(crate) fn push(&mut self, len: usize, content: usize) -> io::Result<()> {
+        let stripped = if content < len { STRIPPED } else { 0 };
+        self.starts.push([self.pos | stripped])?;
+        self.pos += len as u64;
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> io::Result<ChunkSpans> {
+        Ok(ChunkSpans {
+            starts: self.starts.finish()?,
+            len: self.pos,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spool(data: &[u8], max_memory: Option<usize>) -> Spool {
+        let mut writer = SpoolWriter::new(max_memory);
+        for chunk in data.chunks(3) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn spans(spool: &mut Spool, spans: &[(u64, u64)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for &(start, end) in spans {
+            spool.write_span(start, end, &mut out).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn stays_in_memory_within_the_budget() {
+        let spool = spool(b"abcdefgh", Some(8));
+        assert_eq!(spool.as_slice(), Some(&b"abcdefgh"[..]));
+        assert_eq!(spool.len(), 8);
+    }
+
+    #[test]
+    fn spills_past_the_budget() {
+        let mut spool = spool(b"abcdefgh", Some(4));
+        assert!(spool.as_slice().is_none());
+        assert_eq!(spool.len(), 8);
+        assert_eq!(spans(&mut spool, &[(6, 8), (3, 6), (0, 1)]), b"ghdefa");
+        let mut all = Vec::new();
+        spool.reader().unwrap().read_to_end(&mut all).unwrap();
+        assert_eq!(all, b"abcdefgh");
+    }
+
+    #[test]
+    fn spans_longer_than_a_window_stream_through() {
+        let data: Vec<u8> = (0..WINDOW_SIZE * 2 + 5).map(|i| i as u8).collect();
+        let mut spool = spool(&data, Some(1));
+        let len = data.len() as u64;
+        let out = spans(&mut spool, &[(len - 2, len), (1, len - 2), (0, 1)]);
+        let mut expected = data[data.len() - 2..].to_vec();
+        expected.extend_from_slice(&data[1..data.len() - 2]);
+        expected.push(data[0]);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn offsets_stay_within_the_budget() {
+        let budget = 1000;
+        let mut writer = OffsetsWriter::<2>::new(budget);
+        for i in 0..50 {
+            writer.push([i, i * 3]).unwrap();
+            assert!(writer.memory.capacity() * 16 <= budget);
+        }
+        let mut offsets = writer.finish().unwrap();
+        assert!(matches!(offsets, Offsets::Memory(_)));
+        assert_eq!(offsets.get(49).unwrap(), [49, 147]);
+        let mut writer = OffsetsWriter::<2>::new(budget);
+        let rows = WINDOW_SIZE / 16 * 3;
+        for i in 0..rows as u64 {
+            writer.push([i, i * 3]).unwrap();
+            assert!(writer.memory.capacity() * 16 <= budget);
+        }
+        let mut offsets = writer.finish().unwrap();
+        assert!(matches!(offsets, Offsets::File { .. }));
+        assert_eq!(offsets.len(), rows);
+        for i in (0..rows).rev().step_by(7) {
+            assert_eq!(offsets.get(i).unwrap(), [i as u64, i as u64 * 3]);
+        }
+        assert_eq!(offsets.get(rows - 1).unwrap()[0], rows as u64 - 1);
+    }
+
+    #[test]
+    fn chunk_spans_follow_from_the_starts() {
+        let mut writer = ChunkSpansWriter::new(0);
+        for (len, content) in [(3, 2), (1, 0), (4, 4)] {
+            writer.push(len, content).unwrap();
+        }
+        let mut spans = writer.finish().unwrap();
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans.span(0).unwrap(), (0, 3));
+        assert_eq!(spans.content(0, 1).unwrap(), (0, 2));
+        assert_eq!(spans.content(1, 1).unwrap(), (3, 3));
+        assert_eq!(spans.content(2, 1).unwrap(), (4, 8));
+        assert!(!spans.stripped(2).unwrap());
+    }
+
+    #[test]
+    fn in_place_starts_at_the_current_position() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"header\nbody\n").unwrap();
+        file.seek(SeekFrom::Start(7)).unwrap();
+        let mut spool = Spool::in_place(file, 12).unwrap();
+        assert_eq!(spool.len(), 5);
+        assert_eq!(spans(&mut spool, &[(0, 4)]), b"body");
+    }
+}
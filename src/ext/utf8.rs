@@ -4,7 +4,10 @@
 //! selected elements round-trip verbatim. On valid text the elements are
 //! exactly Python's `str` characters (Unicode scalar values).
 
-use crate::{ext::IteratorExt, range::SliceIndex};
+use crate::{
+    ext::{buf_read::Split, IteratorExt},
+    range::SliceIndex,
+};
 use std::{
     collections::VecDeque,
     io::{self, BufRead, Write},
@@ -412,6 +415,55 @@ impl Scanner {
     }
 }
 
+/// Chars mode as a split: every element is one chunk. Only the generic
+/// drivers need it — the `char_*` drivers walk whole blocks instead.
+pub struct Chars(Scanner);
+
+impl Chars {
+    #[inline]
+    pub fn new() -> Self {
+        Self(Scanner::new())
+    }
+}
+
+impl Default for Chars {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Split for Chars {
+    #[inline]
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        self.0.next_element(r, |element| w.write_all(element))
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        self.0.next_element(r, |_| Ok(()))
+    }
+
+    #[inline]
+    fn skip_n<R: BufRead + ?Sized>(&mut self, r: &mut R, n: usize) -> io::Result<usize> {
+        self.0.advance(r, n, |_| Ok(()))
+    }
+
+    /// The scanner's straddle bytes come first, then the reader's remainder.
+    #[inline]
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        self.0.copy_rest(r, w)
+    }
+}
+
 /// Chars-mode unit-step window: skip `start` elements block-wise, then either
 /// copy the rest verbatim (unbounded) or pass `end - start` elements through
 /// as whole-block spans.
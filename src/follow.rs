@@ -0,0 +1,661 @@
+use crate::{
+    ext::{Annotation, Split},
+    is_broken_pipe,
+    range::{AnchoredCursor, DeferredPlan, Plan, SlicePlan, UnionPlan},
+    report_error,
+};
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, BufReader, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// What `--follow` keeps reading after a file is renamed: the open file
+/// (`descriptor`, like `tail -f`) or whatever file the name refers to now
+/// (`name`, like `tail -F`, which survives log rotation).
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, clap::ValueEnum)]
+pub enum Follow {
+    Descriptor,
+    Name,
+}
+
+/// The longest wait without a change notification: the whole wait where
+/// inotify is unavailable, and otherwise the bound on noticing a replaced
+/// file, whose new inode raises nothing on the old watch.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A plan with its length-dependent parts fixed, so every element — present
+/// or yet to be appended — is decided on arrival.
+enum Rule {
+    Span(SlicePlan),
+    /// `len` freezes tail-relative bounds at the startup length; `None` when
+    /// every bound is head-relative.
+    Union {
+        plan: UnionPlan,
+        len: Option<usize>,
+    },
+    Anchored(AnchoredCursor),
+}
+
+impl Rule {
+    fn selects(&mut self, index: usize, content: &[u8]) -> bool {
+        match self {
+            Rule::Span(plan) => plan.contains(index),
+            Rule::Union { plan, len } => plan.selects(index, *len),
+            Rule::Anchored(cursor) => cursor.selects(content),
+        }
+    }
+
+    /// Whether nothing at or past `index` can be selected, so reading on is
+    /// pointless.
+    fn exhausted(&self, index: usize) -> bool {
+        match self {
+            Rule::Span(plan) => plan.exhausted(index),
+            Rule::Union { plan, len: None } if !plan.is_complement() => {
+                plan.next_selected(index).is_none()
+            }
+            Rule::Union { .. } => false,
+            Rule::Anchored(cursor) => cursor.exhausted(),
+        }
+    }
+}
+
+/// What every followed file shares besides its split and plan.
+#[derive(Clone, Copy)]
+pub(crate) struct Options {
+    pub(crate) how: Follow,
+    pub(crate) keep_header: bool,
+    /// Print `==> FILE <==` whenever the output switches files.
+    pub(crate) print_header: bool,
+    pub(crate) annotation: Annotation,
+}
+
+/// One file being followed. Elements are read whole: a final element still
+/// missing its delimiter is left unread until the rest is appended, so the
+/// moment a writer paused never splits an element in two.
+struct Followed<S> {
+    path: PathBuf,
+    split: S,
+    reader: BufReader<fs::File>,
+    /// Offset of the first unread element.
+    pos: u64,
+    buf: Vec<u8>,
+    /// Elements consumed, the header excluded.
+    index: usize,
+    /// `--keep-header` while the header is still incomplete.
+    header: bool,
+    /// A header read at startup, printed with the first output.
+    held_header: Option<Vec<u8>>,
+    rule: Rule,
+    /// The range is exhausted; the file is no longer read.
+    done: bool,
+    annotation: Annotation,
+}
+
+impl<S: Split> Followed<S> {
+    fn open(
+        path: &Path,
+        split: S,
+        plan: &Plan,
+        keep_header: bool,
+        annotation: Annotation,
+    ) -> io::Result<Self> {
+        let mut file = Self {
+            path: path.to_owned(),
+            split,
+            reader: BufReader::new(fs::File::open(path)?),
+            pos: 0,
+            buf: Vec::new(),
+            index: 0,
+            header: keep_header,
+            held_header: None,
+            rule: Rule::Span(SlicePlan::Empty),
+            done: false,
+            annotation,
+        };
+        // Taken first so tail-relative bounds count the records after it.
+        if file.header && file.next()? {
+            file.header = false;
+            file.held_header = Some(std::mem::take(&mut file.buf));
+        }
+        file.rule = match plan {
+            Plan::Resolved(plan) => Rule::Span(*plan),
+            Plan::Union(plan) if plan.delay() == 0 => Rule::Union {
+                plan: plan.clone(),
+                len: None,
+            },
+            Plan::Anchored(plan) => Rule::Anchored(plan.cursor()),
+            Plan::Deferred(_) | Plan::Union(_) => file.resolve(plan)?,
+            Plan::Reverse(_) | Plan::ReverseUnion(_) => {
+                unreachable!("slice_files() rejects reverse ranges with --follow")
+            }
+        };
+        file.done = file.rule.exhausted(file.index);
+        Ok(file)
+    }
+
+    /// Fix the tail-relative bounds of `plan` against the elements present
+    /// now: count them, remembering where the last few start, then rewind to
+    /// the first selected one.
+    fn resolve(&mut self, plan: &Plan) -> io::Result<Rule> {
+        // The furthest any bound reaches back from the end.
+        let keep = match plan {
+            Plan::Deferred(DeferredPlan::Tail { back, .. }) => back.get(),
+            Plan::Union(plan) => plan.delay(),
+            _ => 0,
+        };
+        let origin = self.pos;
+        let mut starts = VecDeque::with_capacity(keep);
+        let mut len = 0;
+        loop {
+            let start = self.pos;
+            if !self.next()? {
+                break;
+            }
+            if keep > 0 {
+                if starts.len() == keep {
+                    starts.pop_front();
+                }
+                starts.push_back(start);
+            }
+            len += 1;
+        }
+        let (rule, first) = match plan {
+            Plan::Deferred(DeferredPlan::Proportional(plan)) => {
+                match plan
+                    .resolve(len as u64)
+                    .expect("an element count fits usize")
+                {
+                    Plan::Resolved(plan) => (Rule::Span(plan), plan.first()),
+                    Plan::Union(plan) => (
+                        Rule::Union {
+                            plan: plan.clone(),
+                            len: Some(len),
+                        },
+                        (0..len).find(|&i| plan.selects(i, Some(len))),
+                    ),
+                    _ => unreachable!("a counted forward range resolves to a span or a union"),
+                }
+            }
+            Plan::Deferred(plan) => {
+                let plan = plan
+                    .resolve(len as u64)
+                    .expect("an element count fits usize");
+                (Rule::Span(plan), plan.first())
+            }
+            Plan::Union(plan) => (
+                Rule::Union {
+                    plan: plan.clone(),
+                    len: Some(len),
+                },
+                (0..len).find(|&i| plan.selects(i, Some(len))),
+            ),
+            _ => unreachable!("only tail-relative plans are resolved"),
+        };
+        // Nothing present is selected: carry on from the end.
+        let Some(first) = first.filter(|&first| first < len) else {
+            self.index = len;
+            return Ok(rule);
+        };
+        let remembered = len - starts.len();
+        self.pos = if first >= remembered {
+            starts[first - remembered]
+        } else {
+            self.reader.seek(SeekFrom::Start(origin))?;
+            self.split.skip_n(&mut self.reader, first)?;
+            self.reader.stream_position()?
+        };
+        self.reader.seek(SeekFrom::Start(self.pos))?;
+        self.index = first;
+        Ok(rule)
+    }
+
+    /// Read the next whole element into `buf`; `false` when none is complete
+    /// yet, with the reader rewound to where it starts.
+    fn next(&mut self) -> io::Result<bool> {
+        self.buf.clear();
+        let read = self.split.read_to(&mut self.reader, &mut self.buf)?;
+        if read == 0 {
+            return Ok(false);
+        }
+        if !self.split.terminated(&self.buf) {
+            self.reader.seek(SeekFrom::Start(self.pos))?;
+            return Ok(false);
+        }
+        self.pos += read as u64;
+        Ok(true)
+    }
+
+    /// Emit every complete element that has arrived and is selected. `announce`
+    /// runs before the first byte written, for the multi-file header.
+    fn drain<W: Write>(
+        &mut self,
+        output: &mut W,
+        mut announce: impl FnMut(&mut W) -> io::Result<()>,
+    ) -> io::Result<()> {
+        if let Some(header) = self.held_header.take() {
+            announce(output)?;
+            output.write_all(&header)?;
+        }
+        while !self.done && self.next()? {
+            if self.header {
+                self.header = false;
+                announce(output)?;
+                output.write_all(&self.buf)?;
+                continue;
+            }
+            if self.rule.selects(self.index, self.split.content(&self.buf)) {
+                announce(output)?;
+                let start = self.pos - self.buf.len() as u64;
+                self.annotation.write(output, self.index as u64, start)?;
+                output.write_all(&self.buf)?;
+            }
+            self.index += 1;
+            self.done = self.rule.exhausted(self.index);
+        }
+        Ok(())
+    }
+
+    /// Notice a truncated file — start over from its beginning, like `tail
+    /// -f` — and, following by name, a file replaced under the name: the rest
+    /// of the old one is drained first, then the new one is read from the
+    /// start. Returns whether a new file was opened.
+    fn reopen<W: Write>(
+        &mut self,
+        how: Follow,
+        output: &mut W,
+        announce: impl FnMut(&mut W) -> io::Result<()>,
+    ) -> io::Result<bool> {
+        let current = self.reader.get_ref().metadata()?;
+        if how == Follow::Name {
+            // A missing name is a rotation in progress: keep the old file
+            // until the new one appears.
+            if let Ok(named) = fs::metadata(&self.path) {
+                if !same_file(&current, &named) {
+                    self.drain(output, announce)?;
+                    eprintln!(
+                        "slice: {}: file replaced; following the new file",
+                        self.path.display()
+                    );
+                    self.reader = BufReader::new(fs::File::open(&self.path)?);
+                    self.pos = 0;
+                    return Ok(true);
+                }
+            }
+        }
+        if current.len() < self.pos {
+            eprintln!("slice: {}: file truncated", self.path.display());
+            self.reader.seek(SeekFrom::Start(0))?;
+            self.pos = 0;
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+// Without inode numbers, a creation time that changed is the sign of a new
+// file.
+#[cfg(not(unix))]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    a.created().ok() == b.created().ok()
+}
+
+/// Blocks until a followed file may have changed: an inotify event on Linux,
+/// or [`POLL_INTERVAL`] elapsing — the only signal elsewhere, or when
+/// inotify cannot be set up.
+struct Waiter {
+    #[cfg(target_os = "linux")]
+    inotify: Option<inotify::Inotify>,
+}
+
+impl Waiter {
+    fn new() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            inotify: inotify::Inotify::init().ok(),
+        }
+    }
+
+    /// Watch the file `path` names now. A failure only costs latency: the
+    /// wait still ends on the poll interval.
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    fn watch(&mut self, path: &Path) {
+        #[cfg(target_os = "linux")]
+        if let Some(inotify) = &mut self.inotify {
+            use inotify::WatchMask;
+            let mask = WatchMask::MODIFY
+                | WatchMask::ATTRIB
+                | WatchMask::CLOSE_WRITE
+                | WatchMask::MOVE_SELF
+                | WatchMask::DELETE_SELF;
+            let _ = inotify.watches().add(path, mask);
+        }
+    }
+
+    fn wait(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(inotify) = &mut self.inotify {
+            use std::os::fd::AsRawFd;
+            let mut fd = libc::pollfd {
+                fd: inotify.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `fd` is one valid pollfd and the count says so.
+            let ready = unsafe { libc::poll(&mut fd, 1, POLL_INTERVAL.as_millis() as libc::c_int) };
+            if ready > 0 {
+                // Only the wakeup matters; every file is re-read regardless.
+                let mut buffer = [0; 4096];
+                while inotify
+                    .read_events(&mut buffer)
+                    .is_ok_and(|events| events.count() > 0)
+                {}
+            }
+            return;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// `--follow`: print what the range selects from each of `paths`, then keep
+/// reading what is appended, printing the elements that fall in the range,
+/// until every range is exhausted — for an unbounded one, until interrupted.
+/// Reverse ranges never get here: their first element out is the last in.
+pub(crate) fn follow<S: Split, W: Write>(
+    paths: &[PathBuf],
+    split: impl Fn() -> S,
+    plan: &Plan,
+    options: Options,
+    mut output: W,
+) -> bool {
+    let Options {
+        how,
+        keep_header,
+        print_header,
+        annotation,
+    } = options;
+    let mut ok = true;
+    let mut waiter = Waiter::new();
+    let mut files = Vec::new();
+    // Which file the output last came from, so a header is printed whenever
+    // the output switches files, as tail(1) does.
+    let mut last = None;
+    for path in paths {
+        let result =
+            Followed::open(path, split(), plan, keep_header, annotation).and_then(|mut file| {
+                if print_header {
+                    writeln!(output, "==> {} <==", path.display())?;
+                }
+                file.drain(&mut output, |_| Ok(()))?;
+                Ok(file)
+            });
+        match result {
+            Ok(file) => {
+                waiter.watch(path);
+                last = Some(path.clone());
+                files.push(file);
+            }
+            Err(err) if is_broken_pipe(&err) => return ok,
+            Err(err) => {
+                report_error(path, &err);
+                ok = false;
+            }
+        }
+    }
+    loop {
+        match output.flush() {
+            Ok(()) => {}
+            Err(err) if is_broken_pipe(&err) => return ok,
+            Err(err) => {
+                eprintln!("slice: {err}");
+                return false;
+            }
+        }
+        files.retain(|file| !file.done);
+        if files.is_empty() {
+            return ok;
+        }
+        waiter.wait();
+        for file in &mut files {
+            let path = file.path.clone();
+            let mut announce = |output: &mut W| -> io::Result<()> {
+                if print_header && last.as_ref() != Some(&path) {
+                    last = Some(path.clone());
+                    writeln!(output, "==> {} <==", path.display())?;
+                }
+                Ok(())
+            };
+            let result = (|| {
+                file.drain(&mut output, &mut announce)?;
+                if file.reopen(how, &mut output, &mut announce)? {
+                    waiter.watch(&path);
+                    file.drain(&mut output, &mut announce)?;
+                }
+                Ok(())
+            })();
+            match result {
+                Ok(()) => {}
+                Err(err) if is_broken_pipe(&err) => return ok,
+                Err(err) => {
+                    report_error(&path, &err);
+                    ok = false;
+                    file.done = true;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ext::{Byte, EachByte},
+        range::SliceRanges,
+    };
+    use std::{
+        str::FromStr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn temp_file(contents: &[u8]) -> PathBuf {
+        let id = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("slice-follow-{}-{}.txt", std::process::id(), id));
+        fs::write(&path, contents).expect("write temp file");
+        path
+    }
+
+    fn append(path: &Path, data: &[u8]) {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(data))
+            .expect("append to temp file");
+    }
+
+    fn plan(range: &str) -> Plan {
+        SliceRanges::from_str(range).unwrap().plan()
+    }
+
+    fn drain<S: Split>(file: &mut Followed<S>) -> Vec<u8> {
+        let mut out = Vec::new();
+        file.drain(&mut out, |_| Ok(())).unwrap();
+        out
+    }
+
+    fn lines(path: &Path, range: &str, keep_header: bool) -> Followed<Byte> {
+        Followed::open(
+            path,
+            Byte(b'\n'),
+            &plan(range),
+            keep_header,
+            Annotation::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn appended_lines_continue_the_range() {
+        let path = temp_file(b"a\nb\nc\n");
+        let mut file = lines(&path, "1:", false);
+        assert_eq!(drain(&mut file), b"b\nc\n");
+        append(&path, b"d\ne\n");
+        assert_eq!(drain(&mut file), b"d\ne\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn annotations_count_past_the_header_and_across_appends() {
+        let path = temp_file(b"h\na\nb\n");
+        let annotation = Annotation {
+            number: Some(1),
+            offsets: true,
+            ..Annotation::default()
+        };
+        let mut file = Followed::open(&path, Byte(b'\n'), &plan("-1:"), true, annotation).unwrap();
+        assert_eq!(drain(&mut file), b"h\n2:4:b\n");
+        append(&path, b"cc\nd\n");
+        assert_eq!(drain(&mut file), b"3:6:cc\n4:9:d\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn partial_line_waits_for_its_delimiter() {
+        let path = temp_file(b"a\nb");
+        let mut file = lines(&path, ":", false);
+        assert_eq!(drain(&mut file), b"a\n");
+        append(&path, b"c");
+        assert_eq!(drain(&mut file), b"");
+        append(&path, b"\n");
+        assert_eq!(drain(&mut file), b"bc\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tail_relative_start_resolves_against_startup_contents() {
+        let path = temp_file(b"0\n1\n2\n3\n4\n");
+        let mut file = lines(&path, "-2:", false);
+        assert_eq!(drain(&mut file), b"3\n4\n");
+        append(&path, b"5\n");
+        assert_eq!(drain(&mut file), b"5\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tail_relative_end_freezes() {
+        let path = temp_file(b"0\n1\n2\n3\n");
+        let mut file = lines(&path, "1:-1", false);
+        assert_eq!(drain(&mut file), b"1\n2\n");
+        append(&path, b"4\n");
+        assert_eq!(drain(&mut file), b"");
+        assert!(file.done);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn stepped_sampling_keeps_its_stride() {
+        let path = temp_file(b"0\n1\n2\n3\n");
+        let mut file = lines(&path, "::3", false);
+        assert_eq!(drain(&mut file), b"0\n3\n");
+        append(&path, b"4\n5\n6\n7\n");
+        assert_eq!(drain(&mut file), b"6\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bounded_range_is_done_once_passed() {
+        let path = temp_file(b"0\n1\n");
+        let mut file = lines(&path, ":3", false);
+        assert_eq!(drain(&mut file), b"0\n1\n");
+        assert!(!file.done);
+        append(&path, b"2\n3\n");
+        assert_eq!(drain(&mut file), b"2\n");
+        assert!(file.done);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn union_with_tail_relative_piece() {
+        let path = temp_file(b"0\n1\n2\n3\n");
+        let mut file = lines(&path, "0:1,-1:", false);
+        assert_eq!(drain(&mut file), b"0\n3\n");
+        append(&path, b"4\n");
+        assert_eq!(drain(&mut file), b"4\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn anchored_range_opens_on_a_later_match() {
+        let path = temp_file(b"x\ny\n");
+        let mut file = lines(&path, "/start/:", false);
+        assert_eq!(drain(&mut file), b"");
+        append(&path, b"start\nz\n");
+        assert_eq!(drain(&mut file), b"start\nz\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn header_precedes_and_is_not_counted() {
+        let path = temp_file(b"h\n0\n1\n2\n");
+        let mut file = lines(&path, "-1:", true);
+        assert_eq!(drain(&mut file), b"h\n2\n");
+        append(&path, b"3\n");
+        assert_eq!(drain(&mut file), b"3\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bytes_follow_one_at_a_time() {
+        let path = temp_file(b"abc");
+        let mut file =
+            Followed::open(&path, EachByte, &plan("-1:"), false, Annotation::default()).unwrap();
+        assert_eq!(drain(&mut file), b"c");
+        append(&path, b"de");
+        assert_eq!(drain(&mut file), b"de");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truncation_restarts_from_the_beginning() {
+        let path = temp_file(b"a\nb\n");
+        let mut file = lines(&path, ":", false);
+        assert_eq!(drain(&mut file), b"a\nb\n");
+        fs::write(&path, b"c\n").unwrap();
+        let mut out = Vec::new();
+        let reopened = file
+            .reopen(Follow::Descriptor, &mut out, |_| Ok(()))
+            .unwrap();
+        assert!(!reopened);
+        assert_eq!(drain(&mut file), b"c\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn name_follows_a_replaced_file() {
+        let path = temp_file(b"a\n");
+        let mut file = lines(&path, ":", false);
+        assert_eq!(drain(&mut file), b"a\n");
+        let rotated = path.with_extension("old");
+        fs::rename(&path, &rotated).unwrap();
+        append(&rotated, b"b\n");
+        fs::write(&path, b"c\n").unwrap();
+        let mut out = Vec::new();
+        let reopened = file.reopen(Follow::Name, &mut out, |_| Ok(())).unwrap();
+        assert!(reopened);
+        assert_eq!(out, b"b\n");
+        assert_eq!(drain(&mut file), b"c\n");
+        fs::remove_file(path).unwrap();
+        fs::remove_file(rotated).unwrap();
+    }
+}
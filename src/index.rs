@@ -0,0 +1,292 @@
+//! `--index`: a sidecar file recording where every [`INTERVAL`]-th element of
+//! a file starts, plus the element count, so a range far into a large file
+//! seeks close to its start instead of splitting everything before it, and a
+//! tail-relative range resolves without a pass over the file. The sidecar is
+//! keyed on the file's size and modification time and on the split it was
+//! built for; a mismatch on either makes it stale, and it is rebuilt.
+//!
+//! Layout, integers little-endian: the magic, the file size (u64), the
+//! modification time (u64 seconds and u32 nanoseconds since the epoch), the
+//! split key (u32 length and bytes), the interval (u64), the element count
+//! (u64), and the offset list (u64 length and u64 entries).
+
+use crate::ext::Split;
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+const MAGIC: &[u8; 8] = b"SLICEIX1";
+
+/// Elements between recorded offsets: a seek lands at most this many
+/// elements short of its target, and the sidecar costs 8 bytes per
+/// `INTERVAL` elements.
+pub(crate) const INTERVAL: u64 = 4096;
+
+/// What an index is valid for: one version of one file, split one way.
+#[derive(Eq, PartialEq, Debug)]
+struct Key {
+    len: u64,
+    modified: (u64, u32),
+    split: Vec<u8>,
+}
+
+impl Key {
+    fn of(file: &fs::File, split: &[u8]) -> io::Result<Self> {
+        let metadata = file.metadata()?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Self {
+            len: metadata.len(),
+            modified: (modified.as_secs(), modified.subsec_nanos()),
+            split: split.to_owned(),
+        })
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct Index {
+    count: u64,
+    interval: u64,
+    /// `offsets[k]` is where element `(k + 1) * interval` starts.
+    offsets: Vec<u64>,
+}
+
+impl Index {
+    /// Split the whole of `input` once, from its start.
+    fn build<S: Split, R: BufRead>(mut split: S, mut input: R) -> io::Result<Self> {
+        let mut index = Index {
+            count: 0,
+            interval: INTERVAL,
+            offsets: Vec::new(),
+        };
+        let mut pos = 0;
+        loop {
+            let len = split.skip(&mut input)?;
+            if len == 0 {
+                return Ok(index);
+            }
+            pos += len as u64;
+            index.count += 1;
+            if index.count % index.interval == 0 {
+                index.offsets.push(pos);
+            }
+        }
+    }
+
+    /// How many elements the file holds.
+    #[inline]
+    pub(crate) fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The recorded element nearest before `element` (or at it), and the
+    /// offset where it starts.
+    pub(crate) fn seek_point(&self, element: u64) -> (u64, u64) {
+        let k = (element / self.interval).min(self.offsets.len() as u64);
+        match k.checked_sub(1) {
+            Some(i) => (k * self.interval, self.offsets[i as usize]),
+            None => (0, 0),
+        }
+    }
+
+    fn read(mut input: impl Read, key: &Key) -> io::Result<Option<Self>> {
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        let len = read_u64(&mut input)?;
+        let modified = (read_u64(&mut input)?, read_u32(&mut input)?);
+        let mut split = vec![0; read_u32(&mut input)? as usize];
+        input.read_exact(&mut split)?;
+        let found = Key {
+            len,
+            modified,
+            split,
+        };
+        if &magic != MAGIC || found != *key {
+            return Ok(None);
+        }
+        let interval = read_u64(&mut input)?;
+        let count = read_u64(&mut input)?;
+        let entries = read_u64(&mut input)?;
+        if interval == 0 || entries != count / interval {
+            return Ok(None);
+        }
+        let offsets = (0..entries)
+            .map(|_| read_u64(&mut input))
+            .collect::<io::Result<_>>()?;
+        Ok(Some(Self {
+            count,
+            interval,
+            offsets,
+        }))
+    }
+
+    fn write(&self, mut output: impl Write, key: &Key) -> io::Result<()> {
+        output.write_all(MAGIC)?;
+        output.write_all(&key.len.to_le_bytes())?;
+        output.write_all(&key.modified.0.to_le_bytes())?;
+        output.write_all(&key.modified.1.to_le_bytes())?;
+        output.write_all(&(key.split.len() as u32).to_le_bytes())?;
+        output.write_all(&key.split)?;
+        output.write_all(&self.interval.to_le_bytes())?;
+        output.write_all(&self.count.to_le_bytes())?;
+        output.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        for offset in &self.offsets {
+            output.write_all(&offset.to_le_bytes())?;
+        }
+        output.flush()
+    }
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Where the index of `path` lives: beside it, so it follows the file.
+pub(crate) fn sidecar(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".slice-index");
+    PathBuf::from(name)
+}
+
+/// The index of `path` for the split named `key`: the one on disk when it is
+/// fresh, else a new one, saved for next time. `rebuild` (`--build-index`)
+/// skips the lookup and makes a failed save an error; otherwise one costs
+/// only the next run its speed, so it is reported and the index still used.
+pub(crate) fn open<S: Split>(
+    path: &Path,
+    split: S,
+    key: &[u8],
+    rebuild: bool,
+) -> io::Result<Index> {
+    let file = fs::File::open(path)?;
+    let key = Key::of(&file, key)?;
+    let sidecar = sidecar(path);
+    if !rebuild {
+        // A sidecar that cannot be read is as stale as a mismatched one.
+        let found =
+            fs::File::open(&sidecar).and_then(|index| Index::read(BufReader::new(index), &key));
+        if let Ok(Some(index)) = found {
+            return Ok(index);
+        }
+    }
+    let index = Index::build(split, BufReader::new(file))?;
+    match save(path, &sidecar, &index, &key) {
+        Err(err) if !rebuild => eprintln!("slice: {}: {}", sidecar.display(), err),
+        result => result?,
+    }
+    Ok(index)
+}
+
+/// Write through a temporary name and rename into place, so a concurrent
+/// reader never sees half an index. A file changed while it was split
+/// leaves an index matching neither version, which is not kept.
+fn save(path: &Path, sidecar: &Path, index: &Index, key: &Key) -> io::Result<()> {
+    if Key::of(&fs::File::open(path)?, &key.split)? != *key {
+        return Err(io::Error::other("input changed while it was indexed"));
+    }
+    let mut temporary = sidecar.as_os_str().to_owned();
+    temporary.push(format!(".{}", std::process::id()));
+    let temporary = PathBuf::from(temporary);
+    let result = fs::File::create(&temporary)
+        .and_then(|file| index.write(BufWriter::new(file), key))
+        .and_then(|()| fs::rename(&temporary, sidecar));
+    if result.is_err() {
+        fs::remove_file(&temporary).ok();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::Byte;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn temp_file(contents: &[u8]) -> PathBuf {
+        let id = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("slice-index-{}-{}.txt", std::process::id(), id));
+        fs::write(&path, contents).expect("write temp file");
+        path
+    }
+
+    fn numbered(lines: u64) -> Vec<u8> {
+        (0..lines)
+            .flat_map(|i| format!("{i}\n").into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn records_every_interval_th_start() {
+        let data = numbered(INTERVAL * 2 + 5);
+        let index = Index::build(Byte(b'\n'), &data[..]).unwrap();
+        assert_eq!(index.count(), INTERVAL * 2 + 5);
+        assert_eq!(index.offsets.len(), 2);
+        for (element, _) in [(INTERVAL, ()), (INTERVAL * 2, ())] {
+            let (at, offset) = index.seek_point(element);
+            assert_eq!(at, element);
+            let line = data[offset as usize..]
+                .split(|&b| b == b'\n')
+                .next()
+                .unwrap();
+            assert_eq!(line, element.to_string().as_bytes());
+        }
+    }
+
+    #[test]
+    fn seek_point_lands_at_or_before_the_target() {
+        let index = Index::build(Byte(b'\n'), &numbered(INTERVAL * 3)[..]).unwrap();
+        assert_eq!(index.seek_point(0).0, 0);
+        assert_eq!(index.seek_point(INTERVAL - 1).0, 0);
+        assert_eq!(index.seek_point(INTERVAL * 2 + 7).0, INTERVAL * 2);
+        assert_eq!(index.seek_point(u64::MAX).0, INTERVAL * 3);
+    }
+
+    #[test]
+    fn round_trips_and_rejects_another_key() {
+        let index = Index::build(Byte(b'\n'), &numbered(INTERVAL + 1)[..]).unwrap();
+        let key = Key {
+            len: 10,
+            modified: (1, 2),
+            split: b"lines".to_vec(),
+        };
+        let mut bytes = Vec::new();
+        index.write(&mut bytes, &key).unwrap();
+        assert_eq!(Index::read(&bytes[..], &key).unwrap(), Some(index));
+        let other = Key { len: 11, ..key };
+        assert_eq!(Index::read(&bytes[..], &other).unwrap(), None);
+    }
+
+    #[test]
+    fn sidecar_is_reused_until_the_file_changes() {
+        let path = temp_file(&numbered(10));
+        let index = open(&path, Byte(b'\n'), b"lines", false).unwrap();
+        assert_eq!(index.count(), 10);
+        assert!(sidecar(&path).exists());
+        // A sidecar for another split is not this one.
+        let other = open(&path, Byte(b','), b"delimiter:,", false).unwrap();
+        assert_eq!(other.count(), 1);
+        fs::write(&path, numbered(12)).unwrap();
+        assert_eq!(
+            open(&path, Byte(b'\n'), b"lines", false).unwrap().count(),
+            12
+        );
+        fs::remove_file(sidecar(&path)).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+}
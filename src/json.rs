@@ -0,0 +1,261 @@
+//! `--format json`/`jsonl`: each selected element as a JSON object naming
+//! the file it came from, its index, the byte offset where it starts in the
+//! input, its length, and its content: a string where the element is UTF-8,
+//! base64 (`content_base64`) where it is not. Index and offset come from the
+//! element's `-n`/`--offsets` prefix, which every plan already carries
+//! along, so a reverse or tail-relative range numbers them like a forward
+//! one.
+
+use crate::{
+    ext::{Annotation, FieldWriter, Rewrite},
+    ElementSink,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+/// How `--format` prints the selected elements: as the input holds them
+/// (`text`), as one JSON array of element objects (`json`), or as one element
+/// object per line (`jsonl`, JSON Lines).
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    #[value(name = "jsonl")]
+    JsonLines,
+}
+
+/// The annotation whose prefix the objects are read from.
+pub(crate) const ANNOTATION: Annotation = Annotation {
+    number: Some(0),
+    offsets: true,
+    index: 0,
+    offset: 0,
+};
+
+/// Prints element objects in `format`, across every input; `finish` closes
+/// the array.
+pub(crate) struct JsonWriter<W: Write> {
+    inner: W,
+    format: OutputFormat,
+    written: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    #[inline]
+    pub(crate) fn new(inner: W, format: OutputFormat) -> Self {
+        debug_assert_ne!(format, OutputFormat::Text);
+        Self {
+            inner,
+            format,
+            written: false,
+        }
+    }
+
+    fn write(
+        &mut self,
+        file: Option<&Path>,
+        index: u64,
+        offset: u64,
+        length: usize,
+        content: &[u8],
+    ) -> io::Result<()> {
+        let out = &mut self.inner;
+        if self.format == OutputFormat::Json {
+            out.write_all(if self.written { b",\n" } else { b"[\n" })?;
+        }
+        self.written = true;
+        out.write_all(b"{\"file\":")?;
+        match file {
+            Some(file) => write_str(out, &file.to_string_lossy())?,
+            None => out.write_all(b"null")?,
+        }
+        write!(
+            out,
+            ",\"index\":{index},\"offset\":{offset},\"length\":{length},"
+        )?;
+        match std::str::from_utf8(content) {
+            Ok(content) => {
+                out.write_all(b"\"content\":")?;
+                write_str(out, content)?;
+            }
+            Err(_) => write!(out, "\"content_base64\":\"{}\"", STANDARD.encode(content))?,
+        }
+        out.write_all(b"}")?;
+        if self.format == OutputFormat::JsonLines {
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Close the array, and flush.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        if self.format == OutputFormat::Json {
+            self.inner
+                .write_all(if self.written { b"\n]\n" } else { b"[]\n" })?;
+        }
+        self.inner.flush()
+    }
+}
+
+/// `s` as a JSON string.
+fn write_str<W: Write + ?Sized>(out: &mut W, s: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    let mut from = 0;
+    for (at, c) in s.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            c if c < ' ' => "",
+            _ => continue,
+        };
+        out.write_all(&s.as_bytes()[from..at])?;
+        if escaped.is_empty() {
+            write!(out, "\\u{:04x}", c as u32)?;
+        } else {
+            out.write_all(escaped.as_bytes())?;
+        }
+        from = at + c.len_utf8();
+    }
+    out.write_all(&s.as_bytes()[from..])?;
+    out.write_all(b"\"")
+}
+
+/// One input's elements, for a [`JsonWriter`]: each arrives prefixed with
+/// [`ANNOTATION`], counted from past the header.
+pub(crate) struct JsonElements<'a, W: Write> {
+    writer: &'a mut JsonWriter<W>,
+    file: Option<&'a Path>,
+    rewrite: Option<&'a Rewrite>,
+    terminator: &'a [u8],
+    /// Whether a header came first, so the elements' input index is one on.
+    header: bool,
+}
+
+impl<'a, W: Write> JsonElements<'a, W> {
+    #[inline]
+    pub(crate) fn new(
+        writer: &'a mut JsonWriter<W>,
+        file: Option<&'a Path>,
+        rewrite: Option<&'a Rewrite>,
+        terminator: &'a [u8],
+    ) -> Self {
+        Self {
+            writer,
+            file,
+            rewrite,
+            terminator,
+            header: false,
+        }
+    }
+
+    /// `element` with only its selected fields or columns.
+    fn rewritten(&self, element: &[u8]) -> io::Result<Vec<u8>> {
+        let mut content = Vec::new();
+        let mut writer = FieldWriter::new(
+            &mut content,
+            self.rewrite,
+            self.terminator,
+            Annotation::default(),
+        );
+        writer.write_all(element)?;
+        writer.flush()?;
+        Ok(content)
+    }
+}
+
+impl<W: Write> ElementSink for JsonElements<'_, W> {
+    /// The header is element 0 of the input, though the range counts from
+    /// the element after it.
+    fn header(&mut self, header: &[u8]) -> io::Result<()> {
+        self.header = true;
+        let content = self.rewritten(header)?;
+        self.writer.write(self.file, 0, 0, header.len(), &content)
+    }
+
+    fn element(&mut self, element: &[u8]) -> io::Result<()> {
+        let (prefix, element) = ANNOTATION.split_prefix(element);
+        let mut numbers = prefix
+            .split(|&b| b == b':')
+            .map(|n| std::str::from_utf8(n).ok()?.parse::<u64>().ok());
+        let (Some(Some(index)), Some(Some(offset))) = (numbers.next(), numbers.next()) else {
+            unreachable!("every element is prefixed with its index and offset");
+        };
+        let index = index + u64::from(self.header);
+        let content = self.rewritten(element)?;
+        self.writer
+            .write(self.file, index, offset, element.len(), &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objects(format: OutputFormat, elements: &[&[u8]]) -> String {
+        let mut out = Vec::new();
+        let mut writer = JsonWriter::new(&mut out, format);
+        let mut sink = JsonElements::new(&mut writer, Some(Path::new("a.txt")), None, b"\n");
+        for element in elements {
+            sink.element(element).unwrap();
+        }
+        writer.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn objects_carry_the_prefix_numbers() {
+        assert_eq!(
+            objects(OutputFormat::JsonLines, &[b"3:12:x\n", b"0:0:\xff\n"]),
+            concat!(
+                r#"{"file":"a.txt","index":3,"offset":12,"length":2,"content":"x\n"}"#,
+                "\n",
+                r#"{"file":"a.txt","index":0,"offset":0,"length":2,"content_base64":"/wo="}"#,
+                "\n",
+            )
+        );
+        assert_eq!(
+            objects(OutputFormat::Json, &[b"1:2:a", b"2:3:b"]),
+            concat!(
+                "[\n",
+                r#"{"file":"a.txt","index":1,"offset":2,"length":1,"content":"a"},"#,
+                "\n",
+                r#"{"file":"a.txt","index":2,"offset":3,"length":1,"content":"b"}"#,
+                "\n]\n",
+            )
+        );
+        assert_eq!(objects(OutputFormat::Json, &[]), "[]\n");
+    }
+
+    #[test]
+    fn a_header_counts_in_the_input_index() {
+        let mut out = Vec::new();
+        let mut writer = JsonWriter::new(&mut out, OutputFormat::JsonLines);
+        let mut sink = JsonElements::new(&mut writer, None, None, b"\n");
+        sink.header(b"0\n").unwrap();
+        sink.element(b"0:2:1\n").unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"file":null,"index":0,"offset":0,"length":2,"content":"0\n"}"#,
+                "\n",
+                r#"{"file":null,"index":1,"offset":2,"length":2,"content":"1\n"}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let mut out = Vec::new();
+        write_str(&mut out, "a\"b\\c\td\u{1}é").unwrap();
+        assert_eq!(out, r#""a\"b\\c\td\u0001é""#.as_bytes());
+    }
+}
@@ -0,0 +1,5433 @@
+//! The engine behind the `slice` command: Python-style `start:end:step`
+//! slicing of lines, bytes, characters, grapheme clusters, delimited and CSV
+//! records, streaming wherever the range allows it.
+//!
+//! [`slice()`] runs a parsed [`SliceRanges`] over a reader, [`slice_files`] over
+//! a list of files the way the command does (headers, seeks into regular
+//! files, `--index`, `--follow`); [`select()`] yields the selected elements
+//! as an iterator instead. Underneath, a [`Plan`] classifies the range
+//! once and a [`Split`] cuts the input into elements; both are exposed for
+//! callers driving their own loop.
+//!
+//! ```
+//! use slice_command::{slice, Options, SliceMode, SliceRanges};
+//!
+//! let range: SliceRanges = "1:3".parse().unwrap();
+//! let mut out = Vec::new();
+//! slice(&b"a\nb\nc\nd\n"[..], &mut out, &range, &SliceMode::Lines, &Options::default()).unwrap();
+//! assert_eq!(out, b"b\nc\n");
+//! ```
+
+mod chunk;
+mod decompress;
+mod ext;
+mod follow;
+mod index;
+mod json;
+mod range;
+
+use crate::{
+    chunk::Chunker,
+    decompress::Codec,
+    ext::{
+        char_lag, char_starts, char_stepped, char_tail, char_window, chunk_spans, delimited_starts,
+        paragraph_spans, read_all_with_record_limit, scans_backward, slice_anchored, slice_gap,
+        slice_lag, slice_lag_with_record_limit, slice_stepped, slice_tail,
+        slice_tail_with_record_limit, slice_union, slice_window, Annotated, Annotation, Ansi,
+        AnsiWriter, ChunkSpans, ChunkSpansWriter, Columns, FieldWriter, Fields, GraphemeElements,
+        ParagraphSpans, Rewrite, SegmentElements, Spool, SpoolWriter, Unit, Utf8Elements,
+    },
+    json::{JsonElements, JsonWriter},
+    range::ReverseWalk,
+};
+pub use crate::{
+    chunk::{ChunkTemplate, ParseChunkTemplateError},
+    ext::{
+        Byte, Bytes, Chars, Chunked, Chunks, Csv, DelimiterRegex, EachByte, FieldSeparator,
+        FixedSize, Graphemes, Paragraphs, RegexDelimited, Selected, Sentences, SliceChunks, Split,
+        Words,
+    },
+    follow::Follow,
+    json::OutputFormat,
+    range::{
+        classify, AnchoredPlan, AnchoredRange, DeferredPlan, ParseSliceRangeError, Plan,
+        ProportionalPlan, ProportionalRange, RangeField, ReversePlan, ReverseUnionPlan, SliceIndex,
+        SlicePlan, SliceRange, SliceRanges, Step, TranslateDialect, TranslateMode, UnionPlan,
+    },
+};
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, BufRead, Read, Seek, SeekFrom, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+
+pub(crate) const WRITE_BUF_SIZE: usize = 8 * 1024;
+
+/// What an element is.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum SliceMode<'b> {
+    /// Newline-terminated lines.
+    Lines,
+    /// Single bytes.
+    Bytes,
+    /// UTF-8 characters (Unicode scalar values); a byte that starts no valid
+    /// sequence is one element of its own, so any input splits losslessly.
+    Chars,
+    /// User-perceived characters (UAX #29 extended grapheme clusters);
+    /// invalid bytes stand alone and terminate the cluster, so any input
+    /// splits losslessly here too.
+    Graphemes,
+    /// UAX #29 words, with the spaces and punctuation between them elements
+    /// of their own; invalid bytes split as for `Graphemes`.
+    Words,
+    /// UAX #29 sentences, each keeping its trailing spaces and line break.
+    Sentences,
+    /// Records terminated by a delimiter. An empty one splits one byte per
+    /// element, so the entry points fold it into `Bytes` and the delimiter
+    /// drivers never see an empty shape.
+    Custom(&'b [u8]),
+    /// RFC 4180 records: newline-terminated, except inside a quoted field.
+    Csv,
+    /// Records terminated by a match of a regular expression.
+    Regex(&'b DelimiterRegex),
+    /// Blocks of lines separated by blank lines, each printed with one empty
+    /// line after it; see [`Paragraphs`].
+    Paragraphs,
+    /// Records of this many bytes each, with no delimiter; the last one may
+    /// be shorter. Record indices map onto byte offsets, so these seek and
+    /// copy like `Bytes`.
+    Records(NonZeroUsize),
+}
+
+impl<'b> SliceMode<'b> {
+    /// The [`Split`] cutting this mode's elements, chosen at run time. The
+    /// drivers behind [`slice()`] dispatch to the concrete kinds instead.
+    pub fn split(&self) -> ModeSplit<'b> {
+        match folded(self) {
+            SliceMode::Lines => ModeSplit::Byte(Byte(b'\n')),
+            SliceMode::Bytes => ModeSplit::EachByte(EachByte),
+            SliceMode::Chars => ModeSplit::Chars(Chars::new()),
+            SliceMode::Graphemes => ModeSplit::Graphemes(Graphemes::new()),
+            SliceMode::Custom(&[b]) => ModeSplit::Byte(Byte(b)),
+            SliceMode::Custom(delimiter) => ModeSplit::Bytes(Box::new(Bytes::new(delimiter))),
+            SliceMode::Csv => ModeSplit::Csv(Csv),
+            SliceMode::Regex(delimiter) => {
+                ModeSplit::Regex(Box::new(RegexDelimited::new(delimiter, None)))
+            }
+            SliceMode::Paragraphs => ModeSplit::Paragraphs(Paragraphs::new()),
+            SliceMode::Words => ModeSplit::Words(Words::new()),
+            SliceMode::Sentences => ModeSplit::Sentences(Sentences::new()),
+            SliceMode::Records(size) => ModeSplit::FixedSize(FixedSize(size)),
+        }
+    }
+}
+
+/// Any [`SliceMode`]'s split, from [`SliceMode::split`].
+pub enum ModeSplit<'b> {
+    Byte(Byte),
+    Bytes(Box<Bytes<'b>>),
+    EachByte(EachByte),
+    Chars(Chars),
+    Graphemes(Graphemes),
+    Csv(Csv),
+    Regex(Box<RegexDelimited<'b>>),
+    Paragraphs(Paragraphs),
+    Words(Words),
+    Sentences(Sentences),
+    FixedSize(FixedSize),
+}
+
+/// Forward a call to whichever split `$split` holds.
+macro_rules! each_split {
+    ($split:expr, $kind:ident => $call:expr) => {
+        match $split {
+            ModeSplit::Byte($kind) => $call,
+            ModeSplit::Bytes($kind) => $call,
+            ModeSplit::EachByte($kind) => $call,
+            ModeSplit::Chars($kind) => $call,
+            ModeSplit::Graphemes($kind) => $call,
+            ModeSplit::Csv($kind) => $call,
+            ModeSplit::Regex($kind) => $call,
+            ModeSplit::Paragraphs($kind) => $call,
+            ModeSplit::Words($kind) => $call,
+            ModeSplit::Sentences($kind) => $call,
+            ModeSplit::FixedSize($kind) => $call,
+        }
+    };
+}
+
+impl Split for ModeSplit<'_> {
+    #[inline]
+    fn read_to<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<usize> {
+        each_split!(self, split => split.read_to(r, w))
+    }
+
+    #[inline]
+    fn skip<R: BufRead + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        each_split!(self, split => split.skip(r))
+    }
+
+    #[inline]
+    fn skip_n<R: BufRead + ?Sized>(&mut self, r: &mut R, n: usize) -> io::Result<usize> {
+        each_split!(self, split => split.skip_n(r, n))
+    }
+
+    #[inline]
+    fn copy_rest<R: BufRead + ?Sized, W: Write + ?Sized>(
+        &mut self,
+        r: &mut R,
+        w: &mut W,
+    ) -> io::Result<u64> {
+        each_split!(self, split => split.copy_rest(r, w))
+    }
+
+    #[inline]
+    fn content<'c>(&self, chunk: &'c [u8]) -> &'c [u8] {
+        each_split!(self, split => split.content(chunk))
+    }
+
+    #[inline]
+    fn terminated(&self, chunk: &[u8]) -> bool {
+        each_split!(self, split => split.terminated(chunk))
+    }
+}
+
+/// The elements `range` selects from `input`, one owned chunk at a time —
+/// [`slice()`] as an iterator, without its [`Options`]. Build a [`Selected`]
+/// directly for a complemented plan, a split of your own, or chunks borrowed
+/// from in-memory data ([`SliceChunks`]); its docs list what each range
+/// shape buffers.
+pub fn select<'b, R: BufRead>(
+    input: R,
+    range: &SliceRanges,
+    mode: &SliceMode<'b>,
+) -> Selected<Chunks<ModeSplit<'b>, R>> {
+    Selected::new(Chunks::new(mode.split(), input), &range.plan())
+}
+
+#[inline]
+fn buf_reader<R: Read>(reader: R, capacity: Option<NonZeroUsize>) -> io::BufReader<R> {
+    if let Some(capacity) = capacity {
+        io::BufReader::with_capacity(capacity.get(), reader)
+    } else {
+        io::BufReader::new(reader)
+    }
+}
+
+#[inline]
+fn buf_writer<W: Write>(writer: W, capacity: Option<NonZeroUsize>) -> io::BufWriter<W> {
+    if let Some(capacity) = capacity {
+        io::BufWriter::with_capacity(capacity.get(), writer)
+    } else {
+        io::BufWriter::new(writer)
+    }
+}
+
+#[inline]
+fn delimit_window<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    delimiter: &[u8],
+    start: usize,
+    end: Option<usize>,
+) -> io::Result<()> {
+    debug_assert!(!delimiter.is_empty(), "empty delimiter is byte mode");
+    match delimiter {
+        &[b] => slice_window(Byte(b), input, output, start, end),
+        multi => slice_window(Bytes::new(multi), input, output, start, end),
+    }
+}
+
+#[inline]
+fn delimit_stepped<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    delimiter: &[u8],
+    start: usize,
+    end: Option<usize>,
+    step: NonZeroUsize,
+) -> io::Result<()> {
+    debug_assert!(!delimiter.is_empty(), "empty delimiter is byte mode");
+    match delimiter {
+        &[b] => slice_stepped(Byte(b), input, output, start, end, step),
+        multi => slice_stepped(Bytes::new(multi), input, output, start, end, step),
+    }
+}
+
+// Byte-mode stepped path: emits the bytes at indices i in [start, min(end, len))
+// with (i - start) % step == 0. `end` is an absolute index from the stream
+// start, so start >= end selects nothing.
+fn byte_mode<R: BufRead, W: Write>(
+    mut input: R,
+    mut output: W,
+    start: usize,
+    end: Option<usize>,
+    step: NonZeroUsize,
+) -> io::Result<()> {
+    let mut remaining = end.map(|end| end.saturating_sub(start));
+    if remaining == Some(0) {
+        return output.flush();
+    }
+    discard(&mut input, start as u64)?;
+    let step = step.get();
+    let mut buf = Vec::with_capacity(WRITE_BUF_SIZE);
+    // Offset of the next selected byte within the unread stream; carried
+    // across fill_buf blocks so the stride stays aligned to `start`.
+    let mut phase = 0;
+    loop {
+        let block = match input.fill_buf() {
+            Ok([]) => break,
+            Ok(block) => block,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        let limit = match remaining {
+            Some(remaining) => block.len().min(remaining),
+            None => block.len(),
+        };
+        while phase < limit {
+            buf.push(block[phase]);
+            if buf.len() == WRITE_BUF_SIZE {
+                output.write_all(&buf)?;
+                buf.clear();
+            }
+            phase = phase.saturating_add(step);
+        }
+        phase -= limit;
+        input.consume(limit);
+        if let Some(remaining) = &mut remaining {
+            *remaining -= limit;
+            if *remaining == 0 {
+                break;
+            }
+        }
+    }
+    if !buf.is_empty() {
+        output.write_all(&buf)?;
+    }
+    output.flush()
+}
+
+// stdin and pipes are not Seek; advance past `start` bytes by consuming them.
+#[inline]
+fn discard<R: BufRead>(reader: &mut R, mut n: u64) -> io::Result<()> {
+    while n > 0 {
+        let consumed = {
+            let buf = match reader.fill_buf() {
+                Ok([]) => break,
+                Ok(buf) => buf,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            buf.len().min(n as usize)
+        };
+        reader.consume(consumed);
+        n -= consumed as u64;
+    }
+    Ok(())
+}
+
+// Byte-mode unit-step fast-path: emits input bytes [start, min(end, len)),
+// matching IteratorExt::slice's take(end).skip(start) ordering — `end` is an
+// absolute index from the stream start, so start >= end yields an empty window.
+#[inline]
+fn byte_window<R, W, S>(
+    mut input: R,
+    mut output: W,
+    start: usize,
+    end: Option<usize>,
+    skip: S,
+) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    S: Fn(&mut R, u64) -> io::Result<()>,
+{
+    if start > 0 {
+        skip(&mut input, start as u64)?;
+    }
+    match end {
+        Some(end) => {
+            let len = end.saturating_sub(start) as u64;
+            io::copy(&mut (&mut input).take(len), &mut output)?;
+        }
+        None => {
+            io::copy(&mut input, &mut output)?;
+        }
+    }
+    output.flush()
+}
+
+// Byte-mode complement of a bounded window: copies bytes [0, start), skips
+// the window, and copies everything from `end` on.
+#[inline]
+fn byte_gap<R, W, S>(
+    mut input: R,
+    mut output: W,
+    start: usize,
+    end: usize,
+    skip: S,
+) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    S: Fn(&mut R, u64) -> io::Result<()>,
+{
+    io::copy(&mut (&mut input).take(start as u64), &mut output)?;
+    skip(&mut input, end.saturating_sub(start) as u64)?;
+    io::copy(&mut input, &mut output)?;
+    output.flush()
+}
+
+/// The byte offset where fixed-size record `index` starts; past `usize` it
+/// is past any input anyway.
+#[inline]
+fn record_offset(index: usize, size: NonZeroUsize) -> usize {
+    index.saturating_mul(size.get())
+}
+
+// Record-mode stride: seeks (or discards) to the first record like
+// byte_window, then steps through the rest record by record.
+fn record_stepped<R, W, S>(
+    mut input: R,
+    output: W,
+    size: NonZeroUsize,
+    start: usize,
+    end: Option<usize>,
+    step: NonZeroUsize,
+    skip: S,
+) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    S: Fn(&mut R, u64) -> io::Result<()>,
+{
+    if start > 0 {
+        skip(&mut input, record_offset(start, size) as u64)?;
+    }
+    let end = end.map(|end| end.saturating_sub(start));
+    slice_stepped(FixedSize(size), input, output, 0, end, step)
+}
+
+/// `--keep-header`: pass the first record through before the range sees
+/// the input, so the range counts from the record after it. Returns the
+/// header's length, where the counted input starts.
+fn keep_header<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<u64> {
+    match mode {
+        SliceMode::Lines => Byte(b'\n').read_to(input, output),
+        SliceMode::Custom(&[b]) => Byte(b).read_to(input, output),
+        SliceMode::Custom(delimiter) => Bytes::new(delimiter).read_to(input, output),
+        SliceMode::Csv => Csv.read_to(input, output),
+        SliceMode::Bytes
+        | SliceMode::Chars
+        | SliceMode::Graphemes
+        | SliceMode::Words
+        | SliceMode::Sentences
+        | SliceMode::Regex(_)
+        | SliceMode::Paragraphs
+        | SliceMode::Records(_) => {
+            unreachable!("Options::check rejects a kept header outside the record modes")
+        }
+    }
+    .map(|len| len as u64)
+}
+
+#[inline]
+fn copy_mode<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<()> {
+    io::copy(&mut input, &mut output)?;
+    output.flush()
+}
+
+#[inline]
+fn apply<R, W, S>(
+    mode: &SliceMode,
+    input: R,
+    mut output: W,
+    plan: SlicePlan,
+    skip: S,
+    max_record_size: Option<usize>,
+) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    S: Fn(&mut R, u64) -> io::Result<()>,
+{
+    match plan {
+        SlicePlan::Empty => output.flush(),
+        // Paragraphs come out normalized, so even the whole input is split.
+        SlicePlan::Copy if *mode == SliceMode::Paragraphs => {
+            slice_window(Paragraphs::new(), input, output, 0, None)
+        }
+        SlicePlan::Copy => copy_mode(input, output),
+        SlicePlan::Window { start, end } => match mode {
+            SliceMode::Lines => slice_window(Byte(b'\n'), input, output, start, end),
+            SliceMode::Bytes => byte_window(input, output, start, end, skip),
+            SliceMode::Chars => char_window(input, output, start, end),
+            SliceMode::Graphemes => slice_window(Graphemes::new(), input, output, start, end),
+            SliceMode::Words => slice_window(Words::new(), input, output, start, end),
+            SliceMode::Sentences => slice_window(Sentences::new(), input, output, start, end),
+            SliceMode::Custom(delimiter) => delimit_window(input, output, delimiter, start, end),
+            SliceMode::Csv => slice_window(Csv, input, output, start, end),
+            SliceMode::Regex(delimiter) => {
+                let split = RegexDelimited::new(delimiter, max_record_size);
+                slice_window(split, input, output, start, end)
+            }
+            SliceMode::Paragraphs => slice_window(Paragraphs::new(), input, output, start, end),
+            SliceMode::Records(size) => {
+                let (start, end) = (
+                    record_offset(start, *size),
+                    end.map(|end| record_offset(end, *size)),
+                );
+                byte_window(input, output, start, end, skip)
+            }
+        },
+        SlicePlan::Stepped { start, end, step } => match mode {
+            SliceMode::Lines => slice_stepped(Byte(b'\n'), input, output, start, end, step),
+            SliceMode::Bytes => byte_mode(input, output, start, end, step),
+            SliceMode::Chars => char_stepped(input, output, start, end, step),
+            SliceMode::Graphemes => {
+                slice_stepped(Graphemes::new(), input, output, start, end, step)
+            }
+            SliceMode::Words => slice_stepped(Words::new(), input, output, start, end, step),
+            SliceMode::Sentences => {
+                slice_stepped(Sentences::new(), input, output, start, end, step)
+            }
+            SliceMode::Custom(delimiter) => {
+                delimit_stepped(input, output, delimiter, start, end, step)
+            }
+            SliceMode::Csv => slice_stepped(Csv, input, output, start, end, step),
+            SliceMode::Regex(delimiter) => {
+                let split = RegexDelimited::new(delimiter, max_record_size);
+                slice_stepped(split, input, output, start, end, step)
+            }
+            SliceMode::Paragraphs => {
+                slice_stepped(Paragraphs::new(), input, output, start, end, step)
+            }
+            SliceMode::Records(size) => {
+                record_stepped(input, output, *size, start, end, step, skip)
+            }
+        },
+        SlicePlan::Gap { start, end } => match mode {
+            SliceMode::Lines => slice_gap(Byte(b'\n'), input, output, start, end),
+            SliceMode::Bytes => byte_gap(input, output, start, end, skip),
+            SliceMode::Chars => slice_gap(Chars::new(), input, output, start, end),
+            SliceMode::Graphemes => slice_gap(Graphemes::new(), input, output, start, end),
+            SliceMode::Words => slice_gap(Words::new(), input, output, start, end),
+            SliceMode::Sentences => slice_gap(Sentences::new(), input, output, start, end),
+            SliceMode::Custom(&[b]) => slice_gap(Byte(b), input, output, start, end),
+            SliceMode::Custom(delimiter) => {
+                slice_gap(Bytes::new(delimiter), input, output, start, end)
+            }
+            SliceMode::Csv => slice_gap(Csv, input, output, start, end),
+            SliceMode::Regex(delimiter) => {
+                let split = RegexDelimited::new(delimiter, max_record_size);
+                slice_gap(split, input, output, start, end)
+            }
+            SliceMode::Paragraphs => slice_gap(Paragraphs::new(), input, output, start, end),
+            SliceMode::Records(size) => {
+                let (start, end) = (record_offset(start, *size), record_offset(end, *size));
+                byte_gap(input, output, start, end, skip)
+            }
+        },
+    }
+}
+
+// Emit the stride-selected bytes of one confirmed segment. `phase` is the
+// offset of the next selected byte relative to the segment start; on return it
+// is relative to the segment end, so consecutive segments share one stride.
+#[inline]
+fn emit_run<W: Write>(
+    seg: &[u8],
+    step: usize,
+    phase: &mut usize,
+    buf: &mut Vec<u8>,
+    output: &mut W,
+) -> io::Result<()> {
+    if step == 1 {
+        return output.write_all(seg);
+    }
+    let mut p = *phase;
+    while p < seg.len() {
+        buf.push(seg[p]);
+        if buf.len() == WRITE_BUF_SIZE {
+            output.write_all(buf)?;
+            buf.clear();
+        }
+        p = p.saturating_add(step);
+    }
+    *phase = p - seg.len();
+    Ok(())
+}
+
+/// GNU `head -c -N` equivalent generalized with start/step: bytes ride a ring
+/// of the last `back` bytes seen and one is confirmed (emitted, stride
+/// permitting) once `back` more bytes arrive, so at EOF the ring holds exactly
+/// the dropped tail. Runs when the resolve/seek fast path is unavailable
+/// (stdin, FIFOs, sizeless files) and assumes plain `BufRead`, so the leading
+/// `start` bytes are read-discarded.
+fn byte_lag<R: BufRead, W: Write>(
+    mut input: R,
+    mut output: W,
+    start: usize,
+    back: NonZeroUsize,
+    step: NonZeroUsize,
+) -> io::Result<()> {
+    discard(&mut input, start as u64)?;
+    let m = back.get();
+    let step = step.get();
+    let mut ring: Vec<u8> = Vec::new();
+    // Bytes appended to the ring. Its window is [filled - ring.len(), filled)
+    // at slots p % m; everything before the window start is already emitted.
+    let mut filled: u64 = 0;
+    let mut buf = Vec::new();
+    let mut phase = 0;
+    loop {
+        let used = {
+            let block = match input.fill_buf() {
+                Ok([]) => break,
+                Ok(block) => block,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            let n = block.len();
+            // The block confirms every byte more than m behind the new total:
+            // the ring window's prefix, then — when the block outsizes the
+            // ring — the block's own prefix. Both are emitted before
+            // ring_extend overwrites exactly those slots.
+            let window_start = filled - ring.len() as u64;
+            let confirmed = (filled + n as u64).saturating_sub(m as u64);
+            let from_ring =
+                (confirmed.saturating_sub(window_start)).min(ring.len() as u64) as usize;
+            if from_ring > 0 {
+                let pos = (window_start % m as u64) as usize;
+                let first = from_ring.min(ring.len() - pos);
+                emit_run(
+                    &ring[pos..pos + first],
+                    step,
+                    &mut phase,
+                    &mut buf,
+                    &mut output,
+                )?;
+                emit_run(
+                    &ring[..from_ring - first],
+                    step,
+                    &mut phase,
+                    &mut buf,
+                    &mut output,
+                )?;
+            }
+            if confirmed > filled {
+                let prefix = (confirmed - filled) as usize;
+                emit_run(&block[..prefix], step, &mut phase, &mut buf, &mut output)?;
+            }
+            ring_extend(&mut ring, m, filled, block);
+            filled += n as u64;
+            n
+        };
+        input.consume(used);
+    }
+    if !buf.is_empty() {
+        output.write_all(&buf)?;
+    }
+    output.flush()
+}
+
+/// Append `data` to the circular buffer of the last `k` bytes: the byte at
+/// absolute index p lives at slot p % k (`filled` counts the bytes appended
+/// before this call). The middle of an oversized block is never copied — only
+/// up to `k - ring.len()` leading bytes (growth) and the last k bytes are
+/// written, in at most two `copy_from_slice` once the ring is full.
+#[inline]
+fn ring_extend(ring: &mut Vec<u8>, k: usize, mut filled: u64, mut data: &[u8]) {
+    // Grown lazily toward k so a huge `-k:` never preallocates past the bytes
+    // actually seen; while growing, slot p % k = p = ring.len().
+    if ring.len() < k {
+        let grow = data.len().min(k - ring.len());
+        ring.extend_from_slice(&data[..grow]);
+        filled += grow as u64;
+        data = &data[grow..];
+        if data.is_empty() {
+            return;
+        }
+    }
+    let keep = data.len().min(k);
+    let tail = &data[data.len() - keep..];
+    let pos = ((filled + (data.len() - keep) as u64) % k as u64) as usize;
+    let first = keep.min(k - pos);
+    ring[pos..pos + first].copy_from_slice(&tail[..first]);
+    ring[..keep - first].copy_from_slice(&tail[first..]);
+}
+
+/// `tail -c N` equivalent generalized with end/step: the last `back` bytes
+/// seen ride a circular buffer; EOF fixes the length and the resolve
+/// arithmetic picks the emitted span, always within the ring's window. An
+/// absolute `end` freezes the ring once reached — the remainder is counted
+/// without copying.
+fn byte_tail<R: BufRead, W: Write>(
+    mut input: R,
+    mut output: W,
+    back: NonZeroUsize,
+    end: Option<SliceIndex>,
+    step: NonZeroUsize,
+) -> io::Result<()> {
+    let k = back.get();
+    let bound = match end {
+        Some(SliceIndex::FromStart(end)) => Some(end as u64),
+        _ => None,
+    };
+    let mut ring: Vec<u8> = Vec::new();
+    // Bytes appended to the ring (capped at `bound`) / consumed from input.
+    let mut filled: u64 = 0;
+    let mut total: u64 = 0;
+    loop {
+        let used = {
+            let block = match input.fill_buf() {
+                Ok([]) => break,
+                Ok(block) => block,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            let take = match bound {
+                Some(end) => block.len().min((end - filled) as usize),
+                None => block.len(),
+            };
+            ring_extend(&mut ring, k, filled, &block[..take]);
+            filled += take as u64;
+            block.len()
+        };
+        input.consume(used);
+        total += used as u64;
+    }
+    let start = SliceIndex::FromEnd(back).resolve(total);
+    let end = end.map_or(total, |end| end.resolve(total));
+    if step.get() == 1 {
+        // The selected span is contiguous in the ring, split at most once at
+        // the wrap boundary.
+        if start < end {
+            let pos = (start % k as u64) as usize;
+            let count = (end - start) as usize;
+            let first = count.min(k - pos);
+            output.write_all(&ring[pos..pos + first])?;
+            output.write_all(&ring[..count - first])?;
+        }
+    } else {
+        let step = step.get() as u64;
+        let mut buf = Vec::with_capacity(WRITE_BUF_SIZE);
+        let mut p = start;
+        while p < end {
+            buf.push(ring[(p % k as u64) as usize]);
+            if buf.len() == WRITE_BUF_SIZE {
+                output.write_all(&buf)?;
+                buf.clear();
+            }
+            p = p.saturating_add(step);
+        }
+        if !buf.is_empty() {
+            output.write_all(&buf)?;
+        }
+    }
+    output.flush()
+}
+
+#[inline]
+fn delimit_tail<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    delimiter: &[u8],
+    back: NonZeroUsize,
+    end: Option<SliceIndex>,
+    step: NonZeroUsize,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    debug_assert!(!delimiter.is_empty(), "empty delimiter is byte mode");
+    match delimiter {
+        &[b] if max_record_size.is_some() => {
+            slice_tail_with_record_limit(Byte(b), input, output, back, end, step, max_record_size)
+        }
+        &[b] => slice_tail(Byte(b), input, output, back, end, step),
+        multi if max_record_size.is_some() => slice_tail_with_record_limit(
+            Bytes::new(multi),
+            input,
+            output,
+            back,
+            end,
+            step,
+            max_record_size,
+        ),
+        multi => slice_tail(Bytes::new(multi), input, output, back, end, step),
+    }
+}
+
+#[inline]
+fn delimit_lag<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    delimiter: &[u8],
+    start: usize,
+    back: NonZeroUsize,
+    step: NonZeroUsize,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    debug_assert!(!delimiter.is_empty(), "empty delimiter is byte mode");
+    match delimiter {
+        &[b] if max_record_size.is_some() => {
+            slice_lag_with_record_limit(Byte(b), input, output, start, back, step, max_record_size)
+        }
+        &[b] => slice_lag(Byte(b), input, output, start, back, step),
+        multi if max_record_size.is_some() => slice_lag_with_record_limit(
+            Bytes::new(multi),
+            input,
+            output,
+            start,
+            back,
+            step,
+            max_record_size,
+        ),
+        multi => slice_lag(Bytes::new(multi), input, output, start, back, step),
+    }
+}
+
+#[inline]
+fn apply_deferred<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: R,
+    output: W,
+    plan: DeferredPlan,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    match plan {
+        DeferredPlan::Tail { back, end, step } => match mode {
+            SliceMode::Lines if max_record_size.is_some() => slice_tail_with_record_limit(
+                Byte(b'\n'),
+                input,
+                output,
+                back,
+                end,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Lines => slice_tail(Byte(b'\n'), input, output, back, end, step),
+            SliceMode::Bytes => byte_tail(input, output, back, end, step),
+            // An element is at most 4 bytes, so the record limit is meaningless
+            // here, like byte mode.
+            SliceMode::Chars => char_tail(input, output, back, end, step),
+            // A cluster is unbounded, so the record limit applies like lines.
+            SliceMode::Graphemes if max_record_size.is_some() => slice_tail_with_record_limit(
+                Graphemes::new(),
+                input,
+                output,
+                back,
+                end,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Graphemes => slice_tail(Graphemes::new(), input, output, back, end, step),
+            // Words and sentences are unbounded too.
+            SliceMode::Words => slice_tail_with_record_limit(
+                Words::new(),
+                input,
+                output,
+                back,
+                end,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Sentences => slice_tail_with_record_limit(
+                Sentences::new(),
+                input,
+                output,
+                back,
+                end,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Custom(delimiter) => {
+                delimit_tail(input, output, delimiter, back, end, step, max_record_size)
+            }
+            SliceMode::Csv => {
+                slice_tail_with_record_limit(Csv, input, output, back, end, step, max_record_size)
+            }
+            SliceMode::Regex(delimiter) => slice_tail(
+                RegexDelimited::new(delimiter, max_record_size),
+                input,
+                output,
+                back,
+                end,
+                step,
+            ),
+            SliceMode::Paragraphs => slice_tail_with_record_limit(
+                Paragraphs::new(),
+                input,
+                output,
+                back,
+                end,
+                step,
+                max_record_size,
+            ),
+            // A record's size is fixed, so the limit has nothing to bound.
+            SliceMode::Records(size) => {
+                slice_tail(FixedSize(*size), input, output, back, end, step)
+            }
+        },
+        DeferredPlan::Lag { start, back, step } => match mode {
+            SliceMode::Lines if max_record_size.is_some() => slice_lag_with_record_limit(
+                Byte(b'\n'),
+                input,
+                output,
+                start,
+                back,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Lines => slice_lag(Byte(b'\n'), input, output, start, back, step),
+            SliceMode::Bytes => byte_lag(input, output, start, back, step),
+            SliceMode::Chars => char_lag(input, output, start, back, step),
+            SliceMode::Graphemes if max_record_size.is_some() => slice_lag_with_record_limit(
+                Graphemes::new(),
+                input,
+                output,
+                start,
+                back,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Graphemes => slice_lag(Graphemes::new(), input, output, start, back, step),
+            SliceMode::Words => slice_lag_with_record_limit(
+                Words::new(),
+                input,
+                output,
+                start,
+                back,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Sentences => slice_lag_with_record_limit(
+                Sentences::new(),
+                input,
+                output,
+                start,
+                back,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Custom(delimiter) => {
+                delimit_lag(input, output, delimiter, start, back, step, max_record_size)
+            }
+            SliceMode::Csv => {
+                slice_lag_with_record_limit(Csv, input, output, start, back, step, max_record_size)
+            }
+            SliceMode::Regex(delimiter) => slice_lag(
+                RegexDelimited::new(delimiter, max_record_size),
+                input,
+                output,
+                start,
+                back,
+                step,
+            ),
+            SliceMode::Paragraphs => slice_lag_with_record_limit(
+                Paragraphs::new(),
+                input,
+                output,
+                start,
+                back,
+                step,
+                max_record_size,
+            ),
+            SliceMode::Records(size) => {
+                slice_lag(FixedSize(*size), input, output, start, back, step)
+            }
+        },
+        DeferredPlan::Proportional(_) => unreachable!("proportional plans are counted first"),
+    }
+}
+
+/// Every mode runs the union through the generic chunk driver; bytes and chars
+/// get their per-element splits, and like their tail-relative paths they
+/// ignore the record limit.
+#[inline]
+fn apply_union<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: R,
+    output: W,
+    plan: &UnionPlan,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    match mode {
+        SliceMode::Lines => slice_union(Byte(b'\n'), input, output, plan, max_record_size),
+        SliceMode::Bytes => slice_union(EachByte, input, output, plan, None),
+        SliceMode::Chars => slice_union(Chars::new(), input, output, plan, None),
+        SliceMode::Graphemes => slice_union(Graphemes::new(), input, output, plan, max_record_size),
+        SliceMode::Words => slice_union(Words::new(), input, output, plan, max_record_size),
+        SliceMode::Sentences => slice_union(Sentences::new(), input, output, plan, max_record_size),
+        SliceMode::Custom(&[b]) => slice_union(Byte(b), input, output, plan, max_record_size),
+        SliceMode::Custom(delimiter) => {
+            slice_union(Bytes::new(delimiter), input, output, plan, max_record_size)
+        }
+        SliceMode::Csv => slice_union(Csv, input, output, plan, max_record_size),
+        SliceMode::Regex(delimiter) => {
+            let split = RegexDelimited::new(delimiter, max_record_size);
+            slice_union(split, input, output, plan, max_record_size)
+        }
+        SliceMode::Paragraphs => {
+            slice_union(Paragraphs::new(), input, output, plan, max_record_size)
+        }
+        SliceMode::Records(size) => slice_union(FixedSize(*size), input, output, plan, None),
+    }
+}
+
+/// Like a union, every mode runs through the generic chunk driver. A chunk is
+/// held only while its content is tested against a pattern, so the record
+/// limit applies as in tail-relative ranges: bytes and chars ignore it.
+#[inline]
+fn apply_anchored<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: R,
+    output: W,
+    plan: &AnchoredPlan,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    match mode {
+        SliceMode::Lines => slice_anchored(Byte(b'\n'), input, output, plan, max_record_size),
+        SliceMode::Bytes => slice_anchored(EachByte, input, output, plan, None),
+        SliceMode::Chars => slice_anchored(Chars::new(), input, output, plan, None),
+        SliceMode::Graphemes => {
+            slice_anchored(Graphemes::new(), input, output, plan, max_record_size)
+        }
+        SliceMode::Words => slice_anchored(Words::new(), input, output, plan, max_record_size),
+        SliceMode::Sentences => {
+            slice_anchored(Sentences::new(), input, output, plan, max_record_size)
+        }
+        SliceMode::Custom(&[b]) => slice_anchored(Byte(b), input, output, plan, max_record_size),
+        SliceMode::Custom(delimiter) => {
+            slice_anchored(Bytes::new(delimiter), input, output, plan, max_record_size)
+        }
+        SliceMode::Csv => slice_anchored(Csv, input, output, plan, max_record_size),
+        SliceMode::Regex(delimiter) => {
+            let split = RegexDelimited::new(delimiter, max_record_size);
+            slice_anchored(split, input, output, plan, max_record_size)
+        }
+        SliceMode::Paragraphs => {
+            slice_anchored(Paragraphs::new(), input, output, plan, max_record_size)
+        }
+        SliceMode::Records(size) => slice_anchored(FixedSize(*size), input, output, plan, None),
+    }
+}
+
+/// `-n`/`--offsets` on a forward plan. The prefixes come from an
+/// [`Annotated`] split, so every mode runs through the generic chunk drivers:
+/// the byte and char block paths, the verbatim copies, and the backward tail
+/// scan never see where one element ends and the next begins. Bytes and
+/// chars ignore the record limit, as on their own paths.
+fn apply_annotated<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: R,
+    output: W,
+    plan: &Plan,
+    annotation: Annotation,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    let limit = max_record_size;
+    match mode {
+        SliceMode::Lines => annotated(
+            Annotated::new(Byte(b'\n'), annotation),
+            input,
+            output,
+            plan,
+            limit,
+        ),
+        SliceMode::Bytes => annotated(
+            Annotated::new(EachByte, annotation),
+            input,
+            output,
+            plan,
+            None,
+        ),
+        SliceMode::Chars => annotated(
+            Annotated::new(Chars::new(), annotation),
+            input,
+            output,
+            plan,
+            None,
+        ),
+        SliceMode::Graphemes => annotated(
+            Annotated::new(Graphemes::new(), annotation),
+            input,
+            output,
+            plan,
+            limit,
+        ),
+        SliceMode::Words => annotated(
+            Annotated::new(Words::new(), annotation),
+            input,
+            output,
+            plan,
+            limit,
+        ),
+        SliceMode::Sentences => annotated(
+            Annotated::new(Sentences::new(), annotation),
+            input,
+            output,
+            plan,
+            limit,
+        ),
+        SliceMode::Custom(&[b]) => annotated(
+            Annotated::new(Byte(b), annotation),
+            input,
+            output,
+            plan,
+            limit,
+        ),
+        SliceMode::Custom(delimiter) => {
+            let split = Annotated::new(Bytes::new(delimiter), annotation);
+            annotated(split, input, output, plan, limit)
+        }
+        SliceMode::Csv => annotated(Annotated::new(Csv, annotation), input, output, plan, limit),
+        SliceMode::Regex(delimiter) => {
+            let split = Annotated::new(RegexDelimited::new(delimiter, limit), annotation);
+            annotated(split, input, output, plan, limit)
+        }
+        SliceMode::Paragraphs => {
+            let split = Annotated::new(Paragraphs::new(), annotation);
+            annotated(split, input, output, plan, limit)
+        }
+        SliceMode::Records(size) => {
+            let split = Annotated::new(FixedSize(*size), annotation);
+            annotated(split, input, output, plan, None)
+        }
+    }
+}
+
+/// `--ansi` on any plan. Every mode but paragraphs copies its elements
+/// verbatim, so one [`Ansi`] wrapper over the mode's own split serves them
+/// all through the generic chunk drivers, annotated or not.
+fn apply_ansi<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: R,
+    output: W,
+    plan: &Plan,
+    max_record_size: Option<usize>,
+    max_memory: Option<usize>,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let limit = match mode {
+        SliceMode::Bytes | SliceMode::Chars | SliceMode::Records(_) => None,
+        _ => max_record_size,
+    };
+    match plan {
+        Plan::Reverse(reverse) => {
+            reverse_ansi(mode, input, output, *reverse, limit, max_memory, annotation)
+        }
+        Plan::ReverseUnion(reverse) => reverse_ansi(
+            mode,
+            input,
+            output,
+            reverse.clone(),
+            limit,
+            max_memory,
+            annotation,
+        ),
+        plan => {
+            let split = Annotated::new(Ansi::new(ansi_split(mode, limit)), annotation);
+            annotated(split, input, output, plan, limit)
+        }
+    }
+}
+
+/// `mode`'s split, with a `/regex/` delimiter held to the record limit.
+fn ansi_split<'b>(mode: &SliceMode<'b>, max_record_size: Option<usize>) -> ModeSplit<'b> {
+    match *mode {
+        SliceMode::Regex(delimiter) => {
+            ModeSplit::Regex(Box::new(RegexDelimited::new(delimiter, max_record_size)))
+        }
+        mode => mode.split(),
+    }
+}
+
+/// A reverse `--ansi` walk. The spool holds every element as it comes out
+/// going forward, color state mark and all, so each reads back on its own
+/// in any order; a second, verbatim pass over it finds their spans.
+fn reverse_ansi<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: R,
+    mut output: W,
+    plan: impl ReverseWalk,
+    max_record_size: Option<usize>,
+    max_memory: Option<usize>,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let mut spool = SpoolWriter::new(max_memory);
+    let split = Ansi::new(ansi_split(mode, max_record_size));
+    read_all_with_record_limit(split, input, &mut spool, max_record_size)?;
+    let mut spool = spool.finish()?;
+    let split = Ansi::verbatim(ansi_split(mode, max_record_size));
+    let table_memory = spool.table_memory(max_memory);
+    let delimiter = match mode {
+        SliceMode::Lines | SliceMode::Csv => 1,
+        SliceMode::Custom(delimiter) => delimiter.len(),
+        _ => 0,
+    };
+    let reader = spool.reader()?;
+    let spans = chunk_spans(split, reader, max_record_size, delimiter, table_memory)?;
+    let (spool, output) = (&mut spool, &mut output);
+    match mode {
+        SliceMode::Lines | SliceMode::Csv => {
+            reverse_chunks(spool, output, spans, b"\n", plan, annotation)?
+        }
+        SliceMode::Custom(delimiter) => {
+            reverse_chunks(spool, output, spans, delimiter, plan, annotation)?
+        }
+        _ => reverse_spans(spool, output, spans, plan, annotation)?,
+    }
+    output.flush()
+}
+
+fn annotated<S: Split, R: BufRead, W: Write>(
+    split: S,
+    input: R,
+    mut output: W,
+    plan: &Plan,
+    max_record_size: Option<usize>,
+) -> io::Result<()> {
+    match plan {
+        Plan::Resolved(plan) => match *plan {
+            SlicePlan::Empty => output.flush(),
+            SlicePlan::Copy => slice_window(split, input, output, 0, None),
+            SlicePlan::Window { start, end } => slice_window(split, input, output, start, end),
+            SlicePlan::Stepped { start, end, step } => {
+                slice_stepped(split, input, output, start, end, step)
+            }
+            SlicePlan::Gap { start, end } => slice_gap(split, input, output, start, end),
+        },
+        Plan::Deferred(DeferredPlan::Tail { back, end, step }) => {
+            slice_tail_with_record_limit(split, input, output, *back, *end, *step, max_record_size)
+        }
+        Plan::Deferred(DeferredPlan::Lag { start, back, step }) => {
+            slice_lag_with_record_limit(split, input, output, *start, *back, *step, max_record_size)
+        }
+        Plan::Deferred(DeferredPlan::Proportional(_)) => {
+            unreachable!("proportional plans are counted first")
+        }
+        Plan::Union(plan) => slice_union(split, input, output, plan, max_record_size),
+        Plan::Anchored(plan) => slice_anchored(split, input, output, plan, max_record_size),
+        Plan::Reverse(_) | Plan::ReverseUnion(_) => {
+            unreachable!("reverse plans are annotated from their element spans")
+        }
+    }
+}
+
+/// The reverse plan needs the whole input at hand: the first element out is
+/// in general the last element in, so unlike Tail's bounded ring no
+/// fixed-size window suffices. What cannot be re-read is spooled — in memory
+/// up to `max_memory`, in a temporary file past it. `--max-record-size`
+/// still bounds each record, enforced while reading so an oversized record
+/// fails after at most the limit's bytes of it, not after the input was
+/// swallowed whole.
+fn apply_reverse<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    mut input: R,
+    output: W,
+    plan: impl ReverseWalk,
+    max_record_size: Option<usize>,
+    max_memory: Option<usize>,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let mut spool = SpoolWriter::new(max_memory);
+    // The record limit is a line/delimiter/segment concept: byte and char
+    // modes ignore it, like their tail-relative paths.
+    match (mode, max_record_size) {
+        (SliceMode::Lines, Some(_)) => {
+            read_all_with_record_limit(Byte(b'\n'), input, &mut spool, max_record_size)?
+        }
+        (SliceMode::Graphemes, Some(_)) => {
+            read_all_with_record_limit(Graphemes::new(), input, &mut spool, max_record_size)?
+        }
+        (SliceMode::Words, Some(_)) => {
+            read_all_with_record_limit(Words::new(), input, &mut spool, max_record_size)?
+        }
+        (SliceMode::Sentences, Some(_)) => {
+            read_all_with_record_limit(Sentences::new(), input, &mut spool, max_record_size)?
+        }
+        (SliceMode::Custom(&[b]), Some(_)) => {
+            read_all_with_record_limit(Byte(b), input, &mut spool, max_record_size)?
+        }
+        (SliceMode::Custom(delimiter), Some(_)) => {
+            read_all_with_record_limit(Bytes::new(delimiter), input, &mut spool, max_record_size)?
+        }
+        (SliceMode::Csv, Some(_)) => {
+            read_all_with_record_limit(Csv, input, &mut spool, max_record_size)?
+        }
+        (SliceMode::Regex(delimiter), Some(_)) => {
+            let split = RegexDelimited::new(delimiter, max_record_size);
+            read_all_with_record_limit(split, input, &mut spool, max_record_size)?
+        }
+        // Paragraphs come out normalized, not as the input holds them, so
+        // they spool verbatim and meet the limit in the walk's span pass.
+        _ => {
+            io::copy(&mut input, &mut spool)?;
+        }
+    }
+    reverse_spool(
+        mode,
+        spool.finish()?,
+        output,
+        plan,
+        max_record_size,
+        max_memory,
+        annotation,
+    )
+}
+
+/// A regular file needs no spool: it is walked backwards in place, from
+/// wherever `input` stands (past a kept header).
+fn reverse_file<W: Write>(
+    mode: &SliceMode,
+    mut input: io::BufReader<fs::File>,
+    output: W,
+    plan: impl ReverseWalk,
+    max_record_size: Option<usize>,
+    max_memory: Option<usize>,
+    annotation: Annotation,
+) -> io::Result<()> {
+    match regular_len(input.get_ref()) {
+        Some(len) => {
+            let pos = input.stream_position()?;
+            let mut file = input.into_inner();
+            file.seek(SeekFrom::Start(pos))?;
+            let spool = Spool::in_place(file, len)?;
+            reverse_spool(
+                mode,
+                spool,
+                output,
+                plan,
+                max_record_size,
+                max_memory,
+                annotation,
+            )
+        }
+        None => apply_reverse(
+            mode,
+            input,
+            output,
+            plan,
+            max_record_size,
+            max_memory,
+            annotation,
+        ),
+    }
+}
+
+/// Walk a spooled or in-place input backwards. In memory the element
+/// boundaries come from the buffer itself; from a file they take one forward
+/// pass, which checks the record limit on the way. Either way they are
+/// tabled within what of `max_memory` the spool leaves. The spans give each
+/// element its `-n`/`--offsets` prefix: the walk's indices are the elements'
+/// own, and the spool starts where `annotation` says the input stands.
+fn reverse_spool<W: Write>(
+    mode: &SliceMode,
+    mut spool: Spool,
+    mut output: W,
+    plan: impl ReverseWalk,
+    max_record_size: Option<usize>,
+    max_memory: Option<usize>,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let table_memory = spool.table_memory(max_memory);
+    let (spool, output) = (&mut spool, &mut output);
+    let limit = max_record_size;
+    match mode {
+        SliceMode::Bytes => reverse_bytes(spool, output, plan, annotation)?,
+        SliceMode::Lines => {
+            let spans = chunk_spans(Byte(b'\n'), spool.reader()?, limit, 1, table_memory)?;
+            reverse_chunks(spool, output, spans, b"\n", plan, annotation)?
+        }
+        SliceMode::Chars => {
+            let spans = match spool.as_slice() {
+                Some(data) => element_spans(Utf8Elements::new(data), table_memory)?,
+                None => chunk_spans(Chars::new(), spool.reader()?, None, 0, table_memory)?,
+            };
+            reverse_spans(spool, output, spans, plan, annotation)?
+        }
+        SliceMode::Graphemes => {
+            let spans = match spool.as_slice() {
+                Some(data) => element_spans(GraphemeElements::new(data), table_memory)?,
+                None => chunk_spans(Graphemes::new(), spool.reader()?, limit, 0, table_memory)?,
+            };
+            reverse_spans(spool, output, spans, plan, annotation)?
+        }
+        SliceMode::Words => {
+            let spans = match spool.as_slice() {
+                Some(data) => element_spans(SegmentElements::new(Unit::Word, data), table_memory)?,
+                None => chunk_spans(Words::new(), spool.reader()?, limit, 0, table_memory)?,
+            };
+            reverse_spans(spool, output, spans, plan, annotation)?
+        }
+        SliceMode::Sentences => {
+            let spans = match spool.as_slice() {
+                Some(data) => {
+                    element_spans(SegmentElements::new(Unit::Sentence, data), table_memory)?
+                }
+                None => chunk_spans(Sentences::new(), spool.reader()?, limit, 0, table_memory)?,
+            };
+            reverse_spans(spool, output, spans, plan, annotation)?
+        }
+        SliceMode::Custom(delimiter @ &[b]) => {
+            let spans = chunk_spans(Byte(b), spool.reader()?, limit, 1, table_memory)?;
+            reverse_chunks(spool, output, spans, delimiter, plan, annotation)?
+        }
+        SliceMode::Custom(delimiter) => {
+            let split = Bytes::new(delimiter);
+            let reader = spool.reader()?;
+            let spans = chunk_spans(split, reader, limit, delimiter.len(), table_memory)?;
+            reverse_chunks(spool, output, spans, delimiter, plan, annotation)?
+        }
+        SliceMode::Csv => {
+            let spans = chunk_spans(Csv, spool.reader()?, limit, 1, table_memory)?;
+            reverse_chunks(spool, output, spans, b"\n", plan, annotation)?
+        }
+        // Each match is its own record's delimiter, with none to float to
+        // the end: the records come out whole, like characters.
+        SliceMode::Regex(delimiter) => {
+            let split = RegexDelimited::new(delimiter, limit);
+            let spans = chunk_spans(split, spool.reader()?, limit, 0, table_memory)?;
+            reverse_spans(spool, output, spans, plan, annotation)?
+        }
+        SliceMode::Paragraphs => {
+            let spans = paragraph_spans(spool.reader()?, limit, table_memory)?;
+            reverse_paragraphs(spool, output, spans, plan, annotation)?
+        }
+        SliceMode::Records(size) => reverse_records(spool, output, *size, plan, annotation)?,
+    }
+    output.flush()
+}
+
+/// The spans of in-memory elements, which must partition the data
+/// contiguously (every element iterator does).
+fn element_spans<'a>(
+    elements: impl Iterator<Item = &'a [u8]>,
+    max_memory: usize,
+) -> io::Result<ChunkSpans> {
+    let mut spans = ChunkSpansWriter::new(max_memory);
+    for element in elements {
+        spans.push(element.len(), element.len())?;
+    }
+    spans.finish()
+}
+
+/// The element count a walk covers, which must fit `usize` — only a spill
+/// past 4 GiB on a 32-bit target does not.
+fn walk_len(len: u64) -> io::Result<usize> {
+    usize::try_from(len).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::OutOfMemory,
+            "input too large to reverse on this platform",
+        )
+    })
+}
+
+/// Chars and graphemes carry no delimiter to re-attach, so emission is a
+/// plain descending walk over the element spans.
+fn reverse_spans<W: Write>(
+    spool: &mut Spool,
+    output: &mut W,
+    mut spans: ChunkSpans,
+    plan: impl ReverseWalk,
+    annotation: Annotation,
+) -> io::Result<()> {
+    for i in plan.indices(spans.len()) {
+        let (start, end) = spans.span(i)?;
+        annotation.write(output, i as u64, start)?;
+        spool.write_span(start, end, output)?;
+    }
+    Ok(())
+}
+
+fn reverse_bytes<W: Write>(
+    spool: &mut Spool,
+    output: &mut W,
+    plan: impl ReverseWalk,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let len = walk_len(spool.len())?;
+    let mut buf = Vec::with_capacity(WRITE_BUF_SIZE);
+    for i in plan.indices(len) {
+        annotation.write(&mut buf, i as u64, i as u64)?;
+        match spool.as_slice() {
+            Some(data) => buf.push(data[i]),
+            None => spool.write_span(i as u64, i as u64 + 1, &mut buf)?,
+        }
+        if buf.len() >= WRITE_BUF_SIZE {
+            output.write_all(&buf)?;
+            buf.clear();
+        }
+    }
+    output.write_all(&buf)
+}
+
+/// Fixed-size records need no span pass: record `i` starts at `i * size`,
+/// and only the last one can be cut short by the end of the spool.
+fn reverse_records<W: Write>(
+    spool: &mut Spool,
+    output: &mut W,
+    size: NonZeroUsize,
+    plan: impl ReverseWalk,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let (len, size) = (spool.len(), size.get() as u64);
+    for i in plan.indices(walk_len(len.div_ceil(size))?) {
+        let start = i as u64 * size;
+        annotation.write(output, i as u64, start)?;
+        spool.write_span(start, (start + size).min(len), output)?;
+    }
+    Ok(())
+}
+
+/// Emit the selected chunks in descending order under the terminator model:
+/// every element is written delimiter-terminated, except that when the
+/// input's unterminated final chunk is selected (it is then the first out),
+/// its missing delimiter floats to the end of the output. `spans` are the
+/// chunks' content spans, delimiters excluded — cut by the mode's split, so
+/// a CSV record's quoted newlines stay inside it — and `delimiter` is what
+/// they are terminated with.
+fn reverse_chunks<W: Write>(
+    spool: &mut Spool,
+    output: &mut W,
+    mut spans: ChunkSpans,
+    delimiter: &[u8],
+    plan: impl ReverseWalk,
+    annotation: Annotation,
+) -> io::Result<()> {
+    debug_assert!(!delimiter.is_empty(), "empty delimiter is byte mode");
+    let mut selected = plan.indices(spans.len()).peekable();
+    // The walk descends, so it can include the last chunk — the only one
+    // that can lack its delimiter — only as its first element.
+    let all_terminated = match selected.peek() {
+        Some(&i) if i == spans.len() - 1 => spans.stripped(i)?,
+        _ => true,
+    };
+    while let Some(i) = selected.next() {
+        let (start, end) = spans.content(i, delimiter.len())?;
+        annotation.write(output, i as u64, start)?;
+        spool.write_span(start, end, output)?;
+        if selected.peek().is_some() || all_terminated {
+            output.write_all(delimiter)?;
+        }
+    }
+    Ok(())
+}
+
+/// Paragraphs float their empty line like [`reverse_chunks`] floats a
+/// delimiter: each printed paragraph is followed by one, except that a last
+/// paragraph no blank line followed passes what did end it — its line
+/// ending, if any — to the end of the output.
+fn reverse_paragraphs<W: Write>(
+    spool: &mut Spool,
+    output: &mut W,
+    mut spans: ParagraphSpans,
+    plan: impl ReverseWalk,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let mut selected = plan.indices(spans.len()).peekable();
+    // The walk descends, so it can include the last paragraph only as its
+    // first element.
+    let tail = match selected.peek() {
+        Some(&i) if i == spans.len() - 1 => {
+            let last = spans.get(i)?;
+            (!last.terminated).then_some((last.content.1, last.end))
+        }
+        _ => None,
+    };
+    while let Some(i) = selected.next() {
+        let span = spans.get(i)?;
+        annotation.write(output, i as u64, span.start)?;
+        spool.write_span(span.content.0, span.content.1, output)?;
+        match tail {
+            Some((start, end)) if selected.peek().is_none() => {
+                spool.write_span(start, end, output)?
+            }
+            _ => output.write_all(span.separator())?,
+        }
+    }
+    Ok(())
+}
+
+/// Only the fixed-delimiter modes and bytes follow: a character split could
+/// not tell a sequence the writer has not finished from an invalid one, nor
+/// a regex split a match from one that what is appended would grow, and a
+/// paragraph split holds the next paragraph's first bytes read ahead.
+fn follow_mode<W: Write>(
+    mode: &SliceMode,
+    files: &[PathBuf],
+    plan: &Plan,
+    options: follow::Options,
+    output: W,
+) -> bool {
+    match mode {
+        SliceMode::Lines => follow::follow(files, || Byte(b'\n'), plan, options, output),
+        SliceMode::Bytes => follow::follow(files, || EachByte, plan, options, output),
+        SliceMode::Custom(&[b]) => follow::follow(files, || Byte(b), plan, options, output),
+        SliceMode::Custom(delimiter) => {
+            follow::follow(files, || Bytes::new(delimiter), plan, options, output)
+        }
+        SliceMode::Csv => follow::follow(files, || Csv, plan, options, output),
+        SliceMode::Records(size) => {
+            follow::follow(files, || FixedSize(*size), plan, options, output)
+        }
+        SliceMode::Chars
+        | SliceMode::Graphemes
+        | SliceMode::Words
+        | SliceMode::Sentences
+        | SliceMode::Regex(_)
+        | SliceMode::Paragraphs => {
+            unreachable!("Options::check rejects following the text, regex, and paragraph modes")
+        }
+    }
+}
+
+fn report_error(path: &Path, err: &io::Error) {
+    eprintln!("slice: {}: {}", path.display(), err);
+}
+
+// A broken pipe means a downstream consumer (e.g. `head`) closed early; that is
+// a normal stop request, not a failure, so we abort quietly without touching
+// the exit status.
+#[inline]
+fn is_broken_pipe(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::BrokenPipe
+}
+
+/// Run `f` over each of `targets` in turn, opened and wrapped by
+/// `input_wrapper`, with `start` called first for every file that opens: the
+/// `==> FILE <==` header, or a new chunk file series.
+#[inline]
+fn multi<W, R, IW, S, F>(targets: &[PathBuf], mut out: W, input_wrapper: IW, start: S, f: F) -> bool
+where
+    R: BufRead,
+    IW: Fn(fs::File) -> R,
+    S: Fn(&Path, &mut W) -> io::Result<()>,
+    F: Fn(&Path, R, &mut W) -> io::Result<()>,
+{
+    let mut ok = true;
+    for target in targets {
+        // Open before printing the header so an unopenable file gets an error
+        // on stderr instead of a header, and the remaining files are still
+        // processed (same convention as head(1)/tail(1)).
+        let file = match fs::File::open(target) {
+            Ok(file) => file,
+            Err(err) => {
+                report_error(target, &err);
+                ok = false;
+                continue;
+            }
+        };
+        let result = (|| {
+            start(target, &mut out)?;
+            f(target, input_wrapper(file), &mut out)
+        })();
+        if let Err(err) = result {
+            if is_broken_pipe(&err) {
+                return ok;
+            }
+            report_error(target, &err);
+            ok = false;
+        }
+    }
+    ok
+}
+
+/// The regular-file counterpart of `discard`. Skips are relative like
+/// `discard`'s, so one skip callback serves a window (skipping from offset 0)
+/// and a gap (skipping from the end of its head) alike.
+#[inline]
+fn seek_forward(reader: &mut io::BufReader<fs::File>, n: u64) -> io::Result<()> {
+    match i64::try_from(n) {
+        Ok(n) => reader.seek_relative(n),
+        // No file outgrows i64, so such a skip lands at EOF.
+        Err(_) => reader.seek(SeekFrom::End(0)).map(drop),
+    }
+}
+
+/// Byte counts are trustworthy only for regular files: FIFOs and procfs-style
+/// files report 0 (or lie), and a 0-length regular file streams to the same
+/// empty output anyway. A regular file whose st_size lies (sysfs attributes)
+/// is taken at its word — the same trust tail(1) places in st_size.
+fn regular_len(file: &fs::File) -> Option<u64> {
+    let metadata = file.metadata().ok()?;
+    (metadata.is_file() && metadata.len() > 0).then_some(metadata.len())
+}
+
+/// Lines, delimited records, and characters of a regular file: count a
+/// tail-relative bound back from EOF instead of streaming the whole file
+/// through a ring, as `tail -n` does. The answer is the byte span
+/// `[from, to)` holding the candidate elements, and the plan to run over
+/// it, now head-relative. `None` where the streaming path remains: a
+/// head-relative end after a tail-relative start needs the total count, and
+/// a self-overlapping delimiter splits differently searched from the end.
+fn tail_span<F: Read + Seek>(
+    mode: &SliceMode,
+    file: &mut F,
+    floor: u64,
+    len: u64,
+    plan: DeferredPlan,
+) -> io::Result<Option<(u64, u64, SlicePlan)>> {
+    let (backs, step) = match plan {
+        DeferredPlan::Tail {
+            back,
+            end: None,
+            step,
+        } => (vec![back.get()], step),
+        DeferredPlan::Tail {
+            back,
+            end: Some(SliceIndex::FromEnd(end)),
+            step,
+        } => (vec![end.get(), back.get()], step),
+        DeferredPlan::Tail {
+            end: Some(SliceIndex::FromStart(_)),
+            ..
+        } => return Ok(None),
+        DeferredPlan::Lag { back, step, .. } => (vec![back.get()], step),
+        DeferredPlan::Proportional(_) => unreachable!("proportional plans are counted first"),
+    };
+    let starts = match mode {
+        SliceMode::Lines => delimited_starts(file, floor, len, b"\n", &backs)?,
+        SliceMode::Custom(delimiter) if scans_backward(delimiter) => {
+            delimited_starts(file, floor, len, delimiter, &backs)?
+        }
+        SliceMode::Chars => char_starts(file, floor, len, &backs)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(match (plan, starts.as_slice()) {
+        (DeferredPlan::Lag { start, .. }, &[to]) => (floor, to, classify(start, None, step)),
+        (_, &[from]) => (from, len, classify(0, None, step)),
+        (_, &[to, from]) => (from, to, classify(0, None, step)),
+        _ => unreachable!("one start per bound"),
+    }))
+}
+
+/// The `--index` of `path` for the mode's split; `None` for the modes it
+/// does not serve, where a byte offset is already its own index.
+fn open_index(mode: &SliceMode, path: &Path, rebuild: bool) -> io::Result<Option<index::Index>> {
+    let key = match mode {
+        SliceMode::Custom(delimiter) => [b"delimiter:".as_slice(), delimiter].concat(),
+        SliceMode::Lines => b"lines".to_vec(),
+        SliceMode::Csv => b"csv".to_vec(),
+        SliceMode::Chars => b"chars".to_vec(),
+        SliceMode::Paragraphs => b"paragraphs".to_vec(),
+        SliceMode::Bytes
+        | SliceMode::Graphemes
+        | SliceMode::Words
+        | SliceMode::Sentences
+        | SliceMode::Regex(_)
+        | SliceMode::Records(_) => return Ok(None),
+    };
+    match mode {
+        SliceMode::Lines => index::open(path, Byte(b'\n'), &key, rebuild),
+        SliceMode::Custom(&[b]) => index::open(path, Byte(b), &key, rebuild),
+        SliceMode::Custom(delimiter) => index::open(path, Bytes::new(delimiter), &key, rebuild),
+        SliceMode::Csv => index::open(path, Csv, &key, rebuild),
+        SliceMode::Chars => index::open(path, Chars::new(), &key, rebuild),
+        SliceMode::Paragraphs => index::open(path, Paragraphs::new(), &key, rebuild),
+        SliceMode::Bytes
+        | SliceMode::Graphemes
+        | SliceMode::Words
+        | SliceMode::Sentences
+        | SliceMode::Regex(_)
+        | SliceMode::Records(_) => {
+            unreachable!("returned above")
+        }
+    }
+    .map(Some)
+}
+
+/// `--index`: a window or stride starts from the indexed element nearest
+/// before its first instead of splitting the whole way there, and a
+/// tail-relative plan resolves against the indexed count. `input` sits past
+/// `base` elements (a kept header); the answer is the plan to run from
+/// wherever `input` is left and how many elements the seek skipped, or
+/// `None` where the index does not help.
+fn indexed_plan(
+    mode: &SliceMode,
+    path: &Path,
+    input: &mut io::BufReader<fs::File>,
+    base: u64,
+    plan: &Plan,
+) -> io::Result<Option<(SlicePlan, u64)>> {
+    if !matches!(plan, Plan::Resolved(_) | Plan::Deferred(_))
+        || regular_len(input.get_ref()).is_none()
+    {
+        return Ok(None);
+    }
+    let Some(index) = open_index(mode, path, false)? else {
+        return Ok(None);
+    };
+    let plan = match plan {
+        Plan::Resolved(plan) => *plan,
+        Plan::Deferred(deferred) => match deferred.resolve(index.count().saturating_sub(base)) {
+            Some(plan) => plan,
+            None => return Ok(None),
+        },
+        _ => unreachable!("checked above"),
+    };
+    let (start, end, step) = match plan {
+        SlicePlan::Window { start, end } => (start, end, NonZeroUsize::MIN),
+        SlicePlan::Stepped { start, end, step } => (start, end, step),
+        plan => return Ok(Some((plan, 0))),
+    };
+    let (at, offset) = index.seek_point(base + start as u64);
+    // The seek point is at or before the first element, so the skipped count
+    // fits the plan's own usize bounds.
+    let skipped = match at.checked_sub(base) {
+        Some(skipped) if skipped > 0 => skipped as usize,
+        _ => return Ok(Some((plan, 0))),
+    };
+    input.seek(SeekFrom::Start(offset))?;
+    let plan = classify(start - skipped, end.map(|end| end - skipped), step);
+    Ok(Some((plan, skipped as u64)))
+}
+
+/// Everything about a slice besides the input, the range, and the mode. The
+/// default slices as `slice RANGE` does with no other flags.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Select every element the range leaves out instead (`--complement`).
+    pub complement: bool,
+    /// Also slice the fields of each selected record (`--fields`).
+    pub fields: Option<SliceRanges>,
+    /// What separates those fields (`--field-delimiter`).
+    pub field_separator: FieldSeparator,
+    /// Also slice the display columns of each selected record
+    /// (`--columns`); a record gets either fields or columns, not both.
+    pub columns: Option<SliceRanges>,
+    /// How many columns apart the tab stops are (`--tab-width`, 8 when
+    /// `None`).
+    pub tab_width: Option<NonZeroUsize>,
+    /// Pass the first record through and count the range from the one after
+    /// it (`--keep-header`).
+    pub keep_header: bool,
+    /// Prefix each element with its index, numbering the first one with this
+    /// (`-n`, 0, or 1 with `--one-based`).
+    pub number: Option<u64>,
+    /// Prefix each element with the byte offset where it starts
+    /// (`--offsets`).
+    pub offsets: bool,
+    /// Count ANSI escape sequences as part of the element after them rather
+    /// than as elements, and carry the color state across what is sliced
+    /// away (`--ansi`).
+    pub ansi: bool,
+    /// The most bytes one element may hold where tail-relative, reverse, and
+    /// `/regex/` ranges buffer it (`--max-record-size`).
+    pub max_record_size: Option<usize>,
+    /// The most a reverse range buffers in memory, input and element
+    /// boundaries together, before spilling to temporary files
+    /// (`--max-memory`).
+    pub max_memory: Option<usize>,
+    /// The capacity of the input and output buffers (`--io-buffer-size`).
+    pub io_buffer_size: Option<NonZeroUsize>,
+    /// Slice what input starting with a gzip, zstd, xz, or bzip2 magic
+    /// number decompresses to; byte ranges and offsets then count
+    /// decompressed bytes (`--decompress`).
+    pub decompress: bool,
+    /// Print each selected element as a JSON object with its index, offset,
+    /// length, and file (`--format`).
+    pub format: OutputFormat,
+    /// [`slice_files`] only: print a `==> FILE <==` header before each file.
+    pub headers: bool,
+    /// [`slice_files`] only: seek through a sidecar index of element offsets,
+    /// built on first use (`--index`).
+    pub index: bool,
+    /// [`slice_files`] only: keep slicing what is appended (`--follow`).
+    pub follow: Option<Follow>,
+}
+
+impl Options {
+    fn plan(&self, range: &SliceRanges) -> Plan {
+        if self.complement {
+            range.plan_complement()
+        } else {
+            range.plan()
+        }
+    }
+
+    fn rewrite(&self) -> Option<Rewrite> {
+        let fields = self.fields.as_ref().map(|fields| {
+            Rewrite::Fields(Fields {
+                plan: fields.plan(),
+                separator: self.field_separator.clone(),
+            })
+        });
+        let columns = self.columns.as_ref().map(|columns| {
+            Rewrite::Columns(Columns {
+                plan: columns.plan(),
+                tab_width: self.tab_width.map_or(8, NonZeroUsize::get),
+            })
+        });
+        fields.or(columns)
+    }
+
+    fn annotation(&self) -> Annotation {
+        Annotation {
+            number: self.number,
+            offsets: self.offsets,
+            ..Annotation::default()
+        }
+    }
+
+    /// The combinations the command line rejects before they get here.
+    fn check(&self, mode: &SliceMode, plan: &Plan) -> io::Result<()> {
+        let records = !matches!(
+            mode,
+            SliceMode::Bytes
+                | SliceMode::Chars
+                | SliceMode::Graphemes
+                | SliceMode::Words
+                | SliceMode::Sentences
+                | SliceMode::Records(_)
+        );
+        if self.fields.is_some() && self.columns.is_some() {
+            return Err(invalid(
+                "a record is sliced by fields or by columns, not both",
+            ));
+        }
+        let per_record = self.fields.is_some() || self.columns.is_some() || self.keep_header;
+        if per_record && !records {
+            return Err(invalid(
+                "fields, columns, and a kept header need lines, CSV records, or a non-empty record delimiter",
+            ));
+        }
+        // Fields and columns are rejoined with the delimiter that ended their
+        // record, and a header's end is only known once a regex or paragraph
+        // split read past it.
+        if per_record && matches!(mode, SliceMode::Regex(_) | SliceMode::Paragraphs) {
+            return Err(invalid(
+                "fields, columns, and a kept header need lines, CSV records, or a fixed record delimiter",
+            ));
+        }
+        if self.index
+            && matches!(
+                mode,
+                SliceMode::Bytes
+                    | SliceMode::Graphemes
+                    | SliceMode::Words
+                    | SliceMode::Sentences
+                    | SliceMode::Regex(_)
+                    | SliceMode::Records(_)
+            )
+        {
+            return Err(invalid(INDEX_MODES));
+        }
+        if self.follow.is_some()
+            && matches!(
+                mode,
+                SliceMode::Chars
+                    | SliceMode::Graphemes
+                    | SliceMode::Words
+                    | SliceMode::Sentences
+                    | SliceMode::Regex(_)
+                    | SliceMode::Paragraphs
+            )
+        {
+            return Err(invalid(
+                "following needs lines, bytes, CSV records, or a fixed record delimiter",
+            ));
+        }
+        if self.decompress && self.follow.is_some() {
+            return Err(invalid("a compressed file cannot be followed"));
+        }
+        // Each object carries its own index and offset.
+        if self.format != OutputFormat::Text
+            && (self.ansi || self.follow.is_some() || !self.annotation().is_empty())
+        {
+            return Err(invalid(
+                "JSON output takes no --ansi, --follow, --number, or --offsets",
+            ));
+        }
+        if self.ansi {
+            if matches!(mode, SliceMode::Paragraphs) {
+                return Err(invalid(
+                    "--ansi needs elements copied as the input holds them",
+                ));
+            }
+            if per_record || self.index || self.follow.is_some() {
+                return Err(invalid(
+                    "--ansi slices elements, not fields, columns, headers, indexes, or followed files",
+                ));
+            }
+            // A reversed element is read back from the spool, where it
+            // carries its color state ahead of it.
+            if self.offsets && matches!(plan, Plan::Reverse(_) | Plan::ReverseUnion(_)) {
+                return Err(invalid("--ansi cannot give the offsets of a reverse range"));
+            }
+        }
+        Ok(())
+    }
+}
+
+const INDEX_MODES: &str =
+    "an index needs lines, CSV records, paragraphs, characters, or a non-empty record delimiter";
+
+#[inline]
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// An empty delimiter splits one byte per element, which is byte mode under
+/// every plan; folding it reaches the seek/copy byte fast paths.
+#[inline]
+fn folded<'b>(mode: &SliceMode<'b>) -> SliceMode<'b> {
+    match *mode {
+        SliceMode::Custom([]) => SliceMode::Bytes,
+        mode => mode,
+    }
+}
+
+/// What ends a record, for the fields rejoined inside it.
+#[inline]
+fn terminator<'b>(mode: &SliceMode<'b>) -> &'b [u8] {
+    match *mode {
+        SliceMode::Custom(delimiter) => delimiter,
+        _ => b"\n",
+    }
+}
+
+/// The codec `input` is compressed with, where [`Options::decompress`] asks.
+fn sniff<R: BufRead>(input: &mut R, options: &Options) -> io::Result<Option<Codec>> {
+    if options.decompress {
+        Codec::sniff(input)
+    } else {
+        Ok(None)
+    }
+}
+
+/// `$body`, with `$input` rebound to what it decompresses to where
+/// [`Options::decompress`] finds it compressed.
+macro_rules! decoded {
+    ($input:ident, $options:expr, $body:expr) => {
+        match sniff(&mut $input, $options)? {
+            Some(codec) => {
+                let $input = buf_reader(codec.decoder($input)?, $options.io_buffer_size);
+                $body
+            }
+            None => $body,
+        }
+    };
+}
+
+/// Print the elements `range` selects from `input` to `output`. Both ends are
+/// buffered here ([`Options::io_buffer_size`]), so pass them unbuffered.
+/// Forward ranges stream; a tail-relative one holds its tail back, and a
+/// reverse one the whole input.
+pub fn slice<R: Read, W: Write>(
+    input: R,
+    output: W,
+    range: &SliceRanges,
+    mode: &SliceMode,
+    options: &Options,
+) -> io::Result<()> {
+    let mode = folded(mode);
+    let plan = options.plan(range);
+    options.check(&mode, &plan)?;
+    let rewrite = options.rewrite();
+    let mut input = buf_reader(input, options.io_buffer_size);
+    if options.format != OutputFormat::Text {
+        let mut writer =
+            JsonWriter::new(buf_writer(output, options.io_buffer_size), options.format);
+        let mut sink = JsonElements::new(&mut writer, None, rewrite.as_ref(), terminator(&mode));
+        decoded!(
+            input,
+            options,
+            each_element(&mode, input, &plan, options, json::ANNOTATION, &mut sink)
+        )?;
+        return writer.finish();
+    }
+    let output = FieldWriter::new(
+        AnsiWriter::new(buf_writer(output, options.io_buffer_size), options.ansi),
+        rewrite.as_ref(),
+        terminator(&mode),
+        options.annotation(),
+    );
+    decoded!(input, options, stream(&mode, input, output, &plan, options))
+}
+
+/// [`slice()`] past its setup: `input` is read once from the start, as
+/// stdin and decompressed files are.
+fn stream<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    mut input: R,
+    mut output: W,
+    plan: &Plan,
+    options: &Options,
+) -> io::Result<()> {
+    let header = if options.keep_header {
+        keep_header(mode, &mut input, &mut output)?
+    } else {
+        0
+    };
+    let annotation = options.annotation().at(0, header);
+    match plan {
+        Plan::Deferred(DeferredPlan::Proportional(plan)) => {
+            apply_proportional(mode, input, &mut output, *plan, options, annotation)
+        }
+        plan => dispatch(mode, input, &mut output, plan, options, annotation),
+    }?;
+    // Drivers flush on completion, but this flush is what finishes an
+    // unterminated last record, so it is not left to any one path.
+    output.flush()
+}
+
+/// [`slice()`]'s drivers, for any plan but a proportional one.
+fn dispatch<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    input: R,
+    mut output: W,
+    plan: &Plan,
+    options: &Options,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let (max_record_size, max_memory) = (options.max_record_size, options.max_memory);
+    match plan {
+        plan if options.ansi => apply_ansi(
+            mode,
+            input,
+            &mut output,
+            plan,
+            max_record_size,
+            max_memory,
+            annotation,
+        ),
+        Plan::Reverse(reverse) => apply_reverse(
+            mode,
+            input,
+            &mut output,
+            *reverse,
+            max_record_size,
+            max_memory,
+            annotation,
+        ),
+        Plan::ReverseUnion(reverse) => apply_reverse(
+            mode,
+            input,
+            &mut output,
+            reverse.clone(),
+            max_record_size,
+            max_memory,
+            annotation,
+        ),
+        plan if !annotation.is_empty() => {
+            apply_annotated(mode, input, &mut output, plan, annotation, max_record_size)
+        }
+        Plan::Resolved(plan) => apply(mode, input, &mut output, *plan, discard, max_record_size),
+        Plan::Deferred(deferred) => {
+            apply_deferred(mode, input, &mut output, *deferred, max_record_size)
+        }
+        Plan::Union(union) => apply_union(mode, input, &mut output, union, max_record_size),
+        Plan::Anchored(anchored) => {
+            apply_anchored(mode, input, &mut output, anchored, max_record_size)
+        }
+    }
+}
+
+/// A proportional range selects nothing until the elements are counted, so
+/// input that cannot be re-read is spooled first, as for a reverse range,
+/// then counted and sliced by the plan its bounds resolve to.
+fn apply_proportional<R: BufRead, W: Write>(
+    mode: &SliceMode,
+    mut input: R,
+    output: W,
+    plan: ProportionalPlan,
+    options: &Options,
+    annotation: Annotation,
+) -> io::Result<()> {
+    let mut spool = SpoolWriter::new(options.max_memory);
+    io::copy(&mut input, &mut spool)?;
+    let spool = spool.finish()?;
+    let plan = match mode {
+        SliceMode::Bytes if !options.ansi => resolve_proportional(plan, spool.len())?,
+        _ => resolve_proportional(plan, element_count(mode, spool.reader()?, options)?)?,
+    };
+    let sliced = dispatch(mode, spool.reader()?, output, &plan, options, annotation);
+    sliced
+}
+
+/// How many elements `input` holds, counted as the slice will cut them.
+fn element_count<R: BufRead>(mode: &SliceMode, mut input: R, options: &Options) -> io::Result<u64> {
+    let count = if options.ansi {
+        Ansi::new(ansi_split(mode, options.max_record_size)).skip_n(&mut input, usize::MAX)?
+    } else {
+        mode.split().skip_n(&mut input, usize::MAX)?
+    };
+    Ok(count as u64)
+}
+
+#[inline]
+fn resolve_proportional(plan: ProportionalPlan, len: u64) -> io::Result<Plan> {
+    plan.resolve(len)
+        .ok_or_else(|| invalid("the input holds more elements than a range can address"))
+}
+
+/// [`slice()`] over tokio's async I/O, awaiting input rather than blocking a
+/// thread on it; pass buffered ends, as nothing is buffered here. Lines,
+/// bytes, characters, delimited, fixed-size and CSV records take a single
+/// forward range, head- or tail-relative; grapheme clusters and reverse,
+/// multi-range and `/pattern/` ranges are rejected with
+/// [`io::ErrorKind::InvalidInput`].
+#[cfg(feature = "tokio")]
+pub async fn slice_async<R, W>(
+    input: R,
+    mut output: W,
+    range: &SliceRanges,
+    mode: &SliceMode<'_>,
+) -> io::Result<()>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let plan = range.plan();
+    if !matches!(plan, Plan::Resolved(_) | Plan::Deferred(_))
+        || matches!(plan, Plan::Deferred(DeferredPlan::Proportional(_)))
+    {
+        return Err(invalid(
+            "async slicing takes one forward range without a /pattern/ endpoint or a percentage",
+        ));
+    }
+    let mode = folded(mode);
+    match (mode, plan) {
+        (
+            SliceMode::Graphemes
+            | SliceMode::Words
+            | SliceMode::Sentences
+            | SliceMode::Regex(_)
+            | SliceMode::Paragraphs,
+            _,
+        ) => Err(invalid(
+            "async slicing needs lines, bytes, characters, or records with a fixed delimiter",
+        )),
+        (SliceMode::Bytes, Plan::Resolved(SlicePlan::Copy)) => {
+            ext::byte_mode_async(input, &mut output, 0, None, NonZeroUsize::MIN).await
+        }
+        (SliceMode::Bytes, Plan::Resolved(SlicePlan::Window { start, end })) => {
+            ext::byte_mode_async(input, &mut output, start, end, NonZeroUsize::MIN).await
+        }
+        (SliceMode::Bytes, Plan::Resolved(SlicePlan::Stepped { start, end, step })) => {
+            ext::byte_mode_async(input, &mut output, start, end, step).await
+        }
+        (SliceMode::Bytes, plan) => split_async(EachByte, input, &mut output, plan).await,
+        (SliceMode::Lines, plan) => split_async(Byte(b'\n'), input, &mut output, plan).await,
+        (SliceMode::Chars, plan) => {
+            split_async(ext::AsyncChars::default(), input, &mut output, plan).await
+        }
+        (SliceMode::Custom(&[b]), plan) => split_async(Byte(b), input, &mut output, plan).await,
+        (SliceMode::Custom(delimiter), plan) => {
+            split_async(Bytes::new(delimiter), input, &mut output, plan).await
+        }
+        (SliceMode::Csv, plan) => split_async(Csv, input, &mut output, plan).await,
+        (SliceMode::Records(size), plan) => {
+            split_async(FixedSize(size), input, &mut output, plan).await
+        }
+    }?;
+    tokio::io::AsyncWriteExt::flush(&mut output).await
+}
+
+/// The async drivers for a forward `plan` over `split`'s elements.
+#[cfg(feature = "tokio")]
+async fn split_async<S, R, W>(split: S, input: R, mut output: W, plan: Plan) -> io::Result<()>
+where
+    S: ext::AsyncSplit,
+    R: tokio::io::AsyncBufRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    match plan {
+        Plan::Resolved(SlicePlan::Empty) => Ok(()),
+        Plan::Resolved(SlicePlan::Copy) => {
+            ext::slice_window_async(split, input, &mut output, 0, None).await
+        }
+        Plan::Resolved(SlicePlan::Window { start, end }) => {
+            ext::slice_window_async(split, input, &mut output, start, end).await
+        }
+        Plan::Resolved(SlicePlan::Stepped { start, end, step }) => {
+            ext::slice_stepped_async(split, input, &mut output, start, end, step).await
+        }
+        Plan::Deferred(DeferredPlan::Tail { back, end, step }) => {
+            ext::slice_tail_async(split, input, &mut output, back, end, step).await
+        }
+        Plan::Deferred(DeferredPlan::Lag { start, back, step }) => {
+            ext::slice_lag_async(split, input, &mut output, start, back, step).await
+        }
+        Plan::Resolved(SlicePlan::Gap { .. })
+        | Plan::Deferred(DeferredPlan::Proportional(_))
+        | Plan::Reverse(_)
+        | Plan::ReverseUnion(_)
+        | Plan::Union(_)
+        | Plan::Anchored(_) => unreachable!("slice_async() takes one forward range"),
+    }
+}
+
+/// [`slice()`] each of `paths` in turn, as the command does its FILES: regular
+/// files are seeked through rather than read past, a file that fails is
+/// reported on stderr and the rest are still sliced, and a closed `output`
+/// stops quietly. Returns whether every file was sliced.
+pub fn slice_files<W: Write>(
+    paths: &[PathBuf],
+    output: W,
+    range: &SliceRanges,
+    mode: &SliceMode,
+    options: &Options,
+) -> bool {
+    let mode = folded(mode);
+    let plan = options.plan(range);
+    let checked = options
+        .check(&mode, &plan)
+        .and_then(|()| match (options.follow, &plan) {
+            (Some(_), Plan::Reverse(_) | Plan::ReverseUnion(_)) => Err(invalid(
+                "a reverse range cannot be followed: its first element out is the last one in",
+            )),
+            _ => Ok(()),
+        });
+    if let Err(err) = checked {
+        eprintln!("slice: {err}");
+        return false;
+    }
+    let rewrite = options.rewrite();
+    let terminator = terminator(&mode);
+    let annotation = options.annotation();
+    if options.format != OutputFormat::Text {
+        // Each object names its file, in place of the header.
+        let mut writer =
+            JsonWriter::new(buf_writer(output, options.io_buffer_size), options.format);
+        let ok = multi(
+            paths,
+            &mut writer,
+            |input| buf_reader(input, options.io_buffer_size),
+            |_, _| Ok(()),
+            |path, mut input, writer| {
+                let mut sink = JsonElements::new(writer, Some(path), rewrite.as_ref(), terminator);
+                decoded!(
+                    input,
+                    options,
+                    each_element(&mode, input, &plan, options, json::ANNOTATION, &mut sink)
+                )
+            },
+        );
+        return match writer.finish() {
+            Ok(()) => ok,
+            Err(err) if is_broken_pipe(&err) => ok,
+            Err(err) => {
+                eprintln!("slice: {err}");
+                false
+            }
+        };
+    }
+    if let Some(how) = options.follow {
+        let follow = follow::Options {
+            how,
+            keep_header: options.keep_header,
+            print_header: options.headers,
+            annotation,
+        };
+        let output = FieldWriter::new(
+            buf_writer(output, options.io_buffer_size),
+            rewrite.as_ref(),
+            terminator,
+            annotation,
+        );
+        return follow_mode(&mode, paths, &plan, follow, output);
+    }
+    multi(
+        paths,
+        buf_writer(output, options.io_buffer_size),
+        |input| buf_reader(input, options.io_buffer_size),
+        |path, output| {
+            if options.headers {
+                writeln!(output, "==> {} <==", path.display())?;
+            }
+            Ok(())
+        },
+        |path, mut input, output| {
+            // Per file, so a file's unterminated last record never joins the
+            // next file's first.
+            let output = AnsiWriter::new(output, options.ansi);
+            let output = FieldWriter::new(output, rewrite.as_ref(), terminator, annotation);
+            match sniff(&mut input, options)? {
+                // Offsets into the file say nothing about the decompressed
+                // stream, so it is read through like stdin.
+                Some(codec) => {
+                    let input = buf_reader(codec.decoder(input)?, options.io_buffer_size);
+                    stream(&mode, input, output, &plan, options)
+                }
+                None => slice_file(&mode, path, input, output, &plan, options),
+            }
+        },
+    )
+}
+
+/// Rewrite each of `paths` to what `range` selects from it, as `sed -i`
+/// does. The selection goes to a temporary file in the same directory,
+/// which is synced and renamed over the original, so no reader ever sees a
+/// half-written file; it keeps the original's permissions. With a `backup`
+/// suffix, the original is kept as `FILE` followed by the suffix. A byte
+/// range keeping a prefix of the file truncates it in place instead of
+/// copying. A file that fails is reported on stderr and left as it was, and
+/// the rest are still rewritten. Returns whether every file was.
+pub fn slice_files_in_place(
+    paths: &[PathBuf],
+    backup: Option<&OsStr>,
+    range: &SliceRanges,
+    mode: &SliceMode,
+    options: &Options,
+) -> bool {
+    let mode = folded(mode);
+    let plan = options.plan(range);
+    let checked = options.check(&mode, &plan).and_then(|()| {
+        if options.follow.is_some() || options.decompress || options.format != OutputFormat::Text {
+            return Err(invalid(
+                "a followed or decompressed file, or JSON output, cannot be rewritten in place",
+            ));
+        }
+        Ok(())
+    });
+    if let Err(err) = checked {
+        eprintln!("slice: {err}");
+        return false;
+    }
+    let rewrite = options.rewrite();
+    multi(
+        paths,
+        (),
+        |input| buf_reader(input, options.io_buffer_size),
+        |_, ()| Ok(()),
+        |path, input, ()| {
+            rewrite_file(&mode, path, input, &plan, rewrite.as_ref(), backup, options)
+        },
+    )
+}
+
+/// One of [`slice_files_in_place`]'s files.
+fn rewrite_file(
+    mode: &SliceMode,
+    path: &Path,
+    input: io::BufReader<fs::File>,
+    plan: &Plan,
+    rewrite: Option<&Rewrite>,
+    backup: Option<&OsStr>,
+    options: &Options,
+) -> io::Result<()> {
+    let metadata = input.get_ref().metadata()?;
+    if !metadata.is_file() {
+        return Err(invalid("only a regular file can be rewritten in place"));
+    }
+    // What a symlink points at is rewritten, rather than the link replaced.
+    let path = fs::canonicalize(path)?;
+    let backup = backup.map(|suffix| {
+        let mut backup = path.clone().into_os_string();
+        backup.push(suffix);
+        PathBuf::from(backup)
+    });
+    let prefix = match mode {
+        SliceMode::Bytes if !options.ansi && options.annotation().is_empty() => {
+            kept_prefix(plan, metadata.len())
+        }
+        _ => None,
+    };
+    if let Some(len) = prefix {
+        drop(input);
+        // A link would share the inode being truncated.
+        if let Some(backup) = &backup {
+            fs::copy(&path, backup)?;
+        }
+        if len < metadata.len() {
+            let file = fs::OpenOptions::new().write(true).open(&path)?;
+            file.set_len(len)?;
+            file.sync_all()?;
+        }
+        return Ok(());
+    }
+    let dir = path.parent().expect("a canonical file path has a parent");
+    let temp = tempfile::Builder::new()
+        .prefix(".slice-")
+        .tempfile_in(dir)?;
+    let output = FieldWriter::new(
+        AnsiWriter::new(
+            buf_writer(temp.as_file(), options.io_buffer_size),
+            options.ansi,
+        ),
+        rewrite,
+        terminator(mode),
+        options.annotation(),
+    );
+    slice_file(mode, &path, input, output, plan, options)?;
+    temp.as_file().sync_all()?;
+    fs::set_permissions(temp.path(), metadata.permissions())?;
+    if let Some(backup) = &backup {
+        // The original's inode lives on as the backup once the rename moves
+        // the name off it, so a link is all a backup takes where it works.
+        match fs::remove_file(backup) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        if fs::hard_link(&path, backup).is_err() {
+            fs::copy(&path, backup)?;
+        }
+    }
+    temp.persist(&path).map_err(|err| err.error)?;
+    // The rename is only durable once the directory holding it is.
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// The length of the prefix of a `len`-byte input `plan` keeps, if what it
+/// selects is one.
+fn kept_prefix(plan: &Plan, len: u64) -> Option<u64> {
+    let plan = match plan {
+        Plan::Resolved(plan) => *plan,
+        Plan::Deferred(deferred) => deferred.resolve(len)?,
+        _ => return None,
+    };
+    match plan {
+        SlicePlan::Empty => Some(0),
+        SlicePlan::Copy => Some(len),
+        SlicePlan::Window { start: 0, end } => Some(end.map_or(len, |end| len.min(end as u64))),
+        SlicePlan::Window { .. } | SlicePlan::Stepped { .. } | SlicePlan::Gap { .. } => None,
+    }
+}
+
+/// [`slice()`] into files of `every` elements each instead, named by
+/// `template`, as `split(1)` chunks its input: a chunk never ends inside an
+/// element, whatever the mode. Chunk files are created, or truncated, as
+/// they are reached, and with [`Options::keep_header`] each one starts with
+/// the header. The elements come from a [`Selected`], which buffers as its
+/// docs list; [`Options::ansi`] and [`Options::follow`] are not available.
+pub fn slice_every<R: Read>(
+    input: R,
+    every: NonZeroUsize,
+    template: &ChunkTemplate,
+    range: &SliceRanges,
+    mode: &SliceMode,
+    options: &Options,
+) -> io::Result<()> {
+    let mode = folded(mode);
+    let plan = options.plan(range);
+    options.check(&mode, &plan)?;
+    check_every(options)?;
+    let rewrite = options.rewrite();
+    let mut chunker = Chunker::new(
+        template,
+        every,
+        rewrite.as_ref(),
+        terminator(&mode),
+        options.annotation(),
+        options.io_buffer_size,
+    );
+    chunker.start(None)?;
+    let mut input = buf_reader(input, options.io_buffer_size);
+    let annotation = options.annotation();
+    decoded!(
+        input,
+        options,
+        each_element(&mode, input, &plan, options, annotation, &mut chunker)
+    )?;
+    chunker.finish()
+}
+
+/// [`slice_every`] over each of `paths` in turn, reporting a file that fails
+/// on stderr as [`slice_files`] does. The chunks run on from one file into
+/// the next unless `template` names each file's own ([`ChunkTemplate`]'s
+/// `%s`). Returns whether every file was sliced.
+pub fn slice_files_every(
+    paths: &[PathBuf],
+    every: NonZeroUsize,
+    template: &ChunkTemplate,
+    range: &SliceRanges,
+    mode: &SliceMode,
+    options: &Options,
+) -> bool {
+    let mode = folded(mode);
+    let plan = options.plan(range);
+    if let Err(err) = options
+        .check(&mode, &plan)
+        .and_then(|()| check_every(options))
+    {
+        eprintln!("slice: {err}");
+        return false;
+    }
+    let rewrite = options.rewrite();
+    let mut chunker = Chunker::new(
+        template,
+        every,
+        rewrite.as_ref(),
+        terminator(&mode),
+        options.annotation(),
+        options.io_buffer_size,
+    );
+    let ok = multi(
+        paths,
+        &mut chunker,
+        |input| buf_reader(input, options.io_buffer_size),
+        |path, chunker| chunker.start(Some(path)),
+        |_, mut input, chunker| {
+            let annotation = options.annotation();
+            decoded!(
+                input,
+                options,
+                each_element(&mode, input, &plan, options, annotation, &mut **chunker)
+            )
+        },
+    );
+    match chunker.finish() {
+        Ok(()) => ok,
+        Err(err) => {
+            eprintln!("slice: {err}");
+            false
+        }
+    }
+}
+
+/// What [`slice_every`] cannot do: carry a color state across chunk files,
+/// keep adding to them, or print objects into them.
+fn check_every(options: &Options) -> io::Result<()> {
+    if options.ansi || options.follow.is_some() || options.format != OutputFormat::Text {
+        return Err(invalid(
+            "an --ansi slice, a followed file, or JSON output cannot be split into chunk files",
+        ));
+    }
+    Ok(())
+}
+
+/// Where [`each_element`] hands the elements it selects, one at a time.
+pub(crate) trait ElementSink {
+    /// The header [`Options::keep_header`] passes through.
+    fn header(&mut self, header: &[u8]) -> io::Result<()>;
+
+    /// One selected element, prefixed as the annotation says.
+    fn element(&mut self, element: &[u8]) -> io::Result<()>;
+}
+
+/// The elements `plan` selects from `input`, each prefixed as `annotation`
+/// says, into `sink`: for output that has to know where every element ends.
+/// They come from [`Selected`], which takes any plan.
+fn each_element<R: BufRead, K: ElementSink>(
+    mode: &SliceMode,
+    mut input: R,
+    plan: &Plan,
+    options: &Options,
+    annotation: Annotation,
+    sink: &mut K,
+) -> io::Result<()> {
+    let header = if options.keep_header {
+        let mut header = Vec::new();
+        keep_header(mode, &mut input, &mut header)?;
+        sink.header(&header)?;
+        header.len() as u64
+    } else {
+        0
+    };
+    let annotation = annotation.at(0, header);
+    if annotation.is_empty() {
+        each_split(mode.split(), input, plan, sink)
+    } else {
+        each_split(Annotated::new(mode.split(), annotation), input, plan, sink)
+    }
+}
+
+#[inline]
+fn each_split<S: Split, R: BufRead, K: ElementSink>(
+    split: S,
+    input: R,
+    plan: &Plan,
+    sink: &mut K,
+) -> io::Result<()> {
+    for element in Selected::new(Chunks::new(split, input), plan) {
+        sink.element(&element?)?;
+    }
+    Ok(())
+}
+
+/// Build the [`Options::index`] sidecar of `path` for `mode`
+/// (`FILE.slice-index`) ahead of time, replacing any stale one.
+pub fn build_index(path: &Path, mode: &SliceMode) -> io::Result<()> {
+    match open_index(&folded(mode), path, true)? {
+        Some(_) => Ok(()),
+        None => Err(invalid(INDEX_MODES)),
+    }
+}
+
+/// One of [`slice_files`]'s files, past its header if any.
+fn slice_file<W: Write>(
+    mode: &SliceMode,
+    path: &Path,
+    mut input: io::BufReader<fs::File>,
+    mut output: W,
+    plan: &Plan,
+    options: &Options,
+) -> io::Result<()> {
+    let header = if options.keep_header {
+        keep_header(mode, &mut input, &mut output)?
+    } else {
+        0
+    };
+    let annotation = options.annotation().at(0, header);
+    let (max_record_size, max_memory) = (options.max_record_size, options.max_memory);
+    if options.index {
+        let base = u64::from(options.keep_header);
+        if let Some((plan, skipped)) = indexed_plan(mode, path, &mut input, base, plan)? {
+            if annotation.is_empty() {
+                apply(
+                    mode,
+                    input,
+                    &mut output,
+                    plan,
+                    seek_forward,
+                    max_record_size,
+                )?;
+            } else {
+                let annotation = annotation.at(skipped, input.stream_position()?);
+                let plan = Plan::Resolved(plan);
+                apply_annotated(mode, input, &mut output, &plan, annotation, max_record_size)?;
+            }
+            return output.flush();
+        }
+    }
+    let resolved;
+    let plan = match plan {
+        // Counted in place, then sliced like any resolved plan.
+        Plan::Deferred(DeferredPlan::Proportional(proportional)) => {
+            let Some(len) = regular_len(input.get_ref()) else {
+                apply_proportional(mode, input, &mut output, *proportional, options, annotation)?;
+                return output.flush();
+            };
+            let pos = input.stream_position()?;
+            let count = match mode {
+                SliceMode::Bytes if !options.ansi => len - pos,
+                SliceMode::Records(size) if !options.ansi => {
+                    (len - pos).div_ceil(size.get() as u64)
+                }
+                _ => {
+                    let count = element_count(mode, &mut input, options)?;
+                    input.seek(SeekFrom::Start(pos))?;
+                    count
+                }
+            };
+            resolved = resolve_proportional(*proportional, count)?;
+            &resolved
+        }
+        plan => plan,
+    };
+    match plan {
+        // Never walked in place or from the end: an element's color state
+        // is only known by reading everything before it.
+        plan if options.ansi => apply_ansi(
+            mode,
+            input,
+            &mut output,
+            plan,
+            max_record_size,
+            max_memory,
+            annotation,
+        ),
+        Plan::Reverse(reverse) => reverse_file(
+            mode,
+            input,
+            &mut output,
+            *reverse,
+            max_record_size,
+            max_memory,
+            annotation,
+        ),
+        Plan::ReverseUnion(reverse) => reverse_file(
+            mode,
+            input,
+            &mut output,
+            reverse.clone(),
+            max_record_size,
+            max_memory,
+            annotation,
+        ),
+        plan if !annotation.is_empty() => {
+            apply_annotated(mode, input, &mut output, plan, annotation, max_record_size)
+        }
+        Plan::Resolved(plan) => apply(
+            mode,
+            input,
+            &mut output,
+            *plan,
+            seek_forward,
+            max_record_size,
+        ),
+        Plan::Deferred(deferred) => {
+            // Byte offsets and fixed-size record counts resolve against the
+            // file size, rejoining the seek/copy fast paths;
+            // line/char/delimiter counts are found by scanning back from the
+            // end.
+            let len = regular_len(input.get_ref());
+            let span = match len {
+                Some(len) if !matches!(mode, SliceMode::Bytes | SliceMode::Records(_)) => {
+                    let floor = input.stream_position()?;
+                    let span = tail_span(mode, input.get_mut(), floor, len, *deferred)?;
+                    let from = span.map_or(floor, |(from, _, _)| from);
+                    input.seek(SeekFrom::Start(from))?;
+                    span
+                }
+                _ => None,
+            };
+            let resolved = match (mode, len) {
+                (SliceMode::Bytes, Some(len)) => deferred.resolve(len),
+                (SliceMode::Records(size), Some(len)) => {
+                    let len = len - input.stream_position()?;
+                    deferred.resolve(len.div_ceil(size.get() as u64))
+                }
+                _ => None,
+            };
+            match (span, resolved) {
+                (Some((from, to, plan)), _) => apply(
+                    mode,
+                    input.take(to - from),
+                    &mut output,
+                    plan,
+                    discard,
+                    max_record_size,
+                ),
+                (None, Some(plan)) => apply(
+                    mode,
+                    input,
+                    &mut output,
+                    plan,
+                    seek_forward,
+                    max_record_size,
+                ),
+                (None, None) => {
+                    apply_deferred(mode, input, &mut output, *deferred, max_record_size)
+                }
+            }
+        }
+        Plan::Union(union) => apply_union(mode, input, &mut output, union, max_record_size),
+        Plan::Anchored(anchored) => {
+            apply_anchored(mode, input, &mut output, anchored, max_record_size)
+        }
+    }?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::{ReversePlan, SliceRange};
+    use std::str::FromStr;
+
+    // The driver helpers below take absolute offsets; tail-relative bounds
+    // have their own deferred drivers and never reach them.
+    fn bounds(range: &SliceRange) -> (usize, Option<usize>) {
+        let start = match range.start {
+            SliceIndex::FromStart(start) => start,
+            SliceIndex::FromEnd(_) => panic!("helper drives head-relative ranges"),
+        };
+        let end = range.end.map(|end| match end {
+            SliceIndex::FromStart(end) => end,
+            SliceIndex::FromEnd(_) => panic!("helper drives head-relative ranges"),
+        });
+        (start, end)
+    }
+
+    fn resolved_plan_of(range: &SliceRange) -> SlicePlan {
+        match range.plan() {
+            Plan::Resolved(plan) => plan,
+            other => panic!("expected a statically resolved plan, planned {other:?}"),
+        }
+    }
+
+    fn resolved_plan(range: &str) -> SlicePlan {
+        resolved_plan_of(&SliceRange::from_str(range).unwrap())
+    }
+
+    fn reverse_plan(range: &str) -> ReversePlan {
+        match SliceRange::from_str(range).unwrap().plan() {
+            Plan::Reverse(reverse) => reverse,
+            other => panic!("{range} must classify as reverse, planned {other:?}"),
+        }
+    }
+
+    mod line {
+        use super::*;
+
+        fn lined(input: &[u8], range: &str) -> Vec<u8> {
+            let range = SliceRange::from_str(range).unwrap();
+            let (start, end) = bounds(&range);
+            let mut out = Vec::new();
+            slice_stepped(
+                Byte(b'\n'),
+                input,
+                &mut out,
+                start,
+                end,
+                range.step.magnitude(),
+            )
+            .expect("");
+            out
+        }
+
+        #[test]
+        fn empty() {
+            assert_eq!(lined(b"", "::"), b"");
+        }
+
+        mod one_line {
+            use super::*;
+
+            const INPUT: &[u8] = b"slice command is simple string slicing command.\n";
+
+            #[test]
+            fn no_slice() {
+                assert_eq!(lined(INPUT, "::"), INPUT);
+            }
+
+            #[test]
+            fn skip_first() {
+                assert_eq!(lined(INPUT, "1:"), b"");
+            }
+
+            #[test]
+            fn skip_over_input() {
+                assert_eq!(lined(INPUT, "2:"), b"");
+            }
+
+            #[test]
+            fn drop_tail() {
+                assert_eq!(lined(INPUT, ":0"), b"");
+            }
+
+            #[test]
+            fn step_two_slice() {
+                assert_eq!(lined(INPUT, "::2"), INPUT);
+            }
+        }
+
+        mod multi_line {
+            use super::*;
+
+            const INPUT: &[u8] =
+                b"slice command is simple string slicing command.\nLike a python slice syntax.\n";
+
+            #[test]
+            fn no_slice() {
+                assert_eq!(lined(INPUT, "::"), INPUT);
+            }
+
+            #[test]
+            fn skip_first() {
+                assert_eq!(lined(INPUT, "1:"), b"Like a python slice syntax.\n");
+            }
+
+            #[test]
+            fn drop_last() {
+                assert_eq!(
+                    lined(INPUT, ":1"),
+                    b"slice command is simple string slicing command.\n"
+                );
+            }
+
+            #[test]
+            fn step_two_slice() {
+                assert_eq!(
+                    lined(&INPUT.repeat(5), "::2"),
+                    b"slice command is simple string slicing command.\n".repeat(5)
+                );
+            }
+
+            #[test]
+            fn without_linebreak() {
+                let input =
+                    b"slice command is simple string slicing command.\nLike a python slice syntax.";
+                assert_eq!(lined(input, "::"), input);
+            }
+
+            #[test]
+            fn binary_stream() {
+                let input = b"slice\xaabinary stream\nslice binary\xaastream";
+                assert_eq!(lined(input, "::"), input);
+            }
+        }
+
+        #[test]
+        fn bounded_step() {
+            assert_eq!(lined(b"l0\nl1\nl2\nl3\nl4\n", "1:4:2"), b"l1\nl3\n");
+        }
+
+        #[test]
+        fn step_selects_terminal_chunk_without_newline() {
+            assert_eq!(lined(b"a\nb\nc", "::2"), b"a\nc");
+        }
+
+        #[test]
+        fn step_skips_terminal_chunk() {
+            assert_eq!(lined(b"a\nb\nc", "1::2"), b"b\n");
+        }
+    }
+
+    mod broken_pipe {
+        use crate::ext::{slice_lag, slice_tail};
+
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // A writer that fails every write with BrokenPipe, mimicking a
+        // downstream consumer (e.g. `head`) that closed the pipe early.
+        struct BrokenPipeWriter;
+
+        impl Write for BrokenPipeWriter {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+        }
+
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        fn temp_file(contents: &[u8]) -> PathBuf {
+            let id = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!(
+                "slice-broken-pipe-{}-{}.txt",
+                std::process::id(),
+                id
+            ));
+            fs::write(&path, contents).expect("write temp file");
+            path
+        }
+
+        #[test]
+        fn aborts_quietly_and_reports_success() {
+            let file = temp_file(b"line one\nline two\n");
+            let ok = multi(
+                std::slice::from_ref(&file),
+                BrokenPipeWriter,
+                io::BufReader::new,
+                |_, _| Ok(()),
+                |_, input, output| {
+                    slice_stepped(Byte(b'\n'), input, output, 0, None, NonZeroUsize::MIN)
+                },
+            );
+            fs::remove_file(&file).ok();
+
+            assert!(ok, "broken pipe must not fail the exit status");
+        }
+
+        #[test]
+        fn preserves_earlier_failure() {
+            let missing = std::env::temp_dir().join(format!(
+                "slice-broken-pipe-missing-{}.txt",
+                std::process::id()
+            ));
+            fs::remove_file(&missing).ok();
+            let readable = temp_file(b"line one\nline two\n");
+
+            // The missing file fails to open (a real error reported to stderr),
+            // then the broken pipe aborts the rest; the earlier failure must
+            // still be reflected in the returned status.
+            let ok = multi(
+                &[missing, readable.clone()],
+                BrokenPipeWriter,
+                io::BufReader::new,
+                |_, _| Ok(()),
+                |_, input, output| {
+                    slice_stepped(Byte(b'\n'), input, output, 0, None, NonZeroUsize::MIN)
+                },
+            );
+            fs::remove_file(&readable).ok();
+
+            assert!(!ok, "a failure before the broken pipe must be preserved");
+        }
+
+        #[test]
+        fn byte_window_propagates_broken_pipe() {
+            let file = temp_file(b"line one\nline two\n");
+            let reader = io::BufReader::new(fs::File::open(&file).expect("open temp file"));
+            let range = SliceRange::from_str("0:3").unwrap();
+            let (start, end) = bounds(&range);
+            let err = byte_window(
+                reader,
+                BrokenPipeWriter,
+                start,
+                end,
+                |r: &mut io::BufReader<fs::File>, n| r.seek(SeekFrom::Start(n)).map(drop),
+            )
+            .expect_err("a broken pipe must propagate from the window path");
+            fs::remove_file(&file).ok();
+
+            assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        }
+
+        #[test]
+        fn slice_window_propagates_broken_pipe() {
+            let file = temp_file(b"line one\nline two\nline three\n");
+            // Both window arms must surface the broken pipe: unbounded `1:`
+            // exercises the io::copy tail, bounded `0:3` the read_to loop.
+            for range in ["1:", "0:3"] {
+                let reader = io::BufReader::new(fs::File::open(&file).expect("open temp file"));
+                let range = SliceRange::from_str(range).unwrap();
+                let (start, end) = bounds(&range);
+                let err = slice_window(Byte(b'\n'), reader, BrokenPipeWriter, start, end)
+                    .expect_err("a broken pipe must propagate from slice_window");
+                assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+            }
+            fs::remove_file(&file).ok();
+        }
+
+        #[test]
+        fn slice_stepped_propagates_broken_pipe() {
+            let file = temp_file(b"line one\nline two\nline three\n");
+            let reader = io::BufReader::new(fs::File::open(&file).expect("open temp file"));
+            let range = SliceRange::from_str("::2").unwrap();
+            let (start, end) = bounds(&range);
+            let err = slice_stepped(
+                Byte(b'\n'),
+                reader,
+                BrokenPipeWriter,
+                start,
+                end,
+                range.step.magnitude(),
+            )
+            .expect_err("a broken pipe must propagate from slice_stepped");
+            fs::remove_file(&file).ok();
+
+            assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        }
+
+        // Lag emission happens mid-stream (a chunk is written once its m-th
+        // successor lands), so the failure must surface from inside the loop.
+        #[test]
+        fn lag_emission_propagates_broken_pipe() {
+            let err = slice_lag(
+                Byte(b'\n'),
+                &b"a\nb\nc\n"[..],
+                BrokenPipeWriter,
+                0,
+                NonZeroUsize::MIN,
+                NonZeroUsize::MIN,
+            )
+            .expect_err("a broken pipe must propagate from slice_lag");
+            assert!(is_broken_pipe(&err));
+
+            let err = byte_lag(
+                &b"abcdefghij"[..],
+                BrokenPipeWriter,
+                0,
+                NonZeroUsize::MIN,
+                NonZeroUsize::MIN,
+            )
+            .expect_err("a broken pipe must propagate from byte_lag");
+            assert!(is_broken_pipe(&err));
+        }
+
+        // Reverse emission happens at EOF, after the input was read in full;
+        // the failure must still surface from the write loop.
+        #[test]
+        fn reverse_emission_propagates_broken_pipe() {
+            for mode in [SliceMode::Lines, SliceMode::Bytes, SliceMode::Custom(b",")] {
+                let err = apply_reverse(
+                    &mode,
+                    &b"a,b\nc,d\n"[..],
+                    BrokenPipeWriter,
+                    reverse_plan("::-1"),
+                    None,
+                    None,
+                    Annotation::default(),
+                )
+                .expect_err("a broken pipe must propagate from apply_reverse");
+                assert!(is_broken_pipe(&err));
+            }
+        }
+
+        // Tail emission happens at EOF, after the input was read in full; the
+        // failure must still surface from the write loop.
+        #[test]
+        fn tail_emission_propagates_broken_pipe() {
+            let err = slice_tail(
+                Byte(b'\n'),
+                &b"a\nb\nc\n"[..],
+                BrokenPipeWriter,
+                NonZeroUsize::MIN,
+                None,
+                NonZeroUsize::MIN,
+            )
+            .expect_err("a broken pipe must propagate from slice_tail");
+            assert!(is_broken_pipe(&err));
+
+            let err = byte_tail(
+                &b"abcdefghij"[..],
+                BrokenPipeWriter,
+                NonZeroUsize::MIN,
+                None,
+                NonZeroUsize::MIN,
+            )
+            .expect_err("a broken pipe must propagate from byte_tail");
+            assert!(is_broken_pipe(&err));
+        }
+    }
+
+    mod chars {
+        use super::*;
+
+        // Drive the same entry-level dispatch a real run takes, so every plan
+        // kind is exercised against the chars mode. Small capacities straddle
+        // multi-byte elements across fill_buf boundaries inside the drivers,
+        // where pending bytes carry across skip_n/read_to alternation.
+        fn sliced(input: &[u8], range: &str) -> Vec<u8> {
+            let range = SliceRanges::from_str(range).unwrap();
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let reader = io::BufReader::with_capacity(capacity, input);
+                let mut out = Vec::new();
+                match range.plan() {
+                    Plan::Resolved(plan) => {
+                        apply(&SliceMode::Chars, reader, &mut out, plan, discard, None)
+                    }
+                    Plan::Deferred(deferred) => {
+                        apply_deferred(&SliceMode::Chars, reader, &mut out, deferred, None)
+                    }
+                    Plan::Reverse(reverse) => apply_reverse(
+                        &SliceMode::Chars,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Union(union) => {
+                        apply_union(&SliceMode::Chars, reader, &mut out, &union, None)
+                    }
+                    Plan::ReverseUnion(reverse) => apply_reverse(
+                        &SliceMode::Chars,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Anchored(anchored) => {
+                        apply_anchored(&SliceMode::Chars, reader, &mut out, &anchored, None)
+                    }
+                }
+                .expect("");
+                out
+            });
+            let first = outputs.next().unwrap();
+            assert!(
+                outputs.all(|out| out == first),
+                "every capacity must produce the same slice"
+            );
+            first
+        }
+
+        const KANA: &[u8] = "あいうえお".as_bytes();
+
+        #[test]
+        fn window() {
+            assert_eq!(sliced(KANA, "1:3"), "いう".as_bytes());
+        }
+
+        #[test]
+        fn unbounded_window() {
+            assert_eq!(sliced(KANA, "2:"), "うえお".as_bytes());
+        }
+
+        #[test]
+        fn stepped() {
+            assert_eq!(sliced(KANA, "::2"), "あうお".as_bytes());
+        }
+
+        #[test]
+        fn tail() {
+            assert_eq!(sliced(KANA, "-2:"), "えお".as_bytes());
+        }
+
+        #[test]
+        fn lag() {
+            assert_eq!(sliced(KANA, ":-2"), "あいう".as_bytes());
+        }
+
+        #[test]
+        fn reverse() {
+            assert_eq!(sliced(KANA, "::-1"), "おえういあ".as_bytes());
+        }
+
+        #[test]
+        fn reverse_window_stepped() {
+            assert_eq!(sliced(KANA, "3:0:-2"), "えい".as_bytes());
+        }
+
+        #[test]
+        fn union() {
+            assert_eq!(sliced(KANA, "0:1,-2:"), "あえお".as_bytes());
+        }
+
+        #[test]
+        fn overlapping_union_prints_each_character_once() {
+            assert_eq!(sliced(KANA, "0:3,1:4"), "あいうえ".as_bytes());
+        }
+
+        #[test]
+        fn pattern_anchored() {
+            assert_eq!(sliced(KANA, "/い/:+2"), "いう".as_bytes());
+            assert_eq!(sliced(KANA, "/[いう]/:/[うえ]/"), "い".as_bytes());
+        }
+
+        #[test]
+        fn four_byte_emoji_is_one_character() {
+            assert_eq!(sliced("a🍣b".as_bytes(), "1:2"), "🍣".as_bytes());
+        }
+
+        #[test]
+        fn copy_round_trips_and_empty_selects_nothing() {
+            let input = [0xFF, 0xE3, 0x81, 0x82, 0x80];
+            assert_eq!(sliced(&input, "::"), input);
+            assert_eq!(sliced(&input, ":0"), b"");
+        }
+
+        #[test]
+        fn invalid_bytes_are_single_elements() {
+            // FF, あ, 80 — three elements.
+            let input = [0xFF, 0xE3, 0x81, 0x82, 0x80];
+            assert_eq!(sliced(&input, "1:2"), "あ".as_bytes());
+            assert_eq!(sliced(&input, "::2"), [0xFF, 0x80]);
+            assert_eq!(sliced(&input, "::-1"), [0x80, 0xE3, 0x81, 0x82, 0xFF]);
+        }
+
+        // The straddled bytes consumed past the skipped prefix must reach the
+        // unbounded window's copy tail.
+        #[test]
+        fn unbounded_window_flushes_straddled_bytes() {
+            let data: &[u8] = &[0xF0, 0x90, 0x41, 0x42];
+            let reader = io::BufReader::with_capacity(2, data);
+            let mut out = Vec::new();
+            char_window(reader, &mut out, 1, None).expect("");
+            assert_eq!(out, [0x90, 0x41, 0x42]);
+        }
+    }
+
+    mod graphemes {
+        use super::*;
+
+        // Drive the same entry-level dispatch a real run takes, so every plan
+        // kind is exercised against the graphemes mode; small capacities
+        // straddle multi-byte scalars and cluster joins across fill_buf
+        // boundaries.
+        fn sliced(input: &[u8], range: &str) -> Vec<u8> {
+            let range = SliceRanges::from_str(range).unwrap();
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let reader = io::BufReader::with_capacity(capacity, input);
+                let mut out = Vec::new();
+                match range.plan() {
+                    Plan::Resolved(plan) => {
+                        apply(&SliceMode::Graphemes, reader, &mut out, plan, discard, None)
+                    }
+                    Plan::Deferred(deferred) => {
+                        apply_deferred(&SliceMode::Graphemes, reader, &mut out, deferred, None)
+                    }
+                    Plan::Reverse(reverse) => apply_reverse(
+                        &SliceMode::Graphemes,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Union(union) => {
+                        apply_union(&SliceMode::Graphemes, reader, &mut out, &union, None)
+                    }
+                    Plan::ReverseUnion(reverse) => apply_reverse(
+                        &SliceMode::Graphemes,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Anchored(anchored) => {
+                        apply_anchored(&SliceMode::Graphemes, reader, &mut out, &anchored, None)
+                    }
+                }
+                .expect("");
+                out
+            });
+            let first = outputs.next().unwrap();
+            assert!(
+                outputs.all(|out| out == first),
+                "every capacity must produce the same slice"
+            );
+            first
+        }
+
+        // a | 👨‍👩‍👧 (ZWJ family) | b | 🇯🇵 (flag) | c — five elements.
+        const INPUT: &[u8] = "a👨‍👩‍👧b🇯🇵c".as_bytes();
+
+        #[test]
+        fn window() {
+            assert_eq!(sliced(INPUT, "1:3"), "👨‍👩‍👧b".as_bytes());
+        }
+
+        #[test]
+        fn unbounded_window() {
+            assert_eq!(sliced(INPUT, "2:"), "b🇯🇵c".as_bytes());
+        }
+
+        #[test]
+        fn stepped() {
+            assert_eq!(sliced(INPUT, "::2"), "abc".as_bytes());
+        }
+
+        #[test]
+        fn tail() {
+            assert_eq!(sliced(INPUT, "-2:"), "🇯🇵c".as_bytes());
+        }
+
+        #[test]
+        fn lag() {
+            assert_eq!(sliced(INPUT, ":-2"), "a👨‍👩‍👧b".as_bytes());
+        }
+
+        #[test]
+        fn reverse() {
+            assert_eq!(sliced(INPUT, "::-1"), "c🇯🇵b👨‍👩‍👧a".as_bytes());
+        }
+
+        #[test]
+        fn union() {
+            assert_eq!(sliced(INPUT, "0:1,-2:"), "a🇯🇵c".as_bytes());
+        }
+
+        #[test]
+        fn reverse_union() {
+            assert_eq!(sliced(INPUT, "-1:-2:-1,1::-1"), "c👨‍👩‍👧a".as_bytes());
+        }
+
+        #[test]
+        fn pattern_matches_whole_clusters() {
+            assert_eq!(sliced(INPUT, "/^b$/:"), "b🇯🇵c".as_bytes());
+            assert_eq!(sliced(INPUT, "/👧$/:/^c/"), "👨‍👩‍👧b🇯🇵".as_bytes());
+        }
+
+        #[test]
+        fn combining_mark_stays_with_its_base() {
+            assert_eq!(sliced("e\u{301}x".as_bytes(), "0:1"), "e\u{301}".as_bytes());
+        }
+
+        #[test]
+        fn crlf_is_one_element() {
+            assert_eq!(sliced(b"a\r\nb", "-2:"), b"\r\nb");
+        }
+
+        #[test]
+        fn copy_round_trips_and_invalid_bytes_stand_alone() {
+            // e | FF | ◌́ — the invalid byte splits the would-be cluster.
+            let mut input = b"e".to_vec();
+            input.push(0xFF);
+            input.extend_from_slice("\u{301}".as_bytes());
+            assert_eq!(sliced(&input, "::"), input);
+            assert_eq!(sliced(&input, "1:2"), [0xFF]);
+            assert_eq!(sliced(&input, "0:1"), b"e");
+        }
+    }
+
+    mod segments {
+        use super::*;
+
+        // Every plan kind through the entry-level dispatch, as for graphemes:
+        // small capacities make the split hold segments back across refills.
+        fn sliced(mode: SliceMode, input: &[u8], range: &str) -> Vec<u8> {
+            let range = SliceRanges::from_str(range).unwrap();
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let reader = io::BufReader::with_capacity(capacity, input);
+                let mut out = Vec::new();
+                let annotation = Annotation::default();
+                match range.plan() {
+                    Plan::Resolved(plan) => apply(&mode, reader, &mut out, plan, discard, None),
+                    Plan::Deferred(deferred) => {
+                        apply_deferred(&mode, reader, &mut out, deferred, None)
+                    }
+                    Plan::Reverse(reverse) => {
+                        apply_reverse(&mode, reader, &mut out, reverse, None, None, annotation)
+                    }
+                    Plan::Union(union) => apply_union(&mode, reader, &mut out, &union, None),
+                    Plan::ReverseUnion(reverse) => {
+                        apply_reverse(&mode, reader, &mut out, reverse, None, None, annotation)
+                    }
+                    Plan::Anchored(anchored) => {
+                        apply_anchored(&mode, reader, &mut out, &anchored, None)
+                    }
+                }
+                .expect("");
+                out
+            });
+            let first = outputs.next().unwrap();
+            assert!(
+                outputs.all(|out| out == first),
+                "every capacity must produce the same slice"
+            );
+            first
+        }
+
+        // Hi | , | ␠ | you're | ␠ | 3.5 | ␠ | ok | ? | \n — ten words.
+        const WORDS: &[u8] = b"Hi, you're 3.5 ok?\n";
+
+        // "One. " | "Two?  " | "Three\n" | "Four" — four sentences.
+        const SENTENCES: &[u8] = b"One. Two?  Three\nFour";
+
+        #[test]
+        fn word_window_and_stride() {
+            assert_eq!(sliced(SliceMode::Words, WORDS, "3:6"), b"you're 3.5");
+            assert_eq!(sliced(SliceMode::Words, WORDS, "::2"), b"Hi   ?");
+        }
+
+        #[test]
+        fn word_tail_and_lag() {
+            assert_eq!(sliced(SliceMode::Words, WORDS, "-3:"), b"ok?\n");
+            assert_eq!(sliced(SliceMode::Words, WORDS, ":-7"), b"Hi, ");
+        }
+
+        #[test]
+        fn word_reverse_and_union() {
+            assert_eq!(
+                sliced(SliceMode::Words, WORDS, "::-1"),
+                b"\n?ok 3.5 you're ,Hi"
+            );
+            assert_eq!(sliced(SliceMode::Words, WORDS, "0:1,-3:-2"), b"Hiok");
+            assert_eq!(sliced(SliceMode::Words, WORDS, "-3:-4:-1,0::-1"), b"okHi");
+        }
+
+        #[test]
+        fn word_pattern_matches_whole_words() {
+            assert_eq!(sliced(SliceMode::Words, WORDS, "/^3/:/^ok$/"), b"3.5 ");
+        }
+
+        #[test]
+        fn sentence_plans() {
+            let mode = SliceMode::Sentences;
+            assert_eq!(sliced(mode, SENTENCES, "1:3"), b"Two?  Three\n");
+            assert_eq!(sliced(mode, SENTENCES, "::2"), b"One. Three\n");
+            assert_eq!(sliced(mode, SENTENCES, "-1:"), b"Four");
+            assert_eq!(sliced(mode, SENTENCES, ":-3"), b"One. ");
+            assert_eq!(sliced(mode, SENTENCES, "::-1"), b"FourThree\nTwo?  One. ");
+            assert_eq!(sliced(mode, SENTENCES, "0:1,-1:"), b"One. Four");
+            assert_eq!(sliced(mode, SENTENCES, "/^Two/:/^Four/"), b"Two?  Three\n");
+        }
+
+        #[test]
+        fn copy_round_trips_and_invalid_bytes_stand_alone() {
+            let input = b"ab\xffcd. ef";
+            for mode in [SliceMode::Words, SliceMode::Sentences] {
+                assert_eq!(sliced(mode, input, "::"), input);
+                assert_eq!(sliced(mode, input, "1:2"), [0xFF]);
+                assert_eq!(sliced(mode, input, "0:1"), b"ab");
+            }
+        }
+    }
+
+    mod ansi {
+        use super::*;
+
+        fn sliced(mode: SliceMode, input: &str, range: &str, options: &Options) -> String {
+            let range = SliceRanges::from_str(range).unwrap();
+            let options = Options {
+                ansi: true,
+                ..options.clone()
+            };
+            let mut out = Vec::new();
+            slice(input.as_bytes(), &mut out, &range, &mode, &options).expect("");
+            String::from_utf8(out).unwrap()
+        }
+
+        const COLORED: &str = "\x1b[1mone\n\x1b[31mtwo\x1b[0m\nthree\n";
+
+        #[test]
+        fn every_plan_kind_restores_the_state() {
+            let options = Options::default();
+            let lines = |range| sliced(SliceMode::Lines, COLORED, range, &options);
+            assert_eq!(lines("1:2"), "\x1b[1m\x1b[31mtwo\x1b[0m\n");
+            assert_eq!(lines("-2:-1"), "\x1b[1m\x1b[31mtwo\x1b[0m\n");
+            assert_eq!(lines("2:3,0:1"), "\x1b[1mone\n\x1b[0mthree\n");
+            assert_eq!(lines("/two/:"), "\x1b[1m\x1b[31mtwo\x1b[0m\nthree\n");
+            assert_eq!(
+                lines("::-1"),
+                "three\n\x1b[1m\x1b[31mtwo\x1b[0m\n\x1b[1mone\n\x1b[0m"
+            );
+        }
+
+        #[test]
+        fn reverse_agrees_spilled_and_in_memory() {
+            let input = "a\x1b[31mb\x1b]0;t\x07c<>\x1b[0md\ne\x1b[4m<>f";
+            let delimiter = DelimiterRegex::from_str("<>").unwrap();
+            let modes = [
+                SliceMode::Lines,
+                SliceMode::Bytes,
+                SliceMode::Chars,
+                SliceMode::Graphemes,
+                SliceMode::Words,
+                SliceMode::Custom(b"<>"),
+                SliceMode::Regex(&delimiter),
+            ];
+            for mode in modes {
+                for range in ["::-1", "-2::-1", "::-2"] {
+                    let spilled = Options {
+                        max_memory: Some(1),
+                        ..Options::default()
+                    };
+                    assert_eq!(
+                        sliced(mode, input, range, &Options::default()),
+                        sliced(mode, input, range, &spilled),
+                        "{mode:?} {range}"
+                    );
+                }
+            }
+            assert_eq!(
+                sliced(SliceMode::Chars, "a\x1b[31mbc", "::-1", &Options::default()),
+                "\x1b[31mc\x1b[31mb\x1b[0ma"
+            );
+        }
+
+        #[test]
+        fn a_whole_copy_round_trips_the_sequences() {
+            let input = "\x1b[ma\x1b[66mb\x1b[39m\x1b[31;1mc\n\x1b[38;5;9md\x1b[2Ke\x1b[m";
+            let modes = [
+                SliceMode::Lines,
+                SliceMode::Bytes,
+                SliceMode::Chars,
+                SliceMode::Graphemes,
+                SliceMode::Words,
+            ];
+            for mode in modes {
+                for range in [":", "0:", "::1"] {
+                    let options = Options::default();
+                    assert_eq!(sliced(mode, input, range, &options), input, "{mode:?}");
+                    let open = &input[..input.len() - 3];
+                    let out = sliced(mode, open, range, &options);
+                    assert_eq!(out, format!("{open}\x1b[0m"), "{mode:?}");
+                }
+            }
+        }
+
+        #[test]
+        fn numbers_stay_outside_the_color() {
+            let options = Options {
+                number: Some(1),
+                ..Options::default()
+            };
+            assert_eq!(
+                sliced(SliceMode::Lines, COLORED, "1:", &options),
+                "2:\x1b[1m\x1b[31mtwo\x1b[0m\n3:three\n"
+            );
+        }
+
+        #[test]
+        fn rejected_combinations() {
+            let range = SliceRanges::from_str("::-1").unwrap();
+            let rejected = |mode: SliceMode, options: Options| {
+                let options = Options {
+                    ansi: true,
+                    ..options
+                };
+                let err = slice(&b""[..], io::sink(), &range, &mode, &options).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            };
+            rejected(SliceMode::Paragraphs, Options::default());
+            let offsets = Options {
+                offsets: true,
+                ..Options::default()
+            };
+            rejected(SliceMode::Lines, offsets);
+            let fields = Options {
+                fields: Some(SliceRanges::from_str("1:").unwrap()),
+                ..Options::default()
+            };
+            rejected(SliceMode::Lines, fields);
+        }
+    }
+
+    mod csv {
+        use super::*;
+
+        // Every plan shape through the CSV split, optionally behind
+        // --keep-header, at capacities that cut records (and quotes) apart.
+        fn sliced(input: &[u8], range: &str, header: bool) -> Vec<u8> {
+            let range = SliceRanges::from_str(range).unwrap();
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let mut reader = io::BufReader::with_capacity(capacity, input);
+                let mut out = Vec::new();
+                if header {
+                    keep_header(&SliceMode::Csv, &mut reader, &mut out).expect("");
+                }
+                match range.plan() {
+                    Plan::Resolved(plan) => {
+                        apply(&SliceMode::Csv, reader, &mut out, plan, discard, None)
+                    }
+                    Plan::Deferred(deferred) => {
+                        apply_deferred(&SliceMode::Csv, reader, &mut out, deferred, None)
+                    }
+                    Plan::Reverse(reverse) => apply_reverse(
+                        &SliceMode::Csv,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Union(union) => {
+                        apply_union(&SliceMode::Csv, reader, &mut out, &union, None)
+                    }
+                    Plan::ReverseUnion(reverse) => apply_reverse(
+                        &SliceMode::Csv,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Anchored(anchored) => {
+                        apply_anchored(&SliceMode::Csv, reader, &mut out, &anchored, None)
+                    }
+                }
+                .expect("");
+                out
+            });
+            let first = outputs.next().unwrap();
+            assert!(
+                outputs.all(|out| out == first),
+                "every capacity must produce the same slice"
+            );
+            first
+        }
+
+        const DATA: &[u8] = b"id,note\n1,\"a\nb\"\n2,\"\"\"\"\n3,c";
+
+        #[test]
+        fn every_shape_keeps_quoted_newlines() {
+            assert_eq!(sliced(DATA, "1:2", false), b"1,\"a\nb\"\n");
+            assert_eq!(sliced(DATA, "::2", false), b"id,note\n2,\"\"\"\"\n");
+            assert_eq!(sliced(DATA, "-2:", false), b"2,\"\"\"\"\n3,c");
+            assert_eq!(sliced(DATA, "1:-2", false), b"1,\"a\nb\"\n");
+            assert_eq!(sliced(DATA, "0:1,-1:", false), b"id,note\n3,c");
+            assert_eq!(sliced(DATA, "/a/:+1", false), b"1,\"a\nb\"\n");
+            assert_eq!(sliced(DATA, "2:", false), b"2,\"\"\"\"\n3,c");
+        }
+
+        #[test]
+        fn reverse_floats_the_missing_newline() {
+            assert_eq!(
+                sliced(DATA, "::-1", false),
+                b"3,c\n2,\"\"\"\"\n1,\"a\nb\"\nid,note"
+            );
+        }
+
+        #[test]
+        fn header_is_kept_and_the_range_counts_after_it() {
+            assert_eq!(sliced(DATA, "-1:", true), b"id,note\n3,c");
+            assert_eq!(sliced(DATA, "0:1", true), b"id,note\n1,\"a\nb\"\n");
+            assert_eq!(
+                sliced(DATA, "::-1", true),
+                b"id,note\n3,c\n2,\"\"\"\"\n1,\"a\nb\""
+            );
+            assert_eq!(sliced(b"id,note", "5:", true), b"id,note");
+        }
+    }
+
+    mod regex {
+        use super::*;
+
+        // Every plan shape through the regex split, at capacities that cut
+        // records and their growing delimiters apart.
+        fn sliced(input: &[u8], range: &str, options: &Options) -> io::Result<Vec<u8>> {
+            let range = SliceRanges::from_str(range).unwrap();
+            let delimiter = DelimiterRegex::from_str(r"\n\s*\n").unwrap();
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let mut out = Vec::new();
+                let options = Options {
+                    io_buffer_size: NonZeroUsize::new(capacity),
+                    ..options.clone()
+                };
+                slice(
+                    input,
+                    &mut out,
+                    &range,
+                    &SliceMode::Regex(&delimiter),
+                    &options,
+                )
+                .map(|()| out)
+            });
+            let first = outputs.next().unwrap()?;
+            for out in outputs {
+                assert_eq!(out?, first, "every capacity must produce the same slice");
+            }
+            Ok(first)
+        }
+
+        fn plain(input: &[u8], range: &str) -> Vec<u8> {
+            sliced(input, range, &Options::default()).expect("")
+        }
+
+        const DATA: &[u8] = b"a\n\nb\n \n\t\nc\nd\n\n\n\ne";
+
+        #[test]
+        fn every_shape_splits_at_whole_matches() {
+            assert_eq!(plain(DATA, "1:2"), b"b\n \n\t\n");
+            assert_eq!(plain(DATA, "::2"), b"a\n\nc\nd\n\n\n\n");
+            assert_eq!(plain(DATA, "-2:"), b"c\nd\n\n\n\ne");
+            assert_eq!(plain(DATA, "1:-1"), b"b\n \n\t\nc\nd\n\n\n\n");
+            assert_eq!(plain(DATA, "0:1,-1:"), b"a\n\ne");
+            assert_eq!(plain(DATA, "/^c/:+1"), b"c\nd\n\n\n\n");
+            assert_eq!(plain(DATA, ":"), DATA);
+        }
+
+        #[test]
+        fn reverse_keeps_each_record_whole() {
+            assert_eq!(plain(DATA, "::-1"), b"ec\nd\n\n\n\nb\n \n\t\na\n\n");
+            assert_eq!(plain(DATA, "-2::-1"), b"c\nd\n\n\n\nb\n \n\t\na\n\n");
+        }
+
+        #[test]
+        fn annotations_count_records() {
+            let options = Options {
+                number: Some(0),
+                offsets: true,
+                ..Options::default()
+            };
+            assert_eq!(
+                sliced(DATA, "1:", &options).expect(""),
+                b"1:3:b\n \n\t\n2:9:c\nd\n\n\n\n3:16:e"
+            );
+        }
+
+        #[test]
+        fn record_limit_applies_on_every_path() {
+            let options = Options {
+                max_record_size: Some(4),
+                ..Options::default()
+            };
+            for range in ["1:2", "-1:", "::-1"] {
+                let err = sliced(DATA, range, &options).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{range}");
+            }
+            assert_eq!(sliced(b"a\n\nb", "::-1", &options).expect(""), b"ba\n\n");
+        }
+
+        #[test]
+        fn fields_and_a_kept_header_are_rejected() {
+            for options in [
+                Options {
+                    keep_header: true,
+                    ..Options::default()
+                },
+                Options {
+                    fields: Some(SliceRanges::from_str("1:").unwrap()),
+                    ..Options::default()
+                },
+            ] {
+                let err = sliced(DATA, ":", &options).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            }
+        }
+    }
+
+    mod paragraph {
+        use super::*;
+
+        // Every plan shape through the paragraph split, at capacities that
+        // cut lines, CRLFs, and blank runs apart.
+        fn sliced(input: &[u8], range: &str, options: &Options) -> io::Result<Vec<u8>> {
+            let range = SliceRanges::from_str(range).unwrap();
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let mut out = Vec::new();
+                let options = Options {
+                    io_buffer_size: NonZeroUsize::new(capacity),
+                    ..options.clone()
+                };
+                slice(input, &mut out, &range, &SliceMode::Paragraphs, &options).map(|()| out)
+            });
+            let first = outputs.next().unwrap()?;
+            for out in outputs {
+                assert_eq!(out?, first, "every capacity must produce the same slice");
+            }
+            Ok(first)
+        }
+
+        fn plain(input: &[u8], range: &str) -> Vec<u8> {
+            sliced(input, range, &Options::default()).expect("")
+        }
+
+        const DATA: &[u8] = b"\n\na\nb\n\n \n\t\nc\r\n\r\nd\n\ne";
+
+        #[test]
+        fn every_shape_normalizes_blank_runs() {
+            assert_eq!(plain(DATA, "1:2"), b"c\r\n\r\n");
+            assert_eq!(plain(DATA, "::2"), b"a\nb\n\nd\n\n");
+            assert_eq!(plain(DATA, "-2:"), b"d\n\ne");
+            assert_eq!(plain(DATA, "1:-1"), b"c\r\n\r\nd\n\n");
+            assert_eq!(plain(DATA, "0:1,-1:"), b"a\nb\n\ne");
+            assert_eq!(plain(DATA, "/^d$/:"), b"d\n\ne");
+            assert_eq!(plain(DATA, ":"), b"a\nb\n\nc\r\n\r\nd\n\ne");
+        }
+
+        #[test]
+        fn reverse_floats_the_empty_line() {
+            assert_eq!(plain(DATA, "::-1"), b"e\n\nd\n\nc\r\n\r\na\nb");
+            assert_eq!(plain(DATA, "-2::-1"), b"d\n\nc\r\n\r\na\nb\n\n");
+            assert_eq!(plain(b"a\n\n\nb\n", "::-1"), b"b\n\na\n");
+            assert_eq!(plain(b"a\n\n\nb\n", "-1:-2:-1"), b"b\n");
+        }
+
+        #[test]
+        fn annotations_count_input_bytes() {
+            let options = Options {
+                number: Some(0),
+                offsets: true,
+                ..Options::default()
+            };
+            assert_eq!(
+                sliced(DATA, "1:", &options).expect(""),
+                b"1:11:c\r\n\r\n2:16:d\n\n3:19:e"
+            );
+            assert_eq!(
+                sliced(DATA, "::-1", &options).expect(""),
+                b"3:19:e\n\n2:16:d\n\n1:11:c\r\n\r\n0:0:a\nb"
+            );
+        }
+
+        #[test]
+        fn record_limit_applies_to_retained_paragraphs() {
+            let options = Options {
+                max_record_size: Some(4),
+                ..Options::default()
+            };
+            for range in ["-1:", ":-1", "::-1"] {
+                let err = sliced(DATA, range, &options).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{range}");
+            }
+            assert_eq!(sliced(b"a\n\nb", "::-1", &options).expect(""), b"b\n\na");
+        }
+
+        #[test]
+        fn fields_and_a_kept_header_are_rejected() {
+            for options in [
+                Options {
+                    keep_header: true,
+                    ..Options::default()
+                },
+                Options {
+                    fields: Some(SliceRanges::from_str("1:").unwrap()),
+                    ..Options::default()
+                },
+            ] {
+                let err = sliced(DATA, ":", &options).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            }
+        }
+
+        // Every paragraph after the first starts where its chunk does, so
+        // an index seek lands on a paragraph's first line.
+        #[test]
+        fn index_seek_agrees_with_streaming() {
+            let count = index::INTERVAL as usize * 2 + 10;
+            let body: String = (0..count).map(|i| format!("{i}\n{i}\n\n \n")).collect();
+            let path = std::env::temp_dir()
+                .join(format!("slice-paragraph-index-{}.txt", std::process::id()));
+            fs::write(&path, format!("\n\n{body}")).expect("write temp file");
+            let edge = index::INTERVAL;
+            for range in [
+                format!("{}:{}", edge - 2, edge + 2),
+                format!("{}::3", edge * 2 - 1),
+                "-3:".to_owned(),
+            ] {
+                let range = SliceRanges::from_str(&range).unwrap();
+                let run = |index: bool| {
+                    let mut out = Vec::new();
+                    let options = Options {
+                        index,
+                        ..Options::default()
+                    };
+                    let files = [path.clone()];
+                    assert!(slice_files(
+                        &files,
+                        &mut out,
+                        &range,
+                        &SliceMode::Paragraphs,
+                        &options
+                    ));
+                    out
+                };
+                assert_eq!(run(true), run(false), "{range:?}");
+            }
+            fs::remove_file(index::sidecar(&path)).ok();
+            fs::remove_file(&path).ok();
+        }
+    }
+
+    mod byte {
+        use super::*;
+
+        const INPUT: &[u8] =
+            b"slice command is simple string slicing command.\nLike a python slice syntax.\n";
+
+        fn byted(input: &[u8], range: &str) -> Vec<u8> {
+            let range = SliceRange::from_str(range).unwrap();
+            let (start, end) = bounds(&range);
+            let mut out = Vec::new();
+            byte_mode(input, &mut out, start, end, range.step.magnitude()).expect("");
+            out
+        }
+
+        #[test]
+        fn empty() {
+            assert_eq!(byted(b"", "::"), b"");
+        }
+
+        #[test]
+        fn no_slice() {
+            assert_eq!(byted(INPUT, "::"), INPUT);
+        }
+
+        #[test]
+        fn skip_first() {
+            assert_eq!(
+                byted(INPUT, "10:"),
+                b"and is simple string slicing command.\nLike a python slice syntax.\n"
+            );
+        }
+
+        #[test]
+        fn drop_last() {
+            assert_eq!(byted(INPUT, ":15"), b"slice command i");
+        }
+
+        #[test]
+        fn skip_first_and_drop_last() {
+            assert_eq!(byted(INPUT, "5:15"), b" command i");
+        }
+
+        #[test]
+        fn skip_two_slice() {
+            assert_eq!(
+                byted(INPUT, "::2"),
+                b"siecmadi ipesrn lcn omn.Lk  yhnsiesna."
+            );
+        }
+
+        #[test]
+        fn stepped_output_crossing_write_buffer_boundary() {
+            let input: Vec<u8> = (0..64 * 1024u32).map(|i| (i % 251) as u8).collect();
+            // "::2" selects exactly 32 KiB (a multiple of the 8 KiB write
+            // buffer), "5::3" leaves a partial final batch.
+            for (range, start, step) in [("::2", 0, 2), ("5::3", 5, 3)] {
+                let expected: Vec<u8> = input.iter().copied().skip(start).step_by(step).collect();
+                assert_eq!(byted(&input, range), expected, "range {range}");
+            }
+        }
+
+        #[test]
+        fn stride_phase_carries_across_single_byte_blocks() {
+            // Capacity 1 forces every byte into its own fill_buf block, so the
+            // selection only works if the stride phase survives each boundary.
+            let reader = io::BufReader::with_capacity(1, b"abcdefghij".as_slice());
+            let mut out = Vec::new();
+            byte_mode(reader, &mut out, 1, None, NonZeroUsize::new(3).unwrap()).expect("");
+            assert_eq!(out, b"beh");
+        }
+
+        #[test]
+        fn stride_parity_with_iterator_oracle() {
+            use crate::ext::IteratorExt;
+
+            // Patterned non-UTF-8 input; a prime length avoids lining up with
+            // any of the reader capacities below.
+            let input: Vec<u8> = (0..1031u32)
+                .map(|i| match i % 7 {
+                    0 => 0x00,
+                    3 => 0xaa,
+                    _ => (i % 251) as u8,
+                })
+                .collect();
+            let ranges = [
+                (0, None),
+                (5, None),
+                (0, Some(13)),
+                (3, Some(17)),
+                (100, None),
+                (0, Some(0)),
+                (2000, None),
+                (3, Some(5000)),
+            ];
+            for (start, end) in ranges {
+                for step in [1, 2, 3, 7, 250] {
+                    let step = NonZeroUsize::new(step).unwrap();
+                    let expected: Vec<u8> = input
+                        .iter()
+                        .copied()
+                        .slice(start, end, Some(step))
+                        .collect();
+                    for capacity in [1, 2, 3, 8192] {
+                        let reader = io::BufReader::with_capacity(capacity, input.as_slice());
+                        let mut out = Vec::new();
+                        byte_mode(reader, &mut out, start, end, step).expect("");
+                        assert_eq!(
+                            out, expected,
+                            "start={start} end={end:?} step={step} capacity={capacity}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    mod byte_window {
+        use super::*;
+
+        const FIXTURE: &[u8] =
+            b"slice command is simple string slicing command.\nLike a python slice syntax.\n";
+
+        fn windowed(input: &[u8], range: &str) -> Vec<u8> {
+            let range = SliceRange::from_str(range).unwrap();
+            let (start, end) = bounds(&range);
+            let mut out = Vec::new();
+            byte_window(input, &mut out, start, end, discard).expect("");
+            out
+        }
+
+        #[test]
+        fn skip_first() {
+            assert_eq!(
+                windowed(FIXTURE, "10:"),
+                b"and is simple string slicing command.\nLike a python slice syntax.\n"
+            );
+        }
+
+        #[test]
+        fn drop_last() {
+            assert_eq!(windowed(FIXTURE, ":15"), b"slice command i");
+        }
+
+        #[test]
+        fn skip_first_and_drop_last() {
+            assert_eq!(windowed(FIXTURE, "5:15"), b" command i");
+        }
+
+        #[test]
+        fn unbounded_from_offset_no_trailing_newline() {
+            assert_eq!(windowed(b"abcde", "2:"), b"cde");
+        }
+
+        #[test]
+        fn start_at_or_past_end_is_empty() {
+            assert_eq!(windowed(FIXTURE, "3:1"), b"");
+        }
+
+        #[test]
+        fn start_past_eof_is_empty() {
+            assert_eq!(windowed(FIXTURE, "200:"), b"");
+        }
+
+        #[test]
+        fn bounded_window_past_eof_stops_at_eof() {
+            assert_eq!(windowed(FIXTURE, "70:1000"), b"ntax.\n");
+        }
+
+        #[test]
+        fn end_beyond_len_stops_at_eof() {
+            assert_eq!(windowed(b"abc", "0:5"), b"abc");
+        }
+
+        #[test]
+        fn empty_input() {
+            assert_eq!(windowed(b"", "::"), b"");
+            assert_eq!(windowed(b"", "0:5"), b"");
+        }
+
+        #[test]
+        fn binary_is_preserved_byte_exact() {
+            let input = b"sl\xaace\xaabinary stream without newline";
+            assert_eq!(
+                windowed(input, "2:"),
+                b"\xaace\xaabinary stream without newline"
+            );
+        }
+    }
+
+    mod seek_vs_discard {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        fn temp_file(contents: &[u8]) -> PathBuf {
+            let id = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!(
+                "slice-seek-vs-discard-{}-{}.txt",
+                std::process::id(),
+                id
+            ));
+            fs::write(&path, contents).expect("write temp file");
+            path
+        }
+
+        fn via_discard(input: &[u8], range: &SliceRange) -> Vec<u8> {
+            let (start, end) = bounds(range);
+            let mut out = Vec::new();
+            byte_window(input, &mut out, start, end, discard).expect("");
+            out
+        }
+
+        fn via_seek(path: &Path, range: &SliceRange) -> Vec<u8> {
+            let (start, end) = bounds(range);
+            let reader = io::BufReader::new(fs::File::open(path).expect("open temp file"));
+            let mut out = Vec::new();
+            byte_window(reader, &mut out, start, end, seek_forward).expect("");
+            out
+        }
+
+        #[test]
+        fn seek_matches_discard() {
+            let input =
+                b"slice command is simple string slicing command.\nLike a python slice syntax.\n";
+            let path = temp_file(input);
+            for range in ["10:", "5:15", "0:4", "200:"] {
+                let range = SliceRange::from_str(range).unwrap();
+                assert_eq!(
+                    via_seek(&path, &range),
+                    via_discard(input, &range),
+                    "seek and discard diverged for {range:?}"
+                );
+            }
+            fs::remove_file(&path).ok();
+        }
+
+        #[test]
+        fn gap_seek_matches_discard() {
+            let input = b"0123456789";
+            let path = temp_file(input);
+            for (start, end) in [(2, 5), (1, 20), (20, 30), (3, 4)] {
+                let mut via_seek = Vec::new();
+                let reader = io::BufReader::new(fs::File::open(&path).expect("open temp file"));
+                byte_gap(reader, &mut via_seek, start, end, seek_forward).expect("");
+                let mut via_discard = Vec::new();
+                byte_gap(&input[..], &mut via_discard, start, end, discard).expect("");
+                assert_eq!(via_seek, via_discard, "diverged for {start}:{end}");
+            }
+            fs::remove_file(&path).ok();
+        }
+    }
+
+    mod records {
+        use super::*;
+
+        const INPUT: &[u8] = b"AAAABBBBCCCCDDDDEE";
+
+        fn records() -> SliceMode<'static> {
+            SliceMode::Records(NonZeroUsize::new(4).unwrap())
+        }
+
+        // Streamed from a reader, and seeked, resolved against the length, or
+        // walked in place from a regular file.
+        fn both(range: &str) -> (Vec<u8>, Vec<u8>) {
+            let ranges = SliceRanges::from_str(range).unwrap();
+            let options = Options::default();
+            let mut streamed = Vec::new();
+            slice(INPUT, &mut streamed, &ranges, &records(), &options).expect("");
+            let path = std::env::temp_dir().join(format!(
+                "slice-records-{}-{}.bin",
+                std::process::id(),
+                range.replace(':', "_")
+            ));
+            fs::write(&path, INPUT).expect("write temp file");
+            let mut seeked = Vec::new();
+            let ok = slice_files(
+                std::slice::from_ref(&path),
+                &mut seeked,
+                &ranges,
+                &records(),
+                &options,
+            );
+            fs::remove_file(&path).ok();
+            assert!(ok, "{range}");
+            (streamed, seeked)
+        }
+
+        #[test]
+        fn indices_map_onto_byte_offsets() {
+            for (range, expected) in [
+                ("1:3", &b"BBBBCCCC"[..]),
+                ("3:", b"DDDDEE"),
+                ("::2", b"AAAACCCCEE"),
+                ("1::3", b"BBBBEE"),
+                ("-2:", b"DDDDEE"),
+                (":-1", b"AAAABBBBCCCCDDDD"),
+                ("1:-2", b"BBBBCCCC"),
+                ("-4::2", b"BBBBDDDD"),
+                ("::-1", b"EEDDDDCCCCBBBBAAAA"),
+                ("-2::-2", b"DDDDBBBB"),
+                ("9:", b""),
+            ] {
+                let (streamed, seeked) = both(range);
+                assert_eq!(streamed, expected, "{range}");
+                assert_eq!(seeked, expected, "{range}");
+            }
+        }
+
+        #[test]
+        fn complement_and_union() {
+            let range = SliceRanges::from_str("1:3").unwrap();
+            let options = Options {
+                complement: true,
+                ..Options::default()
+            };
+            let mut out = Vec::new();
+            slice(INPUT, &mut out, &range, &records(), &options).expect("");
+            assert_eq!(out, b"AAAADDDDEE");
+            let (streamed, seeked) = both("0:1,-1:");
+            assert_eq!(streamed, b"AAAAEE");
+            assert_eq!(seeked, b"AAAAEE");
+        }
+
+        #[test]
+        fn skip_n_counts_a_short_last_record() {
+            let mut input = INPUT;
+            let mut split = FixedSize(NonZeroUsize::new(4).unwrap());
+            assert_eq!(split.skip_n(&mut input, 2).unwrap(), 2);
+            assert_eq!(split.skip_n(&mut input, 9).unwrap(), 3);
+            assert!(!split.terminated(b"EE"));
+            assert!(split.terminated(b"EEEE"));
+        }
+    }
+
+    mod deferred_resolution {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        fn deferred(range: &str) -> DeferredPlan {
+            match SliceRange::from_str(range).unwrap().plan() {
+                Plan::Deferred(deferred) => deferred,
+                other => panic!("{range} must defer, planned {other:?}"),
+            }
+        }
+
+        // The byte fast path: resolve against the known length, then run the
+        // resolved plan with a seeking skip, as slice_file() does for regular files.
+        fn via_resolved(input: &[u8], plan: DeferredPlan) -> Vec<u8> {
+            let plan = plan.resolve(input.len() as u64).expect("length fits usize");
+            let mut out = Vec::new();
+            apply(
+                &SliceMode::Bytes,
+                io::Cursor::new(input),
+                &mut out,
+                plan,
+                |r: &mut io::Cursor<&[u8]>, n| r.seek(SeekFrom::Start(n)).map(drop),
+                None,
+            )
+            .expect("");
+            out
+        }
+
+        // The streaming fallback slice_file() takes when the length is unknowable.
+        fn via_streaming(input: &[u8], plan: DeferredPlan) -> Vec<u8> {
+            let mut out = Vec::new();
+            apply_deferred(&SliceMode::Bytes, input, &mut out, plan, None).expect("");
+            out
+        }
+
+        #[test]
+        fn engines_agree_on_oracle_rows() {
+            let rows: &[(&[u8], &str, &[u8])] = &[
+                (b"abcdefghij", "-5:", b"fghij"),
+                (b"abcdefghij", ":-3", b"abcdefg"),
+                (b"abcdefghij", "-8:-2:3", b"cf"),
+                (b"abcdefghij", "2:-2:2", b"ceg"),
+                (b"abcdefghij", "-10:", b"abcdefghij"),
+                (b"abc", ":-100", b""),
+                (b"abcdefghij", "-100:", b"abcdefghij"),
+                (b"abcdefghij", "-5:8", b"fgh"),
+                (b"abcdefghij", "-2:100", b"ij"),
+                (b"abcdefghij", "5:-8", b""),
+                (b"", "-3:", b""),
+                (b"sl\xaace\x00bin", "-4:", b"\x00bin"),
+            ];
+            for &(input, range, expected) in rows {
+                let plan = deferred(range);
+                assert_eq!(
+                    via_resolved(input, plan),
+                    expected,
+                    "resolved engine for {range}"
+                );
+                assert_eq!(
+                    via_streaming(input, plan),
+                    expected,
+                    "streaming engine for {range}"
+                );
+            }
+        }
+
+        // The backward scan for lines, records, and characters: find the span
+        // from the end, then run the head-relative plan over just that span.
+        fn via_span(mode: &SliceMode, input: &[u8], plan: DeferredPlan) -> Option<Vec<u8>> {
+            let mut file = io::Cursor::new(input);
+            let (from, to, plan) =
+                tail_span(mode, &mut file, 0, input.len() as u64, plan).expect("")?;
+            let mut out = Vec::new();
+            let span = &input[from as usize..to as usize];
+            apply(mode, span, &mut out, plan, discard, None).expect("");
+            Some(out)
+        }
+
+        #[test]
+        fn backward_scan_agrees_with_streaming() {
+            let inputs: &[&[u8]] = &[
+                b"0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n",
+                b"0\n1\n2\n3\n4",
+                b"\n\n\n",
+                b"a<>b<>c<><>d<>",
+                "héllo 👋 wörld".as_bytes(),
+                b"ab\x80\x80\xe2\x82c\xf0",
+                b"x",
+            ];
+            let modes = [
+                SliceMode::Lines,
+                SliceMode::Custom(b"<>"),
+                SliceMode::Custom(b","),
+                SliceMode::Chars,
+            ];
+            let ranges = [
+                "-3:", "-1:", "-100:", "-5:-2", "-8:-2:3", "-4::2", ":-3", "2:-2", "1:-1:2",
+                ":-100", "3:-1",
+            ];
+            for input in inputs {
+                for mode in &modes {
+                    for range in ranges {
+                        let plan = deferred(range);
+                        let streamed = {
+                            let mut out = Vec::new();
+                            apply_deferred(mode, *input, &mut out, plan, None).expect("");
+                            out
+                        };
+                        let scanned = via_span(mode, input, plan).expect("scans back");
+                        assert_eq!(scanned, streamed, "{range} on {input:?}");
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn backward_scan_declines_what_it_cannot_count() {
+            // A head-relative end after a tail-relative start needs the total.
+            assert_eq!(
+                via_span(&SliceMode::Lines, b"a\nb\n", deferred("-2:1")),
+                None
+            );
+            // `aa` splits `aaa` at its first two bytes, not its last two.
+            let overlapping = SliceMode::Custom(b"aa");
+            assert_eq!(via_span(&overlapping, b"aaa", deferred("-1:")), None);
+            assert_eq!(
+                via_span(&SliceMode::Graphemes, b"ab", deferred("-1:")),
+                None
+            );
+        }
+
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        #[test]
+        fn regular_len_requires_a_nonempty_regular_file() {
+            for contents in [&b"abc"[..], b""] {
+                let id = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+                let path = std::env::temp_dir().join(format!(
+                    "slice-regular-len-{}-{}.txt",
+                    std::process::id(),
+                    id
+                ));
+                fs::write(&path, contents).expect("write temp file");
+                let file = fs::File::open(&path).expect("open temp file");
+                let len = regular_len(&file);
+                fs::remove_file(&path).ok();
+                // An empty file must stream: 0 is also what FIFOs report.
+                let expected = (!contents.is_empty()).then_some(contents.len() as u64);
+                assert_eq!(len, expected, "contents {contents:?}");
+            }
+        }
+
+        #[test]
+        fn index_seek_agrees_with_streaming() {
+            let lines = index::INTERVAL as usize * 2 + 10;
+            let body: String = (0..lines).map(|i| format!("{i}\n")).collect();
+            let id = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!(
+                "slice-index-seek-{}-{}.txt",
+                std::process::id(),
+                id
+            ));
+            fs::write(&path, format!("header\n{body}")).expect("write temp file");
+            let edge = index::INTERVAL;
+            let ranges = [
+                format!("{}:{}", edge - 2, edge + 2),
+                format!("{}::3", edge * 2 - 1),
+                format!("{}:", edge * 2 + 5),
+                "-5:".to_owned(),
+                format!("-{}:-{}:2", edge + 3, edge - 3),
+                ":-3".to_owned(),
+                "3:7".to_owned(),
+            ];
+            for keep in [false, true] {
+                for range in &ranges {
+                    let plan = SliceRange::from_str(range).unwrap().plan();
+                    let run = |index: bool| {
+                        let mut input = io::BufReader::new(fs::File::open(&path).expect("open"));
+                        let mut out = Vec::new();
+                        if keep {
+                            keep_header(&SliceMode::Lines, &mut input, &mut out).expect("");
+                        }
+                        let indexed = index
+                            .then(|| {
+                                indexed_plan(
+                                    &SliceMode::Lines,
+                                    &path,
+                                    &mut input,
+                                    keep.into(),
+                                    &plan,
+                                )
+                                .expect("")
+                            })
+                            .flatten();
+                        match (indexed, &plan) {
+                            (Some((plan, _)), _) | (None, &Plan::Resolved(plan)) => {
+                                apply(&SliceMode::Lines, input, &mut out, plan, seek_forward, None)
+                            }
+                            (None, Plan::Deferred(deferred)) => {
+                                apply_deferred(&SliceMode::Lines, input, &mut out, *deferred, None)
+                            }
+                            (None, other) => panic!("unexpected plan {other:?}"),
+                        }
+                        .expect("");
+                        out
+                    };
+                    assert_eq!(run(true), run(false), "{range} keep_header {keep}");
+                }
+            }
+            fs::remove_file(index::sidecar(&path)).ok();
+            fs::remove_file(&path).ok();
+        }
+    }
+
+    mod byte_lag {
+        use super::*;
+        use crate::ext::IteratorExt;
+
+        fn lagged_at(
+            input: &[u8],
+            start: usize,
+            m: usize,
+            step: usize,
+            capacity: usize,
+        ) -> Vec<u8> {
+            let reader = io::BufReader::with_capacity(capacity, input);
+            let mut out = Vec::new();
+            byte_lag(
+                reader,
+                &mut out,
+                start,
+                NonZeroUsize::new(m).unwrap(),
+                NonZeroUsize::new(step).unwrap(),
+            )
+            .expect("");
+            out
+        }
+
+        fn lagged(input: &[u8], start: usize, m: usize, step: usize) -> Vec<u8> {
+            lagged_at(input, start, m, step, 8 * 1024)
+        }
+
+        #[test]
+        fn drops_tail_bytes() {
+            assert_eq!(lagged(b"abcdefghij", 0, 3, 1), b"abcdefg");
+        }
+
+        #[test]
+        fn window_with_stride() {
+            assert_eq!(lagged(b"abcdefghij", 2, 2, 2), b"ceg");
+        }
+
+        #[test]
+        fn back_at_or_past_len_is_empty() {
+            assert_eq!(lagged(b"abc", 0, 100, 1), b"");
+            assert_eq!(lagged(b"abc", 0, 3, 1), b"");
+        }
+
+        #[test]
+        fn start_inside_dropped_tail_is_empty() {
+            assert_eq!(lagged(b"abcdefghij", 8, 5, 1), b"");
+            assert_eq!(lagged(b"abcdefghij", 100, 1, 1), b"");
+        }
+
+        #[test]
+        fn empty_input() {
+            assert_eq!(lagged(b"", 0, 1, 1), b"");
+        }
+
+        #[test]
+        fn binary_is_preserved_byte_exact() {
+            let input = b"sl\xaace\xaabinary\x00stream";
+            assert_eq!(lagged(input, 0, 6, 1), b"sl\xaace\xaabinary\x00");
+        }
+
+        #[test]
+        fn stride_crosses_write_buffer_boundary() {
+            // 64 KiB input with m=5 leaves >8 KiB of selected output at step 2,
+            // forcing the batch buffer to flush mid-run.
+            let input: Vec<u8> = (0..64 * 1024u32).map(|i| (i % 251) as u8).collect();
+            for step in [1usize, 2, 3] {
+                let end = input.len() - 5;
+                let expected: Vec<u8> = input
+                    .iter()
+                    .copied()
+                    .slice(0, Some(end), NonZeroUsize::new(step))
+                    .collect();
+                assert_eq!(lagged(&input, 0, 5, step), expected, "step {step}");
+            }
+        }
+
+        #[test]
+        fn parity_with_iterator_oracle() {
+            // Patterned non-UTF-8 input; the prime length avoids lining up
+            // with any of the reader capacities below.
+            let input: Vec<u8> = (0..1031u32)
+                .map(|i| match i % 7 {
+                    0 => 0x00,
+                    3 => 0xaa,
+                    _ => (i % 251) as u8,
+                })
+                .collect();
+            for start in [0usize, 1, 5, 100, 2000] {
+                for m in [1usize, 2, 3, 7, 250, 1031, 100_000] {
+                    for step in [1usize, 2, 3, 7] {
+                        let end = input.len().saturating_sub(m);
+                        let expected: Vec<u8> = input
+                            .iter()
+                            .copied()
+                            .slice(start, Some(end), NonZeroUsize::new(step))
+                            .collect();
+                        // Capacity 1 keeps every block below m (ring growth and
+                        // wrap), 8192 covers single blocks larger than m.
+                        for capacity in [1, 3, 8192] {
+                            assert_eq!(
+                                lagged_at(&input, start, m, step, capacity),
+                                expected,
+                                "start={start} m={m} step={step} capacity={capacity}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        // Serves its data, then fails instead of reporting EOF: anything the
+        // consumer wrote before the error must have been emitted mid-stream.
+        struct ErrAtEof<'a>(&'a [u8]);
+
+        impl Read for ErrAtEof<'_> {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                unreachable!("driven through BufRead")
+            }
+        }
+
+        impl BufRead for ErrAtEof<'_> {
+            fn fill_buf(&mut self) -> io::Result<&[u8]> {
+                if self.0.is_empty() {
+                    Err(io::Error::other("input failed mid-stream"))
+                } else {
+                    Ok(self.0)
+                }
+            }
+            fn consume(&mut self, amt: usize) {
+                self.0 = &self.0[amt..];
+            }
+        }
+
+        // Pins the streaming contract: a byte is written as soon as its m-th
+        // successor arrives, not at EOF. A buffer-until-EOF rewrite would
+        // leave `out` empty when the error surfaces.
+        #[test]
+        fn streams_confirmed_bytes_before_eof() {
+            let mut out = Vec::new();
+            let err = byte_lag(
+                ErrAtEof(b"abcde"),
+                &mut out,
+                0,
+                NonZeroUsize::new(2).unwrap(),
+                NonZeroUsize::MIN,
+            )
+            .expect_err("the mid-stream failure must surface");
+            assert_eq!(err.kind(), io::ErrorKind::Other);
+            assert_eq!(out, b"abc");
+        }
+    }
+
+    mod byte_tail {
+        use super::*;
+        use crate::ext::IteratorExt;
+
+        fn tailed_at(
+            input: &[u8],
+            k: usize,
+            end: Option<SliceIndex>,
+            step: usize,
+            capacity: usize,
+        ) -> Vec<u8> {
+            let reader = io::BufReader::with_capacity(capacity, input);
+            let mut out = Vec::new();
+            byte_tail(
+                reader,
+                &mut out,
+                NonZeroUsize::new(k).unwrap(),
+                end,
+                NonZeroUsize::new(step).unwrap(),
+            )
+            .expect("");
+            out
+        }
+
+        fn tailed(input: &[u8], k: usize, end: Option<SliceIndex>, step: usize) -> Vec<u8> {
+            tailed_at(input, k, end, step, 8 * 1024)
+        }
+
+        fn at(end: usize) -> Option<SliceIndex> {
+            Some(SliceIndex::FromStart(end))
+        }
+
+        fn from_end(m: usize) -> Option<SliceIndex> {
+            Some(SliceIndex::FromEnd(NonZeroUsize::new(m).unwrap()))
+        }
+
+        #[test]
+        fn keeps_tail_bytes() {
+            assert_eq!(tailed(b"abcdefghij", 5, None, 1), b"fghij");
+        }
+
+        #[test]
+        fn tail_relative_end_with_stride() {
+            // -8:-2:3 == s[2:8:3]
+            assert_eq!(tailed(b"abcdefghij", 8, from_end(2), 3), b"cf");
+        }
+
+        #[test]
+        fn back_at_or_past_len_keeps_whole_input() {
+            assert_eq!(tailed(b"abcdefghij", 10, None, 1), b"abcdefghij");
+            assert_eq!(tailed(b"abcdefghij", 100, None, 1), b"abcdefghij");
+        }
+
+        #[test]
+        fn bounded_end_freezes_ring() {
+            // -5:8 == s[5:8]
+            assert_eq!(tailed(b"abcdefghij", 5, at(8), 1), b"fgh");
+            // -2:1 selects [max(0, L-2), 1): byte 0 for L <= 2, nothing after.
+            assert_eq!(tailed(b"a", 2, at(1), 1), b"a");
+            assert_eq!(tailed(b"abc", 2, at(1), 1), b"");
+            // end >= L never freezes.
+            assert_eq!(tailed(b"abcdefghij", 2, at(100), 1), b"ij");
+        }
+
+        #[test]
+        fn empty_input() {
+            assert_eq!(tailed(b"", 3, None, 1), b"");
+            assert_eq!(tailed(b"", 3, at(1), 1), b"");
+        }
+
+        #[test]
+        fn binary_is_preserved_byte_exact() {
+            let input = b"sl\xaace\xaabinary\x00stream";
+            assert_eq!(tailed(input, 8, None, 1), b"y\x00stream");
+        }
+
+        #[test]
+        fn stride_crosses_write_buffer_boundary() {
+            // k = 40000 at step 2 selects 20000 bytes, forcing the 8 KiB batch
+            // buffer to flush mid-run.
+            let input: Vec<u8> = (0..64 * 1024u32).map(|i| (i % 251) as u8).collect();
+            let expected: Vec<u8> = input
+                .iter()
+                .copied()
+                .slice(input.len() - 40000, None, NonZeroUsize::new(2))
+                .collect();
+            assert_eq!(tailed(&input, 40000, None, 2), expected);
+        }
+
+        #[test]
+        fn parity_with_iterator_oracle() {
+            // Patterned non-UTF-8 input; the prime length avoids lining up
+            // with any of the reader capacities below.
+            let input: Vec<u8> = (0..1031u32)
+                .map(|i| match i % 7 {
+                    0 => 0x00,
+                    3 => 0xaa,
+                    _ => (i % 251) as u8,
+                })
+                .collect();
+            for k in [1usize, 2, 3, 7, 250, 1031, 100_000] {
+                for end in [
+                    None,
+                    at(1),
+                    at(500),
+                    at(2000),
+                    from_end(1),
+                    from_end(100),
+                    from_end(2000),
+                ] {
+                    for step in [1usize, 2, 3, 7] {
+                        let resolved_end = match end {
+                            None => input.len(),
+                            Some(SliceIndex::FromStart(end)) => end.min(input.len()),
+                            Some(SliceIndex::FromEnd(m)) => input.len().saturating_sub(m.get()),
+                        };
+                        let expected: Vec<u8> = input
+                            .iter()
+                            .copied()
+                            .slice(
+                                input.len().saturating_sub(k),
+                                Some(resolved_end),
+                                NonZeroUsize::new(step),
+                            )
+                            .collect();
+                        // Capacity 1 keeps every block below k (ring growth
+                        // and wrap), 8192 covers single blocks larger than k.
+                        for capacity in [1, 3, 8192] {
+                            assert_eq!(
+                                tailed_at(&input, k, end, step, capacity),
+                                expected,
+                                "k={k} end={end:?} step={step} capacity={capacity}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    mod copy {
+        use super::*;
+
+        #[test]
+        fn empty_input() {
+            let mut out = Vec::new();
+            copy_mode(b"".as_slice(), &mut out).expect("");
+            assert_eq!(out, b"");
+        }
+
+        #[test]
+        fn verbatim_including_binary_and_missing_eol() {
+            let input = b"slice\xaabinary stream\nno trailing eol";
+            let mut out = Vec::new();
+            copy_mode(input.as_slice(), &mut out).expect("");
+            assert_eq!(out, input);
+        }
+    }
+
+    mod kept_prefix {
+        use super::*;
+
+        fn prefix(range: &str, complement: bool) -> Option<u64> {
+            let plan = SliceRanges::from_str(range).unwrap().plan();
+            let plan = if complement { plan.complement() } else { plan };
+            kept_prefix(&plan, 10)
+        }
+
+        #[test]
+        fn only_a_selection_from_the_first_byte_truncates() {
+            assert_eq!(prefix(":4", false), Some(4));
+            assert_eq!(prefix(":-3", false), Some(7));
+            assert_eq!(prefix(":50%", false), Some(5));
+            assert_eq!(prefix("0:20", false), Some(10));
+            assert_eq!(prefix(":", false), Some(10));
+            assert_eq!(prefix(":0", false), Some(0));
+            // The complement of a suffix is a prefix.
+            assert_eq!(prefix("6:", true), Some(6));
+            for range in ["1:4", "::2", "-3:", "::-1", "0:2,5:6"] {
+                assert_eq!(prefix(range, false), None, "{range}");
+            }
+        }
+    }
+
+    mod identity_fast_path {
+        use super::*;
+
+        const INPUT: &[u8] = b"a,b,c\nd,e,f\n";
+
+        fn applied(mode: SliceMode, range: &str) -> Vec<u8> {
+            let mut out = Vec::new();
+            apply(&mode, INPUT, &mut out, resolved_plan(range), discard, None).expect("");
+            out
+        }
+
+        #[test]
+        fn lines_colon() {
+            assert_eq!(applied(SliceMode::Lines, ":"), INPUT);
+        }
+
+        #[test]
+        fn lines_colon_colon() {
+            assert_eq!(applied(SliceMode::Lines, "::"), INPUT);
+        }
+
+        #[test]
+        fn lines_explicit_unit_step() {
+            assert_eq!(applied(SliceMode::Lines, "0::1"), INPUT);
+        }
+
+        #[test]
+        fn bytes() {
+            assert_eq!(applied(SliceMode::Bytes, "::"), INPUT);
+        }
+
+        #[test]
+        fn custom_delimiter() {
+            assert_eq!(applied(SliceMode::Custom(b",".as_slice()), "::"), INPUT);
+        }
+
+        #[test]
+        fn minus_zero_start_is_copy() {
+            assert_eq!(resolved_plan("-0:"), SlicePlan::Copy);
+            assert_eq!(applied(SliceMode::Lines, "-0:"), INPUT);
+        }
+
+        #[test]
+        fn non_identity_still_slices() {
+            let mut out = Vec::new();
+            apply(
+                &SliceMode::Lines,
+                b"a\nb\nc\n".as_slice(),
+                &mut out,
+                resolved_plan("1:"),
+                discard,
+                None,
+            )
+            .expect("");
+            assert_eq!(out, b"b\nc\n");
+        }
+
+        #[test]
+        fn custom_unit_step_matches_stepped_driver() {
+            // The unit-step Custom window (delimit_window) must agree with the
+            // stepped driver run at step 1 for every delimiter shape.
+            fn agree(delimiter: &[u8], input: &[u8], range: &str) -> Vec<u8> {
+                let range = SliceRange::from_str(range).unwrap();
+                let (start, end) = bounds(&range);
+                let mut via_apply = Vec::new();
+                apply(
+                    &SliceMode::Custom(delimiter),
+                    input,
+                    &mut via_apply,
+                    resolved_plan_of(&range),
+                    discard,
+                    None,
+                )
+                .expect("");
+                let mut via_stepped = Vec::new();
+                delimit_stepped(
+                    input,
+                    &mut via_stepped,
+                    delimiter,
+                    start,
+                    end,
+                    range.step.magnitude(),
+                )
+                .expect("");
+                assert_eq!(
+                    via_apply, via_stepped,
+                    "apply vs delimit_stepped for {delimiter:?} {range:?}"
+                );
+                via_apply
+            }
+            assert_eq!(agree(b"||", b"a||b||c\n", "1:"), b"b||c\n"); // multi-byte
+            assert_eq!(agree(b",", b"a,b,c,", "1:"), b"b,c,"); // single-byte
+        }
+    }
+
+    mod reverse {
+        use super::*;
+
+        // Crosses the WRITE_BUF_SIZE batching boundary: a flush/clear bug in
+        // reverse_bytes shows up only past the buffer size.
+        #[test]
+        fn bytes_batching_survives_buffer_boundary() {
+            let data: Vec<u8> = (0..3 * WRITE_BUF_SIZE + 17)
+                .map(|i| (i % 251) as u8)
+                .collect();
+            let mut out = Vec::new();
+            reverse_bytes(
+                &mut Spool::Memory(data.clone()),
+                &mut out,
+                reverse_plan("::-1"),
+                Annotation::default(),
+            )
+            .expect("");
+            assert_eq!(out, data.iter().rev().copied().collect::<Vec<u8>>());
+
+            let mut out = Vec::new();
+            reverse_bytes(
+                &mut Spool::Memory(data.clone()),
+                &mut out,
+                reverse_plan("::-3"),
+                Annotation::default(),
+            )
+            .expect("");
+            let expected: Vec<u8> = data.iter().rev().copied().step_by(3).collect();
+            assert_eq!(out, expected);
+        }
+
+        // Memory, a spill past the budget, and a regular file read in place
+        // are three stores under one walk: they must agree everywhere, with
+        // the element tables in memory or spilled too.
+        #[test]
+        fn spill_and_in_place_agree_with_memory() {
+            let input = [
+                "a\nbé\n\"q\nq\",x\nc<>d👨\u{200d}👩\u{200d}👧<>e".as_bytes(),
+                b"\xff\n",
+            ]
+            .concat();
+            let path = std::env::temp_dir()
+                .join(format!("slice-reverse-in-place-{}.txt", std::process::id()));
+            fs::write(&path, &input).expect("write temp file");
+            let modes = [
+                SliceMode::Lines,
+                SliceMode::Bytes,
+                SliceMode::Chars,
+                SliceMode::Graphemes,
+                SliceMode::Words,
+                SliceMode::Sentences,
+                SliceMode::Custom(b"<>"),
+                SliceMode::Custom(b"e"),
+                SliceMode::Csv,
+                SliceMode::Paragraphs,
+                SliceMode::Records(NonZeroUsize::new(3).unwrap()),
+            ];
+            for mode in &modes {
+                for range in ["::-1", "::-2", "-2::-1", "3:0:-1", "-1:-3:-1"] {
+                    let plan = reverse_plan(range);
+                    let mut memory = Vec::new();
+                    apply_reverse(
+                        mode,
+                        &input[..],
+                        &mut memory,
+                        plan,
+                        None,
+                        None,
+                        Annotation::default(),
+                    )
+                    .expect("");
+                    let mut spilled = Vec::new();
+                    apply_reverse(
+                        mode,
+                        &input[..],
+                        &mut spilled,
+                        plan,
+                        None,
+                        Some(1),
+                        Annotation::default(),
+                    )
+                    .expect("");
+                    assert_eq!(spilled, memory, "spilled {range}");
+                    for max_memory in [None, Some(1)] {
+                        let file = fs::File::open(&path).expect("open temp file");
+                        let mut in_place = Vec::new();
+                        reverse_file(
+                            mode,
+                            io::BufReader::new(file),
+                            &mut in_place,
+                            plan,
+                            None,
+                            max_memory,
+                            Annotation::default(),
+                        )
+                        .expect("");
+                        assert_eq!(in_place, memory, "in place {range} {max_memory:?}");
+                    }
+                }
+            }
+            fs::remove_file(&path).ok();
+        }
+
+        // The record limit aborts an oversized record during the read; byte
+        // mode ignores it like the tail-relative byte paths.
+        #[test]
+        fn record_limit_applies_to_lines_not_bytes() {
+            let input = b"toolong\nab\n";
+            let mut out = Vec::new();
+            let err = apply_reverse(
+                &SliceMode::Lines,
+                &input[..],
+                &mut out,
+                reverse_plan("::-1"),
+                Some(4),
+                None,
+                Annotation::default(),
+            )
+            .expect_err("an oversized record must fail the reverse read");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+            let mut out = Vec::new();
+            apply_reverse(
+                &SliceMode::Bytes,
+                &input[..],
+                &mut out,
+                reverse_plan("::-1"),
+                Some(4),
+                None,
+                Annotation::default(),
+            )
+            .expect("byte mode ignores the record limit");
+            assert_eq!(out, input.iter().rev().copied().collect::<Vec<u8>>());
+        }
+    }
+
+    mod empty_delimiter {
+        use super::*;
+
+        // folded is the routing slice() and slice_files() use; the empty
+        // delimiter must land in byte mode and produce byte-identical output
+        // across every plan shape (Copy, Window, Stepped, Empty, Tail, Lag).
+        #[test]
+        fn routes_through_byte_machinery() {
+            const INPUT: &[u8] = b"slice\xaabinary\nstream";
+            for range in [
+                "::", "1:", ":3", "2:5", "::2", "1::3", "5:3", ":-2", "-3:", "-4:-1:2", "::-1",
+                "5:1:-2",
+            ] {
+                let plan = SliceRange::from_str(range).unwrap().plan();
+                let apply_with = |mode: &SliceMode| {
+                    let mut out = Vec::new();
+                    match &plan {
+                        Plan::Resolved(plan) => {
+                            apply(mode, INPUT, &mut out, *plan, discard, None).expect("")
+                        }
+                        Plan::Deferred(deferred) => {
+                            apply_deferred(mode, INPUT, &mut out, *deferred, None).expect("")
+                        }
+                        Plan::Reverse(reverse) => apply_reverse(
+                            mode,
+                            INPUT,
+                            &mut out,
+                            *reverse,
+                            None,
+                            None,
+                            Annotation::default(),
+                        )
+                        .expect(""),
+                        other => unreachable!("a single range never merges, planned {other:?}"),
+                    }
+                    out
+                };
+                assert_eq!(
+                    apply_with(&folded(&SliceMode::Custom(b""))),
+                    apply_with(&SliceMode::Bytes),
+                    "empty delimiter diverged from byte mode for {range}"
+                );
+            }
+        }
+    }
+
+    mod empty_plan {
+        use super::*;
+
+        // Any read attempt fails, so a passing test proves the Empty plan never
+        // touched the input.
+        struct NoReadReader;
+
+        impl Read for NoReadReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("input must not be read"))
+            }
+        }
+
+        impl BufRead for NoReadReader {
+            fn fill_buf(&mut self) -> io::Result<&[u8]> {
+                Err(io::Error::other("input must not be read"))
+            }
+            fn consume(&mut self, _amt: usize) {
+                panic!("input must not be consumed");
+            }
+        }
+
+        fn applied(mode: SliceMode, range: &str) -> Vec<u8> {
+            let mut out = Vec::new();
+            apply(
+                &mode,
+                NoReadReader,
+                &mut out,
+                resolved_plan(range),
+                discard,
+                None,
+            )
+            .expect("an empty plan must succeed without reading input");
+            out
+        }
+
+        #[test]
+        fn lines_emit_nothing_without_reading() {
+            assert_eq!(applied(SliceMode::Lines, "5:3"), b"");
+            assert_eq!(applied(SliceMode::Lines, ":0"), b"");
+        }
+
+        #[test]
+        fn bytes_emit_nothing_without_reading() {
+            assert_eq!(applied(SliceMode::Bytes, "5:3"), b"");
+            assert_eq!(applied(SliceMode::Bytes, "5:5"), b"");
+        }
+
+        #[test]
+        fn custom_delimiter_emits_nothing_without_reading() {
+            assert_eq!(applied(SliceMode::Custom(b",".as_slice()), "5:5"), b"");
+        }
+
+        #[test]
+        fn stepped_empty_range_emits_nothing_without_reading() {
+            assert_eq!(applied(SliceMode::Lines, "5:3:2"), b"");
+            assert_eq!(applied(SliceMode::Bytes, "5:3:2"), b"");
+        }
+
+        #[test]
+        fn static_negative_pairs_emit_nothing_without_reading() {
+            assert_eq!(applied(SliceMode::Lines, "-2:-5"), b"");
+            assert_eq!(applied(SliceMode::Lines, "-3:-3"), b"");
+            assert_eq!(applied(SliceMode::Bytes, "-5:0"), b"");
+        }
+
+        // Control: a genuinely tail-relative start has no static answer and
+        // must read the input.
+        #[test]
+        fn tail_relative_start_reads_input() {
+            let plan = match SliceRange::from_str("-1:").unwrap().plan() {
+                Plan::Deferred(deferred) => deferred,
+                other => panic!("-1: must defer, planned {other:?}"),
+            };
+            let mut out = Vec::new();
+            apply_deferred(&SliceMode::Lines, NoReadReader, &mut out, plan, None)
+                .expect_err("a tail-relative start must read the input");
+        }
+    }
+
+    mod slice_window {
+        use super::*;
+        use crate::ext::Byte;
+
+        fn windowed(input: &[u8], range: &str) -> Vec<u8> {
+            let range = SliceRange::from_str(range).unwrap();
+            let (start, end) = bounds(&range);
+            let mut out = Vec::new();
+            slice_window(Byte(b'\n'), input, &mut out, start, end).expect("");
+            out
+        }
+
+        #[test]
+        fn unbounded_from_offset() {
+            assert_eq!(windowed(b"a\nb\nc\n", "1:"), b"b\nc\n");
+        }
+
+        #[test]
+        fn bounded_drop_tail() {
+            assert_eq!(windowed(b"a\nb\nc\n", ":1"), b"a\n");
+        }
+
+        #[test]
+        fn unbounded_last_line_without_newline() {
+            assert_eq!(windowed(b"a\nb", "1:"), b"b");
+        }
+
+        #[test]
+        fn empty_bounded_window() {
+            assert_eq!(windowed(b"a\nb\nc\n", ":0"), b"");
+        }
+
+        #[test]
+        fn skip_past_eof_is_empty() {
+            assert_eq!(windowed(b"a\nb\n", "2:"), b"");
+        }
+
+        #[test]
+        fn empty_input() {
+            assert_eq!(windowed(b"", "::"), b"");
+            assert_eq!(windowed(b"", "1:"), b"");
+            assert_eq!(windowed(b"", "0:3"), b"");
+        }
+    }
+
+    mod complement {
+        use super::*;
+        use crate::range::Step;
+
+        // The same entry-level dispatch a real run takes, with the plan
+        // inverted; small capacities straddle elements across fill_buf
+        // boundaries.
+        fn sliced(mode: &SliceMode, input: &[u8], range: &str, complement: bool) -> Vec<u8> {
+            let range = SliceRanges::from_str(range).unwrap();
+            let plan = if complement {
+                range.plan_complement()
+            } else {
+                range.plan()
+            };
+            let mut outputs = [1, 2, 3, 8192].into_iter().map(|capacity| {
+                let reader = io::BufReader::with_capacity(capacity, input);
+                let mut out = Vec::new();
+                match plan.clone() {
+                    Plan::Resolved(plan) => apply(mode, reader, &mut out, plan, discard, None),
+                    Plan::Deferred(deferred) => {
+                        apply_deferred(mode, reader, &mut out, deferred, None)
+                    }
+                    Plan::Reverse(reverse) => apply_reverse(
+                        mode,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Union(union) => apply_union(mode, reader, &mut out, &union, None),
+                    Plan::ReverseUnion(reverse) => apply_reverse(
+                        mode,
+                        reader,
+                        &mut out,
+                        reverse,
+                        None,
+                        None,
+                        Annotation::default(),
+                    ),
+                    Plan::Anchored(anchored) => {
+                        apply_anchored(mode, reader, &mut out, &anchored, None)
+                    }
+                }
+                .expect("");
+                out
+            });
+            let first = outputs.next().unwrap();
+            assert!(
+                outputs.all(|out| out == first),
+                "every capacity must produce the same slice"
+            );
+            first
+        }
+
+        // Numbered lines make the selection its own oracle: the complement
+        // must print exactly the lines the plain range leaves out, in the
+        // range's direction.
+        #[test]
+        fn prints_exactly_what_the_range_leaves_out() {
+            let input: String = (0..12).map(|i| format!("{i}\n")).collect();
+            for range in [
+                "::",
+                ":",
+                "5:3",
+                ":0",
+                "2:5",
+                ":4",
+                "4:",
+                "::3",
+                "1:9:2",
+                "-3:",
+                ":-3",
+                "2:-3",
+                "-5:-2",
+                "-5:8",
+                "-5::2",
+                "1:-1:3",
+                "::-1",
+                "8:2:-2",
+                "-2::-3",
+                "0:2,5:7",
+                "0:2,-3:",
+                "::4,1::4",
+                "-1:-3:-1,2::-1",
+            ] {
+                let selected = sliced(&SliceMode::Lines, input.as_bytes(), range, false);
+                let selected: Vec<&str> = std::str::from_utf8(&selected).unwrap().lines().collect();
+                let mut expected: Vec<String> = (0..12)
+                    .map(|i| i.to_string())
+                    .filter(|line| !selected.contains(&line.as_str()))
+                    .map(|line| line + "\n")
+                    .collect();
+                let first = range.split(',').next().unwrap();
+                if let Step::Backward(_) = SliceRange::from_str(first).unwrap().step {
+                    expected.reverse();
+                }
+                assert_eq!(
+                    sliced(&SliceMode::Lines, input.as_bytes(), range, true),
+                    expected.concat().as_bytes(),
+                    "complement of {range}"
+                );
+            }
+        }
+
+        #[test]
+        fn window_is_head_and_tail() {
+            assert_eq!(
+                sliced(&SliceMode::Bytes, b"abcdefghij", "2:5", true),
+                b"abfghij"
+            );
+            assert_eq!(
+                sliced(&SliceMode::Custom(b"||"), b"a||b||c||d", "1:3", true),
+                b"a||d"
+            );
+        }
+
+        #[test]
+        fn tail_and_lag_trade_places() {
+            assert_eq!(sliced(&SliceMode::Bytes, b"abcdefg", "-3:", true), b"abcd");
+            assert_eq!(sliced(&SliceMode::Bytes, b"abcdefg", ":-3", true), b"efg");
+            assert_eq!(
+                sliced(&SliceMode::Lines, b"a\nb\nc", "-1:", true),
+                b"a\nb\n"
+            );
+        }
+
+        #[test]
+        fn chars() {
+            const KANA: &[u8] = "あいうえお".as_bytes();
+            assert_eq!(
+                sliced(&SliceMode::Chars, KANA, "1:3", true),
+                "あえお".as_bytes()
+            );
+            assert_eq!(
+                sliced(&SliceMode::Chars, KANA, "::2", true),
+                "いえ".as_bytes()
+            );
+            assert_eq!(
+                sliced(&SliceMode::Chars, KANA, "1:-1", true),
+                "あお".as_bytes()
+            );
+        }
+
+        #[test]
+        fn graphemes() {
+            // a | 👨‍👩‍👧 | b | 🇯🇵 | c
+            let input = "a👨‍👩‍👧b🇯🇵c".as_bytes();
+            assert_eq!(
+                sliced(&SliceMode::Graphemes, input, "1:3", true),
+                "a🇯🇵c".as_bytes()
+            );
+            assert_eq!(
+                sliced(&SliceMode::Graphemes, input, "-2:", true),
+                "a👨‍👩‍👧b".as_bytes()
+            );
+        }
+
+        // Span shapes invert into span shapes, so inverting twice restores
+        // the original plan.
+        #[test]
+        fn span_complements_round_trip() {
+            for range in ["::", "5:3", "2:5", ":3", "3:", "-3:", ":-3", "0:1,-2:"] {
+                let plan = SliceRanges::from_str(range).unwrap().plan();
+                assert_eq!(plan.clone().complement().complement(), plan, "{range}");
+            }
+        }
+    }
+}
@@ -1,3 +1,10 @@
+mod anchored;
+mod proportional;
+
+pub(crate) use anchored::{Anchor, AnchoredCursor, AnchoredEnd};
+pub use anchored::{AnchoredPlan, AnchoredRange};
+use bytesize::ByteSize;
+pub use proportional::{ProportionalPlan, ProportionalRange};
 use std::{
     num::{IntErrorKind, NonZeroUsize, ParseIntError},
     str::FromStr,
@@ -7,7 +14,7 @@ use std::{
 /// the lexeme `-0` normalizes to `FromStart(0)` at parse time (Python has no
 /// -0), so zero distance is unrepresentable here.
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub(crate) enum SliceIndex {
+pub enum SliceIndex {
     FromStart(usize),
     FromEnd(NonZeroUsize),
 }
@@ -15,7 +22,7 @@ pub(crate) enum SliceIndex {
 impl SliceIndex {
     /// Resolve to an absolute offset clamped to [0, len] (Python slice.indices).
     #[inline]
-    pub(crate) fn resolve(self, len: u64) -> u64 {
+    pub fn resolve(self, len: u64) -> u64 {
         match self {
             SliceIndex::FromStart(i) => (i as u64).min(len),
             SliceIndex::FromEnd(k) => len.saturating_sub(k.get() as u64),
@@ -26,14 +33,14 @@ impl SliceIndex {
 /// A step with its direction. Python's negative step selects in reverse;
 /// zero stays unrepresentable (the parse rejects it).
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub(crate) enum Step {
+pub enum Step {
     Forward(NonZeroUsize),
     Backward(NonZeroUsize),
 }
 
 impl Step {
     #[inline]
-    pub(crate) fn magnitude(self) -> NonZeroUsize {
+    pub fn magnitude(self) -> NonZeroUsize {
         match self {
             Step::Forward(step) | Step::Backward(step) => step,
         }
@@ -47,22 +54,44 @@ impl Step {
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub(crate) struct SliceRange {
+pub struct SliceRange {
     /// The parse defaults an absent start to `FromStart(0)` for a forward
     /// step and `FromEnd(1)` (the last element) for a reverse one.
-    pub(crate) start: SliceIndex,
+    pub start: SliceIndex,
     /// `None` means unbounded (run to the end of input; for a reverse step,
     /// back through the first element).
-    pub(crate) end: Option<SliceIndex>,
+    pub end: Option<SliceIndex>,
     /// The parse defaults an absent step to `Forward(1)`.
-    pub(crate) step: Step,
+    pub step: Step,
+}
+
+/// The `<RANGE>` argument.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum SliceRanges {
+    /// One or more comma-separated ranges (`0:5,100:110,-3:`). Never empty,
+    /// and the steps all point the same way — the parse rejects a list
+    /// mixing forward and reverse ranges.
+    Indexed(Vec<SliceRange>),
+    /// A single range with a `/regex/` endpoint; the parse rejects one in a
+    /// list.
+    Anchored(AnchoredRange),
+    /// A single range with a percentage (`50%:`); the parse rejects one in
+    /// a list.
+    Proportional(ProportionalRange),
+}
+
+impl From<SliceRange> for SliceRanges {
+    #[inline]
+    fn from(range: SliceRange) -> Self {
+        Self::Indexed(vec![range])
+    }
 }
 
 /// What an element is, for `--translate`. Mirrors the `SliceMode` taxonomy in
-/// `main.rs` but drops the borrowed delimiter bytes — the translation only
+/// `lib.rs` but drops the borrowed delimiter bytes — the translation only
 /// needs the kind to pick a tool family.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub(crate) enum TranslateMode {
+pub enum TranslateMode {
     Lines,
     Bytes,
     Chars,
@@ -78,7 +107,7 @@ pub(crate) enum TranslateMode {
 // the long layout), diverging from `--generate`'s inline style. The dialect
 // meanings live in the README and the help text instead.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, clap::ValueEnum)]
-pub(crate) enum TranslateDialect {
+pub enum TranslateDialect {
     // Strictly POSIX.1-portable.
     Posix,
     // POSIX plus the common BSD/macOS extensions (`head -c`).
@@ -103,7 +132,7 @@ enum Dialect {
 
 /// Which numeric field of a `start:end:step` range failed to parse.
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub(crate) enum RangeField {
+pub enum RangeField {
     Start,
     End,
     Step,
@@ -121,7 +150,7 @@ impl std::fmt::Display for RangeField {
 
 /// Error produced while parsing a `SliceRange` from its `start:end:step` form.
 #[derive(Clone, Eq, PartialEq, Debug, thiserror::Error)]
-pub(crate) enum ParseSliceRangeError {
+pub enum ParseSliceRangeError {
     #[error("range requires a ':' separator (e.g. '3:4', '3:', or ':3')")]
     MissingColon,
     #[error("invalid {field} value '{value}': {source}")]
@@ -130,19 +159,45 @@ pub(crate) enum ParseSliceRangeError {
         value: String,
         source: ParseIntError,
     },
+    #[error("invalid {field} size '{value}': {message}")]
+    InvalidSize {
+        field: RangeField,
+        value: String,
+        message: String,
+    },
     #[error("a relative end ('+' or '+-') requires a count (e.g. '5:+3' or '5:+-3')")]
     MissingRelativeAmount,
     #[error("a negative step cannot be combined with a relative end ('+' or '+-')")]
     NegativeStepWithRelativeEnd,
     #[error("too many ':' separators in range (expected at most start:end:step)")]
     TooManyParts,
+    #[error("a comma-separated range list cannot mix forward and reverse steps")]
+    MixedStepDirections,
+    #[error("unterminated pattern: a '/' opens a pattern that no '/' closes")]
+    UnterminatedPattern,
+    #[error("invalid pattern {pattern}: {message}")]
+    InvalidPattern { pattern: String, message: String },
+    #[error("a /pattern/ endpoint cannot be combined with a tail-relative bound, a '+-' window, or a negative step")]
+    UnsupportedPatternBound,
+    #[error("a /pattern/ endpoint cannot appear in a comma-separated range list")]
+    PatternInList,
+    #[error("invalid {field} percentage '{value}': {message}")]
+    InvalidPercentage {
+        field: RangeField,
+        value: String,
+        message: String,
+    },
+    #[error("a percentage cannot be combined with a negative step")]
+    NegativeStepWithPercentage,
+    #[error("a percentage cannot appear in a comma-separated range list")]
+    PercentageInList,
 }
 
 /// How a parsed range executes. `SliceRange` stays the `start:end:step` as
 /// written; classifying it once lets dispatch match on the processing shape
 /// instead of re-testing field combinations at every branch.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub(crate) enum SlicePlan {
+pub enum SlicePlan {
     /// Bounded with `start >= end` (`5:3`, `:0`): selects nothing in any mode,
     /// so the input need not be read at all.
     Empty,
@@ -160,11 +215,47 @@ pub(crate) enum SlicePlan {
         end: Option<usize>,
         step: NonZeroUsize,
     },
+    /// The complement of a bounded window (`--complement` of `s:e`, `s > 0`):
+    /// the head `[0, start)` then everything from `end` on, two verbatim
+    /// spans with the window skipped between them.
+    Gap { start: usize, end: usize },
+}
+
+impl SlicePlan {
+    /// The plan selecting exactly what this one leaves out, in input order.
+    /// `None` for `Stepped`: its complement interleaves with the stride, so
+    /// no span shape expresses it.
+    pub(crate) fn complement(self) -> Option<SlicePlan> {
+        Some(match self {
+            SlicePlan::Empty => SlicePlan::Copy,
+            SlicePlan::Copy => SlicePlan::Empty,
+            SlicePlan::Window {
+                start: 0,
+                end: Some(end),
+            } => SlicePlan::Window {
+                start: end,
+                end: None,
+            },
+            SlicePlan::Window { start, end: None } => SlicePlan::Window {
+                start: 0,
+                end: Some(start),
+            },
+            SlicePlan::Window {
+                start,
+                end: Some(end),
+            } => SlicePlan::Gap { start, end },
+            SlicePlan::Gap { start, end } => SlicePlan::Window {
+                start,
+                end: Some(end),
+            },
+            SlicePlan::Stepped { .. } => return None,
+        })
+    }
 }
 
 /// Whether a parsed range can be classified before any input is seen.
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub(crate) enum Plan {
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Plan {
     /// Head-relative or statically decidable: classified once, up front.
     Resolved(SlicePlan),
     /// Tail-relative: resolution needs the input length (per input), or a
@@ -174,13 +265,21 @@ pub(crate) enum Plan {
     /// in, so unlike Tail's bounded ring no fixed-size window suffices —
     /// execution buffers the whole input.
     Reverse(ReversePlan),
+    /// Several forward ranges: one streaming pass emits their union.
+    Union(UnionPlan),
+    /// Several reverse ranges: the union of their walks, buffered like
+    /// `Reverse`.
+    ReverseUnion(ReverseUnionPlan),
+    /// A `/regex/` endpoint: the bounds are found while streaming.
+    Anchored(AnchoredPlan),
 }
 
 /// Tail (no output before EOF) and Lag (streams with a fixed delay) are
 /// distinct execution mechanisms, so they are separate variants rather than
-/// one shape matched at runtime.
+/// one shape matched at runtime; Proportional needs the whole count before
+/// it selects anything.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub(crate) enum DeferredPlan {
+pub enum DeferredPlan {
     /// `-k:…` — tail-relative start. `plan()` establishes the invariants:
     /// `end == Some(FromEnd(m))` only with `m < back`, `end ==
     /// Some(FromStart(e))` only with `e >= 1` (other pairs are statically
@@ -196,12 +295,16 @@ pub(crate) enum DeferredPlan {
         back: NonZeroUsize,
         step: NonZeroUsize,
     },
+    /// `50%:…` — bounds or a step that are shares of the element count.
+    /// Execution counts the input first (regular files in place, other
+    /// input buffered as for a reverse range), then runs what it resolved to.
+    Proportional(ProportionalPlan),
 }
 
-/// A negative-step range. Bounds keep their parsed form; [`Self::indices`]
+/// A negative-step range. Bounds keep their parsed form; `indices`
 /// maps them onto a descending index walk once the element count is known.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub(crate) struct ReversePlan {
+pub struct ReversePlan {
     /// Descending origin (`FromEnd(1)` is the last element).
     start: SliceIndex,
     /// Exclusive lower bound; `None` runs through the first element.
@@ -240,9 +343,132 @@ impl ReversePlan {
     }
 }
 
+/// A descending index walk over `len` elements — all the reverse executor
+/// needs from a plan, so one range and a union of several share it.
+pub(crate) trait ReverseWalk {
+    fn indices(&self, len: usize) -> impl Iterator<Item = usize>;
+}
+
+impl ReverseWalk for ReversePlan {
+    #[inline]
+    fn indices(&self, len: usize) -> impl Iterator<Item = usize> {
+        ReversePlan::indices(self, len)
+    }
+}
+
+/// Several forward ranges, none statically empty or whole-input (planning
+/// folds those away). An element is emitted once however many pieces select
+/// it, in input order, so the whole list is a single pass. Head-relative
+/// bounds decide each element on arrival; a tail-relative one decides only
+/// `delay()` elements later, or at EOF. A complemented plan emits the
+/// elements no piece selects instead — also the fallback for any single
+/// range whose complement has no span shape.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct UnionPlan {
+    pieces: Vec<SliceRange>,
+    complement: bool,
+}
+
+impl UnionPlan {
+    /// How many successors must be read before an element's selection is
+    /// known: the largest tail-relative distance among the bounds, or 0 when
+    /// every bound is head-relative.
+    pub(crate) fn delay(&self) -> usize {
+        self.pieces
+            .iter()
+            .flat_map(|piece| [Some(piece.start), piece.end])
+            .map(|bound| match bound {
+                Some(SliceIndex::FromEnd(back)) => back.get(),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether this plan emits the elements its pieces leave out.
+    #[inline]
+    pub(crate) fn is_complement(&self) -> bool {
+        self.complement
+    }
+
+    /// Whether element `index` is selected. `len` is the input length once
+    /// EOF fixed it; `None` asserts that more than [`Self::delay`] elements
+    /// follow `index`, which settles every tail-relative bound: a start
+    /// `length-k` still lies past `index`, an end `length-m` beyond it.
+    pub(crate) fn selects(&self, index: usize, len: Option<usize>) -> bool {
+        self.complement
+            != self.pieces.iter().any(|piece| {
+                let start = match piece.start {
+                    SliceIndex::FromStart(start) => start,
+                    SliceIndex::FromEnd(back) => match len {
+                        Some(len) => len.saturating_sub(back.get()),
+                        None => return false,
+                    },
+                };
+                let end = match piece.end {
+                    None => usize::MAX,
+                    Some(SliceIndex::FromStart(end)) => end,
+                    Some(SliceIndex::FromEnd(back)) => {
+                        len.map_or(usize::MAX, |len| len.saturating_sub(back.get()))
+                    }
+                };
+                (start..end).contains(&index) && (index - start) % piece.step.magnitude().get() == 0
+            })
+    }
+
+    /// The first selected index at or after `from`, for a plan whose bounds
+    /// are all head-relative (`delay() == 0`); `None` past the last one, so
+    /// a bounded union stops reading there. Not for a complement, which is
+    /// never bounded.
+    pub(crate) fn next_selected(&self, from: usize) -> Option<usize> {
+        debug_assert_eq!(self.delay(), 0, "tail-relative bounds need the length");
+        debug_assert!(!self.complement, "a complement runs to the end of input");
+        self.pieces
+            .iter()
+            .filter_map(|piece| {
+                let SliceIndex::FromStart(start) = piece.start else {
+                    return None;
+                };
+                let step = piece.step.magnitude().get();
+                let next = match from.checked_sub(start) {
+                    None | Some(0) => start,
+                    Some(past) => start.checked_add(past.div_ceil(step).checked_mul(step)?)?,
+                };
+                match piece.end {
+                    Some(SliceIndex::FromStart(end)) if next >= end => None,
+                    _ => Some(next),
+                }
+            })
+            .min()
+    }
+}
+
+/// Several reverse ranges. Like a lone reverse range the whole input is
+/// buffered; the output is the union of the pieces' walks, each element once,
+/// in descending order. A complemented plan walks the elements no piece
+/// selects, still descending.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ReverseUnionPlan {
+    pieces: Vec<ReversePlan>,
+    complement: bool,
+}
+
+impl ReverseWalk for ReverseUnionPlan {
+    fn indices(&self, len: usize) -> impl Iterator<Item = usize> {
+        let mut selected = vec![false; len];
+        for piece in &self.pieces {
+            for i in piece.indices(len) {
+                selected[i] = true;
+            }
+        }
+        let complement = self.complement;
+        (0..len).rev().filter(move |&i| selected[i] != complement)
+    }
+}
+
 /// Classify absolute (head-relative) bounds into an execution plan.
 #[inline]
-fn classify(start: usize, end: Option<usize>, step: NonZeroUsize) -> SlicePlan {
+pub fn classify(start: usize, end: Option<usize>, step: NonZeroUsize) -> SlicePlan {
     // Checked before step: no step can select anything from `start >= end`.
     if end.is_some_and(|end| start >= end) {
         SlicePlan::Empty
@@ -255,13 +481,209 @@ fn classify(start: usize, end: Option<usize>, step: NonZeroUsize) -> SlicePlan {
     }
 }
 
+impl Plan {
+    /// `--complement`: the plan emitting exactly the elements this one leaves
+    /// out. Span shapes stay span shapes — a window turns into a head and
+    /// tail copy, `-k:` and `:-k` trade places — so the inverse keeps its
+    /// fast path; any other forward shape streams through a complemented
+    /// union, and a reverse one walks the leftovers descending.
+    pub fn complement(self) -> Plan {
+        match self {
+            Plan::Resolved(plan) => match plan.complement() {
+                Some(plan) => Plan::Resolved(plan),
+                None => Plan::Union(UnionPlan::complement_of(plan.range())),
+            },
+            Plan::Deferred(plan) => match plan.complement() {
+                Some(plan) => Plan::Deferred(plan),
+                None => Plan::Union(UnionPlan::complement_of(plan.range())),
+            },
+            Plan::Reverse(plan) => Plan::ReverseUnion(ReverseUnionPlan {
+                pieces: vec![plan],
+                complement: true,
+            }),
+            Plan::Union(plan) => Plan::Union(UnionPlan {
+                complement: !plan.complement,
+                ..plan
+            }),
+            Plan::ReverseUnion(plan) => Plan::ReverseUnion(ReverseUnionPlan {
+                complement: !plan.complement,
+                ..plan
+            }),
+            Plan::Anchored(plan) => Plan::Anchored(plan.complement()),
+        }
+    }
+
+    /// The selected indices of `elements`, in output order. `--fields` holds
+    /// each record's fields in memory, so every shape — tail-relative,
+    /// reverse, anchored — resolves against the field count at once, with
+    /// the same rules the streaming drivers apply to rows.
+    pub(crate) fn select(&self, elements: &[&[u8]]) -> Vec<usize> {
+        let len = elements.len();
+        match self {
+            Plan::Resolved(plan) => plan.indices(len),
+            Plan::Deferred(DeferredPlan::Proportional(plan)) => plan.indices(len),
+            Plan::Deferred(plan) => plan
+                .resolve(len as u64)
+                .expect("an in-memory count fits usize")
+                .indices(len),
+            Plan::Reverse(plan) => plan.indices(len).collect(),
+            Plan::Union(plan) => (0..len).filter(|&i| plan.selects(i, Some(len))).collect(),
+            Plan::ReverseUnion(plan) => plan.indices(len).collect(),
+            Plan::Anchored(plan) => plan.select(elements),
+        }
+    }
+}
+
+impl UnionPlan {
+    /// Everything `range` leaves out, as a one-piece union.
+    fn complement_of(range: SliceRange) -> Self {
+        Self {
+            pieces: vec![range],
+            complement: true,
+        }
+    }
+}
+
+impl SlicePlan {
+    /// Whether element `index` is selected, whatever the input length: the
+    /// bounds are absolute, so an element is decided on arrival.
+    pub(crate) fn contains(self, index: usize) -> bool {
+        match self {
+            SlicePlan::Empty => false,
+            SlicePlan::Copy => true,
+            SlicePlan::Window { start, end } => index >= start && end.is_none_or(|end| index < end),
+            SlicePlan::Stepped { start, end, step } => {
+                index >= start
+                    && end.is_none_or(|end| index < end)
+                    && (index - start) % step.get() == 0
+            }
+            SlicePlan::Gap { start, end } => index < start || index >= end,
+        }
+    }
+
+    /// Whether no element at or past `index` is selected.
+    pub(crate) fn exhausted(self, index: usize) -> bool {
+        match self {
+            SlicePlan::Empty => true,
+            SlicePlan::Window { end, .. } | SlicePlan::Stepped { end, .. } => {
+                end.is_some_and(|end| index >= end)
+            }
+            SlicePlan::Copy | SlicePlan::Gap { .. } => false,
+        }
+    }
+
+    /// The first selected index, if any element can be selected at all.
+    pub(crate) fn first(self) -> Option<usize> {
+        match self {
+            SlicePlan::Empty => None,
+            SlicePlan::Window { start, .. } | SlicePlan::Stepped { start, .. } => Some(start),
+            SlicePlan::Copy => Some(0),
+            SlicePlan::Gap { start: 0, end } => Some(end),
+            SlicePlan::Gap { .. } => Some(0),
+        }
+    }
+
+    /// The first selected index at or after `from`; `None` past the last one.
+    pub(crate) fn next_selected(self, from: usize) -> Option<usize> {
+        let next = match self {
+            SlicePlan::Empty => return None,
+            SlicePlan::Copy => from,
+            SlicePlan::Window { start, .. } => from.max(start),
+            SlicePlan::Stepped { start, step, .. } => match from.checked_sub(start) {
+                None | Some(0) => start,
+                Some(past) => {
+                    start.checked_add(past.div_ceil(step.get()).checked_mul(step.get())?)?
+                }
+            },
+            SlicePlan::Gap { start, end } if from >= start => from.max(end),
+            SlicePlan::Gap { .. } => from,
+        };
+        (!self.exhausted(next)).then_some(next)
+    }
+
+    /// The selected indices among `len` elements, ascending.
+    fn indices(self, len: usize) -> Vec<usize> {
+        let clamp = |end: Option<usize>| end.map_or(len, |end| end.min(len));
+        match self {
+            SlicePlan::Empty => Vec::new(),
+            SlicePlan::Copy => (0..len).collect(),
+            SlicePlan::Window { start, end } => (start..clamp(end)).collect(),
+            SlicePlan::Stepped { start, end, step } => {
+                (start..clamp(end)).step_by(step.get()).collect()
+            }
+            SlicePlan::Gap { start, end } => (0..start.min(len)).chain(end..len).collect(),
+        }
+    }
+
+    /// The forward range this plan executes. Only `Stepped` reaches here:
+    /// every other shape has a span complement.
+    fn range(self) -> SliceRange {
+        let SlicePlan::Stepped { start, end, step } = self else {
+            unreachable!("only a stepped plan lacks a span complement");
+        };
+        SliceRange {
+            start: SliceIndex::FromStart(start),
+            end: end.map(SliceIndex::FromStart),
+            step: Step::Forward(step),
+        }
+    }
+}
+
 impl DeferredPlan {
+    /// The streaming complement where one exists: unit-step `-k:` leaves out
+    /// exactly what `:-k` selects, and the reverse.
+    fn complement(self) -> Option<DeferredPlan> {
+        match self {
+            DeferredPlan::Tail {
+                back,
+                end: None,
+                step,
+            } if step.get() == 1 => Some(DeferredPlan::Lag {
+                start: 0,
+                back,
+                step,
+            }),
+            DeferredPlan::Lag {
+                start: 0,
+                back,
+                step,
+            } if step.get() == 1 => Some(DeferredPlan::Tail {
+                back,
+                end: None,
+                step,
+            }),
+            DeferredPlan::Proportional(plan) => Some(DeferredPlan::Proportional(plan.complement())),
+            _ => None,
+        }
+    }
+
+    /// The range as written, for the union fallback.
+    fn range(self) -> SliceRange {
+        let (start, end, step) = match self {
+            DeferredPlan::Tail { back, end, step } => (SliceIndex::FromEnd(back), end, step),
+            DeferredPlan::Lag { start, back, step } => (
+                SliceIndex::FromStart(start),
+                Some(SliceIndex::FromEnd(back)),
+                step,
+            ),
+            DeferredPlan::Proportional(_) => {
+                unreachable!("a proportional plan complements in place")
+            }
+        };
+        SliceRange {
+            start,
+            end,
+            step: Step::Forward(step),
+        }
+    }
+
     /// Absolutize against a known length and classify through the same rules
     /// as head-relative ranges. An end at or past `len` normalizes to
     /// unbounded, which re-enables the Copy / unbounded io::copy fast paths.
-    /// `None` only when the offsets do not fit usize (32-bit, >4GiB): callers
-    /// stream instead.
-    pub(crate) fn resolve(&self, len: u64) -> Option<SlicePlan> {
+    /// `None` when the offsets do not fit usize (32-bit, >4GiB), or when a
+    /// proportional plan resolves to no span shape (a complemented stride):
+    /// callers stream instead.
+    pub fn resolve(&self, len: u64) -> Option<SlicePlan> {
         let (start, end, step) = match *self {
             DeferredPlan::Tail { back, end, step } => (SliceIndex::FromEnd(back), end, step),
             DeferredPlan::Lag { start, back, step } => (
@@ -269,6 +691,7 @@ impl DeferredPlan {
                 Some(SliceIndex::FromEnd(back)),
                 step,
             ),
+            DeferredPlan::Proportional(plan) => return plan.span(len),
         };
         let start = start.resolve(len);
         let end = end.map(|end| end.resolve(len)).filter(|&end| end < len);
@@ -280,9 +703,139 @@ impl DeferredPlan {
     }
 }
 
+impl SliceRanges {
+    /// A single range plans exactly as itself. A list drops its statically
+    /// empty pieces, collapses to a whole-input copy when any piece is one,
+    /// and merges the rest into one union pass.
+    pub fn plan(&self) -> Plan {
+        let ranges = match self {
+            Self::Indexed(ranges) => ranges,
+            Self::Anchored(range) => return Plan::Anchored(range.plan()),
+            Self::Proportional(range) => {
+                return Plan::Deferred(DeferredPlan::Proportional(range.plan()))
+            }
+        };
+        if let [range] = ranges.as_slice() {
+            return range.plan();
+        }
+        let mut forward = Vec::new();
+        let mut reverse = Vec::new();
+        for range in ranges {
+            match range.plan() {
+                Plan::Resolved(SlicePlan::Empty) => {}
+                // Every element is already selected, in input order.
+                Plan::Resolved(SlicePlan::Copy) => return Plan::Resolved(SlicePlan::Copy),
+                Plan::Reverse(plan) => reverse.push(plan),
+                _ => forward.push(range.clone()),
+            }
+        }
+        match (forward.len(), reverse.len()) {
+            (0, 0) => Plan::Resolved(SlicePlan::Empty),
+            (1, 0) => forward[0].plan(),
+            (0, 1) => Plan::Reverse(reverse[0]),
+            (_, 0) => Plan::Union(UnionPlan {
+                pieces: forward,
+                complement: false,
+            }),
+            (0, _) => Plan::ReverseUnion(ReverseUnionPlan {
+                pieces: reverse,
+                complement: false,
+            }),
+            _ => unreachable!("the parse rejects mixed step directions"),
+        }
+    }
+
+    /// [`SliceRange::explain`] for each range in turn; a list adds how the
+    /// pieces combine.
+    pub fn explain(&self, unit: &str) -> String {
+        let ranges = match self {
+            Self::Indexed(ranges) => ranges,
+            Self::Anchored(range) => return range.explain(unit),
+            Self::Proportional(range) => return range.explain(unit),
+        };
+        if let [range] = ranges.as_slice() {
+            return range.explain(unit);
+        }
+        let mut out = String::new();
+        for (i, range) in ranges.iter().enumerate() {
+            out.push_str(&format!("range {} of {}:\n", i + 1, ranges.len()));
+            out.push_str(&range.explain(unit));
+        }
+        out.push_str(&format!(
+            "union: a {unit} selected by several ranges is printed once, in {}\n",
+            self.order()
+        ));
+        out
+    }
+
+    /// [`Plan::complement`] of [`Self::plan`], for `--complement`. Reverse
+    /// ranges that select nothing plan as [`SlicePlan::Empty`], which has no
+    /// direction, so their complement is spelled out here: every element,
+    /// descending.
+    pub fn plan_complement(&self) -> Plan {
+        match self.plan() {
+            Plan::Resolved(SlicePlan::Empty) if self.is_reverse() => Plan::Reverse(ReversePlan {
+                start: SliceIndex::FromEnd(NonZeroUsize::MIN),
+                end: None,
+                step: NonZeroUsize::MIN,
+            }),
+            plan => plan.complement(),
+        }
+    }
+
+    /// [`Self::explain`] for `--complement`, which prints what the ranges
+    /// leave out instead.
+    pub fn explain_complement(&self, unit: &str) -> String {
+        let subject = match self {
+            Self::Indexed(ranges) if ranges.len() > 1 => "every range",
+            _ => "the range",
+        };
+        format!(
+            "{}complement: every {unit} not selected by {subject} is printed, in {}\n",
+            self.explain(unit),
+            self.order()
+        )
+    }
+
+    #[inline]
+    fn is_reverse(&self) -> bool {
+        matches!(self, Self::Indexed(ranges) if matches!(ranges[0].step, Step::Backward(_)))
+    }
+
+    #[inline]
+    fn order(&self) -> &'static str {
+        if self.is_reverse() {
+            "reverse input order"
+        } else {
+            "input order"
+        }
+    }
+
+    /// [`SliceRange::translate`] for a single range; no dialect expresses a
+    /// union of ranges as one command, nor a regex endpoint with its
+    /// first-match, exclusive-end semantics.
+    pub fn translate(&self, mode: TranslateMode, dialect: TranslateDialect) -> String {
+        let reason = match self {
+            Self::Indexed(ranges) => match ranges.as_slice() {
+                [range] => return range.translate(mode, dialect),
+                _ => UNION_REASON,
+            },
+            Self::Anchored(_) => PATTERN_REASON,
+            Self::Proportional(_) => PERCENTAGE_REASON,
+        };
+        match dialect {
+            TranslateDialect::All => [Dialect::Posix, Dialect::Bsd, Dialect::Gnu, Dialect::Awk]
+                .into_iter()
+                .map(|d| format!("# {}  (no equivalent)\n", dialect_label(d)))
+                .collect(),
+            _ => render_untranslatable(reason),
+        }
+    }
+}
+
 impl SliceRange {
     #[inline]
-    pub(crate) fn plan(&self) -> Plan {
+    pub fn plan(&self) -> Plan {
         use SliceIndex::*;
         let step = match self.step {
             Step::Backward(step) => {
@@ -320,7 +873,7 @@ impl SliceRange {
 
     /// Render a human-readable description of what this resolved range selects,
     /// without reading any input. `unit` names the elements (e.g. "line").
-    pub(crate) fn explain(&self, unit: &str) -> String {
+    pub fn explain(&self, unit: &str) -> String {
         let step = match self.step {
             Step::Backward(step) => return explain_reverse(self.start, self.end, step.get(), unit),
             Step::Forward(step) => step.get(),
@@ -340,7 +893,7 @@ impl SliceRange {
     /// Render the nearest equivalent shell command(s) for this range and
     /// `mode`, without reading any input. Mirrors [`Self::explain`]: the same
     /// four `(start, end)` arms, classified once into a candidate per dialect.
-    pub(crate) fn translate(&self, mode: TranslateMode, dialect: TranslateDialect) -> String {
+    pub fn translate(&self, mode: TranslateMode, dialect: TranslateDialect) -> String {
         let plan = self.plan();
         let empty = matches!(plan, Plan::Resolved(SlicePlan::Empty));
         let copy = matches!(plan, Plan::Resolved(SlicePlan::Copy));
@@ -407,14 +960,40 @@ impl SliceRange {
     }
 }
 
+/// A header value, with its human size alongside when it counts bytes and
+/// is at least 1 KiB (`1052672 (1 MiB + 4 KiB)`).
+fn amount(value: usize, unit: &str) -> String {
+    if unit == "byte" && value >= 1024 {
+        format!("{value} ({})", iec(value as u64))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `value` bytes as a sum of binary units, largest first, so it adds up to
+/// exactly the number it stands beside (`1 MiB + 4 KiB`) where a rounded
+/// `1.0 MiB` would not.
+fn iec(mut value: u64) -> String {
+    const UNITS: [&str; 7] = ["EiB", "PiB", "TiB", "GiB", "MiB", "KiB", "B"];
+    let mut parts = Vec::new();
+    for (power, unit) in (0..UNITS.len() as u32).rev().zip(UNITS) {
+        let size = 1u64 << (10 * power);
+        if value >= size {
+            parts.push(format!("{} {unit}", value / size));
+            value %= size;
+        }
+    }
+    parts.join(" + ")
+}
+
 fn explain_resolved(start: usize, end: Option<usize>, step: usize, unit: &str) -> String {
     let mut out = String::new();
-    out.push_str(&format!("start: {start}\n"));
+    out.push_str(&format!("start: {}\n", amount(start, unit)));
     match end {
         None => out.push_str("end:   end of input\n"),
-        Some(end) => out.push_str(&format!("end:   {end} (exclusive)\n")),
+        Some(end) => out.push_str(&format!("end:   {} (exclusive)\n", amount(end, unit))),
     }
-    out.push_str(&format!("step:  {step}\n"));
+    out.push_str(&format!("step:  {}\n", amount(step, unit)));
 
     match end {
         None => out.push_str(&format!(
@@ -481,9 +1060,12 @@ fn explain_resolved(start: usize, end: Option<usize>, step: usize, unit: &str) -
 /// without reading the input, so positions are described symbolically.
 fn explain_lag(start: usize, back: NonZeroUsize, step: usize, unit: &str) -> String {
     let mut out = String::new();
-    out.push_str(&format!("start: {start}\n"));
-    out.push_str(&format!("end:   {back} from the end (exclusive)\n"));
-    out.push_str(&format!("step:  {step}\n"));
+    out.push_str(&format!("start: {}\n", amount(start, unit)));
+    out.push_str(&format!(
+        "end:   {} from the end (exclusive)\n",
+        amount(back.get(), unit)
+    ));
+    out.push_str(&format!("step:  {}\n", amount(step, unit)));
 
     out.push_str(&format!(
         "0-based: {unit}s at indices [{start}, length-{back})"
@@ -519,15 +1101,21 @@ fn explain_lag(start: usize, back: NonZeroUsize, step: usize, unit: &str) -> Str
 /// without reading the input, so positions are described symbolically.
 fn explain_tail(back: NonZeroUsize, end: Option<SliceIndex>, step: usize, unit: &str) -> String {
     let mut out = String::new();
-    out.push_str(&format!("start: {back} from the end\n"));
+    out.push_str(&format!(
+        "start: {} from the end\n",
+        amount(back.get(), unit)
+    ));
     match end {
         None => out.push_str("end:   end of input\n"),
-        Some(SliceIndex::FromStart(end)) => out.push_str(&format!("end:   {end} (exclusive)\n")),
-        Some(SliceIndex::FromEnd(m)) => {
-            out.push_str(&format!("end:   {m} from the end (exclusive)\n"))
+        Some(SliceIndex::FromStart(end)) => {
+            out.push_str(&format!("end:   {} (exclusive)\n", amount(end, unit)))
         }
+        Some(SliceIndex::FromEnd(m)) => out.push_str(&format!(
+            "end:   {} from the end (exclusive)\n",
+            amount(m.get(), unit)
+        )),
     }
-    out.push_str(&format!("step:  {step}\n"));
+    out.push_str(&format!("step:  {}\n", amount(step, unit)));
 
     // The statically empty pairs ([L-k, L-m) with k <= m, and end 0) mirror
     // the resolved empty form: no clamping note, count 0.
@@ -603,15 +1191,18 @@ fn explain_reverse(start: SliceIndex, end: Option<SliceIndex>, step: usize, unit
     let mut out = String::new();
     match start {
         FromEnd(k) if k.get() == 1 => out.push_str(&format!("start: last {unit}\n")),
-        FromEnd(k) => out.push_str(&format!("start: {k} from the end\n")),
-        FromStart(s) => out.push_str(&format!("start: {s}\n")),
+        FromEnd(k) => out.push_str(&format!("start: {} from the end\n", amount(k.get(), unit))),
+        FromStart(s) => out.push_str(&format!("start: {}\n", amount(s, unit))),
     }
     match end {
         None => out.push_str("end:   start of input\n"),
-        Some(FromStart(e)) => out.push_str(&format!("end:   {e} (exclusive)\n")),
-        Some(FromEnd(m)) => out.push_str(&format!("end:   {m} from the end (exclusive)\n")),
+        Some(FromStart(e)) => out.push_str(&format!("end:   {} (exclusive)\n", amount(e, unit))),
+        Some(FromEnd(m)) => out.push_str(&format!(
+            "end:   {} from the end (exclusive)\n",
+            amount(m.get(), unit)
+        )),
     }
-    out.push_str(&format!("step:  -{step} (reverse)\n"));
+    out.push_str(&format!("step:  -{} (reverse)\n", amount(step, unit)));
 
     // Must stay in lockstep with the static-Empty arms in `plan()` so
     // --explain agrees with execution.
@@ -775,6 +1366,10 @@ const REVERSE_POSIX_REASON: &str =
 const REVERSE_PARTIAL_REASON: &str = "reversing a sub-range needs a pipeline, not a single command";
 const REVERSE_STEP_REASON: &str = "a reverse with a step needs a pipeline, not a single command";
 const REVERSE_BYTES_REASON: &str = "no standard tool reverses a byte stream";
+const UNION_REASON: &str = "a comma-separated range list has no single-command equivalent";
+const PATTERN_REASON: &str = "a /pattern/ endpoint has no single-command equivalent";
+const PERCENTAGE_REASON: &str =
+    "a percentage needs the input counted first, which no single command does";
 
 fn dialect_label(dialect: Dialect) -> &'static str {
     match dialect {
@@ -1092,19 +1687,77 @@ fn digit_shaped_magnitude(s: &str) -> Option<&str> {
         .filter(|magnitude| magnitude.as_bytes().first().is_some_and(u8::is_ascii_digit))
 }
 
+/// Why a field's number failed to parse: as an integer, or as a size.
+enum NumberError {
+    Int(ParseIntError),
+    Size(String),
+}
+
+impl NumberError {
+    fn at(self, field: RangeField, value: &str) -> ParseSliceRangeError {
+        let value = value.to_owned();
+        match self {
+            NumberError::Int(source) => ParseSliceRangeError::InvalidField {
+                field,
+                value,
+                source,
+            },
+            NumberError::Size(message) => ParseSliceRangeError::InvalidSize {
+                field,
+                value,
+                message,
+            },
+        }
+    }
+}
+
+/// The number a field holds, `None` when it is empty. Decimal, and for byte
+/// offsets (`bytes`) also hex (`0x1f00`) or a size with a suffix (`4KiB`,
+/// `1M`), read as `--max-memory` reads one.
+fn parse_number(s: &str, bytes: bool) -> Result<Option<usize>, NumberError> {
+    if bytes {
+        if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            if !hex.as_bytes().first().is_some_and(u8::is_ascii_hexdigit) {
+                return Err(NumberError::Size(
+                    "expected hex digits after '0x'".to_owned(),
+                ));
+            }
+            return usize::from_str_radix(hex, 16)
+                .map(Some)
+                .map_err(NumberError::Int);
+        }
+        let sized = s.as_bytes().first().is_some_and(u8::is_ascii_digit)
+            && s.as_bytes().last().is_some_and(u8::is_ascii_alphabetic);
+        if sized {
+            let size = ByteSize::from_str(s).map_err(|err| NumberError::Size(err.to_string()))?;
+            return usize::try_from(size.0)
+                .map(Some)
+                .map_err(|_| NumberError::Size("size is too large for this platform".to_owned()));
+        }
+    }
+    match s.parse::<usize>() {
+        Ok(v) => Ok(Some(v)),
+        Err(err) if *err.kind() == IntErrorKind::Empty => Ok(None),
+        Err(err) => Err(NumberError::Int(err)),
+    }
+}
+
 /// Parse one bound of the range. A leading `-` followed by a bare digit string
 /// is tail-relative; anything else (`-`, `--1`, `-+1`) keeps the plain-integer
 /// parse error so rejection messages stay unchanged, while a digit-shaped
 /// magnitude reports its own failure (overflow) instead of blaming the `-`.
-fn parse_index(s: &str, field: RangeField) -> Result<Option<SliceIndex>, ParseSliceRangeError> {
-    match s.parse::<usize>() {
-        Ok(v) => Ok(Some(SliceIndex::FromStart(v))),
-        Err(err) if *err.kind() == IntErrorKind::Empty => Ok(None),
-        Err(source) => {
-            let source = match digit_shaped_magnitude(s) {
-                Some(magnitude) => match magnitude.parse::<usize>() {
+fn parse_index(
+    s: &str,
+    field: RangeField,
+    bytes: bool,
+) -> Result<Option<SliceIndex>, ParseSliceRangeError> {
+    match parse_number(s, bytes) {
+        Ok(v) => Ok(v.map(SliceIndex::FromStart)),
+        Err(err) => {
+            let err = match digit_shaped_magnitude(s) {
+                Some(magnitude) => match parse_number(magnitude, bytes) {
                     Ok(v) => {
-                        return Ok(Some(match NonZeroUsize::new(v) {
+                        return Ok(Some(match v.and_then(NonZeroUsize::new) {
                             Some(back) => SliceIndex::FromEnd(back),
                             // Python has no -0: it means the head, not the end.
                             None => SliceIndex::FromStart(0),
@@ -1112,13 +1765,9 @@ fn parse_index(s: &str, field: RangeField) -> Result<Option<SliceIndex>, ParseSl
                     }
                     Err(inner) => inner,
                 },
-                None => source,
+                None => err,
             };
-            Err(ParseSliceRangeError::InvalidField {
-                field,
-                value: s.to_owned(),
-                source,
-            })
+            Err(err.at(field, s))
         }
     }
 }
@@ -1128,23 +1777,29 @@ fn parse_index(s: &str, field: RangeField) -> Result<Option<SliceIndex>, ParseSl
 /// plain-integer parse error, so rejection messages stay unchanged, while a
 /// digit-shaped magnitude reports its own failure (zero, overflow) instead of
 /// blaming the `-`.
-fn parse_step(s: &str) -> Result<Option<Step>, ParseSliceRangeError> {
-    match s.parse::<NonZeroUsize>() {
-        Ok(step) => Ok(Some(Step::Forward(step))),
-        Err(err) if *err.kind() == IntErrorKind::Empty => Ok(None),
-        Err(source) => {
-            let source = match digit_shaped_magnitude(s) {
-                Some(magnitude) => match magnitude.parse::<NonZeroUsize>() {
-                    Ok(step) => return Ok(Some(Step::Backward(step))),
+fn parse_step(s: &str, bytes: bool) -> Result<Option<Step>, ParseSliceRangeError> {
+    let nonzero = |v: usize| {
+        NonZeroUsize::new(v).ok_or_else(|| {
+            NumberError::Int("0".parse::<NonZeroUsize>().expect_err("zero is rejected"))
+        })
+    };
+    match parse_number(s, bytes) {
+        Ok(None) => Ok(None),
+        Ok(Some(v)) => nonzero(v)
+            .map(|step| Some(Step::Forward(step)))
+            .map_err(|err| err.at(RangeField::Step, s)),
+        Err(err) => {
+            let err = match digit_shaped_magnitude(s) {
+                Some(magnitude) => match parse_number(magnitude, bytes) {
+                    Ok(v) => match nonzero(v.unwrap_or_default()) {
+                        Ok(step) => return Ok(Some(Step::Backward(step))),
+                        Err(inner) => inner,
+                    },
                     Err(inner) => inner,
                 },
-                None => source,
+                None => err,
             };
-            Err(ParseSliceRangeError::InvalidField {
-                field: RangeField::Step,
-                value: s.to_owned(),
-                source,
-            })
+            Err(err.at(RangeField::Step, s))
         }
     }
 }
@@ -1160,23 +1815,25 @@ fn end_minus(k: NonZeroUsize, n: usize) -> Option<SliceIndex> {
 impl FromStr for SliceRange {
     type Err = ParseSliceRangeError;
 
+    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        fn parse<T: FromStr<Err = ParseIntError>>(
-            s: &str,
-            field: RangeField,
-        ) -> Result<Option<T>, ParseSliceRangeError> {
-            match s.parse::<T>() {
-                Ok(v) => Ok(Some(v)),
-                Err(err) if *err.kind() == IntErrorKind::Empty => Ok(None),
-                Err(source) => Err(ParseSliceRangeError::InvalidField {
-                    field,
-                    value: s.to_owned(),
-                    source,
-                }),
-            }
-        }
+        Self::parse(s, false)
+    }
+}
+
+impl SliceRange {
+    /// Parse a range over bytes, whose numbers may also be hex offsets
+    /// (`0x1f00`) or sizes with a suffix (`1MiB:+4KiB`, `::4K`).
+    #[inline]
+    pub fn parse_bytes(s: &str) -> Result<Self, ParseSliceRangeError> {
+        Self::parse(s, true)
+    }
+
+    fn parse(s: &str, bytes: bool) -> Result<Self, ParseSliceRangeError> {
         let relative_amount = |amount: &str| -> Result<usize, ParseSliceRangeError> {
-            parse(amount, RangeField::End)?.ok_or(ParseSliceRangeError::MissingRelativeAmount)
+            parse_number(amount, bytes)
+                .map_err(|err| err.at(RangeField::End, amount))?
+                .ok_or(ParseSliceRangeError::MissingRelativeAmount)
         };
 
         /// The end field's parsed form, held until the step is known: the
@@ -1189,7 +1846,7 @@ impl FromStr for SliceRange {
         }
 
         let mut ptn = s.split(':');
-        let start = parse_index(ptn.next().unwrap_or(""), RangeField::Start)?;
+        let start = parse_index(ptn.next().unwrap_or(""), RangeField::Start, bytes)?;
         let maybe_end = ptn.next().ok_or(ParseSliceRangeError::MissingColon)?;
         // Parse the end before the step so field errors keep reporting left
         // to right.
@@ -1198,10 +1855,10 @@ impl FromStr for SliceRange {
         } else if let Some(amount) = maybe_end.strip_prefix('+') {
             End::Ahead(relative_amount(amount)?)
         } else {
-            End::Plain(parse_index(maybe_end, RangeField::End)?)
+            End::Plain(parse_index(maybe_end, RangeField::End, bytes)?)
         };
         let step = match ptn.next() {
-            Some(step) => parse_step(step)?,
+            Some(step) => parse_step(step, bytes)?,
             None => None,
         }
         .unwrap_or(Step::Forward(NonZeroUsize::MIN));
@@ -1248,6 +1905,76 @@ impl FromStr for SliceRange {
     }
 }
 
+impl FromStr for SliceRanges {
+    type Err = ParseSliceRangeError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, false)
+    }
+}
+
+impl SliceRanges {
+    /// [`SliceRange::parse_bytes`] for every range of a list; a
+    /// `/pattern/` range keeps plain counts.
+    #[inline]
+    pub fn parse_bytes(s: &str) -> Result<Self, ParseSliceRangeError> {
+        Self::parse(s, true)
+    }
+
+    fn parse(s: &str, bytes: bool) -> Result<Self, ParseSliceRangeError> {
+        let pieces = split_fields(s, ',')?;
+        if pieces.iter().any(|piece| piece.contains('/')) {
+            return match pieces.as_slice() {
+                [piece] => Ok(Self::Anchored(piece.parse()?)),
+                _ => Err(ParseSliceRangeError::PatternInList),
+            };
+        }
+        if pieces.iter().any(|piece| piece.contains('%')) {
+            return match pieces.as_slice() {
+                [piece] => Ok(Self::Proportional(ProportionalRange::parse(piece, bytes)?)),
+                _ => Err(ParseSliceRangeError::PercentageInList),
+            };
+        }
+        let ranges = pieces
+            .into_iter()
+            .map(|piece| SliceRange::parse(piece, bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        let reverse = |range: &SliceRange| matches!(range.step, Step::Backward(_));
+        if ranges.iter().any(reverse) && !ranges.iter().all(reverse) {
+            return Err(ParseSliceRangeError::MixedStepDirections);
+        }
+        Ok(Self::Indexed(ranges))
+    }
+}
+
+/// Split `s` on `sep` outside `/pattern/`s, where `\` escapes the next
+/// character, so a pattern may contain `:`, `,` and `\/`. Index fields never
+/// contain a `/`, so toggling on every unescaped one tracks the patterns.
+fn split_fields(s: &str, sep: char) -> Result<Vec<&str>, ParseSliceRangeError> {
+    let mut fields = Vec::new();
+    let mut in_pattern = false;
+    let mut escaped = false;
+    let mut from = 0;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && in_pattern {
+            escaped = true;
+        } else if c == '/' {
+            in_pattern = !in_pattern;
+        } else if c == sep && !in_pattern {
+            fields.push(&s[from..i]);
+            from = i + c.len_utf8();
+        }
+    }
+    if in_pattern {
+        return Err(ParseSliceRangeError::UnterminatedPattern);
+    }
+    fields.push(&s[from..]);
+    Ok(fields)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1655,6 +2382,9 @@ mod tests {
                     ..end.map_or(len, |end| end.min(len)))
                     .step_by(step.get())
                     .collect(),
+                SlicePlan::Gap { start, end } => {
+                    (0..len).filter(|&i| i < start || i >= end).collect()
+                }
             }
         }
 
@@ -1673,9 +2403,7 @@ mod tests {
                             Plan::Deferred(deferred) => deferred
                                 .resolve(len as u64)
                                 .expect("offsets fit usize on this platform"),
-                            Plan::Reverse(reverse) => {
-                                panic!("forward range planned {reverse:?}")
-                            }
+                            other => panic!("forward range planned {other:?}"),
                         };
                         assert_eq!(
                             selected(plan, len),
@@ -2560,4 +3288,506 @@ mod tests {
             }
         }
     }
+
+    mod union {
+        use super::*;
+
+        fn plan(ranges: &str) -> Plan {
+            SliceRanges::from_str(ranges).unwrap().plan()
+        }
+
+        /// Python's `range(*slice.indices(len))` for one forward range, via
+        /// the clamping `resolve` already checked against Python above.
+        fn piece_indices(range: &SliceRange, len: usize) -> Vec<usize> {
+            let len64 = len as u64;
+            let start = range.start.resolve(len64) as usize;
+            let end = range.end.map_or(len, |end| end.resolve(len64) as usize);
+            (start..end).step_by(range.step.magnitude().get()).collect()
+        }
+
+        #[test]
+        fn single_range_plans_as_itself() {
+            for range in ["1:5", "-3:", ":-2", "::-1", "5:1", "::3"] {
+                assert_eq!(
+                    plan(range),
+                    SliceRange::from_str(range).unwrap().plan(),
+                    "{range}"
+                );
+            }
+        }
+
+        #[test]
+        fn list_parses_every_piece() {
+            assert_eq!(
+                SliceRanges::from_str("0:5,-3:").unwrap(),
+                SliceRanges::Indexed(vec![
+                    SliceRange::from_str("0:5").unwrap(),
+                    SliceRange::from_str("-3:").unwrap(),
+                ])
+            );
+        }
+
+        #[test]
+        fn piece_errors_surface_unchanged() {
+            assert_eq!(
+                SliceRanges::from_str("0:5,x:").unwrap_err(),
+                SliceRange::from_str("x:").unwrap_err()
+            );
+            assert_eq!(
+                SliceRanges::from_str("0:5,").unwrap_err(),
+                ParseSliceRangeError::MissingColon
+            );
+        }
+
+        #[test]
+        fn mixed_step_directions_rejected() {
+            for ranges in ["0:5,::-1", "::-1,1:", "-1:-3:-1,::2"] {
+                assert_eq!(
+                    SliceRanges::from_str(ranges).unwrap_err(),
+                    ParseSliceRangeError::MixedStepDirections,
+                    "{ranges}"
+                );
+            }
+        }
+
+        #[test]
+        fn planning_folds_empty_and_whole_input_pieces() {
+            assert_eq!(plan("5:3,:0,-2:-4"), Plan::Resolved(SlicePlan::Empty));
+            assert_eq!(plan("5:3,2:4"), plan("2:4"));
+            assert_eq!(plan("1:3,:,-2:"), Plan::Resolved(SlicePlan::Copy));
+            assert_eq!(plan("1:5:-1,::-2"), plan("::-2"));
+            assert!(matches!(plan("0:2,4:6"), Plan::Union(_)));
+            assert!(matches!(plan("::-2,3:0:-1"), Plan::ReverseUnion(_)));
+        }
+
+        #[test]
+        fn delay_is_the_largest_tail_distance() {
+            for (ranges, delay) in [
+                ("0:2,4:6", 0),
+                ("0:2,-3:", 3),
+                ("1:-5,-2:", 5),
+                ("-7:-4,3:9", 7),
+            ] {
+                let Plan::Union(union) = plan(ranges) else {
+                    panic!("{ranges} must merge into a union");
+                };
+                assert_eq!(union.delay(), delay, "{ranges}");
+            }
+        }
+
+        // Every decision path of the union agrees with the union of Python's
+        // per-range selections: the EOF-time decision with the exact length,
+        // the early decision once more than `delay` elements follow, and the
+        // head-relative skip walk.
+        #[test]
+        fn union_matches_python_per_range_selections() {
+            for ranges in [
+                "0:3,5:7",
+                "0:5,2:4",
+                "::3,1::4",
+                "0:5,100:110,-3:",
+                "1:-2:2,-4:",
+                "-6:-3,:2",
+                "2:-1,-5:-2:2",
+                "-3:8,10:",
+                "0:1,-1:",
+            ] {
+                let list = SliceRanges::from_str(ranges).unwrap();
+                let Plan::Union(union) = list.plan() else {
+                    panic!("{ranges} must merge into a union");
+                };
+                let SliceRanges::Indexed(pieces) = &list else {
+                    unreachable!("index ranges parse as an indexed list");
+                };
+                for len in 0..=25usize {
+                    let mut expected: Vec<usize> = pieces
+                        .iter()
+                        .flat_map(|range| piece_indices(range, len))
+                        .collect();
+                    expected.sort_unstable();
+                    expected.dedup();
+                    let at_eof: Vec<usize> =
+                        (0..len).filter(|&i| union.selects(i, Some(len))).collect();
+                    assert_eq!(at_eof, expected, "{ranges} len={len}");
+                    for i in (0..len).filter(|&i| i + union.delay() < len) {
+                        assert_eq!(
+                            union.selects(i, None),
+                            union.selects(i, Some(len)),
+                            "{ranges} len={len} index={i}"
+                        );
+                    }
+                    if union.delay() == 0 {
+                        let walked: Vec<usize> =
+                            std::iter::successors(union.next_selected(0), |&i| {
+                                union.next_selected(i + 1)
+                            })
+                            .take_while(|&i| i < len)
+                            .collect();
+                        assert_eq!(walked, expected, "{ranges} len={len} walk");
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn reverse_union_descends_through_each_index_once() {
+            let Plan::ReverseUnion(union) = plan("::-2,-1:-4:-1") else {
+                panic!("reverse pieces must merge into a reverse union");
+            };
+            assert_eq!(union.indices(6).collect::<Vec<_>>(), [5, 4, 3, 1]);
+            assert_eq!(union.indices(0).count(), 0);
+        }
+
+        #[test]
+        fn explain_lists_each_range_and_the_union_rule() {
+            let text = SliceRanges::from_str("0:2,-3:").unwrap().explain("line");
+            assert!(text.starts_with("range 1 of 2:\nstart: 0\n"), "{text}");
+            assert!(
+                text.contains("range 2 of 2:\nstart: 3 from the end\n"),
+                "{text}"
+            );
+            assert!(
+                text.ends_with(
+                    "union: a line selected by several ranges is printed once, in input order\n"
+                ),
+                "{text}"
+            );
+        }
+
+        #[test]
+        fn translate_has_no_single_command_equivalent() {
+            let list = SliceRanges::from_str("0:2,5:7").unwrap();
+            assert_eq!(
+                list.translate(TranslateMode::Lines, TranslateDialect::Posix),
+                format!("# no equivalent: {UNION_REASON}\n")
+            );
+            assert_eq!(
+                list.translate(TranslateMode::Lines, TranslateDialect::All),
+                "# posix  (no equivalent)\n# bsd  (no equivalent)\n# gnu  (no equivalent)\n# awk  (no equivalent)\n"
+            );
+        }
+    }
+
+    mod complement {
+        use super::*;
+
+        fn complement(ranges: &str) -> Plan {
+            SliceRanges::from_str(ranges).unwrap().plan_complement()
+        }
+
+        fn nz(n: usize) -> NonZeroUsize {
+            NonZeroUsize::new(n).unwrap()
+        }
+
+        #[test]
+        fn empty_and_copy_swap() {
+            assert_eq!(
+                complement("::"),
+                Plan::Resolved(SlicePlan::Copy.complement().unwrap())
+            );
+            assert_eq!(complement("::"), Plan::Resolved(SlicePlan::Empty));
+            assert_eq!(complement("5:3"), Plan::Resolved(SlicePlan::Copy));
+        }
+
+        #[test]
+        fn windows_invert_to_spans() {
+            assert_eq!(
+                complement(":3"),
+                Plan::Resolved(SlicePlan::Window {
+                    start: 3,
+                    end: None
+                })
+            );
+            assert_eq!(
+                complement("3:"),
+                Plan::Resolved(SlicePlan::Window {
+                    start: 0,
+                    end: Some(3)
+                })
+            );
+            assert_eq!(
+                complement("2:5"),
+                Plan::Resolved(SlicePlan::Gap { start: 2, end: 5 })
+            );
+        }
+
+        #[test]
+        fn tail_and_lag_trade_places() {
+            assert_eq!(
+                complement("-3:"),
+                Plan::Deferred(DeferredPlan::Lag {
+                    start: 0,
+                    back: nz(3),
+                    step: nz(1)
+                })
+            );
+            assert_eq!(
+                complement(":-3"),
+                Plan::Deferred(DeferredPlan::Tail {
+                    back: nz(3),
+                    end: None,
+                    step: nz(1)
+                })
+            );
+        }
+
+        #[test]
+        fn other_forward_shapes_stream_as_a_complemented_union() {
+            for range in ["::2", "1:-2", "-5:-2", "-4:7", "-4::2", "0:2,-3:"] {
+                let Plan::Union(union) = complement(range) else {
+                    panic!("{range} must invert into a union");
+                };
+                assert!(union.is_complement(), "{range}");
+            }
+        }
+
+        #[test]
+        fn complemented_union_selects_the_leftovers() {
+            let Plan::Union(union) = complement("1:-2,::4") else {
+                panic!("a list inverts into a union");
+            };
+            let kept: Vec<usize> = (0..10).filter(|&i| union.selects(i, Some(10))).collect();
+            assert_eq!(kept, [9]);
+        }
+
+        #[test]
+        fn reverse_walks_the_leftovers_descending() {
+            let Plan::ReverseUnion(reverse) = complement("8:2:-2") else {
+                panic!("a reverse range inverts into a reverse union");
+            };
+            assert_eq!(
+                reverse.indices(10).collect::<Vec<_>>(),
+                [9, 7, 5, 3, 2, 1, 0]
+            );
+        }
+
+        #[test]
+        fn empty_reverse_ranges_invert_to_a_reverse_walk() {
+            let everything = SliceRanges::from_str("::-1").unwrap().plan();
+            for range in ["5:7:-3", "-20:-2:-1", "3:-1:-1", "2:4:-1,-3:-1:-1"] {
+                assert_eq!(complement(range), everything, "{range}");
+            }
+            assert_eq!(complement("7:5"), Plan::Resolved(SlicePlan::Copy));
+        }
+
+        #[test]
+        fn explain_names_the_complement() {
+            let explanation = SliceRanges::from_str("2:5")
+                .unwrap()
+                .explain_complement("line");
+            assert!(explanation.starts_with(&SliceRange::from_str("2:5").unwrap().explain("line")));
+            assert!(explanation.ends_with(
+                "complement: every line not selected by the range is printed, in input order\n"
+            ));
+        }
+    }
+
+    mod pattern {
+        use super::*;
+
+        #[test]
+        fn a_slash_makes_the_range_anchored() {
+            assert_eq!(
+                SliceRanges::from_str("/^BEGIN/:/^END/").unwrap(),
+                SliceRanges::Anchored(AnchoredRange::from_str("/^BEGIN/:/^END/").unwrap())
+            );
+            assert!(matches!(
+                SliceRanges::from_str("/a,b/:").unwrap().plan(),
+                Plan::Anchored(_)
+            ));
+        }
+
+        #[test]
+        fn lists_reject_patterns() {
+            for ranges in ["/a/:,0:1", "0:1,/a/:", "/a/:,/b/:"] {
+                assert_eq!(
+                    SliceRanges::from_str(ranges),
+                    Err(ParseSliceRangeError::PatternInList),
+                    "{ranges}"
+                );
+            }
+        }
+
+        #[test]
+        fn stray_slash_is_unterminated() {
+            assert_eq!(
+                SliceRanges::from_str("0:5/"),
+                Err(ParseSliceRangeError::UnterminatedPattern)
+            );
+        }
+
+        #[test]
+        fn complement_flips_the_anchored_plan() {
+            let Plan::Anchored(plan) = SliceRanges::from_str("/a/:").unwrap().plan().complement()
+            else {
+                panic!("an anchored range stays anchored");
+            };
+            assert!(plan.is_complement());
+        }
+
+        #[test]
+        fn translate_has_no_equivalent() {
+            let range = SliceRanges::from_str("/a/:").unwrap();
+            assert_eq!(
+                range.translate(TranslateMode::Lines, TranslateDialect::Gnu),
+                format!("# no equivalent: {PATTERN_REASON}\n")
+            );
+        }
+    }
+
+    mod select {
+        use super::*;
+
+        fn select(range: &str, elements: &[&[u8]]) -> Vec<usize> {
+            SliceRanges::from_str(range)
+                .unwrap()
+                .plan()
+                .select(elements)
+        }
+
+        const FIVE: [&[u8]; 5] = [b"a", b"b", b"c", b"d", b"e"];
+
+        #[test]
+        fn every_shape_resolves_against_the_count() {
+            assert_eq!(select(":", &FIVE), [0, 1, 2, 3, 4]);
+            assert_eq!(select("5:1", &FIVE), [] as [usize; 0]);
+            assert_eq!(select("1:3", &FIVE), [1, 2]);
+            assert_eq!(select("1::2", &FIVE), [1, 3]);
+            assert_eq!(select("-2:", &FIVE), [3, 4]);
+            assert_eq!(select("1:-1", &FIVE), [1, 2, 3]);
+            assert_eq!(select("::-2", &FIVE), [4, 2, 0]);
+            assert_eq!(select("0:1,-1:", &FIVE), [0, 4]);
+            assert_eq!(select("3:4:-1,1:0:-1", &FIVE), [1]);
+            assert_eq!(select("4:2:-1,1::-1", &FIVE), [4, 3, 1, 0]);
+            assert_eq!(select("/b/:/d/", &FIVE), [1, 2]);
+        }
+
+        #[test]
+        fn complements_select_the_leftovers() {
+            let complement = |range: &str| {
+                SliceRanges::from_str(range)
+                    .unwrap()
+                    .plan_complement()
+                    .select(&FIVE)
+            };
+            assert_eq!(complement("1:3"), [0, 3, 4]);
+            assert_eq!(complement("::2"), [1, 3]);
+            assert_eq!(complement("::-2"), [3, 1]);
+            assert_eq!(complement("5:7:-3"), [4, 3, 2, 1, 0]);
+            assert_eq!(complement("/c/:"), [0, 1]);
+        }
+
+        #[test]
+        fn bounds_past_the_count_clamp() {
+            assert_eq!(select("3:100", &FIVE), [3, 4]);
+            assert_eq!(select("-100:2", &FIVE), [0, 1]);
+            assert_eq!(select("7:", &FIVE), [] as [usize; 0]);
+            assert_eq!(select("100::-1", &[]), [] as [usize; 0]);
+        }
+
+        #[test]
+        fn next_selected_agrees_with_contains() {
+            let shapes = [
+                SlicePlan::Empty,
+                SlicePlan::Copy,
+                classify(2, Some(7), NonZeroUsize::MIN),
+                classify(3, None, NonZeroUsize::MIN),
+                classify(1, Some(9), NonZeroUsize::new(3).unwrap()),
+                classify(4, None, NonZeroUsize::new(2).unwrap()),
+                SlicePlan::Gap { start: 2, end: 5 },
+                SlicePlan::Gap { start: 0, end: 3 },
+            ];
+            for plan in shapes {
+                for from in 0..12 {
+                    let expected = (from..40).find(|&i| plan.contains(i));
+                    assert_eq!(plan.next_selected(from), expected, "{plan:?} from {from}");
+                }
+            }
+        }
+
+        #[test]
+        fn anchored_ranges_follow_the_streaming_rules() {
+            // The end is searched after the start, which never ends the range.
+            assert_eq!(select("/./:/./", &FIVE), [0]);
+            assert_eq!(select("/z/:", &FIVE), [] as [usize; 0]);
+            assert_eq!(select("/c/:/z/", &FIVE), [2, 3, 4]);
+            assert_eq!(select("/b/:+2", &FIVE), [1, 2]);
+            assert_eq!(select("1:/e/:2", &FIVE), [1, 3]);
+        }
+    }
+
+    mod sizes {
+        use super::*;
+
+        fn bytes(s: &str) -> SliceRange {
+            SliceRange::parse_bytes(s).unwrap()
+        }
+
+        #[test]
+        fn suffixes_and_hex_read_as_byte_counts() {
+            assert_eq!(bytes("1MiB:+4KiB"), bytes("1048576:1052672"));
+            assert_eq!(bytes("1K:2k"), bytes("1000:2000"));
+            assert_eq!(bytes("0x1f00:0X2000"), bytes("7936:8192"));
+            assert_eq!(bytes("::4KiB"), bytes("::4096"));
+            assert_eq!(bytes("-1KiB:"), bytes("-1024:"));
+            assert_eq!(bytes("::-0x10"), bytes("::-16"));
+            assert_eq!(bytes("1.5KiB:"), bytes("1536:"));
+        }
+
+        #[test]
+        fn plain_parsing_stays_strict() {
+            assert!(matches!(
+                SliceRange::from_str("1K:").unwrap_err(),
+                ParseSliceRangeError::InvalidField {
+                    field: RangeField::Start,
+                    ..
+                }
+            ));
+            assert!(SliceRange::from_str("0x10:").is_err());
+            assert!(SliceRanges::from_str("1K:,4:").is_err());
+            assert_eq!(
+                SliceRanges::parse_bytes("1K:2K,0x10:+1").unwrap(),
+                SliceRanges::from_str("1000:2000,16:17").unwrap()
+            );
+        }
+
+        #[test]
+        fn bad_sizes_name_the_field() {
+            let err = SliceRange::parse_bytes("4Q:").unwrap_err();
+            assert!(matches!(
+                err,
+                ParseSliceRangeError::InvalidSize {
+                    field: RangeField::Start,
+                    ..
+                }
+            ));
+            assert!(err.to_string().starts_with("invalid start size '4Q':"));
+            assert!(matches!(
+                SliceRange::parse_bytes("1:+0x").unwrap_err(),
+                ParseSliceRangeError::InvalidSize {
+                    field: RangeField::End,
+                    ..
+                }
+            ));
+            assert_eq!(
+                SliceRange::parse_bytes("::0K").unwrap_err().to_string(),
+                "invalid step value '0K': number would be zero for non-zero type"
+            );
+        }
+
+        #[test]
+        fn explain_shows_human_sizes_for_bytes() {
+            let text = bytes("1MiB:+4KiB").explain("byte");
+            assert!(text.contains("start: 1048576 (1 MiB)"));
+            assert!(text.contains("end:   1052672 (1 MiB + 4 KiB) (exclusive)"));
+            assert!(text.contains("step:  1\n"));
+            let text = bytes("-2KiB:").explain("byte");
+            assert!(text.contains("start: 2048 (2 KiB) from the end"));
+            let text = bytes("1025:0x40000001").explain("byte");
+            assert!(text.contains("start: 1025 (1 KiB + 1 B)"));
+            assert!(text.contains("end:   1073741825 (1 GiB + 1 B) (exclusive)"));
+            // Only bytes are sizes.
+            assert!(bytes("2048:").explain("line").contains("start: 2048\n"));
+        }
+    }
 }
@@ -0,0 +1,518 @@
+use super::{
+    parse_index, parse_step, split_fields, ParseSliceRangeError, RangeField, SliceIndex, Step,
+};
+use regex::bytes::Regex;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
+    str::FromStr,
+};
+
+/// A `/regex/` endpoint. It matches against an element's content — the
+/// element without its trailing delimiter, so `$` anchors at the end of a
+/// line. Compared, ordered and hashed by its source, so ranges holding one
+/// stay plain values.
+#[derive(Clone, Debug)]
+pub(crate) struct Pattern {
+    /// The text between the slashes, as written (`\/` escapes intact).
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    #[inline]
+    pub(crate) fn is_match(&self, content: &[u8]) -> bool {
+        self.regex.is_match(content)
+    }
+
+    /// Parse a whole `/regex/` field; `None` when the field is not a pattern
+    /// at all, so the caller falls back to the integer parse.
+    fn parse(field: &str) -> Option<Result<Self, ParseSliceRangeError>> {
+        let body = field.strip_prefix('/')?;
+        let mut escaped = false;
+        let close = body.char_indices().find_map(|(i, c)| match c {
+            _ if escaped => {
+                escaped = false;
+                None
+            }
+            '\\' => {
+                escaped = true;
+                None
+            }
+            '/' => Some(i),
+            _ => None,
+        });
+        // `split_fields` already rejected an unclosed pattern.
+        let close = close.expect("split_fields balances the slashes");
+        if close + 1 != body.len() {
+            return Some(Err(ParseSliceRangeError::InvalidPattern {
+                pattern: field.to_owned(),
+                message: "unexpected text after the closing '/'".to_owned(),
+            }));
+        }
+        let source = &body[..close];
+        Some(
+            Regex::new(&source.replace("\\/", "/"))
+                .map(|regex| Self {
+                    source: source.to_owned(),
+                    regex,
+                })
+                .map_err(|err| ParseSliceRangeError::InvalidPattern {
+                    pattern: field.to_owned(),
+                    message: err.to_string(),
+                }),
+        )
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}/", self.source)
+    }
+}
+
+impl PartialEq for Pattern {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
+impl PartialOrd for Pattern {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pattern {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.source.cmp(&other.source)
+    }
+}
+
+impl Hash for Pattern {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
+
+/// Where an anchored range starts: a head-relative index, or the first
+/// element matching a pattern.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum Anchor {
+    Index(usize),
+    Match(Pattern),
+}
+
+/// Where an anchored range stops (exclusive).
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum AnchoredEnd {
+    /// An absolute index, as in `/BEGIN/:100`.
+    Index(usize),
+    /// `+n`: `n` elements counted from the start, wherever it matched.
+    Ahead(usize),
+    /// The first element after the start matching the pattern; the start
+    /// element itself never ends the range.
+    Match(Pattern),
+}
+
+/// A forward range with at least one `/regex/` endpoint (`/^BEGIN/:/^END/`,
+/// `/ERROR/:+5`). Patterns resolve only while streaming — the first match
+/// anchors the range — so tail-relative bounds, `+-` windows and reverse
+/// steps, which all need the length or the whole input, are rejected by the
+/// parse.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct AnchoredRange {
+    pub(crate) start: Anchor,
+    /// `None` runs to the end of input.
+    pub(crate) end: Option<AnchoredEnd>,
+    pub(crate) step: NonZeroUsize,
+}
+
+/// An anchored range as executed: one streaming pass decides each element on
+/// arrival. A complemented plan emits the elements the range leaves out.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AnchoredPlan {
+    range: AnchoredRange,
+    complement: bool,
+}
+
+impl AnchoredPlan {
+    #[inline]
+    pub(crate) fn range(&self) -> &AnchoredRange {
+        &self.range
+    }
+
+    /// Whether this plan emits the elements the range leaves out.
+    #[inline]
+    pub(crate) fn is_complement(&self) -> bool {
+        self.complement
+    }
+
+    #[inline]
+    pub(crate) fn complement(self) -> Self {
+        Self {
+            complement: !self.complement,
+            ..self
+        }
+    }
+
+    /// [`Plan::select`](super::Plan::select) for an anchored range: the
+    /// indices of `elements` it selects (or, complemented, leaves out), with
+    /// the streaming semantics — the end pattern is searched after the start,
+    /// an unmatched start selects nothing, an unmatched end runs to the end.
+    pub(crate) fn select(&self, elements: &[&[u8]]) -> Vec<usize> {
+        let len = elements.len();
+        let mut selected = vec![false; len];
+        if let Some((start, end)) = self.range.bounds(elements) {
+            for i in (start..end).step_by(self.range.step.get()) {
+                selected[i] = true;
+            }
+        }
+        (0..len)
+            .filter(|&i| selected[i] != self.complement)
+            .collect()
+    }
+}
+
+/// An anchored plan decided one element at a time, for input that never
+/// reaches its end (`--follow`). Feeds on each element's content in order and
+/// applies the streaming rules of `slice_anchored`.
+#[derive(Clone, Debug)]
+pub(crate) struct AnchoredCursor {
+    plan: AnchoredPlan,
+    /// Elements seen so far.
+    index: usize,
+    /// The index the range opened at, once it has.
+    start: Option<usize>,
+    /// Whether the range has closed; nothing after it is selected.
+    closed: bool,
+}
+
+impl AnchoredCursor {
+    /// Whether the next element, with content `content`, is selected.
+    pub(crate) fn selects(&mut self, content: &[u8]) -> bool {
+        let index = self.index;
+        self.index += 1;
+        self.in_range(index, content) != self.plan.complement
+    }
+
+    /// Whether no later element can be selected.
+    #[inline]
+    pub(crate) fn exhausted(&self) -> bool {
+        self.closed && !self.plan.complement
+    }
+
+    fn in_range(&mut self, index: usize, content: &[u8]) -> bool {
+        let range = &self.plan.range;
+        if self.closed {
+            return false;
+        }
+        let start = match self.start {
+            Some(start) => start,
+            None => {
+                let opens = match &range.start {
+                    Anchor::Index(start) => index >= *start,
+                    Anchor::Match(pattern) => pattern.is_match(content),
+                };
+                if !opens {
+                    return false;
+                }
+                self.start = Some(index);
+                index
+            }
+        };
+        let offset = index - start;
+        let ended = match &range.end {
+            None => false,
+            Some(AnchoredEnd::Index(end)) => index >= *end,
+            Some(AnchoredEnd::Ahead(count)) => offset >= *count,
+            // The start element itself never ends the range.
+            Some(AnchoredEnd::Match(pattern)) => offset > 0 && pattern.is_match(content),
+        };
+        if ended {
+            self.closed = true;
+            return false;
+        }
+        offset % range.step.get() == 0
+    }
+}
+
+impl AnchoredPlan {
+    #[inline]
+    pub(crate) fn cursor(&self) -> AnchoredCursor {
+        AnchoredCursor {
+            plan: self.clone(),
+            index: 0,
+            start: None,
+            closed: false,
+        }
+    }
+}
+
+impl AnchoredRange {
+    #[inline]
+    pub(crate) fn plan(&self) -> AnchoredPlan {
+        AnchoredPlan {
+            range: self.clone(),
+            complement: false,
+        }
+    }
+
+    /// The selected span `[start, end)` of `elements`, clamped to their
+    /// count; `None` when the start pattern matches nothing.
+    fn bounds(&self, elements: &[&[u8]]) -> Option<(usize, usize)> {
+        let len = elements.len();
+        let start = match &self.start {
+            Anchor::Index(start) => *start,
+            Anchor::Match(pattern) => elements.iter().position(|e| pattern.is_match(e))?,
+        };
+        let end = match &self.end {
+            None => len,
+            Some(AnchoredEnd::Index(end)) => *end,
+            Some(AnchoredEnd::Ahead(count)) => start.saturating_add(*count),
+            Some(AnchoredEnd::Match(pattern)) => elements
+                .iter()
+                .skip(start.saturating_add(1))
+                .position(|e| pattern.is_match(e))
+                .map_or(len, |i| start + 1 + i),
+        };
+        Some((start.min(len), end.min(len)))
+    }
+
+    /// Describe the selection in the same `start:`/`end:`/`step:` layout as
+    /// an index range; positions hang on the input, so no count is given.
+    pub(crate) fn explain(&self, unit: &str) -> String {
+        let mut out = String::new();
+        match &self.start {
+            Anchor::Index(start) => out.push_str(&format!("start: {start}\n")),
+            Anchor::Match(pattern) => {
+                out.push_str(&format!("start: the first {unit} matching {pattern}\n"))
+            }
+        }
+        match &self.end {
+            None => out.push_str("end:   end of input\n"),
+            Some(AnchoredEnd::Index(end)) => out.push_str(&format!("end:   {end} (exclusive)\n")),
+            Some(AnchoredEnd::Ahead(count)) => out.push_str(&format!(
+                "end:   {count} {unit}{} after the start (exclusive)\n",
+                if *count == 1 { "" } else { "s" }
+            )),
+            Some(AnchoredEnd::Match(pattern)) => out.push_str(&format!(
+                "end:   the first {unit} after the start matching {pattern} (exclusive)\n"
+            )),
+        }
+        out.push_str(&format!("step:  {}\n", self.step));
+        if let Anchor::Match(pattern) = &self.start {
+            out.push_str(&format!(
+                "nothing is selected when no {unit} matches {pattern}\n"
+            ));
+        }
+        if let Some(AnchoredEnd::Match(pattern)) = &self.end {
+            out.push_str(&format!(
+                "the range runs to the end of input when no later {unit} matches {pattern}\n"
+            ));
+        }
+        out.push_str("count: depends on the input\n");
+        out
+    }
+}
+
+impl FromStr for AnchoredRange {
+    type Err = ParseSliceRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = split_fields(s, ':')?;
+        let (start, end, step) = match fields.as_slice() {
+            [_] => return Err(ParseSliceRangeError::MissingColon),
+            [start, end] => (*start, *end, None),
+            [start, end, step] => (*start, *end, Some(*step)),
+            _ => return Err(ParseSliceRangeError::TooManyParts),
+        };
+        let start = match Pattern::parse(start) {
+            Some(pattern) => Anchor::Match(pattern?),
+            None => match parse_index(start, RangeField::Start, false)? {
+                None => Anchor::Index(0),
+                Some(SliceIndex::FromStart(start)) => Anchor::Index(start),
+                Some(SliceIndex::FromEnd(_)) => {
+                    return Err(ParseSliceRangeError::UnsupportedPatternBound)
+                }
+            },
+        };
+        let end = if let Some(pattern) = Pattern::parse(end) {
+            Some(AnchoredEnd::Match(pattern?))
+        } else if end.starts_with("+-") {
+            return Err(ParseSliceRangeError::UnsupportedPatternBound);
+        } else if let Some(count) = end.strip_prefix('+') {
+            let count = match count.parse::<usize>() {
+                Ok(count) => count,
+                Err(_) if count.is_empty() => {
+                    return Err(ParseSliceRangeError::MissingRelativeAmount)
+                }
+                Err(source) => {
+                    return Err(ParseSliceRangeError::InvalidField {
+                        field: RangeField::End,
+                        value: count.to_owned(),
+                        source,
+                    })
+                }
+            };
+            Some(match start {
+                // A counted end from a fixed start is just an index.
+                Anchor::Index(start) => AnchoredEnd::Index(start.saturating_add(count)),
+                Anchor::Match(_) => AnchoredEnd::Ahead(count),
+            })
+        } else {
+            match parse_index(end, RangeField::End, false)? {
+                None => None,
+                Some(SliceIndex::FromStart(end)) => Some(AnchoredEnd::Index(end)),
+                Some(SliceIndex::FromEnd(_)) => {
+                    return Err(ParseSliceRangeError::UnsupportedPatternBound)
+                }
+            }
+        };
+        let step = match step
+            .map(|step| parse_step(step, false))
+            .transpose()?
+            .flatten()
+        {
+            None => NonZeroUsize::MIN,
+            Some(Step::Forward(step)) => step,
+            Some(Step::Backward(_)) => return Err(ParseSliceRangeError::UnsupportedPatternBound),
+        };
+        Ok(Self { start, end, step })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(source: &str) -> Pattern {
+        Pattern::parse(&format!("/{source}/")).unwrap().unwrap()
+    }
+
+    fn anchored(range: &str) -> AnchoredRange {
+        AnchoredRange::from_str(range).unwrap()
+    }
+
+    #[test]
+    fn pattern_to_pattern() {
+        assert_eq!(
+            anchored("/^BEGIN/:/^END/"),
+            AnchoredRange {
+                start: Anchor::Match(pattern("^BEGIN")),
+                end: Some(AnchoredEnd::Match(pattern("^END"))),
+                step: NonZeroUsize::MIN,
+            }
+        );
+    }
+
+    #[test]
+    fn relative_end_counts_from_the_match() {
+        assert_eq!(anchored("/ERROR/:+5").end, Some(AnchoredEnd::Ahead(5)));
+    }
+
+    #[test]
+    fn relative_end_from_an_index_is_an_index() {
+        assert_eq!(anchored("3:+5").end, Some(AnchoredEnd::Index(8)));
+        assert_eq!(anchored("3:+5:2"), anchored("3:8:2"));
+    }
+
+    #[test]
+    fn omitted_fields_default_like_index_ranges() {
+        let range = anchored(":/END/");
+        assert_eq!(range.start, Anchor::Index(0));
+        assert_eq!(range.step, NonZeroUsize::MIN);
+        assert_eq!(anchored("/a/:").end, None);
+        assert_eq!(anchored("/a/::3").step.get(), 3);
+    }
+
+    #[test]
+    fn separators_inside_a_pattern_are_literal() {
+        let range = anchored(r"/a:b,c\/d/:");
+        assert_eq!(range.start, Anchor::Match(pattern(r"a:b,c\/d")));
+        let Anchor::Match(start) = range.start else {
+            unreachable!()
+        };
+        assert!(start.is_match(b"xa:b,c/d"));
+    }
+
+    #[test]
+    fn pattern_matches_anywhere_in_the_content() {
+        let p = pattern("ERR");
+        assert!(p.is_match(b"an ERROR here"));
+        assert!(!p.is_match(b"all good"));
+        assert!(pattern("^END$").is_match(b"END"));
+    }
+
+    #[test]
+    fn display_keeps_the_source() {
+        assert_eq!(pattern(r"a\/b").to_string(), r"/a\/b/");
+    }
+
+    #[test]
+    fn unsupported_bounds_are_rejected() {
+        for range in ["/a/:-1", "-3:/a/", "/a/:+-2", "/a/::-1", "/a/:/b/:-2"] {
+            assert_eq!(
+                AnchoredRange::from_str(range),
+                Err(ParseSliceRangeError::UnsupportedPatternBound),
+                "{range}"
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_patterns_are_rejected() {
+        assert_eq!(
+            AnchoredRange::from_str("/a:"),
+            Err(ParseSliceRangeError::UnterminatedPattern)
+        );
+        assert!(matches!(
+            AnchoredRange::from_str("/a/b:"),
+            Err(ParseSliceRangeError::InvalidPattern { .. })
+        ));
+        assert!(matches!(
+            AnchoredRange::from_str("/(/:"),
+            Err(ParseSliceRangeError::InvalidPattern { .. })
+        ));
+        assert_eq!(
+            AnchoredRange::from_str("/a/"),
+            Err(ParseSliceRangeError::MissingColon)
+        );
+        assert_eq!(
+            AnchoredRange::from_str("/a/:1:1:1"),
+            Err(ParseSliceRangeError::TooManyParts)
+        );
+        assert_eq!(
+            AnchoredRange::from_str("/a/:+"),
+            Err(ParseSliceRangeError::MissingRelativeAmount)
+        );
+    }
+
+    #[test]
+    fn explain_describes_the_anchors() {
+        assert_eq!(
+            anchored("/^BEGIN/:/^END/").explain("line"),
+            "start: the first line matching /^BEGIN/\n\
+             end:   the first line after the start matching /^END/ (exclusive)\n\
+             step:  1\n\
+             nothing is selected when no line matches /^BEGIN/\n\
+             the range runs to the end of input when no later line matches /^END/\n\
+             count: depends on the input\n"
+        );
+        assert_eq!(
+            anchored("/ERROR/:+5:2").explain("line"),
+            "start: the first line matching /ERROR/\n\
+             end:   5 lines after the start (exclusive)\n\
+             step:  2\n\
+             nothing is selected when no line matches /ERROR/\n\
+             count: depends on the input\n"
+        );
+    }
+}
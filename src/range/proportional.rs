@@ -0,0 +1,375 @@
+use super::{
+    amount, classify, parse_index, parse_number, parse_step, ParseSliceRangeError, Plan,
+    RangeField, SliceIndex, SlicePlan, Step,
+};
+use std::{fmt, num::NonZeroUsize};
+
+/// A share of the input, in millionths of it: `100%` is 1 000 000, so four
+/// decimal places of a percentage are exact.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct Percent(u32);
+
+impl Percent {
+    const WHOLE: u32 = 1_000_000;
+
+    /// This share of `len` elements, rounded down.
+    #[inline]
+    pub(crate) fn of(self, len: u64) -> u64 {
+        (u128::from(len) * u128::from(self.0) / u128::from(Self::WHOLE)) as u64
+    }
+
+    /// Parse a field ending in `%` (`50%`, `12.5%`); `None` when the field
+    /// is not a percentage at all, so the caller falls back to the count.
+    fn parse(field: &str, name: RangeField) -> Option<Result<Self, ParseSliceRangeError>> {
+        let number = field.strip_suffix('%')?;
+        let invalid = |message: &str| ParseSliceRangeError::InvalidPercentage {
+            field: name.clone(),
+            value: field.to_owned(),
+            message: message.to_owned(),
+        };
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        Some(if number.starts_with('-') {
+            Err(invalid("a percentage counts from the start of the input"))
+        } else if whole.is_empty() || !digits(whole) || !digits(fraction) {
+            Err(invalid("expected a number such as '50%' or '12.5%'"))
+        } else if number.ends_with('.') || fraction.len() > 4 {
+            Err(invalid("expected one to four decimal places"))
+        } else {
+            let whole = whole.trim_start_matches('0');
+            let scaled = format!("{whole}{fraction:0<4}");
+            match scaled.parse::<u32>() {
+                Ok(share) if share <= Self::WHOLE => Ok(Self(share)),
+                _ => Err(invalid("a percentage is at most 100%")),
+            }
+        })
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (whole, fraction) = (self.0 / 10_000, self.0 % 10_000);
+        if fraction == 0 {
+            write!(f, "{whole}%")
+        } else {
+            let fraction = format!("{fraction:04}");
+            write!(f, "{whole}.{}%", fraction.trim_end_matches('0'))
+        }
+    }
+}
+
+/// A bound of a proportional range: an index as in any range, or a share of
+/// the input length.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum Position {
+    Index(SliceIndex),
+    Percent(Percent),
+}
+
+/// A number of elements: a plain count, or a share of the input length.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum Amount {
+    Count(usize),
+    Percent(Percent),
+}
+
+/// Where a proportional range stops (exclusive).
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum ProportionalEnd {
+    At(Position),
+    /// `+n`: `n` elements counted from the start.
+    Ahead(Amount),
+    /// `+-n`: the `n` elements either side of the start; the start moves
+    /// back by `n` too.
+    Window(Amount),
+}
+
+/// A forward range with at least one percentage (`50%:`, `10%:20%`,
+/// `::1%`). Percentages are shares of the element count, so the whole range
+/// resolves once the input was counted; reverse steps are rejected by the
+/// parse, as is a percentage in a list.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ProportionalRange {
+    pub(crate) start: Position,
+    /// `None` runs to the end of input.
+    pub(crate) end: Option<ProportionalEnd>,
+    /// At least one element, however small a share of the input it is.
+    pub(crate) step: Amount,
+}
+
+/// A proportional range as executed: nothing is selected before the input
+/// is counted, and then the range selects like the head-relative one its
+/// bounds resolve to. A complemented plan emits what the range leaves out.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct ProportionalPlan {
+    range: ProportionalRange,
+    complement: bool,
+}
+
+impl ProportionalPlan {
+    #[inline]
+    pub(crate) fn complement(self) -> Self {
+        Self {
+            complement: !self.complement,
+            ..self
+        }
+    }
+
+    /// The plan of the head-relative range this one is over `len` elements;
+    /// `None` only when the offsets do not fit usize.
+    pub(crate) fn resolve(&self, len: u64) -> Option<Plan> {
+        let position = |position| match position {
+            Position::Index(index) => SliceIndex::resolve(index, len),
+            Position::Percent(share) => share.of(len),
+        };
+        let count = |amount| match amount {
+            Amount::Count(count) => count as u64,
+            Amount::Percent(share) => share.of(len),
+        };
+        let start = position(self.range.start);
+        let (start, end) = match self.range.end {
+            None => (start, None),
+            Some(ProportionalEnd::At(end)) => (start, Some(position(end))),
+            Some(ProportionalEnd::Ahead(ahead)) => {
+                (start, Some(start.saturating_add(count(ahead))))
+            }
+            Some(ProportionalEnd::Window(either)) => (
+                start.saturating_sub(count(either)),
+                Some(start.saturating_add(count(either))),
+            ),
+        };
+        let step = usize::try_from(count(self.range.step)).ok()?;
+        // An end at or past `len` unbounds, as for a tail-relative range.
+        let end = end.filter(|&end| end < len);
+        let plan = Plan::Resolved(classify(
+            usize::try_from(start).ok()?,
+            end.map(usize::try_from).transpose().ok()?,
+            NonZeroUsize::new(step).unwrap_or(NonZeroUsize::MIN),
+        ));
+        Some(if self.complement {
+            plan.complement()
+        } else {
+            plan
+        })
+    }
+
+    /// The indices selected among `len` elements, ascending.
+    pub(crate) fn indices(&self, len: usize) -> Vec<usize> {
+        match self.resolve(len as u64) {
+            Some(Plan::Resolved(plan)) => plan.indices(len),
+            Some(Plan::Union(plan)) => (0..len).filter(|&i| plan.selects(i, Some(len))).collect(),
+            _ => unreachable!("a counted forward range resolves to a span or a union"),
+        }
+    }
+
+    /// The span plan over `len` elements, when the range resolves to one.
+    #[inline]
+    pub(crate) fn span(&self, len: u64) -> Option<SlicePlan> {
+        match self.resolve(len)? {
+            Plan::Resolved(plan) => Some(plan),
+            _ => None,
+        }
+    }
+}
+
+impl ProportionalRange {
+    #[inline]
+    pub(crate) fn plan(&self) -> ProportionalPlan {
+        ProportionalPlan {
+            range: *self,
+            complement: false,
+        }
+    }
+
+    /// Parse `start:end:step` with percentages allowed in every field; the
+    /// other numbers read as [`SliceRange::parse_bytes`] reads them when
+    /// `bytes` is set.
+    ///
+    /// [`SliceRange::parse_bytes`]: super::SliceRange::parse_bytes
+    pub(crate) fn parse(s: &str, bytes: bool) -> Result<Self, ParseSliceRangeError> {
+        let relative = |field: &str| -> Result<Amount, ParseSliceRangeError> {
+            if let Some(share) = Percent::parse(field, RangeField::End) {
+                return Ok(Amount::Percent(share?));
+            }
+            parse_number(field, bytes)
+                .map_err(|err| err.at(RangeField::End, field))?
+                .map(Amount::Count)
+                .ok_or(ParseSliceRangeError::MissingRelativeAmount)
+        };
+        let position = |field: &str, name: RangeField| match Percent::parse(field, name.clone()) {
+            Some(share) => share.map(|share| Some(Position::Percent(share))),
+            None => Ok(parse_index(field, name, bytes)?.map(Position::Index)),
+        };
+
+        let mut fields = s.split(':');
+        let start = position(fields.next().unwrap_or(""), RangeField::Start)?;
+        let end = fields.next().ok_or(ParseSliceRangeError::MissingColon)?;
+        let end = if let Some(count) = end.strip_prefix("+-") {
+            Some(ProportionalEnd::Window(relative(count)?))
+        } else if let Some(count) = end.strip_prefix('+') {
+            Some(ProportionalEnd::Ahead(relative(count)?))
+        } else {
+            position(end, RangeField::End)?.map(ProportionalEnd::At)
+        };
+        let step = match fields.next() {
+            Some(step) => match Percent::parse(step, RangeField::Step) {
+                Some(share) => match share? {
+                    // Rejected as a zero count is.
+                    Percent(0) => {
+                        return Err(ParseSliceRangeError::InvalidField {
+                            field: RangeField::Step,
+                            value: step.to_owned(),
+                            source: "0".parse::<NonZeroUsize>().expect_err("zero is rejected"),
+                        })
+                    }
+                    share => Some(Amount::Percent(share)),
+                },
+                None => match parse_step(step, bytes)? {
+                    None => None,
+                    Some(Step::Forward(step)) => Some(Amount::Count(step.get())),
+                    Some(Step::Backward(_)) => {
+                        return Err(ParseSliceRangeError::NegativeStepWithPercentage)
+                    }
+                },
+            },
+            None => None,
+        };
+        if fields.next().is_some() {
+            return Err(ParseSliceRangeError::TooManyParts);
+        }
+        Ok(Self {
+            start: start.unwrap_or(Position::Index(SliceIndex::FromStart(0))),
+            end,
+            step: step.unwrap_or(Amount::Count(1)),
+        })
+    }
+
+    /// Describe the selection in the same `start:`/`end:`/`step:` layout as
+    /// an index range; positions hang on the element count, so no count is
+    /// given.
+    pub(crate) fn explain(&self, unit: &str) -> String {
+        let position = |position| match position {
+            Position::Index(SliceIndex::FromStart(index)) => amount(index, unit),
+            Position::Index(SliceIndex::FromEnd(back)) => {
+                format!("{} from the end", amount(back.get(), unit))
+            }
+            Position::Percent(share) => format!("{share} of the {unit}s"),
+        };
+        let count = |count| match count {
+            Amount::Count(1) => format!("1 {unit}"),
+            Amount::Count(count) => format!("{} {unit}s", amount(count, unit)),
+            Amount::Percent(share) => format!("{share} of the {unit}s"),
+        };
+        let mut out = String::new();
+        let start = position(self.start);
+        match self.end {
+            None => out.push_str(&format!("start: {start}\nend:   end of input\n")),
+            Some(ProportionalEnd::At(end)) => out.push_str(&format!(
+                "start: {start}\nend:   {} (exclusive)\n",
+                position(end)
+            )),
+            Some(ProportionalEnd::Ahead(ahead)) => out.push_str(&format!(
+                "start: {start}\nend:   {} after the start (exclusive)\n",
+                count(ahead)
+            )),
+            Some(ProportionalEnd::Window(either)) => {
+                let either = count(either);
+                out.push_str(&format!(
+                    "start: {either} before {start}\nend:   {either} after {start} (exclusive)\n"
+                ))
+            }
+        }
+        match self.step {
+            Amount::Count(step) => out.push_str(&format!("step:  {}\n", amount(step, unit))),
+            Amount::Percent(share) => {
+                out.push_str(&format!("step:  {share} of the {unit}s, at least 1\n"))
+            }
+        }
+        out.push_str(&format!(
+            "percentages round down to whole {unit}s once the input is counted\n"
+        ));
+        out.push_str("count: depends on the input length\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proportional(range: &str) -> ProportionalPlan {
+        ProportionalRange::parse(range, false).unwrap().plan()
+    }
+
+    fn selected(range: &str, len: usize) -> Vec<usize> {
+        proportional(range).indices(len)
+    }
+
+    #[test]
+    fn percentages_read_as_millionths() {
+        let share = |field: &str| Percent::parse(field, RangeField::Start).unwrap();
+        assert_eq!(share("50%"), Ok(Percent(500_000)));
+        assert_eq!(share("12.5%"), Ok(Percent(125_000)));
+        assert_eq!(share("0.0001%"), Ok(Percent(1)));
+        assert_eq!(share("100%"), Ok(Percent(1_000_000)));
+        assert_eq!(share("000%"), Ok(Percent(0)));
+        for field in ["101%", "%", "-5%", "5.%", "1.23456%", "x%", "+5%"] {
+            assert!(share(field).is_err(), "{field}");
+        }
+        assert!(Percent::parse("50", RangeField::Start).is_none());
+        assert_eq!(Percent(125_000).to_string(), "12.5%");
+        assert_eq!(Percent(10_000).to_string(), "1%");
+    }
+
+    #[test]
+    fn bounds_round_down() {
+        assert_eq!(selected("50%:", 5), [2, 3, 4]);
+        assert_eq!(selected(":50%", 5), [0, 1]);
+        assert_eq!(selected("10%:20%", 100), (10..20).collect::<Vec<_>>());
+        assert_eq!(selected("50%:", 0), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn steps_and_counts_scale_with_the_length() {
+        assert_eq!(selected("::25%", 8), [0, 2, 4, 6]);
+        // A step rounding to zero still moves on by one.
+        assert_eq!(selected("::1%", 3), [0, 1, 2]);
+        assert_eq!(selected("50%:+10%", 20), [10, 11]);
+        assert_eq!(selected("50%:+-10%", 20), [8, 9, 10, 11]);
+        assert_eq!(selected("-3:90%", 10), [7, 8]);
+        assert_eq!(selected("1:+2:50%", 100), [1]);
+    }
+
+    #[test]
+    fn complement_leaves_out_the_selection() {
+        let plan = proportional("::50%").complement();
+        assert_eq!(plan.indices(4), [1, 3]);
+        assert_eq!(plan.span(4), None);
+        assert_eq!(
+            proportional("50%:").complement().span(4),
+            Some(SlicePlan::Window {
+                start: 0,
+                end: Some(2)
+            })
+        );
+    }
+
+    #[test]
+    fn reverse_steps_and_zero_steps_are_rejected() {
+        assert_eq!(
+            ProportionalRange::parse("50%::-1", false),
+            Err(ParseSliceRangeError::NegativeStepWithPercentage)
+        );
+        assert!(matches!(
+            ProportionalRange::parse("::0%", false),
+            Err(ParseSliceRangeError::InvalidField {
+                field: RangeField::Step,
+                ..
+            })
+        ));
+        assert_eq!(
+            ProportionalRange::parse("50%:+", false),
+            Err(ParseSliceRangeError::MissingRelativeAmount)
+        );
+    }
+}
@@ -0,0 +1,639 @@
+use slice_command::{
+    build_index, classify, select, slice, slice_every, slice_files, slice_files_in_place, Byte,
+    Bytes, ChunkTemplate, DeferredPlan, DelimiterRegex, FieldSeparator, Graphemes, Options,
+    OutputFormat, Paragraphs, ParseSliceRangeError, Plan, Selected, Sentences, SliceChunks,
+    SliceIndex, SliceMode, SlicePlan, SliceRange, SliceRanges, Split, Step,
+};
+use std::{
+    fs, io,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn temp_file(contents: &[u8]) -> PathBuf {
+    let id = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("slice-api-{}-{}.txt", std::process::id(), id));
+    fs::write(&path, contents).expect("write temp file");
+    path
+}
+
+fn sliced(input: &[u8], range: &str, mode: SliceMode, options: &Options) -> Vec<u8> {
+    let range: SliceRanges = range.parse().unwrap();
+    let mut out = Vec::new();
+    slice(input, &mut out, &range, &mode, options).unwrap();
+    out
+}
+
+fn nonzero(n: usize) -> NonZeroUsize {
+    NonZeroUsize::new(n).unwrap()
+}
+
+#[test]
+fn ranges_parse_into_their_fields() {
+    let range: SliceRange = "2:-1:3".parse().unwrap();
+    assert_eq!(range.start, SliceIndex::FromStart(2));
+    assert_eq!(range.end, Some(SliceIndex::FromEnd(nonzero(1))));
+    assert_eq!(range.step, Step::Forward(nonzero(3)));
+    assert_eq!(
+        "7".parse::<SliceRange>(),
+        Err(ParseSliceRangeError::MissingColon)
+    );
+    assert!("1:3,-2:".parse::<SliceRanges>().is_ok());
+}
+
+#[test]
+fn plans_classify_the_range_shape() {
+    let plan = |range: &str| range.parse::<SliceRanges>().unwrap().plan();
+    assert_eq!(
+        plan("1:3"),
+        Plan::Resolved(SlicePlan::Window {
+            start: 1,
+            end: Some(3)
+        })
+    );
+    assert_eq!(plan(":"), Plan::Resolved(SlicePlan::Copy));
+    assert_eq!(
+        plan("-3:"),
+        Plan::Deferred(DeferredPlan::Tail {
+            back: nonzero(3),
+            end: None,
+            step: NonZeroUsize::MIN,
+        })
+    );
+    assert!(matches!(plan("::-1"), Plan::Reverse(_)));
+    assert!(matches!(plan("0:2,5:"), Plan::Union(_)));
+    assert!(matches!(plan("/a/:"), Plan::Anchored(_)));
+    assert_eq!(
+        plan("1:3").complement(),
+        Plan::Resolved(SlicePlan::Gap { start: 1, end: 3 })
+    );
+    assert_eq!(classify(4, Some(4), NonZeroUsize::MIN), SlicePlan::Empty);
+}
+
+#[test]
+fn splits_cut_one_element_per_read() {
+    fn elements<S: Split>(mut split: S, mut input: &[u8]) -> Vec<Vec<u8>> {
+        let mut elements = Vec::new();
+        loop {
+            let mut element = Vec::new();
+            if split.read_to(&mut input, &mut element).unwrap() == 0 {
+                return elements;
+            }
+            elements.push(element);
+        }
+    }
+    assert_eq!(elements(Byte(b'\n'), b"a\nb"), [&b"a\n"[..], b"b"]);
+    assert_eq!(elements(Bytes::new(b"--"), b"a--b--"), [b"a--", b"b--"]);
+    assert_eq!(
+        elements(Graphemes::new(), "e\u{301}x".as_bytes()),
+        ["e\u{301}".as_bytes(), b"x"]
+    );
+}
+
+#[test]
+fn slice_covers_every_mode_and_plan() {
+    let options = Options::default();
+    let lines = b"a\nb\nc\nd\n";
+    assert_eq!(sliced(lines, "1:3", SliceMode::Lines, &options), b"b\nc\n");
+    assert_eq!(sliced(lines, "-2:", SliceMode::Lines, &options), b"c\nd\n");
+    assert_eq!(sliced(lines, ":-3", SliceMode::Lines, &options), b"a\n");
+    assert_eq!(sliced(lines, "::-2", SliceMode::Lines, &options), b"d\nb\n");
+    assert_eq!(
+        sliced(lines, "0:1,-1:", SliceMode::Lines, &options),
+        b"a\nd\n"
+    );
+    assert_eq!(sliced(lines, "/c/:", SliceMode::Lines, &options), b"c\nd\n");
+    assert_eq!(
+        sliced(b"abcdef", "1::2", SliceMode::Bytes, &options),
+        b"bdf"
+    );
+    assert_eq!(
+        sliced("aéb".as_bytes(), "1:2", SliceMode::Chars, &options),
+        "é".as_bytes()
+    );
+    assert_eq!(
+        sliced("e\u{301}x".as_bytes(), ":1", SliceMode::Graphemes, &options),
+        "e\u{301}".as_bytes()
+    );
+    assert_eq!(
+        sliced(b"a--b--c", "-1:", SliceMode::Custom(b"--"), &options),
+        b"c"
+    );
+    assert_eq!(
+        sliced(b"h\n\"a\nb\"\nc\n", "1:2", SliceMode::Csv, &options),
+        b"\"a\nb\"\n"
+    );
+    // An empty delimiter splits every byte.
+    assert_eq!(
+        sliced(b"abc", "1:", SliceMode::Custom(b""), &options),
+        b"bc"
+    );
+}
+
+#[test]
+fn options_match_their_flags() {
+    let lines = b"h x\na b\nc d\n";
+    let complement = Options {
+        complement: true,
+        ..Options::default()
+    };
+    assert_eq!(
+        sliced(lines, "1:2", SliceMode::Lines, &complement),
+        b"h x\nc d\n"
+    );
+    let header = Options {
+        keep_header: true,
+        fields: Some("1:".parse().unwrap()),
+        field_separator: FieldSeparator::Whitespace,
+        number: Some(1),
+        offsets: true,
+        ..Options::default()
+    };
+    assert_eq!(
+        sliced(lines, "1:", SliceMode::Lines, &header),
+        b"h x\n2:8:d\n"
+    );
+}
+
+#[test]
+fn options_a_mode_cannot_honor_are_rejected() {
+    let range: SliceRanges = ":".parse().unwrap();
+    let options = Options {
+        keep_header: true,
+        ..Options::default()
+    };
+    let err = slice(&b"ab"[..], io::sink(), &range, &SliceMode::Bytes, &options)
+        .expect_err("bytes have no header record");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn columns_slice_inside_the_selected_rows() {
+    let lines = "ab\tc\n漢字x\nskip\n".as_bytes();
+    let options = Options {
+        columns: Some(":4".parse().unwrap()),
+        tab_width: NonZeroUsize::new(2),
+        ..Options::default()
+    };
+    assert_eq!(
+        sliced(lines, ":2", SliceMode::Lines, &options),
+        "ab\t\n漢字\n".as_bytes()
+    );
+    let both = Options {
+        fields: Some("1:".parse().unwrap()),
+        ..options
+    };
+    let range: SliceRanges = ":".parse().unwrap();
+    let err = slice(lines, io::sink(), &range, &SliceMode::Lines, &both)
+        .expect_err("fields and columns both rewrite the record");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn fixed_size_records_have_no_delimiter() {
+    let records = SliceMode::Records(NonZeroUsize::new(3).unwrap());
+    let options = Options::default();
+    assert_eq!(sliced(b"abcdefgh", "1:", records, &options), b"defgh");
+    assert_eq!(sliced(b"abcdefgh", "::-1", records, &options), b"ghdefabc");
+    let chunks: Vec<_> = select(&b"abcdefgh"[..], &":".parse().unwrap(), &records)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(chunks, [&b"abc"[..], b"def", b"gh"]);
+}
+
+#[test]
+fn byte_ranges_take_sizes_and_hex() {
+    let range = SliceRanges::parse_bytes("0x10:+1KiB,2K:").unwrap();
+    assert_eq!(range, "16:1040,2000:".parse::<SliceRanges>().unwrap());
+    let input = vec![b'x'; 4096];
+    let mut out = Vec::new();
+    slice(
+        &input[..],
+        &mut out,
+        &range,
+        &SliceMode::Bytes,
+        &Options::default(),
+    )
+    .unwrap();
+    assert_eq!(out.len(), 1024 + 2096);
+    assert!(matches!(
+        "0x10:".parse::<SliceRanges>(),
+        Err(ParseSliceRangeError::InvalidField { .. })
+    ));
+}
+
+#[test]
+fn percentages_resolve_against_the_element_count() {
+    let lines = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+    let options = Options::default();
+    assert_eq!(
+        sliced(lines, "80%:", SliceMode::Lines, &options),
+        b"9\n10\n"
+    );
+    assert_eq!(
+        sliced(lines, "::40%", SliceMode::Lines, &options),
+        b"1\n5\n9\n"
+    );
+    assert_eq!(
+        sliced(b"abcdefgh", "25%:+25%", SliceMode::Bytes, &options),
+        b"cd"
+    );
+    let complement = Options {
+        complement: true,
+        ..Options::default()
+    };
+    assert_eq!(
+        sliced(b"abcdefgh", "::50%", SliceMode::Bytes, &complement),
+        b"bcdfgh"
+    );
+    assert!(matches!(
+        "50%:".parse::<SliceRanges>().unwrap().plan(),
+        Plan::Deferred(DeferredPlan::Proportional(_))
+    ));
+    assert_eq!(
+        "50%:,1:".parse::<SliceRanges>(),
+        Err(ParseSliceRangeError::PercentageInList)
+    );
+    let chunks: Vec<_> = select(&lines[..], &"90%:".parse().unwrap(), &SliceMode::Lines)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(chunks, [&b"10\n"[..]]);
+
+    let file = temp_file(lines);
+    let mut out = Vec::new();
+    let range = "30%:50%".parse().unwrap();
+    assert!(slice_files(
+        std::slice::from_ref(&file),
+        &mut out,
+        &range,
+        &SliceMode::Lines,
+        &options
+    ));
+    fs::remove_file(&file).ok();
+    assert_eq!(out, b"4\n5\n");
+}
+
+#[test]
+fn ansi_sequences_are_zero_width() {
+    let colored = "ok: \x1b[31merror\x1b[0m: oops\n".as_bytes();
+    let options = Options {
+        ansi: true,
+        ..Options::default()
+    };
+    assert_eq!(
+        sliced(colored, "5:7", SliceMode::Chars, &options),
+        "\x1b[31mrr\x1b[0m".as_bytes()
+    );
+    assert_eq!(sliced(colored, ":", SliceMode::Lines, &options), colored);
+}
+
+#[test]
+fn slice_files_heads_each_file_and_continues_past_failures() {
+    let first = temp_file(b"1\n2\n3\n");
+    let second = temp_file(b"4\n5\n");
+    let missing = std::env::temp_dir().join(format!("slice-api-missing-{}", std::process::id()));
+    let range: SliceRanges = "-1:".parse().unwrap();
+    let options = Options {
+        headers: true,
+        ..Options::default()
+    };
+    let mut out = Vec::new();
+    let paths = [first.clone(), missing, second.clone()];
+    let ok = slice_files(&paths, &mut out, &range, &SliceMode::Lines, &options);
+    assert!(!ok, "the missing file fails the run");
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!(
+            "==> {} <==\n3\n==> {} <==\n5\n",
+            first.display(),
+            second.display()
+        )
+    );
+    fs::remove_file(first).unwrap();
+    fs::remove_file(second).unwrap();
+}
+
+#[test]
+fn an_index_seeks_to_the_same_elements() {
+    let data: Vec<u8> = (0..10_000)
+        .flat_map(|i| format!("{i}\n").into_bytes())
+        .collect();
+    let path = temp_file(&data);
+    build_index(&path, &SliceMode::Lines).unwrap();
+    let sidecar = PathBuf::from(format!("{}.slice-index", path.display()));
+    assert!(sidecar.exists());
+    let range: SliceRanges = "9000:9002".parse().unwrap();
+    let options = Options {
+        index: true,
+        ..Options::default()
+    };
+    let mut out = Vec::new();
+    let paths = [path.clone()];
+    assert!(slice_files(
+        &paths,
+        &mut out,
+        &range,
+        &SliceMode::Lines,
+        &options
+    ));
+    assert_eq!(out, b"9000\n9001\n");
+    assert!(build_index(&path, &SliceMode::Bytes).is_err());
+    fs::remove_file(sidecar).unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn selected_elements_iterate_per_mode_and_plan() {
+    let collect = |range: &str, mode: SliceMode, input: &[u8]| -> Vec<Vec<u8>> {
+        let range: SliceRanges = range.parse().unwrap();
+        select(input, &range, &mode)
+            .collect::<io::Result<_>>()
+            .unwrap()
+    };
+    let lines = b"a\nb\nc\nd\n";
+    assert_eq!(collect("1:3", SliceMode::Lines, lines), [b"b\n", b"c\n"]);
+    assert_eq!(collect("-1:", SliceMode::Lines, lines), [b"d\n"]);
+    assert_eq!(collect("::-3", SliceMode::Lines, lines), [b"d\n", b"a\n"]);
+    assert_eq!(collect("/b/:+1", SliceMode::Lines, lines), [b"b\n"]);
+    assert_eq!(
+        collect(":-1", SliceMode::Custom(b"--"), b"x--y--z"),
+        [&b"x--"[..], b"y--"]
+    );
+    assert_eq!(
+        collect("1:", SliceMode::Chars, "aé".as_bytes()),
+        ["é".as_bytes()]
+    );
+
+    // Any plan, over chunks borrowed from the data.
+    let plan = "1:3".parse::<SliceRanges>().unwrap().plan().complement();
+    let borrowed: Vec<&[u8]> = Selected::new(SliceChunks::new(Byte(b'\n'), lines), &plan)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(borrowed, [&b"a\n"[..], b"d\n"]);
+}
+
+#[test]
+fn regex_delimited_records_end_at_whole_matches() {
+    let delimiter: DelimiterRegex = r"\n\s*\n".parse().unwrap();
+    let mode = SliceMode::Regex(&delimiter);
+    let input = b"a\n\nb\n \n\t\nc";
+    assert_eq!(
+        sliced(input, "1:", mode, &Options::default()),
+        b"b\n \n\t\nc"
+    );
+    assert_eq!(
+        sliced(input, "::-1", mode, &Options::default()),
+        b"cb\n \n\t\na\n\n"
+    );
+    let range: SliceRanges = "-2:".parse().unwrap();
+    let records: Vec<Vec<u8>> = select(&input[..], &range, &mode)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(records, [&b"b\n \n\t\n"[..], b"c"]);
+    assert!("x*".parse::<DelimiterRegex>().is_err());
+}
+
+#[test]
+fn paragraphs_collapse_blank_line_runs() {
+    let input = b"\n[a]\nx = 1\n\n \n\n[b]\r\ny = 2\r\n\r\n[c]\n";
+    let range: SliceRanges = "::".parse().unwrap();
+    let paragraphs: Vec<Vec<u8>> = select(&input[..], &range, &SliceMode::Paragraphs)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(
+        paragraphs,
+        [&b"[a]\nx = 1\n\n"[..], b"[b]\r\ny = 2\r\n\r\n", b"[c]\n"]
+    );
+    assert_eq!(
+        sliced(input, "-1:", SliceMode::Paragraphs, &Options::default()),
+        b"[c]\n"
+    );
+    // Borrowed chunks are the input each paragraph covers, blank lines and all.
+    let covered: Vec<&[u8]> = SliceChunks::new(Paragraphs::new(), &input[..])
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(covered.concat(), input);
+}
+
+#[test]
+fn words_and_sentences_follow_uax29() {
+    let input = b"Dr. No said: \"don't.\" Then left.\n";
+    let range: SliceRanges = "::".parse().unwrap();
+    let words: Vec<Vec<u8>> = select(&input[..], &range, &SliceMode::Words)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert!(words.contains(&b"don't".to_vec()));
+    assert_eq!(words.concat(), input);
+    let sentences: Vec<Vec<u8>> = select(&input[..], &range, &SliceMode::Sentences)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(sentences.last().unwrap(), b"Then left.\n");
+    assert_eq!(sentences.concat(), input);
+    // Borrowed chunks come from the streaming split and agree with `select`.
+    let chunks: Vec<&[u8]> = SliceChunks::new(Sentences::new(), &input[..])
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(chunks, sentences);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn slice_async_reads_a_duplex_stream() {
+    use slice_command::slice_async;
+    use tokio::io::{duplex, AsyncWriteExt, BufReader};
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    let (mut tx, rx) = duplex(3);
+    let range: SliceRanges = "-2:".parse().unwrap();
+    let mut out = Vec::new();
+    let job = slice_async(BufReader::new(rx), &mut out, &range, &SliceMode::Lines);
+    assert_send(&job);
+    let feed = async move {
+        tx.write_all(b"a\nb\nc\n").await.unwrap();
+    };
+    let (sliced, ()) = tokio::join!(job, feed);
+    sliced.unwrap();
+    assert_eq!(out, b"b\nc\n");
+
+    let mut out = Vec::new();
+    let range: SliceRanges = "1::2".parse().unwrap();
+    slice_async("aéb€c".as_bytes(), &mut out, &range, &SliceMode::Chars)
+        .await
+        .unwrap();
+    assert_eq!(out, "é€".as_bytes());
+
+    let mut out = Vec::new();
+    let records = SliceMode::Records(NonZeroUsize::new(2).unwrap());
+    let range: SliceRanges = "-2:".parse().unwrap();
+    slice_async(&b"aabbccd"[..], &mut out, &range, &records)
+        .await
+        .unwrap();
+    assert_eq!(out, b"ccd");
+
+    let range: SliceRanges = "::-1".parse().unwrap();
+    let err = slice_async(&b"a\n"[..], tokio::io::sink(), &range, &SliceMode::Lines)
+        .await
+        .expect_err("reverse ranges buffer the whole input");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn decompress_slices_the_decompressed_stream() {
+    let gzip: &[u8] = include_bytes!("cmd/option_decompress_files.in/a.log.gz");
+    let options = Options {
+        decompress: true,
+        ..Options::default()
+    };
+    assert_eq!(
+        sliced(gzip, "1:3", SliceMode::Lines, &options),
+        b"beta\ngamma\n"
+    );
+    assert_eq!(sliced(gzip, "-6:", SliceMode::Bytes, &options), b"delta\n");
+    // Without it, the compressed bytes are sliced as they are.
+    assert_eq!(
+        sliced(gzip, ":2", SliceMode::Bytes, &Options::default()),
+        &gzip[..2]
+    );
+    assert_eq!(
+        sliced(b"plain\ntext\n", "1:", SliceMode::Lines, &options),
+        b"text\n"
+    );
+
+    let file = temp_file(gzip);
+    let mut out = Vec::new();
+    let range = "50%:".parse().unwrap();
+    assert!(slice_files(
+        std::slice::from_ref(&file),
+        &mut out,
+        &range,
+        &SliceMode::Lines,
+        &options
+    ));
+    fs::remove_file(&file).ok();
+    assert_eq!(out, b"gamma\ndelta\n");
+}
+
+#[test]
+fn slice_every_cuts_chunks_between_elements() {
+    let dir = std::env::temp_dir().join(format!("slice-api-chunks-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let template: ChunkTemplate = dir.join("part-%02d").to_str().unwrap().parse().unwrap();
+    let range = "1:".parse().unwrap();
+    let every = nonzero(2);
+    // Graphemes: a chunk never separates an accent from its letter.
+    let input = "ae\u{301}io".as_bytes();
+    slice_every(
+        input,
+        every,
+        &template,
+        &range,
+        &SliceMode::Graphemes,
+        &Options::default(),
+    )
+    .unwrap();
+    let chunks: Vec<_> = (0..3)
+        .map(|n| fs::read(template.path(None, n)).ok())
+        .collect();
+    fs::remove_dir_all(&dir).ok();
+    assert_eq!(
+        chunks,
+        [Some("e\u{301}i".into()), Some(b"o".to_vec()), None]
+    );
+}
+
+#[test]
+fn slice_files_in_place_rewrites_each_file() {
+    let lines = temp_file(b"a\nb\nc\nd\n");
+    let bytes = temp_file(b"keep-drop");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&lines, fs::Permissions::from_mode(0o640)).unwrap();
+    }
+    let range = "-2:".parse().unwrap();
+    let backup = std::ffi::OsStr::new(".orig");
+    assert!(slice_files_in_place(
+        std::slice::from_ref(&lines),
+        Some(backup),
+        &range,
+        &SliceMode::Lines,
+        &Options::default()
+    ));
+    // A byte prefix is truncated in place.
+    let range = ":4".parse().unwrap();
+    assert!(slice_files_in_place(
+        std::slice::from_ref(&bytes),
+        None,
+        &range,
+        &SliceMode::Bytes,
+        &Options::default()
+    ));
+
+    let mut original = lines.clone().into_os_string();
+    original.push(backup);
+    let results = (
+        fs::read(&lines).unwrap(),
+        fs::read(&original).unwrap(),
+        fs::read(&bytes).unwrap(),
+    );
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(&lines).unwrap().permissions().mode() & 0o777
+    };
+    for path in [&lines, &PathBuf::from(original), &bytes] {
+        fs::remove_file(path).ok();
+    }
+    assert_eq!(
+        results,
+        (
+            b"c\nd\n".to_vec(),
+            b"a\nb\nc\nd\n".to_vec(),
+            b"keep".to_vec()
+        )
+    );
+    #[cfg(unix)]
+    assert_eq!(mode, 0o640);
+}
+
+#[test]
+fn json_format_numbers_elements_as_the_input_holds_them() {
+    let options = Options {
+        format: OutputFormat::JsonLines,
+        ..Options::default()
+    };
+    // A reverse range: the objects come out last first, each still at the
+    // index and offset it has in the input.
+    let range = "::-2".parse().unwrap();
+    let mut out = Vec::new();
+    slice(
+        &b"a\nbb\n\xff\n"[..],
+        &mut out,
+        &range,
+        &SliceMode::Lines,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        concat!(
+            r#"{"file":null,"index":2,"offset":5,"length":2,"content_base64":"/wo="}"#,
+            "\n",
+            r#"{"file":null,"index":0,"offset":0,"length":2,"content":"a\n"}"#,
+            "\n",
+        )
+    );
+
+    let options = Options {
+        format: OutputFormat::Json,
+        number: Some(1),
+        ..Options::default()
+    };
+    let err = slice(&b""[..], io::sink(), &range, &SliceMode::Lines, &options).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}